@@ -0,0 +1,84 @@
+//! Compares the display conversion of whole images one pixel at a time, through
+//! `DisplayPipeline::stages`, with the one eight pixels at a time of `LanePipeline`.
+//! Both run on a single thread, so that the numbers don't depend on the core count.
+//!
+//!     cargo bench --bench display
+use std::time::{Duration, Instant};
+
+use iced_framebuffer::color_pipeline::{DisplayPipeline, DisplaySettings, TonemapKind};
+use iced_framebuffer::display_lanes::LanePipeline;
+use iced_framebuffer::render::{render_scene_linear, RenderSettings, SceneKind};
+
+const WIDTH: usize = 1920;
+const HEIGHT: usize = 1080;
+/// Each conversion is timed this many times, keeping the fastest run
+const RUNS: usize = 10;
+
+fn fastest(mut run: impl FnMut()) -> Duration {
+    (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            run();
+            start.elapsed()
+        })
+        .min()
+        .expect("RUNS is not zero")
+}
+
+fn main() {
+    let render_settings = RenderSettings {
+        width: WIDTH,
+        height: HEIGHT,
+        scene: SceneKind::Fractal,
+        ..RenderSettings::default()
+    };
+    let framebuffer = render_scene_linear(&render_settings);
+    let pixels = framebuffer.pixels();
+    println!("{WIDTH}x{HEIGHT}, fastest of {RUNS} runs on one thread");
+
+    for tonemap in [
+        TonemapKind::Perceptual,
+        TonemapKind::Reinhard,
+        TonemapKind::AcesFilmic,
+        TonemapKind::Clamp,
+    ] {
+        let settings = DisplaySettings {
+            tonemap,
+            exposure: 1.0,
+            ..DisplaySettings::default()
+        };
+
+        let pipeline = DisplayPipeline::baked(&settings, WIDTH * HEIGHT);
+        let mut per_pixel = vec![0; pixels.len()];
+        let per_pixel_time = fastest(|| {
+            for (index, (pixel, display)) in pixels
+                .chunks_exact(4)
+                .zip(per_pixel.chunks_exact_mut(4))
+                .enumerate()
+            {
+                display.copy_from_slice(&pipeline.stages(pixel, index % WIDTH, index / WIDTH).rgba);
+            }
+        });
+
+        let lanes = LanePipeline::new(&settings, WIDTH * HEIGHT)
+            .expect("the default settings are handled by the lanes");
+        let mut lane_pixels = vec![0; pixels.len()];
+        let lanes_time = fastest(|| lanes.convert_into(pixels, &mut lane_pixels));
+
+        let differences = std::iter::zip(&per_pixel, &lane_pixels)
+            .filter(|(a, b)| a != b)
+            .count();
+        let largest = std::iter::zip(&per_pixel, &lane_pixels)
+            .map(|(&a, &b)| a.abs_diff(b))
+            .max()
+            .unwrap_or(0);
+        println!(
+            "{:<16} per pixel {:>8.2?}   lanes {:>8.2?}   {:.1}x faster   {differences} of {} channels differ, by up to {largest}",
+            tonemap.to_string(),
+            per_pixel_time,
+            lanes_time,
+            per_pixel_time.as_secs_f64() / lanes_time.as_secs_f64(),
+            per_pixel.len(),
+        );
+    }
+}
@@ -0,0 +1,64 @@
+//! Drop to a cheaper proxy render when interactive updates can't keep up
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use crate::console;
+
+/// Time we can spend on a single interactive frame, to stay around 30 FPS
+const FRAME_BUDGET: Duration = Duration::from_millis(33);
+/// How many recent frames are averaged before changing quality
+const FRAME_HISTORY_LENGTH: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Quality {
+    Full,
+    Proxy,
+}
+
+/// Tracks how long the recent interactive frames took, and lowers the quality
+/// when they go over the frame budget. Quality is only restored on `pause()`,
+/// otherwise we would keep bouncing between full and proxy renders.
+pub struct QualityController {
+    frame_times: VecDeque<Duration>,
+    quality: Quality,
+}
+
+impl QualityController {
+    pub fn new() -> Self {
+        QualityController {
+            frame_times: VecDeque::with_capacity(FRAME_HISTORY_LENGTH),
+            quality: Quality::Full,
+        }
+    }
+
+    pub fn quality(&self) -> Quality {
+        self.quality
+    }
+
+    pub fn record_frame(&mut self, frame_time: Duration) {
+        if self.frame_times.len() == FRAME_HISTORY_LENGTH {
+            self.frame_times.pop_front();
+        }
+        self.frame_times.push_back(frame_time);
+
+        let average = self.frame_times.iter().sum::<Duration>() / self.frame_times.len() as u32;
+        if average > FRAME_BUDGET {
+            if self.quality == Quality::Full {
+                console::info(format!(
+                    "Frames are taking {:.1}ms on average, switching to proxy quality",
+                    average.as_secs_f64() * 1000.0
+                ));
+                // Start measuring the proxy frames from scratch
+                self.frame_times.clear();
+            }
+            self.quality = Quality::Proxy;
+        }
+    }
+
+    /// Interaction stopped: forget the recent timings and go back to full quality.
+    /// Returns the quality that was in use until now.
+    pub fn pause(&mut self) -> Quality {
+        self.frame_times.clear();
+        std::mem::replace(&mut self.quality, Quality::Full)
+    }
+}
@@ -0,0 +1,163 @@
+//! The look of the UI: the built-in dark and light themes, or a custom palette
+//! whose background grey can be set to suit image review. The choice is kept
+//! across sessions in a small TOML file.
+use iced::theme::{Palette, Theme};
+use iced::Color;
+use toml::value::{Table, Value};
+
+use crate::console;
+use crate::i18n::{tr_args, Text};
+
+/// Where the theme is persisted, next to the recent files
+pub const THEME_PATH: &str = "theme.toml";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThemeKind {
+    Dark,
+    Light,
+    Custom,
+}
+
+impl ThemeKind {
+    pub const ALL: [ThemeKind; 3] = [ThemeKind::Dark, ThemeKind::Light, ThemeKind::Custom];
+}
+
+impl std::fmt::Display for ThemeKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ThemeKind::Dark => "Dark",
+            ThemeKind::Light => "Light",
+            ThemeKind::Custom => "Custom",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Colors of the custom theme
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CustomPalette {
+    /// Of the buttons, sliders and selections
+    pub accent: Color,
+    /// Encoded grey level of the background, from 0 to 1. A neutral grey keeps
+    /// the surroundings of the image from skewing how bright it looks.
+    pub background: f32,
+}
+
+impl Default for CustomPalette {
+    fn default() -> Self {
+        CustomPalette {
+            accent: Palette::DARK.primary,
+            background: 0.2,
+        }
+    }
+}
+
+impl CustomPalette {
+    fn palette(&self) -> Palette {
+        let level = self.background.clamp(0.0, 1.0);
+        // Light text on the darker greys, dark text on the lighter ones
+        let text = if level < 0.5 { 0.9 } else { 0.1 };
+        Palette {
+            background: Color::from_rgb(level, level, level),
+            text: Color::from_rgb(text, text, text),
+            primary: self.accent,
+            ..Palette::DARK
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Appearance {
+    pub kind: ThemeKind,
+    /// Kept while another theme is picked, to come back to it
+    pub custom: CustomPalette,
+}
+
+impl Default for Appearance {
+    fn default() -> Self {
+        Appearance {
+            kind: ThemeKind::Dark,
+            custom: CustomPalette::default(),
+        }
+    }
+}
+
+impl Appearance {
+    pub fn theme(&self) -> Theme {
+        match self.kind {
+            ThemeKind::Dark => Theme::Dark,
+            ThemeKind::Light => Theme::Light,
+            ThemeKind::Custom => Theme::custom(self.custom.palette()),
+        }
+    }
+
+    /// The persisted appearance, the default one if there's none yet.
+    /// Whatever the file gets wrong is reported and left to its default.
+    pub fn load() -> Appearance {
+        let mut appearance = Appearance::default();
+        let Ok(contents) = std::fs::read_to_string(THEME_PATH) else {
+            return appearance;
+        };
+        let table = match contents.parse::<Value>() {
+            Ok(Value::Table(table)) => table,
+            Ok(_) => return appearance,
+            Err(e) => {
+                console::warning(format!("could not read {THEME_PATH}: {e}"));
+                return appearance;
+            }
+        };
+
+        if let Some(name) = table.get("theme").and_then(Value::as_str) {
+            match ThemeKind::ALL.iter().find(|kind| kind.to_string() == name) {
+                Some(kind) => appearance.kind = *kind,
+                None => console::warning(format!("unknown theme \"{name}\" in {THEME_PATH}")),
+            }
+        }
+        if let Some(accent) = table.get("accent").and_then(Value::as_str) {
+            match parse_hex(accent) {
+                Some(color) => appearance.custom.accent = color,
+                None => console::warning(format!(
+                    "\"{accent}\" isn't a #RRGGBB color in {THEME_PATH}"
+                )),
+            }
+        }
+        if let Some(background) = table.get("background").and_then(Value::as_float) {
+            appearance.custom.background = (background as f32).clamp(0.0, 1.0);
+        }
+        appearance
+    }
+
+    /// Persist the appearance. Failing to only costs the choice, so it's just logged.
+    pub fn save(&self) {
+        let mut table = Table::new();
+        table.insert("theme".into(), Value::String(self.kind.to_string()));
+        table.insert("accent".into(), Value::String(to_hex(self.custom.accent)));
+        table.insert(
+            "background".into(),
+            // Rounded like the slider steps, rather than the f32's exact value
+            Value::Float((self.custom.background as f64 * 100.0).round() / 100.0),
+        );
+
+        let result = toml::to_string(&Value::Table(table))
+            .map_err(|e| e.to_string())
+            .and_then(|contents| std::fs::write(THEME_PATH, contents).map_err(|e| e.to_string()));
+        if let Err(e) = result {
+            console::error(tr_args(Text::CouldNotSave, &[&THEME_PATH, &e]));
+        }
+    }
+}
+
+/// Parse a `#RRGGBB` color, the `#` being optional
+pub fn parse_hex(text: &str) -> Option<Color> {
+    let digits = text.trim().trim_start_matches('#');
+    if digits.len() != 6 || !digits.is_ascii() {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&digits[i..i + 2], 16).ok();
+    Some(Color::from_rgb8(channel(0)?, channel(2)?, channel(4)?))
+}
+
+pub fn to_hex(color: Color) -> String {
+    let [r, g, b, _] = color.into_rgba8();
+    format!("#{r:02X}{g:02X}{b:02X}")
+}
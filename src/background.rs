@@ -0,0 +1,77 @@
+//! What the viewer shows behind partially transparent pixels.
+//! Only the viewer composites over it, the saved and copied images keep their alpha.
+use crate::color_pipeline::DisplayTransform;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ViewerBackground {
+    Checkerboard,
+    Black,
+    /// 18% gray, once encoded for the display
+    MidGray,
+    White,
+}
+
+impl ViewerBackground {
+    pub const ALL: [ViewerBackground; 4] = [
+        ViewerBackground::Checkerboard,
+        ViewerBackground::Black,
+        ViewerBackground::MidGray,
+        ViewerBackground::White,
+    ];
+
+    /// Encoded value of the background at the pixel `x`, `y` of the image
+    fn level(&self, x: usize, y: usize) -> u8 {
+        match self {
+            ViewerBackground::Checkerboard => {
+                CHECKER_LEVELS[(x / CHECKER_SIZE + y / CHECKER_SIZE) % 2]
+            }
+            ViewerBackground::Black => 0,
+            ViewerBackground::MidGray => 118,
+            ViewerBackground::White => 255,
+        }
+    }
+}
+
+impl std::fmt::Display for ViewerBackground {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ViewerBackground::Checkerboard => "Checkerboard",
+            ViewerBackground::Black => "Black",
+            ViewerBackground::MidGray => "Mid gray",
+            ViewerBackground::White => "White",
+        };
+        write!(f, "{name} background")
+    }
+}
+
+/// Side of the checkerboard squares, in pixels of the image
+const CHECKER_SIZE: usize = 8;
+/// Encoded values of the dark and light squares
+const CHECKER_LEVELS: [u8; 2] = [102, 153];
+
+/// Composite the straight alpha RGBA `pixels`, which are `width` wide and encoded
+/// for `display`, over `background`. The blending is done on display linear values,
+/// and leaves the pixels opaque.
+pub fn composite(
+    pixels: &mut [u8],
+    width: usize,
+    background: ViewerBackground,
+    display: DisplayTransform,
+) {
+    let decoded: Vec<f32> = (0..=255)
+        .map(|level| display.decode_channel(level as f32 / 255.0))
+        .collect();
+
+    for (index, pixel) in pixels.chunks_exact_mut(4).enumerate() {
+        if pixel[3] == 255 {
+            continue;
+        }
+        let alpha = pixel[3] as f32 / 255.0;
+        let behind = decoded[background.level(index % width, index / width) as usize];
+        for channel in &mut pixel[..3] {
+            let linear = decoded[*channel as usize] * alpha + behind * (1.0 - alpha);
+            *channel = (display.encode_channel(linear).clamp(0.0, 1.0) * 255.0).round() as u8;
+        }
+        pixel[3] = 255;
+    }
+}
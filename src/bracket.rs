@@ -0,0 +1,48 @@
+//! Exposure bracket contact sheet: the same scene linear image shown at several exposures
+//! side by side, to judge how much detail there is in the highlights and the shadows.
+use crate::color_pipeline::{scene_to_display, DisplaySettings, ViewMode};
+use crate::post_process;
+use crate::render::Framebuffer;
+use crate::resample::subsample_rgba;
+
+/// Exposure of each tile relative to the current one, in stops, in reading order
+pub const BRACKET_STOPS: [f32; 5] = [-4.0, -2.0, 0.0, 2.0, 4.0];
+/// Tiles are a third of the image in both directions, three on the top row, two below
+const COLUMNS: usize = 3;
+
+/// RGBA contact sheet of the `framebuffer`, the same size as the image.
+/// The tiles are subsampled rather than filtered, so that single hot pixels still show;
+/// what they don't cover is left transparent, for the viewer background to show through.
+pub fn contact_sheet(framebuffer: &Framebuffer, settings: &DisplaySettings) -> Vec<u8> {
+    let (width, height) = (framebuffer.width(), framebuffer.height());
+    let mut sheet = vec![0; width * height * 4];
+    let processed = post_process::apply_stack(&settings.post_passes, framebuffer);
+    let (tile, tile_width, tile_height) =
+        subsample_rgba(processed.pixels(), width, height, COLUMNS);
+    let tile = Framebuffer::from_pixels(tile_width, tile_height, tile);
+    let rows = BRACKET_STOPS.len().div_ceil(COLUMNS);
+    let top = height.saturating_sub(rows * height / COLUMNS) / 2;
+
+    for (index, stops) in BRACKET_STOPS.into_iter().enumerate() {
+        let (row, column) = (index / COLUMNS, index % COLUMNS);
+        // The last row is centered when it isn't full
+        let in_row = (BRACKET_STOPS.len() - row * COLUMNS).min(COLUMNS);
+        let left = (COLUMNS - in_row) * width / (2 * COLUMNS) + column * width / COLUMNS;
+        let tile_top = top + row * height / COLUMNS;
+
+        let mut tile_settings = settings.clone();
+        tile_settings.exposure += stops;
+        tile_settings.view_mode = ViewMode::Normal;
+        let pixels = scene_to_display(&tile, &tile_settings);
+
+        let visible_width = tile_width.min(width.saturating_sub(left));
+        for y in 0..tile_height.min(height.saturating_sub(tile_top)) {
+            let source = y * tile_width * 4;
+            let destination = ((tile_top + y) * width + left) * 4;
+            sheet[destination..destination + visible_width * 4]
+                .copy_from_slice(&pixels[source..source + visible_width * 4]);
+        }
+    }
+
+    sheet
+}
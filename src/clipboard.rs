@@ -0,0 +1,76 @@
+//! Copying the viewer image to the system clipboard.
+//! The iced clipboard only takes text, and there is no image clipboard crate
+//! among the dependencies, so the image is handed over as a PNG to the clipboard
+//! tool of the platform: `wl-copy` on Wayland, `xclip` on X11 and `osascript` on macOS.
+use image::ImageEncoder;
+
+use crate::i18n::{tr_args, Text};
+
+/// Put the display referred RGBA `pixels` on the clipboard
+pub fn copy_image(width: usize, height: usize, pixels: &[u8]) -> Result<(), String> {
+    let error = |e: &dyn std::fmt::Display| tr_args(Text::CouldNotCopy, &[&e]);
+
+    let mut png = Vec::new();
+    image::codecs::png::PngEncoder::new(&mut png)
+        .write_image(pixels, width as u32, height as u32, image::ColorType::Rgba8)
+        .map_err(|e| error(&e))?;
+
+    copy_png(&png).map_err(|e| error(&e))
+}
+
+#[cfg(target_os = "macos")]
+fn copy_png(png: &[u8]) -> Result<(), String> {
+    // AppleScript can only read the image back from a file
+    let path = std::env::temp_dir().join("iced-framebuffer-clipboard.png");
+    std::fs::write(&path, png).map_err(|e| e.to_string())?;
+    let script = format!(
+        "set the clipboard to (read (POSIX file \"{}\") as «class PNGf»)",
+        path.display()
+    );
+    run("osascript", &["-e", &script], &[])
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn copy_png(png: &[u8]) -> Result<(), String> {
+    if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        run("wl-copy", &["--type", "image/png"], png)
+    } else {
+        run(
+            "xclip",
+            &["-selection", "clipboard", "-target", "image/png", "-in"],
+            png,
+        )
+    }
+}
+
+#[cfg(not(unix))]
+fn copy_png(_png: &[u8]) -> Result<(), String> {
+    Err(String::from("not supported on this platform yet"))
+}
+
+/// Run `program`, feeding it `input`. The clipboard tools keep serving the
+/// image in the background once they've read it, so this doesn't block.
+#[cfg(unix)]
+fn run(program: &str, args: &[&str], input: &[u8]) -> Result<(), String> {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("{program}: {e}"))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(input)
+            .map_err(|e| format!("{program}: {e}"))?;
+    }
+
+    let status = child.wait().map_err(|e| format!("{program}: {e}"))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("{program} failed ({status})"))
+    }
+}
@@ -0,0 +1,65 @@
+//! Simulation of the dichromat color vision deficiencies, with the matrices of
+//! Machado, Oliveira and Fernandes (2009) at full severity
+//! See https://www.inf.ufrgs.br/~oliveira/pubs_files/CVD_Simulation/CVD_Simulation.html
+use crate::white_balance::{multiply_vector, Matrix};
+
+// These operate on linear values with sRGB primaries
+const PROTANOPIA: Matrix = [
+    [0.152_286, 1.052_583, -0.204_868],
+    [0.114_503, 0.786_281, 0.099_216],
+    [-0.003_882, -0.048_116, 1.051_998],
+];
+const DEUTERANOPIA: Matrix = [
+    [0.367_322, 0.860_646, -0.227_968],
+    [0.280_085, 0.672_501, 0.047_413],
+    [-0.011_820, 0.042_940, 0.968_881],
+];
+const TRITANOPIA: Matrix = [
+    [1.255_528, -0.076_749, -0.178_779],
+    [-0.078_411, 0.930_809, 0.147_602],
+    [0.004_733, 0.691_367, 0.303_900],
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorBlindness {
+    None,
+    /// No long wavelength (red) cones
+    Protanopia,
+    /// No medium wavelength (green) cones
+    Deuteranopia,
+    /// No short wavelength (blue) cones
+    Tritanopia,
+}
+
+impl ColorBlindness {
+    pub const ALL: [ColorBlindness; 4] = [
+        ColorBlindness::None,
+        ColorBlindness::Protanopia,
+        ColorBlindness::Deuteranopia,
+        ColorBlindness::Tritanopia,
+    ];
+
+    /// Simulate how display linear `rgb` is seen. For displays with wider primaries
+    /// than sRGB, the matrices are applied as is, which is only an approximation.
+    pub fn simulate(&self, rgb: [f32; 3]) -> [f32; 3] {
+        let matrix = match self {
+            ColorBlindness::None => return rgb,
+            ColorBlindness::Protanopia => &PROTANOPIA,
+            ColorBlindness::Deuteranopia => &DEUTERANOPIA,
+            ColorBlindness::Tritanopia => &TRITANOPIA,
+        };
+        multiply_vector(matrix, rgb)
+    }
+}
+
+impl std::fmt::Display for ColorBlindness {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ColorBlindness::None => "Normal vision",
+            ColorBlindness::Protanopia => "Protanopia",
+            ColorBlindness::Deuteranopia => "Deuteranopia",
+            ColorBlindness::Tritanopia => "Tritanopia",
+        };
+        write!(f, "{name}")
+    }
+}
@@ -0,0 +1,46 @@
+//! The color pickers of the gradient stops. They edit the scene linear ACEScg
+//! values directly, which can go over 1, and offer a few sRGB swatches to start from.
+use colstodian::spaces::{AcesCg, LinearSrgb};
+use colstodian::{color, Scene};
+
+use crate::color_pipeline::DisplayTransform;
+
+/// The encoded sRGB colors offered to pick from
+pub const SWATCHES: [[u8; 3]; 8] = [
+    [255, 0, 0],
+    [255, 128, 0],
+    [255, 255, 0],
+    [0, 255, 0],
+    [0, 255, 255],
+    [0, 0, 255],
+    [255, 0, 255],
+    [255, 255, 255],
+];
+
+/// The scene linear ACEScg value of the encoded sRGB `swatch`
+pub fn from_srgb([r, g, b]: [u8; 3]) -> [f32; 3] {
+    let acescg = color::srgb_u8(r, g, b).convert::<AcesCg>();
+    [acescg.r, acescg.g, acescg.b]
+}
+
+/// What the ACEScg `color` looks like on an sRGB monitor, clipped, to show it in the UI
+pub fn to_ui_color([r, g, b]: [f32; 3]) -> iced::Color {
+    let linear = color::acescg::<Scene>(r, g, b).convert::<LinearSrgb>();
+    let [r, g, b] = [linear.r, linear.g, linear.b]
+        .map(|channel| DisplayTransform::Srgb.encode_channel(channel.clamp(0.0, 1.0)));
+    iced::Color::from_rgb(r, g, b)
+}
+
+/// The text field of a channel, with enough digits for the swatches to round trip
+pub fn format_channel(channel: f32) -> String {
+    format!("{channel:.4}")
+}
+
+/// A channel as typed in its text field: any finite number from 0, which is black
+pub fn parse_channel(text: &str) -> Result<f32, String> {
+    match text.trim().parse::<f32>() {
+        Ok(channel) if channel.is_finite() && channel >= 0.0 => Ok(channel),
+        Ok(_) => Err(String::from("must be a number from 0")),
+        Err(e) => Err(e.to_string()),
+    }
+}
@@ -0,0 +1,926 @@
+//! Scene linear (ACEScg) to display referred (8bit sRGB, Rec.709 or Display P3) conversion
+use colstodian::spaces::{AcesCg, CieXYZ, DisplayP3, EncodedDisplayP3, EncodedSrgb, LinearSrgb};
+use colstodian::tonemap::{PerceptualTonemapper, PerceptualTonemapperParams, Tonemapper};
+use colstodian::{color, Color, Display, Scene};
+
+use std::ops::RangeInclusive;
+use std::sync::{Arc, OnceLock};
+
+use crate::color_blindness::ColorBlindness;
+use crate::display_lanes::LanePipeline;
+use crate::icc::IccProfile;
+use crate::lut::{Lut3d, LutMode};
+use crate::post_process::PostPass;
+use crate::render::Framebuffer;
+use crate::tone_curve::ToneCurve;
+use crate::white_balance::{adapt, WhiteBalance};
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TonemapKind {
+    /// Tonemap the full color with the colstodian perceptual tonemapper
+    Perceptual,
+    /// Tonemap only the luminance and scale each channel by the same ratio,
+    /// so saturated highlights keep their chroma instead of shifting to white
+    LuminanceOnly,
+    /// Simple `x / (1 + x)` curve on each channel
+    Reinhard,
+    /// Krzysztof Narkowicz's fit of the ACES filmic curve, on each channel
+    AcesFilmic,
+    /// No tonemapping, everything above 1 is clipped
+    Clamp,
+    /// The custom tone curve of the display settings, on each channel
+    Curve,
+}
+
+impl TonemapKind {
+    pub const ALL: [TonemapKind; 6] = [
+        TonemapKind::Perceptual,
+        TonemapKind::LuminanceOnly,
+        TonemapKind::Reinhard,
+        TonemapKind::AcesFilmic,
+        TonemapKind::Clamp,
+        TonemapKind::Curve,
+    ];
+}
+
+impl std::fmt::Display for TonemapKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            TonemapKind::Perceptual => "Perceptual",
+            TonemapKind::LuminanceOnly => "Luminance only",
+            TonemapKind::Reinhard => "Reinhard",
+            TonemapKind::AcesFilmic => "ACES filmic",
+            TonemapKind::Clamp => "Clamp",
+            TonemapKind::Curve => "Custom curve",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Colorspace of the display the pixels are encoded for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DisplayTransform {
+    Srgb,
+    /// sRGB primaries, with the pure 2.4 gamma of a BT.1886 display
+    Rec709,
+    /// Wider gamut, with the sRGB transfer function
+    DisplayP3,
+}
+
+impl DisplayTransform {
+    pub const ALL: [DisplayTransform; 3] = [
+        DisplayTransform::Srgb,
+        DisplayTransform::Rec709,
+        DisplayTransform::DisplayP3,
+    ];
+
+    /// Encode a display linear color in this colorspace
+    fn encode(&self, color: Color<AcesCg, Display>) -> [f32; 3] {
+        match self {
+            DisplayTransform::Srgb => {
+                let encoded = color.convert::<EncodedSrgb>();
+                [encoded.r, encoded.g, encoded.b]
+            }
+            DisplayTransform::Rec709 => {
+                let linear = color.convert::<LinearSrgb>();
+                [linear.r, linear.g, linear.b].map(|channel| channel.max(0.0).powf(1.0 / 2.4))
+            }
+            DisplayTransform::DisplayP3 => {
+                let encoded = color.convert::<EncodedDisplayP3>();
+                [encoded.r, encoded.g, encoded.b]
+            }
+        }
+    }
+
+    /// Undo the transfer function of an encoded channel, back to display linear
+    pub fn decode_channel(&self, channel: f32) -> f32 {
+        match self {
+            DisplayTransform::Srgb | DisplayTransform::DisplayP3 => {
+                if channel <= 0.04045 {
+                    channel / 12.92
+                } else {
+                    ((channel + 0.055) / 1.055).powf(2.4)
+                }
+            }
+            DisplayTransform::Rec709 => channel.max(0.0).powf(2.4),
+        }
+    }
+
+    /// Apply the transfer function to a display linear channel
+    pub fn encode_channel(&self, channel: f32) -> f32 {
+        match self {
+            DisplayTransform::Srgb | DisplayTransform::DisplayP3 => {
+                if channel <= 0.003_130_8 {
+                    channel * 12.92
+                } else {
+                    1.055 * channel.powf(1.0 / 2.4) - 0.055
+                }
+            }
+            DisplayTransform::Rec709 => channel.max(0.0).powf(1.0 / 2.4),
+        }
+    }
+
+    /// Bring a display linear color inside the gamut of this display
+    fn map_gamut(
+        &self,
+        color: Color<AcesCg, Display>,
+        mapping: GamutMapping,
+    ) -> Color<AcesCg, Display> {
+        // Colors that don't need any mapping are returned as is, as the round trip
+        // through the display primaries isn't exact
+        match self {
+            DisplayTransform::Srgb | DisplayTransform::Rec709 => {
+                let linear = color.convert::<LinearSrgb>();
+                let rgb = [linear.r, linear.g, linear.b];
+                let [r, g, b] = mapping.apply(rgb, REC709_LUMINANCE);
+                if [r, g, b] == rgb {
+                    return color;
+                }
+                Color::<LinearSrgb, Display>::new(r, g, b).convert()
+            }
+            DisplayTransform::DisplayP3 => {
+                let linear = color.convert::<DisplayP3>();
+                let rgb = [linear.r, linear.g, linear.b];
+                let [r, g, b] = mapping.apply(rgb, P3_LUMINANCE);
+                if [r, g, b] == rgb {
+                    return color;
+                }
+                Color::<DisplayP3, Display>::new(r, g, b).convert()
+            }
+        }
+    }
+}
+
+/// Luminance weights of linear sRGB/Rec.709 and Display P3 values
+const REC709_LUMINANCE: [f32; 3] = [0.2126, 0.7152, 0.0722];
+const P3_LUMINANCE: [f32; 3] = [0.228_975, 0.691_739, 0.079_287];
+
+/// How the colors that the display can't show (e.g. saturated ACEScg primaries
+/// on an sRGB display) are brought back inside its gamut
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GamutMapping {
+    /// Leave them as they are, the negative components get clipped by the 8bit conversion
+    Clip,
+    /// Blend them towards the gray of the same luminance, just enough to fit
+    Desaturate,
+    /// Compress the distance from the achromatic axis of the most saturated colors,
+    /// like the ACES reference gamut compression. Colors well inside the gamut are untouched.
+    /// See https://docs.acescentral.com/specifications/rgc/
+    SoftCompress,
+}
+
+impl GamutMapping {
+    pub const ALL: [GamutMapping; 3] = [
+        GamutMapping::Clip,
+        GamutMapping::Desaturate,
+        GamutMapping::SoftCompress,
+    ];
+
+    /// Map a linear `rgb` color of the display gamut, whose luminance is given by `weights`
+    fn apply(&self, rgb: [f32; 3], weights: [f32; 3]) -> [f32; 3] {
+        match self {
+            GamutMapping::Clip => rgb,
+            GamutMapping::Desaturate => {
+                let luminance: f32 = (0..3).map(|i| weights[i] * rgb[i]).sum();
+                if luminance <= 0.0 {
+                    return [0.0; 3];
+                }
+
+                // Smallest blend towards gray that brings every channel within 0..1
+                // (or within 0..luminance for the highlights, which can't get any darker)
+                let ceiling = luminance.max(1.0);
+                let amount = rgb
+                    .iter()
+                    .map(|&channel| {
+                        if channel < 0.0 {
+                            channel / (channel - luminance)
+                        } else if channel > ceiling {
+                            (channel - ceiling) / (channel - luminance)
+                        } else {
+                            0.0
+                        }
+                    })
+                    .fold(0.0_f32, f32::max)
+                    .min(1.0);
+                rgb.map(|channel| channel + amount * (luminance - channel))
+            }
+            GamutMapping::SoftCompress => {
+                // Defaults of the ACES reference gamut compression, for the
+                // cyan, magenta and yellow distances (the red, green and blue channels)
+                const THRESHOLD: [f32; 3] = [0.815, 0.803, 0.880];
+                const LIMIT: [f32; 3] = [1.147, 1.264, 1.312];
+                const POWER: f32 = 1.2;
+
+                let achromatic = rgb[0].max(rgb[1]).max(rgb[2]);
+                if achromatic <= 0.0 {
+                    return rgb;
+                }
+
+                let mut compressed = rgb;
+                for (channel, value) in compressed.iter_mut().enumerate() {
+                    let distance = (achromatic - *value) / achromatic;
+                    let (threshold, limit) = (THRESHOLD[channel], LIMIT[channel]);
+                    if distance < threshold {
+                        continue;
+                    }
+
+                    // Scale of the curve, so that a distance of `limit` ends up right on the gamut boundary
+                    let scale = (limit - threshold)
+                        / (((1.0 - threshold) / (limit - threshold)).powf(-POWER) - 1.0)
+                            .powf(1.0 / POWER);
+                    let excess = (distance - threshold) / scale;
+                    let distance =
+                        threshold + scale * excess / (1.0 + excess.powf(POWER)).powf(1.0 / POWER);
+                    *value = achromatic - distance * achromatic;
+                }
+                compressed
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for GamutMapping {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            GamutMapping::Clip => "Clip",
+            GamutMapping::Desaturate => "Desaturate",
+            GamutMapping::SoftCompress => "Soft compress",
+        };
+        write!(f, "{name}")
+    }
+}
+
+pub(crate) fn to_u8(channel: f32) -> u8 {
+    (channel.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+/// 8x8 Bayer matrix, the thresholds of ordered dithering
+const BAYER: [[u8; 8]; 8] = [
+    [0, 32, 8, 40, 2, 34, 10, 42],
+    [48, 16, 56, 24, 50, 18, 58, 26],
+    [12, 44, 4, 36, 14, 46, 6, 38],
+    [60, 28, 52, 20, 62, 30, 54, 22],
+    [3, 35, 11, 43, 1, 33, 9, 41],
+    [51, 19, 59, 27, 49, 17, 57, 25],
+    [15, 47, 7, 39, 13, 45, 5, 37],
+    [63, 31, 55, 23, 61, 29, 53, 21],
+];
+
+/// Noise added before rounding to 8bit, to break up the banding of smooth gradients
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dithering {
+    None,
+    /// Bayer matrix, a regular cross hatch pattern
+    Ordered,
+    /// Approximated with the R2 low discrepancy sequence, which has a similar
+    /// spectrum without needing a noise texture.
+    /// See https://extremelearning.com.au/unreasonable-effectiveness-of-quasirandom-sequences/
+    BlueNoise,
+}
+
+impl Dithering {
+    pub const ALL: [Dithering; 3] = [Dithering::None, Dithering::Ordered, Dithering::BlueNoise];
+
+    /// Offset to add to the pixel at `x`, `y`, in 8bit steps between -0.5 and 0.5
+    fn offset(&self, x: usize, y: usize) -> f32 {
+        match self {
+            Dithering::None => 0.0,
+            Dithering::Ordered => (BAYER[y % 8][x % 8] as f32 + 0.5) / 64.0 - 0.5,
+            Dithering::BlueNoise => {
+                // Inverse of the plastic number, and its square
+                let (a1, a2) = (0.754_877_7_f64, 0.569_840_3_f64);
+                (x as f64 * a1 + y as f64 * a2).fract() as f32 - 0.5
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for Dithering {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Dithering::None => "No dithering",
+            Dithering::Ordered => "Ordered dithering",
+            Dithering::BlueNoise => "Blue noise dithering",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl std::fmt::Display for DisplayTransform {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            DisplayTransform::Srgb => "sRGB",
+            DisplayTransform::Rec709 => "Rec.709",
+            DisplayTransform::DisplayP3 => "Display P3",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// What the viewer shows of the image
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ViewMode {
+    Normal,
+    /// Exposure check: each band of scene linear luminance gets its own color
+    FalseColor,
+    /// A single channel of the encoded display values, as grayscale
+    Red,
+    Green,
+    Blue,
+    Alpha,
+    /// Rec.709 weighted sum of the encoded display values
+    Luma,
+    /// The pixels with a NaN or infinite channel, over a dimmed grayscale of the others
+    NonFinite,
+    /// The image at several exposures side by side, laid out by `bracket::contact_sheet`;
+    /// each of its pixels goes through the pipeline like a normal one
+    Bracket,
+}
+
+impl ViewMode {
+    pub const ALL: [ViewMode; 9] = [
+        ViewMode::Normal,
+        ViewMode::FalseColor,
+        ViewMode::Red,
+        ViewMode::Green,
+        ViewMode::Blue,
+        ViewMode::Alpha,
+        ViewMode::Luma,
+        ViewMode::NonFinite,
+        ViewMode::Bracket,
+    ];
+}
+
+impl std::fmt::Display for ViewMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ViewMode::Normal => "Normal",
+            ViewMode::FalseColor => "False color",
+            ViewMode::Red => "Red",
+            ViewMode::Green => "Green",
+            ViewMode::Blue => "Blue",
+            ViewMode::Alpha => "Alpha",
+            ViewMode::Luma => "Luma",
+            ViewMode::NonFinite => "NaN/Inf",
+            ViewMode::Bracket => "Exposure bracket",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Bands of the false color view: up to how many stops above (or below) 18% gray,
+/// and the encoded color they are shown with. Anything brighter than the last one is red.
+pub const FALSE_COLOR_BANDS: [(f32, [f32; 3], &str); 7] = [
+    (-4.0, [0.5, 0.0, 0.6], "Crushed"),
+    (-2.0, [0.0, 0.3, 1.0], "Deep shadows"),
+    (-0.5, [0.3, 0.3, 0.3], "Shadows"),
+    (0.5, [0.0, 0.8, 0.2], "Mid gray"),
+    (2.0, [0.7, 0.7, 0.7], "Highlights"),
+    (4.0, [1.0, 0.9, 0.0], "Bright highlights"),
+    (6.0, [1.0, 0.5, 0.0], "Near clipping"),
+];
+pub const FALSE_COLOR_CLIPPED: [f32; 3] = [1.0, 0.0, 0.0];
+
+/// Shown instead of the pixels that go above 1 after the display transform, when warning
+pub const CLIPPING_WARNING_COLOR: [u8; 3] = [255, 0, 255];
+/// Shown instead of the pixels that are outside of the sRGB gamut, when warning
+pub const GAMUT_WARNING_COLOR: [u8; 3] = [0, 255, 255];
+/// Shown instead of the pixels with a NaN or infinite channel, in their view mode
+pub const NON_FINITE_COLOR: [u8; 3] = [255, 0, 255];
+
+fn false_color(luminance: f32) -> [f32; 3] {
+    let stops = (luminance / 0.18).log2();
+    FALSE_COLOR_BANDS
+        .iter()
+        .find(|(upper_stops, _, _)| stops < *upper_stops)
+        .map_or(FALSE_COLOR_CLIPPED, |(_, color, _)| *color)
+}
+
+pub const GAMMA_RANGE: RangeInclusive<f32> = 0.2..=5.0;
+/// In stops
+pub const EXPOSURE_RANGE: RangeInclusive<f32> = -8.0..=8.0;
+
+/// Parameters of the scene linear to display conversion
+#[derive(Debug, Clone, PartialEq)]
+pub struct DisplaySettings {
+    /// Multiplies the alpha channel of the whole image
+    pub global_alpha: f32,
+    /// Adapts the scene linear values to the white of the display, before tonemapping
+    pub white_balance: WhiteBalance,
+    /// In stops, scales the scene linear values before tonemapping
+    pub exposure: f32,
+    pub tonemap: TonemapKind,
+    pub tone_curve: ToneCurve,
+    /// Run in order over the scene linear image, before the rest of the conversion
+    pub post_passes: Vec<PostPass>,
+    /// Viewing adjustment applied to the display linear values, on top of the sRGB encoding
+    pub gamma: f32,
+    pub display: DisplayTransform,
+    /// Profile of the monitor, used for the final encode instead of `display` when loaded
+    pub icc_profile: Option<Arc<IccProfile>>,
+    pub gamut_mapping: GamutMapping,
+    /// Shared, so that cloning the settings for every render stays cheap
+    pub lut: Option<Arc<Lut3d>>,
+    pub lut_mode: LutMode,
+    pub view_mode: ViewMode,
+    /// Simulated on the encoded values, as the very last step
+    pub color_blindness: ColorBlindness,
+    /// Replace the clipped and out of gamut pixels with solid warning colors
+    pub clipping_warning: bool,
+    pub dithering: Dithering,
+}
+
+impl Default for DisplaySettings {
+    fn default() -> Self {
+        DisplaySettings {
+            global_alpha: 1.0,
+            white_balance: WhiteBalance::default(),
+            exposure: 0.0,
+            tonemap: TonemapKind::Perceptual,
+            tone_curve: ToneCurve::default(),
+            post_passes: Vec::new(),
+            gamma: 1.0,
+            display: DisplayTransform::Srgb,
+            icc_profile: None,
+            gamut_mapping: GamutMapping::Clip,
+            lut: None,
+            lut_mode: LutMode::AfterTonemap,
+            view_mode: ViewMode::Normal,
+            color_blindness: ColorBlindness::None,
+            clipping_warning: false,
+            dithering: Dithering::None,
+        }
+    }
+}
+
+/// Luminance of a linear ACEScg color.
+// These are the AP1 weights adapted to D65 (like colstodian's CIE XYZ),
+// rather than the D60 ones from the ACES spec.
+pub fn acescg_luminance<St>(color: Color<AcesCg, St>) -> f32 {
+    0.266_680_8 * color.r + 0.676_209 * color.g + 0.057_110_23 * color.b
+}
+
+/// Luminance of every pixel of a scene linear ACEScg RGBA buffer
+pub fn luminance_plane(pixels: &[f32]) -> Vec<f32> {
+    pixels
+        .chunks_exact(4)
+        .map(|pixel| acescg_luminance(color::acescg::<Scene>(pixel[0], pixel[1], pixel[2])))
+        .collect()
+}
+
+/// Tonemap the luminance of `color` only, then scale each channel by `new_luma/old_luma`
+pub fn tonemap_luminance_only(
+    color: Color<AcesCg, Scene>,
+    params: PerceptualTonemapperParams,
+) -> Color<AcesCg, Display> {
+    let luma = acescg_luminance(color);
+
+    // Nothing to scale at (or below) black, and it would divide by zero
+    if luma <= f32::EPSILON {
+        return color::acescg(0.0, 0.0, 0.0);
+    }
+
+    // Run an achromatic color of the same luminance through the tonemapper
+    let gray = color::acescg::<Scene>(luma, luma, luma);
+    let tonemapped_gray: Color<AcesCg, Display> =
+        PerceptualTonemapper::tonemap(gray, params).convert();
+    let tonemapped_luma = acescg_luminance(tonemapped_gray);
+
+    (color * (tonemapped_luma / luma)).cast_state()
+}
+
+/// Apply `curve` to each channel of `color` separately
+fn tonemap_channels(
+    color: Color<AcesCg, Scene>,
+    curve: impl Fn(f32) -> f32,
+) -> Color<AcesCg, Display> {
+    color::acescg(curve(color.r), curve(color.g), curve(color.b))
+}
+
+pub(crate) fn reinhard(x: f32) -> f32 {
+    let x = x.max(0.0);
+    x / (1.0 + x)
+}
+
+/// See https://knarkowicz.wordpress.com/2016/01/06/aces-filmic-tone-mapping-curve/
+pub(crate) fn aces_filmic(x: f32) -> f32 {
+    // The fit expects the exposure of the original ACES curve to be already applied
+    let x = x.max(0.0) * 0.6;
+    let (a, b, c, d, e) = (2.51, 0.03, 2.43, 0.59, 0.14);
+    ((x * (a * x + b)) / (x * (c * x + d) + e)).clamp(0.0, 1.0)
+}
+
+/// Go from ACEScg HDR to SDR using the given tonemapper
+/// `curve` is only used by `TonemapKind::Curve`
+pub fn tonemap(
+    color: Color<AcesCg, Scene>,
+    kind: TonemapKind,
+    curve: &ToneCurve,
+) -> Color<AcesCg, Display> {
+    let params = PerceptualTonemapperParams::default();
+    match kind {
+        TonemapKind::Perceptual => {
+            // It turns negative values into NaNs, or into colors outside of the gamut
+            let positive = color::acescg(color.r.max(0.0), color.g.max(0.0), color.b.max(0.0));
+            PerceptualTonemapper::tonemap(positive, params).convert()
+        }
+        TonemapKind::LuminanceOnly => tonemap_luminance_only(color, params),
+        TonemapKind::Reinhard => tonemap_channels(color, reinhard),
+        TonemapKind::AcesFilmic => tonemap_channels(color, aces_filmic),
+        TonemapKind::Clamp => tonemap_channels(color, |x| x.clamp(0.0, 1.0)),
+        TonemapKind::Curve => tonemap_channels(color, |x| curve.apply(x)),
+    }
+}
+
+/// Every intermediate value of the display conversion of a single pixel
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PipelineStages {
+    /// Scene linear ACEScg, as rendered
+    pub linear: [f32; 3],
+    pub white_balanced: [f32; 3],
+    pub exposed: [f32; 3],
+    /// Display linear ACEScg after tonemapping and gamma,
+    /// None when a LUT or the false color view replace them
+    pub tonemapped: Option<[f32; 3]>,
+    /// Display linear ACEScg brought inside the gamut of the display, None like `tonemapped`
+    pub gamut_mapped: Option<[f32; 3]>,
+    /// Encoded for the display, after the LUT if any
+    pub encoded: [f32; 3],
+    /// Some encoded values are above 1, and had to be clipped
+    pub clipped: bool,
+    /// The tonemapped color has negative sRGB components
+    pub out_of_gamut: bool,
+    /// What is shown, between 0 and 1, before the quantization to 8bit
+    pub display: [f32; 4],
+    pub rgba: [u8; 4],
+}
+
+/// Entries along each axis of the baked tonemap LUT, enough for it to be within an 8bit
+/// step of the full conversion
+const BAKED_LUT_SIZE: usize = 96;
+/// Range of exposed values covered by the baked LUT, anything outside is clamped.
+/// `tonemap` clamps negative values to 0 too, for the perceptual tonemapper.
+const BAKED_LUT_RANGE: (f32, f32) = (0.0, 256.0);
+/// The baked LUT is indexed by `asinh(x / BAKED_LUT_SOFTNESS)`, which is linear in the
+/// shadows and logarithmic in the highlights
+const BAKED_LUT_SOFTNESS: f32 = 0.005;
+
+/// The perceptual tonemap, baked the first time a big image needs it
+static BAKED_TONEMAP: OnceLock<Lut3d> = OnceLock::new();
+
+pub(crate) fn baked_lut_shaper(channel: f32) -> f32 {
+    (channel / BAKED_LUT_SOFTNESS).asinh()
+}
+
+/// The perceptual tonemap, indexed by `baked_lut_shaper` of the exposed values
+fn baked_perceptual_tonemap() -> &'static Lut3d {
+    BAKED_TONEMAP.get_or_init(|| {
+        let (min, max) = BAKED_LUT_RANGE;
+        let (min, max) = (baked_lut_shaper(min), baked_lut_shaper(max));
+        Lut3d::bake(
+            "baked tonemap",
+            BAKED_LUT_SIZE,
+            [min; 3],
+            [max; 3],
+            |shaped| {
+                let [r, g, b] = shaped.map(|channel| channel.sinh() * BAKED_LUT_SOFTNESS);
+                let tonemapped = tonemap(
+                    color::acescg(r, g, b),
+                    TonemapKind::Perceptual,
+                    &ToneCurve::default(),
+                );
+                [tonemapped.r, tonemapped.g, tonemapped.b]
+            },
+        )
+    })
+}
+
+/// The display conversion, with whatever can be computed once per image already done
+pub struct DisplayPipeline<'a> {
+    settings: &'a DisplaySettings,
+    white_balance: Option<[[f32; 3]; 3]>,
+    exposure_scale: f32,
+    inverse_gamma: f32,
+    /// Perceptual tonemapping sampled once, which is much cheaper than going through
+    /// the ICtCp conversions of the tonemapper for every pixel of a big image
+    baked_tonemap: Option<&'static Lut3d>,
+}
+
+impl<'a> DisplayPipeline<'a> {
+    pub fn new(settings: &'a DisplaySettings) -> Self {
+        DisplayPipeline {
+            settings,
+            white_balance: (!settings.white_balance.is_neutral())
+                .then(|| settings.white_balance.adaptation_matrix()),
+            exposure_scale: 2.0_f32.powf(settings.exposure),
+            inverse_gamma: 1.0 / settings.gamma,
+            baked_tonemap: None,
+        }
+    }
+
+    /// Same as `new`, but tonemaps through a baked LUT when it's worth it for
+    /// `pixel_count` pixels, at the cost of a small interpolation error
+    pub fn baked(settings: &'a DisplaySettings, pixel_count: usize) -> Self {
+        let mut pipeline = DisplayPipeline::new(settings);
+
+        // Baking the LUT costs as much as converting its number of entries, but it's
+        // only done once
+        let worth_it = pixel_count > BAKED_LUT_SIZE.pow(3) / 4;
+        // The other tonemappers are cheap enough, and their kinks or unbounded highlights
+        // would be hard to interpolate
+        let uses_perceptual = settings.tonemap == TonemapKind::Perceptual
+            && settings.view_mode != ViewMode::FalseColor
+            && !(settings.lut.is_some() && settings.lut_mode == LutMode::InsteadOfTonemap);
+
+        if worth_it && uses_perceptual {
+            pipeline.baked_tonemap = Some(baked_perceptual_tonemap());
+        }
+
+        pipeline
+    }
+
+    /// The baked perceptual tonemap, without the gamma, indexed by `baked_lut_shaper` of
+    /// the exposed values
+    pub(crate) fn baked_tonemap(&self) -> Option<&Lut3d> {
+        self.baked_tonemap
+    }
+
+    /// Convert the scene linear RGBA pixel at `x`, `y`, keeping track of all the steps.
+    /// The position is only used for dithering.
+    pub fn stages(&self, f32_pixel: &[f32], x: usize, y: usize) -> PipelineStages {
+        let settings = self.settings;
+        let rgb = |color: Color<AcesCg, Scene>| [color.r, color.g, color.b];
+
+        // For the sake of simplicity and saving memory, our array is composed of f32
+        // instead of colostodian Color structs. Here we recreate the colstodian struct
+        // on the fly so we can do the conversion to 8bit and go to display referred
+        // by applying default a SDR tone mapping
+        let rendered_color = colstodian::color::acescg(f32_pixel[0], f32_pixel[1], f32_pixel[2]);
+        let balanced = match &self.white_balance {
+            Some(matrix) => adapt(rendered_color, matrix),
+            None => rendered_color,
+        };
+        let exposed = balanced * self.exposure_scale;
+
+        let (tonemapped, gamut_mapped, encoded) = match settings.view_mode {
+            ViewMode::FalseColor => (None, None, false_color(acescg_luminance(exposed))),
+            _ => self.tonemap_and_encode(exposed),
+        };
+        let alpha = (f32_pixel[3] * settings.global_alpha).clamp(0.0, 1.0);
+
+        let clipped = encoded.iter().any(|&channel| channel > 1.0);
+        // Checked before the gamut mapping, to see the colors it had to change
+        let out_of_gamut = tonemapped.is_some_and(|[r, g, b]| {
+            let srgb = color::acescg::<Display>(r, g, b).convert::<LinearSrgb>();
+            // A little tolerance, for the rounding errors of the conversions
+            [srgb.r, srgb.g, srgb.b]
+                .iter()
+                .any(|&channel| channel < -1e-4)
+        });
+
+        // Isolated channels are shown opaque, otherwise a transparent image would hide them
+        let isolated_channel = match settings.view_mode {
+            ViewMode::Normal | ViewMode::FalseColor | ViewMode::Bracket => None,
+            ViewMode::Red => Some(encoded[0]),
+            ViewMode::Green => Some(encoded[1]),
+            ViewMode::Blue => Some(encoded[2]),
+            ViewMode::Alpha => Some(alpha),
+            ViewMode::Luma => Some(0.2126 * encoded[0] + 0.7152 * encoded[1] + 0.0722 * encoded[2]),
+            ViewMode::NonFinite => {
+                Some(0.5 * (0.2126 * encoded[0] + 0.7152 * encoded[1] + 0.0722 * encoded[2]))
+            }
+        };
+        let non_finite = settings.view_mode == ViewMode::NonFinite
+            && f32_pixel.iter().any(|channel| !channel.is_finite());
+        let (shown, alpha) = match isolated_channel {
+            Some(value) => ([value; 3], 1.0),
+            None if settings.color_blindness != ColorBlindness::None => {
+                let display = settings.display;
+                let linear = encoded.map(|channel| display.decode_channel(channel.clamp(0.0, 1.0)));
+                let simulated = settings.color_blindness.simulate(linear);
+                (
+                    simulated.map(|channel| display.encode_channel(channel.max(0.0))),
+                    alpha,
+                )
+            }
+            None => (encoded, alpha),
+        };
+
+        let [r, g, b] = match (settings.clipping_warning, clipped, out_of_gamut) {
+            _ if non_finite => NON_FINITE_COLOR.map(|channel| channel as f32 / 255.0),
+            // Nothing else is colored in that view, for the NaNs to stand out
+            _ if settings.view_mode == ViewMode::NonFinite => {
+                shown.map(|channel| channel.clamp(0.0, 1.0))
+            }
+            (true, true, _) => CLIPPING_WARNING_COLOR.map(|channel| channel as f32 / 255.0),
+            (true, _, true) => GAMUT_WARNING_COLOR.map(|channel| channel as f32 / 255.0),
+            _ => shown.map(|channel| channel.clamp(0.0, 1.0)),
+        };
+
+        // Convert to 8bit
+        let dither = settings.dithering.offset(x, y) / 255.0;
+        let rgb8 = [r, g, b].map(|channel| to_u8(channel + dither));
+
+        PipelineStages {
+            linear: rgb(rendered_color),
+            white_balanced: rgb(balanced),
+            exposed: rgb(exposed),
+            tonemapped,
+            gamut_mapped,
+            encoded,
+            clipped,
+            out_of_gamut,
+            display: [r, g, b, alpha],
+            rgba: [rgb8[0], rgb8[1], rgb8[2], (255.0 * alpha) as u8],
+        }
+    }
+
+    fn apply_gamma(&self, tonemapped: Color<AcesCg, Display>) -> Color<AcesCg, Display> {
+        if self.settings.gamma == 1.0 {
+            return tonemapped;
+        }
+
+        let adjust = |channel: f32| channel.max(0.0).powf(self.inverse_gamma);
+        color::acescg(
+            adjust(tonemapped.r),
+            adjust(tonemapped.g),
+            adjust(tonemapped.b),
+        )
+    }
+
+    /// Go to the encoded display values, through the tonemapper and/or the LUT.
+    /// Returns the tonemapped, gamut mapped and encoded values.
+    fn tonemap_and_encode(
+        &self,
+        exposed: Color<AcesCg, Scene>,
+    ) -> (Option<[f32; 3]>, Option<[f32; 3]>, [f32; 3]) {
+        let settings = self.settings;
+        let rgb = |color: Color<AcesCg, Display>| [color.r, color.g, color.b];
+
+        match &settings.lut {
+            Some(lut) if settings.lut_mode == LutMode::InsteadOfTonemap => {
+                (None, None, lut.apply([exposed.r, exposed.g, exposed.b]))
+            }
+            lut => {
+                let tonemapped = match &self.baked_tonemap {
+                    Some(baked) => {
+                        let shaped = [exposed.r, exposed.g, exposed.b].map(baked_lut_shaper);
+                        let [r, g, b] = baked.apply(shaped);
+                        self.apply_gamma(color::acescg(r, g, b))
+                    }
+                    None => {
+                        self.apply_gamma(tonemap(exposed, settings.tonemap, &settings.tone_curve))
+                    }
+                };
+
+                let gamut_mapped = settings
+                    .display
+                    .map_gamut(tonemapped, settings.gamut_mapping);
+
+                // Encode for the display, so we're ready to display or write to an image
+                let encoded = match &settings.icc_profile {
+                    Some(profile) => {
+                        let xyz = gamut_mapped.convert::<CieXYZ>();
+                        profile.encode([xyz.x, xyz.y, xyz.z])
+                    }
+                    None => settings.display.encode(gamut_mapped),
+                };
+                let encoded = match lut {
+                    Some(lut) => lut.apply(encoded),
+                    None => encoded,
+                };
+                (Some(rgb(tonemapped)), Some(rgb(gamut_mapped)), encoded)
+            }
+        }
+    }
+}
+
+/// Do the scene linear to display conversion of a whole image
+pub fn scene_to_display(framebuffer: &Framebuffer, settings: &DisplaySettings) -> Vec<u8> {
+    scene_to_display_tile(framebuffer, (0, 0), settings)
+}
+
+/// Same as `scene_to_display`, for a `tile` whose top left corner is at `origin` in the
+/// image, which keeps the dithering pattern continuous across the tiles
+pub fn scene_to_display_tile(
+    tile: &Framebuffer,
+    origin: (usize, usize),
+    settings: &DisplaySettings,
+) -> Vec<u8> {
+    match LanePipeline::new(settings, tile.width() * tile.height()) {
+        Some(lanes) => lanes.convert(tile.pixels()),
+        None => convert_pixels(tile, origin, settings, |stages| stages.rgba),
+    }
+}
+
+/// Same as `scene_to_display`, but quantized to 16 bit instead, without dithering
+pub fn scene_to_display_16(framebuffer: &Framebuffer, settings: &DisplaySettings) -> Vec<u16> {
+    convert_pixels(framebuffer, (0, 0), settings, |stages| {
+        stages
+            .display
+            .map(|channel| (channel * 65535.0).round() as u16)
+    })
+}
+
+/// Cineon code values of black and of the reference white, out of 1023
+pub const CINEON_BLACK: u16 = 95;
+pub const CINEON_WHITE: u16 = 685;
+
+/// Encode a display linear value to a 10 bit Cineon printing density code,
+/// with the usual 0.002 density per code value and 0.6 negative gamma
+pub fn cineon_encode(linear: f32) -> u16 {
+    let codes_per_decade = 0.6 / 0.002;
+    let black_offset = 10f32.powf((CINEON_BLACK as f32 - CINEON_WHITE as f32) / codes_per_decade);
+    let density = (linear.max(0.0) * (1.0 - black_offset) + black_offset).log10();
+    (CINEON_WHITE as f32 + codes_per_decade * density)
+        .round()
+        .clamp(0.0, 1023.0) as u16
+}
+
+/// Same as `scene_to_display`, but log encoded to 10 bit Cineon code values, for DPX files.
+/// The shown values are decoded with the transfer function of `settings.display` first.
+pub fn scene_to_display_log10(framebuffer: &Framebuffer, settings: &DisplaySettings) -> Vec<u16> {
+    let display = settings.display;
+    convert_pixels(framebuffer, (0, 0), settings, |stages| {
+        let [r, g, b, a] = stages.display;
+        let [r, g, b] = [r, g, b].map(|channel| cineon_encode(display.decode_channel(channel)));
+        [r, g, b, (a * 1023.0).round() as u16]
+    })
+}
+
+fn convert_pixels<T: Copy + Default + Send>(
+    framebuffer: &Framebuffer,
+    (left, top): (usize, usize),
+    settings: &DisplaySettings,
+    quantize: impl Fn(PipelineStages) -> [T; 4] + Sync,
+) -> Vec<T> {
+    let linear_render_buffer = framebuffer.pixels();
+    let width = framebuffer.width();
+    let mut display_buffer = vec![T::default(); linear_render_buffer.len()];
+    let pipeline = DisplayPipeline::baked(settings, linear_render_buffer.len() / 4);
+
+    let convert_pixel = |(index, (f32_pixel, display_pixel)): (usize, (&[f32], &mut [T]))| {
+        let (x, y) = (left + index % width, top + index / width);
+        display_pixel.copy_from_slice(&quantize(pipeline.stages(f32_pixel, x, y)));
+    };
+
+    #[cfg(feature = "parallel")]
+    linear_render_buffer
+        .par_chunks_exact(4)
+        .zip(display_buffer.par_chunks_exact_mut(4))
+        .enumerate()
+        .for_each(convert_pixel);
+
+    #[cfg(not(feature = "parallel"))]
+    std::iter::zip(
+        linear_render_buffer.chunks_exact(4),
+        display_buffer.chunks_exact_mut(4),
+    )
+    .enumerate()
+    .for_each(convert_pixel);
+
+    display_buffer
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Ratios of the red and green channels to the blue one
+    fn ratios(color: Color<AcesCg, Display>) -> [f32; 2] {
+        [color.r / color.b, color.g / color.b]
+    }
+
+    #[test]
+    fn luminance_only_keeps_the_hue_of_highlights() {
+        let highlight = color::acescg::<Scene>(8.0, 2.0, 0.5);
+        let curve = ToneCurve::default();
+        let expected = [16.0, 4.0];
+
+        let luminance_only = tonemap(highlight, TonemapKind::LuminanceOnly, &curve);
+        for (ratio, expected) in ratios(luminance_only).into_iter().zip(expected) {
+            assert!((ratio - expected).abs() < 1e-3 * expected, "{ratio}");
+        }
+
+        // Tonemapping each channel compresses the brightest one the most
+        for kind in [TonemapKind::Reinhard, TonemapKind::AcesFilmic] {
+            let per_channel = tonemap(highlight, kind, &curve);
+            let [red, green] = ratios(per_channel);
+            assert!(red < 0.5 * expected[0], "{kind}: {red}");
+            assert!(green < expected[1], "{kind}: {green}");
+        }
+    }
+
+    #[test]
+    fn luminance_only_keeps_black() {
+        let black = tonemap(
+            color::acescg(0.0, 0.0, 0.0),
+            TonemapKind::LuminanceOnly,
+            &ToneCurve::default(),
+        );
+        assert_eq!([black.r, black.g, black.b], [0.0; 3]);
+    }
+}
@@ -0,0 +1,90 @@
+//! What happened during the session (renders, saves, loads, warnings...), for the
+//! console panel. Everything logged here also goes to stderr, as before. The log
+//! is global, so that the workers and the modules without access to the
+//! application state can report too.
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Only the last ones are kept
+const MAX_ENTRIES: usize = 500;
+
+static ENTRIES: Mutex<Vec<Entry>> = Mutex::new(Vec::new());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(f, "{name}")
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Entry {
+    /// Seconds since midnight, UTC (there's no time zone database to build with)
+    pub time: u64,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl std::fmt::Display for Entry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (hours, minutes, seconds) = (self.time / 3600, self.time / 60 % 60, self.time % 60);
+        write!(
+            f,
+            "{hours:02}:{minutes:02}:{seconds:02} {:<7} {}",
+            self.severity, self.message
+        )
+    }
+}
+
+pub fn info(message: impl Into<String>) {
+    log(Severity::Info, message.into());
+}
+
+pub fn warning(message: impl Into<String>) {
+    log(Severity::Warning, message.into());
+}
+
+pub fn error(message: impl Into<String>) {
+    log(Severity::Error, message.into());
+}
+
+fn log(severity: Severity, message: String) {
+    match severity {
+        Severity::Warning => eprintln!("Warning: {message}"),
+        Severity::Info | Severity::Error => eprintln!("{message}"),
+    }
+
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since_epoch| since_epoch.as_secs() % 86400);
+    // A panic while holding the lock can only have left a complete list behind
+    let mut entries = ENTRIES.lock().unwrap_or_else(|e| e.into_inner());
+    if entries.len() == MAX_ENTRIES {
+        entries.remove(0);
+    }
+    entries.push(Entry {
+        time,
+        severity,
+        message,
+    });
+}
+
+/// Everything logged so far, oldest first
+pub fn entries() -> Vec<Entry> {
+    ENTRIES.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+pub fn clear() {
+    ENTRIES.lock().unwrap_or_else(|e| e.into_inner()).clear();
+}
@@ -0,0 +1,267 @@
+//! Editor of the custom tone curve: clicking adds a point, dragging moves it, and a
+//! right click removes it. Drawn with quads, as iced is built without its canvas.
+use iced_native::event::{self, Event};
+use iced_native::layout::{self, Layout};
+use iced_native::mouse;
+use iced_native::renderer;
+use iced_native::widget::{tree, Tree};
+use iced_native::{
+    Clipboard, Color, Element, Length, Point, Rectangle, Shell, Size, Vector, Widget,
+};
+
+use crate::tone_curve::{ToneCurve, MAX_CURVE_POINTS};
+
+/// Width and height of the square editor
+const SIZE: f32 = 256.0;
+/// How close to a point the cursor has to be to grab it, in logical pixels
+const GRAB_RADIUS: f32 = 8.0;
+const POINT_SIZE: f32 = 8.0;
+const CURVE_WIDTH: f32 = 2.0;
+const BACKGROUND_COLOR: Color = Color::from_rgb(0.1, 0.1, 0.1);
+const GRID_COLOR: Color = Color::from_rgb(0.25, 0.25, 0.25);
+const MID_GRAY_COLOR: Color = Color::from_rgb(0.4, 0.4, 0.4);
+const CURVE_COLOR: Color = Color::from_rgb(0.9, 0.9, 0.9);
+const POINT_COLOR: Color = Color::from_rgb(1.0, 0.75, 0.0);
+
+/// A change to the curve, with positions on the axes of the curve, from 0 to 1
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CurveEdit {
+    Added([f32; 2]),
+    Moved { index: usize, position: [f32; 2] },
+    Removed(usize),
+}
+
+#[derive(Default)]
+struct State {
+    // Where the left button went down, and the point being dragged since then
+    press_position: Option<Point>,
+    dragged_point: Option<usize>,
+}
+
+pub struct CurveEditor<'a, Message> {
+    curve: &'a ToneCurve,
+    on_edit: Box<dyn Fn(CurveEdit) -> Message + 'a>,
+}
+
+impl<'a, Message> CurveEditor<'a, Message> {
+    pub fn new(curve: &'a ToneCurve, on_edit: impl Fn(CurveEdit) -> Message + 'a) -> Self {
+        CurveEditor {
+            curve,
+            on_edit: Box::new(on_edit),
+        }
+    }
+
+    fn to_screen(bounds: Rectangle, [x, y]: [f32; 2]) -> Point {
+        Point::new(
+            bounds.x + x * bounds.width,
+            bounds.y + (1.0 - y) * bounds.height,
+        )
+    }
+
+    fn to_curve(bounds: Rectangle, point: Point) -> [f32; 2] {
+        [
+            ((point.x - bounds.x) / bounds.width).clamp(0.0, 1.0),
+            (1.0 - (point.y - bounds.y) / bounds.height).clamp(0.0, 1.0),
+        ]
+    }
+
+    /// The point of the curve under `position`, if any
+    fn grab(&self, bounds: Rectangle, position: Point) -> Option<usize> {
+        self.curve
+            .points
+            .iter()
+            .map(|&point| Self::to_screen(bounds, point).distance(position))
+            .enumerate()
+            .filter(|(_, distance)| *distance <= GRAB_RADIUS)
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(index, _)| index)
+    }
+}
+
+impl<'a, Message, Renderer> Widget<Message, Renderer> for CurveEditor<'a, Message>
+where
+    Renderer: renderer::Renderer,
+{
+    fn tag(&self) -> tree::Tag {
+        tree::Tag::of::<State>()
+    }
+
+    fn state(&self) -> tree::State {
+        tree::State::new(State::default())
+    }
+
+    fn width(&self) -> Length {
+        Length::Fixed(SIZE)
+    }
+
+    fn height(&self) -> Length {
+        Length::Fixed(SIZE)
+    }
+
+    fn layout(&self, _renderer: &Renderer, limits: &layout::Limits) -> layout::Node {
+        let limits = limits
+            .width(Length::Fixed(SIZE))
+            .height(Length::Fixed(SIZE));
+        layout::Node::new(limits.resolve(Size::ZERO))
+    }
+
+    fn on_event(
+        &mut self,
+        tree: &mut Tree,
+        event: Event,
+        layout: Layout<'_>,
+        cursor_position: Point,
+        _renderer: &Renderer,
+        _clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message>,
+    ) -> event::Status {
+        let bounds = layout.bounds();
+        let state = tree.state.downcast_mut::<State>();
+        let is_over = bounds.contains(cursor_position);
+
+        let edit = match event {
+            Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) if is_over => {
+                state.press_position = Some(cursor_position);
+                state.dragged_point = self.grab(bounds, cursor_position);
+                if state.dragged_point.is_some() || self.curve.points.len() >= MAX_CURVE_POINTS {
+                    return event::Status::Captured;
+                }
+                CurveEdit::Added(Self::to_curve(bounds, cursor_position))
+            }
+            Event::Mouse(mouse::Event::CursorMoved { .. }) => {
+                let Some(press_position) = state.press_position else {
+                    return event::Status::Ignored;
+                };
+                // A point added by the press is only there once the curve was updated
+                if state.dragged_point.is_none() {
+                    state.dragged_point = self.grab(bounds, press_position);
+                }
+                let Some(index) = state.dragged_point else {
+                    return event::Status::Ignored;
+                };
+                CurveEdit::Moved {
+                    index,
+                    position: Self::to_curve(bounds, cursor_position),
+                }
+            }
+            Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)) => {
+                state.press_position = None;
+                state.dragged_point = None;
+                return event::Status::Ignored;
+            }
+            Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Right)) if is_over => {
+                match self.grab(bounds, cursor_position) {
+                    Some(index) => CurveEdit::Removed(index),
+                    None => return event::Status::Ignored,
+                }
+            }
+            _ => return event::Status::Ignored,
+        };
+
+        shell.publish((self.on_edit)(edit));
+        event::Status::Captured
+    }
+
+    fn draw(
+        &self,
+        _tree: &Tree,
+        renderer: &mut Renderer,
+        _theme: &Renderer::Theme,
+        _style: &renderer::Style,
+        layout: Layout<'_>,
+        _cursor_position: Point,
+        _viewport: &Rectangle,
+    ) {
+        let bounds = layout.bounds();
+        let mut fill = |bounds: Rectangle, color: Color, radius: f32| {
+            let quad = renderer::Quad {
+                bounds,
+                border_radius: radius.into(),
+                border_width: 0.0,
+                border_color: Color::TRANSPARENT,
+            };
+            renderer.fill_quad(quad, color);
+        };
+
+        fill(bounds, BACKGROUND_COLOR, 0.0);
+        for quarter in 1..4 {
+            let offset = quarter as f32 / 4.0;
+            let vertical = Rectangle::new(
+                Point::new(bounds.x + offset * bounds.width, bounds.y),
+                Size::new(1.0, bounds.height),
+            );
+            let horizontal = Rectangle::new(
+                Point::new(bounds.x, bounds.y + offset * bounds.height),
+                Size::new(bounds.width, 1.0),
+            );
+            fill(vertical, GRID_COLOR, 0.0);
+            fill(horizontal, GRID_COLOR, 0.0);
+        }
+        let mid_gray = Rectangle::new(
+            Point::new(
+                bounds.x + ToneCurve::input_position(0.18) * bounds.width,
+                bounds.y,
+            ),
+            Size::new(1.0, bounds.height),
+        );
+        fill(mid_gray, MID_GRAY_COLOR, 0.0);
+
+        // A column of the curve for every pixel, tall enough to join the next one
+        let columns = bounds.width.max(1.0) as usize;
+        let heights: Vec<f32> = (0..=columns)
+            .map(|column| {
+                let x = column as f32 / columns as f32;
+                Self::to_screen(bounds, [x, self.curve.sample(x)]).y
+            })
+            .collect();
+        for (column, pair) in heights.windows(2).enumerate() {
+            let (top, bottom) = (pair[0].min(pair[1]), pair[0].max(pair[1]));
+            let segment = Rectangle::new(
+                Point::new(bounds.x + column as f32, top - CURVE_WIDTH / 2.0),
+                Size::new(CURVE_WIDTH, bottom - top + CURVE_WIDTH),
+            );
+            fill(segment, CURVE_COLOR, 0.0);
+        }
+
+        for &point in &self.curve.points {
+            let center = Self::to_screen(bounds, point);
+            let corner = center - Vector::new(POINT_SIZE / 2.0, POINT_SIZE / 2.0);
+            fill(
+                Rectangle::new(corner, Size::new(POINT_SIZE, POINT_SIZE)),
+                POINT_COLOR,
+                POINT_SIZE / 2.0,
+            );
+        }
+    }
+
+    fn mouse_interaction(
+        &self,
+        tree: &Tree,
+        layout: Layout<'_>,
+        cursor_position: Point,
+        _viewport: &Rectangle,
+        _renderer: &Renderer,
+    ) -> mouse::Interaction {
+        let state = tree.state.downcast_ref::<State>();
+
+        if state.dragged_point.is_some() {
+            mouse::Interaction::Grabbing
+        } else if self.grab(layout.bounds(), cursor_position).is_some() {
+            mouse::Interaction::Grab
+        } else if layout.bounds().contains(cursor_position) {
+            mouse::Interaction::Crosshair
+        } else {
+            mouse::Interaction::Idle
+        }
+    }
+}
+
+impl<'a, Message, Renderer> From<CurveEditor<'a, Message>> for Element<'a, Message, Renderer>
+where
+    Message: 'a,
+    Renderer: renderer::Renderer + 'a,
+{
+    fn from(editor: CurveEditor<'a, Message>) -> Self {
+        Element::new(editor)
+    }
+}
@@ -0,0 +1,80 @@
+//! Per pixel difference between the render and a reference image, shown as a heatmap
+//! to check that a change to the render code didn't change the image (or by how much)
+
+/// Colors of the heatmap, evenly spaced from no difference to a scaled difference of 1
+const HEAT_STOPS: [[f32; 3]; 5] = [
+    [0.0, 0.0, 0.0],
+    [0.25, 0.0, 0.6],
+    [0.9, 0.1, 0.2],
+    [1.0, 0.75, 0.0],
+    [1.0, 1.0, 1.0],
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DifferenceStats {
+    /// Largest difference of any channel, in scene linear values, ignoring NaNs
+    pub max: f32,
+    /// Of the per pixel differences which aren't NaN
+    pub mean: f32,
+    /// How many pixels differ at all, NaNs included
+    pub differing_pixels: usize,
+}
+
+/// Difference of each pixel of the RGBA `current` image to `reference`:
+/// the largest absolute difference of its channels, NaN if any of them is
+pub fn per_pixel(current: &[f32], reference: &[f32]) -> Vec<f32> {
+    current
+        .chunks_exact(4)
+        .zip(reference.chunks_exact(4))
+        .map(|(a, b)| {
+            a.iter()
+                .zip(b)
+                .map(|(a, b)| (a - b).abs())
+                .fold(0.0, |max, difference| {
+                    if difference.is_nan() || difference > max {
+                        difference
+                    } else {
+                        max
+                    }
+                })
+        })
+        .collect()
+}
+
+impl DifferenceStats {
+    pub fn new(differences: &[f32]) -> DifferenceStats {
+        let numbers = || differences.iter().copied().filter(|d| !d.is_nan());
+        let sum: f64 = numbers().map(|d| d as f64).sum();
+        DifferenceStats {
+            max: numbers().fold(0.0, f32::max),
+            mean: (sum / numbers().count().max(1) as f64) as f32,
+            differing_pixels: differences.iter().filter(|&&d| d != 0.0).count(),
+        }
+    }
+}
+
+/// RGBA heatmap of the `differences`, multiplied by `gain` so that small ones show
+pub fn heatmap(differences: &[f32], gain: f32) -> Vec<u8> {
+    let last = (HEAT_STOPS.len() - 1) as f32;
+    differences
+        .iter()
+        .flat_map(|&difference| {
+            // NaNs compare as different to everything, show them as the hottest
+            let scaled = if difference.is_nan() {
+                last
+            } else {
+                (difference * gain).clamp(0.0, 1.0) * last
+            };
+            let index = (scaled as usize).min(HEAT_STOPS.len() - 2);
+            let t = scaled - index as f32;
+            let (from, to) = (HEAT_STOPS[index], HEAT_STOPS[index + 1]);
+            [0, 1, 2]
+                .map(|channel| {
+                    let level = from[channel] + (to[channel] - from[channel]) * t;
+                    (level * 255.0).round() as u8
+                })
+                .into_iter()
+                .chain([255])
+        })
+        .collect()
+}
@@ -0,0 +1,314 @@
+//! The 8bit display conversion of `scene_to_display`, eight pixels at a time, for the
+//! settings most images are viewed with. The channels of the pixels are split into arrays
+//! of eight lanes, so that the matrices and tonemapping curves are plain arithmetic on
+//! those arrays, which the compiler turns into SSE or NEON instructions. The transfer
+//! function and the rounding to 8bit are replaced by a search among the display linear
+//! values where each code value starts, which gives the same codes.
+//! Everything else goes through `DisplayPipeline::stages`, one pixel at a time.
+use colstodian::spaces::{DisplayP3, EncodedDisplayP3, EncodedSrgb, LinearSrgb};
+use colstodian::{color, Color, Display, Scene};
+
+use std::sync::OnceLock;
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use crate::color_blindness::ColorBlindness;
+use crate::color_pipeline::{
+    aces_filmic, baked_lut_shaper, reinhard, to_u8, DisplayPipeline, DisplaySettings,
+    DisplayTransform, Dithering, GamutMapping, TonemapKind, ViewMode,
+};
+use crate::white_balance::{adapt, Matrix};
+
+/// Pixels converted together
+pub const LANES: usize = 8;
+/// Pixels converted by each task when running on all cores
+#[cfg(feature = "parallel")]
+const PIXELS_PER_TASK: usize = 4096;
+
+type Lanes = [f32; LANES];
+
+/// Display linear values where each 8bit code from 1 to 255 starts, for each display
+static CODE_THRESHOLDS: [OnceLock<[f32; 255]>; 3] =
+    [OnceLock::new(), OnceLock::new(), OnceLock::new()];
+
+pub struct LanePipeline<'a> {
+    /// Holds the baked perceptual tonemap, when that's the tonemapper
+    pipeline: DisplayPipeline<'a>,
+    white_balance: Option<Matrix>,
+    exposure_scale: f32,
+    tonemap: TonemapKind,
+    /// From display linear ACEScg to the linear primaries of the display
+    display_matrix: Matrix,
+    thresholds: &'static [f32; 255],
+    global_alpha: f32,
+}
+
+impl<'a> LanePipeline<'a> {
+    /// The pipeline for `pixel_count` pixels with `settings`, if it handles all of them
+    pub fn new(settings: &'a DisplaySettings, pixel_count: usize) -> Option<Self> {
+        let pipeline = DisplayPipeline::baked(settings, pixel_count);
+        let supported = matches!(settings.view_mode, ViewMode::Normal | ViewMode::Bracket)
+            && settings.lut.is_none()
+            && settings.icc_profile.is_none()
+            && settings.color_blindness == ColorBlindness::None
+            && !settings.clipping_warning
+            && settings.gamut_mapping == GamutMapping::Clip
+            && settings.dithering == Dithering::None
+            && settings.gamma == 1.0
+            && match settings.tonemap {
+                TonemapKind::Perceptual => pipeline.baked_tonemap().is_some(),
+                TonemapKind::Reinhard | TonemapKind::AcesFilmic | TonemapKind::Clamp => true,
+                TonemapKind::LuminanceOnly | TonemapKind::Curve => false,
+            };
+        if !supported {
+            return None;
+        }
+
+        // Both are linear, so their columns are where they take the primaries
+        let columns = |transform: &dyn Fn([f32; 3]) -> [f32; 3]| {
+            let [r, g, b] = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]].map(transform);
+            [0, 1, 2].map(|row| [r[row], g[row], b[row]])
+        };
+        let white_balance = (!settings.white_balance.is_neutral()).then(|| {
+            let matrix = settings.white_balance.adaptation_matrix();
+            columns(&|[r, g, b]| {
+                let adapted = adapt(color::acescg::<Scene>(r, g, b), &matrix);
+                [adapted.r, adapted.g, adapted.b]
+            })
+        });
+        let display_matrix = columns(&|[r, g, b]| {
+            let primary = color::acescg::<Display>(r, g, b);
+            match settings.display {
+                DisplayTransform::Srgb | DisplayTransform::Rec709 => {
+                    let linear = primary.convert::<LinearSrgb>();
+                    [linear.r, linear.g, linear.b]
+                }
+                DisplayTransform::DisplayP3 => {
+                    let linear = primary.convert::<DisplayP3>();
+                    [linear.r, linear.g, linear.b]
+                }
+            }
+        });
+
+        Some(LanePipeline {
+            pipeline,
+            white_balance,
+            exposure_scale: 2.0_f32.powf(settings.exposure),
+            tonemap: settings.tonemap,
+            display_matrix,
+            thresholds: code_thresholds(settings.display),
+            global_alpha: settings.global_alpha,
+        })
+    }
+
+    /// Convert the scene linear RGBA `pixels`, on all cores with the `parallel` feature
+    pub fn convert(&self, pixels: &[f32]) -> Vec<u8> {
+        let mut display_buffer = vec![0; pixels.len()];
+
+        #[cfg(feature = "parallel")]
+        pixels
+            .par_chunks(PIXELS_PER_TASK * 4)
+            .zip(display_buffer.par_chunks_mut(PIXELS_PER_TASK * 4))
+            .for_each(|(pixels, display)| self.convert_into(pixels, display));
+
+        #[cfg(not(feature = "parallel"))]
+        self.convert_into(pixels, &mut display_buffer);
+
+        display_buffer
+    }
+
+    /// Convert the scene linear RGBA `pixels` to the 8bit RGBA `display` ones, on this thread
+    pub fn convert_into(&self, pixels: &[f32], display: &mut [u8]) {
+        let mut pixel_chunks = pixels.chunks_exact(LANES * 4);
+        let mut display_chunks = display.chunks_exact_mut(LANES * 4);
+        for (pixels, display) in (&mut pixel_chunks).zip(&mut display_chunks) {
+            self.convert_lanes(pixels, display);
+        }
+
+        // The last few pixels, padded with black
+        let (remainder, display) = (pixel_chunks.remainder(), display_chunks.into_remainder());
+        if !remainder.is_empty() {
+            let mut padded = [0.0; LANES * 4];
+            padded[..remainder.len()].copy_from_slice(remainder);
+            let mut converted = [0; LANES * 4];
+            self.convert_lanes(&padded, &mut converted);
+            display.copy_from_slice(&converted[..display.len()]);
+        }
+    }
+
+    /// Convert exactly `LANES` pixels
+    fn convert_lanes(&self, pixels: &[f32], display: &mut [u8]) {
+        let mut rgba = [[0.0; LANES]; 4];
+        for (lane, pixel) in pixels.chunks_exact(4).enumerate() {
+            for (channel, value) in rgba.iter_mut().zip(pixel) {
+                channel[lane] = *value;
+            }
+        }
+        let [r, g, b, a] = rgba;
+
+        let mut rgb = [r, g, b];
+        if let Some(matrix) = &self.white_balance {
+            rgb = transform(matrix, rgb);
+        }
+        for channel in &mut rgb {
+            for value in channel.iter_mut() {
+                *value *= self.exposure_scale;
+            }
+        }
+
+        let tonemapped = match (self.tonemap, self.pipeline.baked_tonemap()) {
+            (TonemapKind::Perceptual, Some(baked)) => {
+                // The lookups can't be vectorized, the rest still is
+                let mut tonemapped = [[0.0; LANES]; 3];
+                for lane in 0..LANES {
+                    let shaped = [rgb[0][lane], rgb[1][lane], rgb[2][lane]].map(baked_lut_shaper);
+                    for (channel, value) in tonemapped.iter_mut().zip(baked.apply(shaped)) {
+                        channel[lane] = value;
+                    }
+                }
+                tonemapped
+            }
+            (TonemapKind::Reinhard, _) => rgb.map(|channel| channel.map(reinhard)),
+            (TonemapKind::AcesFilmic, _) => rgb.map(|channel| channel.map(aces_filmic)),
+            _ => rgb.map(|channel| channel.map(|value| value.clamp(0.0, 1.0))),
+        };
+        let linear = transform(&self.display_matrix, tonemapped);
+
+        for lane in 0..LANES {
+            let alpha = (a[lane] * self.global_alpha).clamp(0.0, 1.0);
+            display[lane * 4..lane * 4 + 4].copy_from_slice(&[
+                code(self.thresholds, linear[0][lane]),
+                code(self.thresholds, linear[1][lane]),
+                code(self.thresholds, linear[2][lane]),
+                (255.0 * alpha) as u8,
+            ]);
+        }
+    }
+}
+
+fn transform(matrix: &Matrix, [r, g, b]: [Lanes; 3]) -> [Lanes; 3] {
+    matrix.map(|row| {
+        let mut channel = [0.0; LANES];
+        for (lane, value) in channel.iter_mut().enumerate() {
+            *value = row[0] * r[lane] + row[1] * g[lane] + row[2] * b[lane];
+        }
+        channel
+    })
+}
+
+/// 8bit code of the display linear `value`: the number of thresholds at or below it,
+/// found by a binary search without branches. NaNs are 0, like with `to_u8`.
+fn code(thresholds: &[f32; 255], value: f32) -> u8 {
+    let mut code = 0;
+    for step in [128, 64, 32, 16, 8, 4, 2, 1] {
+        code += step * usize::from(value >= thresholds[code + step - 1]);
+    }
+    code as u8
+}
+
+fn code_thresholds(display: DisplayTransform) -> &'static [f32; 255] {
+    let index = DisplayTransform::ALL
+        .iter()
+        .position(|&transform| transform == display)
+        .expect("every display transform is in ALL");
+    CODE_THRESHOLDS[index].get_or_init(|| {
+        // The transfer function of `DisplayTransform::encode`, on a single channel
+        let encode = |value: f32| match display {
+            DisplayTransform::Srgb => {
+                Color::<LinearSrgb, Display>::new(value, value, value)
+                    .convert::<EncodedSrgb>()
+                    .r
+            }
+            DisplayTransform::Rec709 => value.max(0.0).powf(1.0 / 2.4),
+            DisplayTransform::DisplayP3 => {
+                Color::<DisplayP3, Display>::new(value, value, value)
+                    .convert::<EncodedDisplayP3>()
+                    .r
+            }
+        };
+
+        // Bisect down to the smallest value that reaches each code, as the transfer
+        // function only ever goes up
+        std::array::from_fn(|index| {
+            let code = index as u8 + 1;
+            let (mut below, mut above) = (0.0_f32, 1.0_f32);
+            loop {
+                let middle = 0.5 * (below + above);
+                if middle <= below || middle >= above {
+                    break above;
+                }
+                if to_u8(encode(middle)) >= code {
+                    above = middle;
+                } else {
+                    below = middle;
+                }
+            }
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::white_balance::WhiteBalance;
+
+    /// Pixels of an image large enough for the perceptual tonemap to be baked
+    const LARGE_IMAGE: usize = 4096 * 4096;
+
+    /// Both conversions of a ramp from far below black to well above white, as part of
+    /// a large image so that both use the same baked tonemap
+    fn per_pixel_and_lanes(settings: &DisplaySettings) -> (Vec<u8>, Option<Vec<u8>>) {
+        let pixels: Vec<f32> = (0..1001)
+            .flat_map(|index| {
+                let value = index as f32 / 100.0 - 1.0;
+                [value, value * 0.5, 8.0 - value, 0.5]
+            })
+            .collect();
+
+        let pipeline = DisplayPipeline::baked(settings, LARGE_IMAGE);
+        assert_eq!(
+            pipeline.baked_tonemap().is_some(),
+            settings.tonemap == TonemapKind::Perceptual
+        );
+        let per_pixel = pixels
+            .chunks_exact(4)
+            .flat_map(|pixel| pipeline.stages(pixel, 0, 0).rgba)
+            .collect();
+        let lanes = LanePipeline::new(settings, LARGE_IMAGE).map(|lanes| lanes.convert(&pixels));
+        (per_pixel, lanes)
+    }
+
+    #[test]
+    fn lanes_match_per_pixel() {
+        for display in DisplayTransform::ALL {
+            for tonemap in [
+                TonemapKind::Perceptual,
+                TonemapKind::Reinhard,
+                TonemapKind::AcesFilmic,
+                TonemapKind::Clamp,
+            ] {
+                let settings = DisplaySettings {
+                    white_balance: WhiteBalance {
+                        temperature: 5000.0,
+                        tint: 0.1,
+                    },
+                    tonemap,
+                    display,
+                    ..DisplaySettings::default()
+                };
+                let (per_pixel, lanes) = per_pixel_and_lanes(&settings);
+                assert_eq!(Some(per_pixel), lanes, "{display}, {tonemap}");
+            }
+        }
+    }
+
+    #[test]
+    fn unsupported_settings_fall_back() {
+        let settings = DisplaySettings {
+            tonemap: TonemapKind::LuminanceOnly,
+            ..DisplaySettings::default()
+        };
+        assert_eq!(per_pixel_and_lanes(&settings).1, None);
+    }
+}
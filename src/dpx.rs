@@ -0,0 +1,133 @@
+//! Writing 10 bit log DPX files (SMPTE 268M), for handing frames to DI and film tools
+use std::path::Path;
+
+use crate::color_pipeline::{CINEON_BLACK, CINEON_WHITE};
+use crate::i18n::{tr_args, Text};
+
+/// Size of the headers, the image data starts right after them
+const HEADER_SIZE: usize = 2048;
+/// Generic headers: file information, image information and image orientation
+const GENERIC_HEADER_SIZE: u32 = 768 + 640 + 256;
+/// Industry specific headers: motion picture film and television
+const INDUSTRY_HEADER_SIZE: u32 = 256 + 128;
+
+/// What the header fields with no value are set to, as the standard asks
+const UNDEFINED: u8 = 0xff;
+
+/// Name and version of the application, as stored in the files it writes
+const CREATOR: &str = concat!(env!("CARGO_PKG_NAME"), " ", env!("CARGO_PKG_VERSION"));
+
+/// The big-endian header, filled field by field at the offsets of the standard
+struct Header(Vec<u8>);
+
+impl Header {
+    fn u8(&mut self, offset: usize, value: u8) {
+        self.0[offset] = value;
+    }
+
+    fn u16(&mut self, offset: usize, value: u16) {
+        self.0[offset..offset + 2].copy_from_slice(&value.to_be_bytes());
+    }
+
+    fn u32(&mut self, offset: usize, value: u32) {
+        self.0[offset..offset + 4].copy_from_slice(&value.to_be_bytes());
+    }
+
+    fn f32(&mut self, offset: usize, value: f32) {
+        self.0[offset..offset + 4].copy_from_slice(&value.to_be_bytes());
+    }
+
+    /// A NUL padded ASCII field of `length` bytes, truncated if needed
+    fn ascii(&mut self, offset: usize, length: usize, value: &str) {
+        let field = &mut self.0[offset..offset + length];
+        field.fill(0);
+        for (byte, c) in field[..length - 1].iter_mut().zip(value.chars()) {
+            *byte = if c.is_ascii() { c as u8 } else { b'?' };
+        }
+    }
+
+    fn undefined(&mut self, offset: usize, length: usize) {
+        self.0[offset..offset + length].fill(UNDEFINED);
+    }
+}
+
+fn header(path: &Path, width: usize, height: usize) -> Header {
+    let image_size = width * height * 4;
+    let mut header = Header(vec![0; HEADER_SIZE]);
+
+    // File information
+    header.0[..4].copy_from_slice(b"SDPX");
+    header.u32(4, HEADER_SIZE as u32);
+    header.ascii(8, 8, "V2.0");
+    header.u32(16, (HEADER_SIZE + image_size) as u32);
+    // Ditto key: this is a new frame, not the same as the previous one
+    header.u32(20, 1);
+    header.u32(24, GENERIC_HEADER_SIZE);
+    header.u32(28, INDUSTRY_HEADER_SIZE);
+    header.u32(32, 0);
+    let file_name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or("");
+    header.ascii(36, 100, file_name);
+    header.ascii(160, 100, CREATOR);
+    // Not encrypted
+    header.undefined(660, 4);
+
+    // Image information, a single element with the three color channels
+    header.u16(768, 0); // Left to right, top to bottom
+    header.u16(770, 1);
+    header.u32(772, width as u32);
+    header.u32(776, height as u32);
+    let element = 780;
+    header.u32(element, 0); // Unsigned
+    header.u32(element + 4, CINEON_BLACK as u32);
+    header.f32(element + 8, 0.0);
+    header.u32(element + 12, CINEON_WHITE as u32);
+    // Printing density of the reference white
+    header.f32(element + 16, 2.048);
+    header.u8(element + 20, 50); // RGB
+    header.u8(element + 21, 1); // Printing density transfer
+    header.u8(element + 22, 1); // Printing density colorimetry
+    header.u8(element + 23, 10);
+    header.u16(element + 24, 1); // Filled to 32 bit words, method A
+    header.u16(element + 26, 0); // No run length encoding
+    header.u32(element + 28, HEADER_SIZE as u32);
+    header.u32(element + 32, 0);
+    header.u32(element + 36, 0);
+    header.ascii(element + 40, 32, "Display referred, log encoded");
+    // The other seven elements are unused
+    header.undefined(element + 72, 7 * 72);
+
+    // Image orientation, nothing was cropped or moved
+    header.u32(1408, 0);
+    header.u32(1412, 0);
+    header.undefined(1416, 8);
+    header.u32(1424, width as u32);
+    header.u32(1428, height as u32);
+    // Border validity
+    header.undefined(1620, 8);
+    // Square pixels
+    header.u32(1628, 1);
+    header.u32(1632, 1);
+
+    // Motion picture film and television information are unknown
+    header.undefined(1712, 32);
+    header.undefined(1920, 128);
+
+    header
+}
+
+/// Write 10 bit Cineon code values, as RGBA (alpha is dropped), to a DPX file.
+/// Each pixel gets packed in a 32 bit word, with 2 bits of padding.
+pub fn write_dpx(path: &Path, width: usize, height: usize, codes: &[u16]) -> Result<(), String> {
+    let mut contents = header(path, width, height).0;
+    contents.reserve(width * height * 4);
+    for pixel in codes.chunks_exact(4) {
+        let [r, g, b] = [pixel[0], pixel[1], pixel[2]].map(|code| code.min(1023) as u32);
+        let word = (r << 22) | (g << 12) | (b << 2);
+        contents.extend_from_slice(&word.to_be_bytes());
+    }
+
+    std::fs::write(path, contents).map_err(|e| tr_args(Text::CouldNotSave, &[&path.display(), &e]))
+}
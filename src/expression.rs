@@ -0,0 +1,480 @@
+//! Per-pixel expressions like `sin(u * 10) * cos(v * 10)`, compiled to a small stack machine.
+//!
+//! An expression is either a single value, used for all three channels, or three
+//! comma separated values for red, green and blue.
+
+/// What the expression scene starts with
+pub const DEFAULT_EXPRESSION: &str = "0.5 + 0.5 * sin(u * 10) * cos(v * 10)";
+
+/// Deepest stack an expression can need, deeper ones are rejected when compiling
+const MAX_STACK_SIZE: usize = 32;
+/// Deepest nesting of parentheses, function calls and unary minus the parser recurses
+/// into, so that a pathological expression is an error rather than a stack overflow
+const MAX_NESTING: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Function {
+    Sin,
+    Cos,
+    Tan,
+    Abs,
+    Sqrt,
+    Exp,
+    Ln,
+    Floor,
+    Fract,
+    Pow,
+    Min,
+    Max,
+    Clamp,
+    Mix,
+}
+
+impl Function {
+    fn from_name(name: &str) -> Option<Function> {
+        let function = match name {
+            "sin" => Function::Sin,
+            "cos" => Function::Cos,
+            "tan" => Function::Tan,
+            "abs" => Function::Abs,
+            "sqrt" => Function::Sqrt,
+            "exp" => Function::Exp,
+            "ln" | "log" => Function::Ln,
+            "floor" => Function::Floor,
+            "fract" => Function::Fract,
+            "pow" => Function::Pow,
+            "min" => Function::Min,
+            "max" => Function::Max,
+            "clamp" => Function::Clamp,
+            "mix" => Function::Mix,
+            _ => return None,
+        };
+        Some(function)
+    }
+
+    /// How many arguments the function takes
+    fn arity(&self) -> usize {
+        match self {
+            Function::Pow | Function::Min | Function::Max => 2,
+            Function::Clamp | Function::Mix => 3,
+            _ => 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Variable {
+    /// Normalized coordinates, with V going up
+    U,
+    V,
+    /// Pixel coordinates, with Y going up
+    X,
+    Y,
+    /// Time of the frame, in seconds
+    T,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Constant(f32),
+    Load(Variable),
+    Negate,
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Modulo,
+    Power,
+    Call(Function),
+}
+
+/// Values of the variables for the pixel being evaluated
+pub struct Inputs {
+    pub u: f32,
+    pub v: f32,
+    pub x: f32,
+    pub y: f32,
+    pub t: f32,
+}
+
+/// A compiled expression, cheap to evaluate for every pixel
+#[derive(Debug, Clone, PartialEq)]
+pub struct Program {
+    ops: Vec<Op>,
+    /// 1 for grayscale, 3 for RGB
+    output_count: usize,
+}
+
+impl Program {
+    pub fn compile(source: &str) -> Result<Self, String> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser {
+            tokens: &tokens,
+            position: 0,
+            depth: 0,
+            ops: Vec::new(),
+        };
+
+        let mut output_count = 1;
+        parser.expression()?;
+        while parser.eat(&Token::Comma) {
+            parser.expression()?;
+            output_count += 1;
+        }
+        if let Some(token) = parser.peek() {
+            return Err(format!("unexpected {token}"));
+        }
+        if output_count != 1 && output_count != 3 {
+            return Err(format!(
+                "expected 1 (gray) or 3 (RGB) values, got {output_count}"
+            ));
+        }
+
+        let program = Program {
+            ops: parser.ops,
+            output_count,
+        };
+        if program.max_stack_size() > MAX_STACK_SIZE {
+            return Err(String::from("expression is nested too deeply"));
+        }
+
+        Ok(program)
+    }
+
+    fn max_stack_size(&self) -> usize {
+        let mut size: usize = 0;
+        let mut max_size = 0;
+
+        for op in &self.ops {
+            size = match op {
+                Op::Constant(_) | Op::Load(_) => size + 1,
+                Op::Negate => size,
+                Op::Call(function) => size + 1 - function.arity(),
+                _ => size - 1,
+            };
+            max_size = max_size.max(size);
+        }
+
+        max_size
+    }
+
+    /// Red, green and blue values of the pixel
+    pub fn evaluate(&self, inputs: &Inputs) -> [f32; 3] {
+        let mut stack = [0.0_f32; MAX_STACK_SIZE];
+        let mut size = 0;
+
+        for op in &self.ops {
+            // Binary ops pop `b`, then overwrite `a` with the result
+            match *op {
+                Op::Constant(value) => {
+                    stack[size] = value;
+                    size += 1;
+                }
+                Op::Load(variable) => {
+                    stack[size] = match variable {
+                        Variable::U => inputs.u,
+                        Variable::V => inputs.v,
+                        Variable::X => inputs.x,
+                        Variable::Y => inputs.y,
+                        Variable::T => inputs.t,
+                    };
+                    size += 1;
+                }
+                Op::Negate => stack[size - 1] = -stack[size - 1],
+                Op::Call(function) => size = call(function, &mut stack, size),
+                binary => {
+                    size -= 1;
+                    let (a, b) = (stack[size - 1], stack[size]);
+                    stack[size - 1] = match binary {
+                        Op::Add => a + b,
+                        Op::Subtract => a - b,
+                        Op::Multiply => a * b,
+                        Op::Divide => a / b,
+                        Op::Modulo => a.rem_euclid(b),
+                        Op::Power => a.powf(b),
+                        _ => unreachable!("not a binary op: {binary:?}"),
+                    };
+                }
+            }
+        }
+
+        match self.output_count {
+            1 => [stack[0]; 3],
+            _ => [stack[0], stack[1], stack[2]],
+        }
+    }
+}
+
+/// Apply `function` to the arguments at the top of the stack, returns the new stack size
+fn call(function: Function, stack: &mut [f32], size: usize) -> usize {
+    let new_size = size + 1 - function.arity();
+    let arguments = &stack[new_size - 1..size];
+
+    let result = match function {
+        Function::Sin => arguments[0].sin(),
+        Function::Cos => arguments[0].cos(),
+        Function::Tan => arguments[0].tan(),
+        Function::Abs => arguments[0].abs(),
+        Function::Sqrt => arguments[0].sqrt(),
+        Function::Exp => arguments[0].exp(),
+        Function::Ln => arguments[0].ln(),
+        Function::Floor => arguments[0].floor(),
+        Function::Fract => arguments[0] - arguments[0].floor(),
+        Function::Pow => arguments[0].powf(arguments[1]),
+        Function::Min => arguments[0].min(arguments[1]),
+        Function::Max => arguments[0].max(arguments[1]),
+        Function::Clamp => arguments[0].max(arguments[1]).min(arguments[2]),
+        Function::Mix => arguments[0] + (arguments[1] - arguments[0]) * arguments[2],
+    };
+
+    stack[new_size - 1] = result;
+    new_size
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f32),
+    Identifier(String),
+    Operator(char),
+    OpenParenthesis,
+    CloseParenthesis,
+    Comma,
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Number(value) => write!(f, "number {value}"),
+            Token::Identifier(name) => write!(f, "'{name}'"),
+            Token::Operator(operator) => write!(f, "'{operator}'"),
+            Token::OpenParenthesis => write!(f, "'('"),
+            Token::CloseParenthesis => write!(f, "')'"),
+            Token::Comma => write!(f, "','"),
+        }
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = source.char_indices().peekable();
+
+    while let Some((start, c)) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '+' | '-' | '*' | '/' | '%' | '^' => Token::Operator(c),
+            '(' => Token::OpenParenthesis,
+            ')' => Token::CloseParenthesis,
+            ',' => Token::Comma,
+            c if c.is_ascii_digit() || c == '.' => {
+                let mut end = start + c.len_utf8();
+                while let Some(&(index, next)) = chars.peek() {
+                    if !(next.is_ascii_digit() || next == '.') {
+                        break;
+                    }
+                    end = index + next.len_utf8();
+                    chars.next();
+                }
+                let number = &source[start..end];
+                let value = number
+                    .parse()
+                    .map_err(|_| format!("invalid number '{number}'"))?;
+                Token::Number(value)
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let mut end = start + c.len_utf8();
+                while let Some(&(index, next)) = chars.peek() {
+                    if !(next.is_alphanumeric() || next == '_') {
+                        break;
+                    }
+                    end = index + next.len_utf8();
+                    chars.next();
+                }
+                Token::Identifier(source[start..end].to_lowercase())
+            }
+            _ => return Err(format!("unexpected character '{c}'")),
+        };
+        tokens.push(token);
+    }
+
+    Ok(tokens)
+}
+
+/// Recursive descent parser, emitting the ops in reverse polish order as it goes
+struct Parser<'a> {
+    tokens: &'a [Token],
+    position: usize,
+    /// How many `unary` calls are in progress, every recursion goes through one
+    depth: usize,
+    ops: Vec<Op>,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<&Token> {
+        let token = self.tokens.get(self.position);
+        self.position += 1;
+        token
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        if self.peek() == Some(token) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &Token) -> Result<(), String> {
+        match self.next() {
+            Some(next) if next == token => Ok(()),
+            Some(next) => Err(format!("expected {token}, found {next}")),
+            None => Err(format!("expected {token} at the end")),
+        }
+    }
+
+    // expression := term (('+' | '-') term)*
+    fn expression(&mut self) -> Result<(), String> {
+        self.term()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Operator('+')) => Op::Add,
+                Some(Token::Operator('-')) => Op::Subtract,
+                _ => return Ok(()),
+            };
+            self.position += 1;
+            self.term()?;
+            self.ops.push(op);
+        }
+    }
+
+    // term := unary (('*' | '/' | '%') unary)*
+    fn term(&mut self) -> Result<(), String> {
+        self.unary()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Operator('*')) => Op::Multiply,
+                Some(Token::Operator('/')) => Op::Divide,
+                Some(Token::Operator('%')) => Op::Modulo,
+                _ => return Ok(()),
+            };
+            self.position += 1;
+            self.unary()?;
+            self.ops.push(op);
+        }
+    }
+
+    // unary := '-' unary | power
+    fn unary(&mut self) -> Result<(), String> {
+        if self.depth >= MAX_NESTING {
+            return Err(String::from("expression is nested too deeply"));
+        }
+        self.depth += 1;
+        let result = if self.eat(&Token::Operator('-')) {
+            self.unary().map(|()| self.ops.push(Op::Negate))
+        } else {
+            self.power()
+        };
+        self.depth -= 1;
+        result
+    }
+
+    // power := atom ('^' unary)?, so that 2^3^2 is 2^(3^2) and 2^-1 works
+    fn power(&mut self) -> Result<(), String> {
+        self.atom()?;
+        if self.eat(&Token::Operator('^')) {
+            self.unary()?;
+            self.ops.push(Op::Power);
+        }
+        Ok(())
+    }
+
+    // atom := number | variable | constant | function '(' arguments ')' | '(' expression ')'
+    fn atom(&mut self) -> Result<(), String> {
+        match self.next().cloned() {
+            Some(Token::Number(value)) => self.ops.push(Op::Constant(value)),
+            Some(Token::OpenParenthesis) => {
+                self.expression()?;
+                self.expect(&Token::CloseParenthesis)?;
+            }
+            Some(Token::Identifier(name)) => {
+                let op = match name.as_str() {
+                    "u" => Op::Load(Variable::U),
+                    "v" => Op::Load(Variable::V),
+                    "x" => Op::Load(Variable::X),
+                    "y" => Op::Load(Variable::Y),
+                    "t" => Op::Load(Variable::T),
+                    "pi" => Op::Constant(std::f32::consts::PI),
+                    "e" => Op::Constant(std::f32::consts::E),
+                    _ => return self.function_call(&name),
+                };
+                self.ops.push(op);
+            }
+            Some(token) => return Err(format!("unexpected {token}")),
+            None => return Err(String::from("unexpected end of the expression")),
+        }
+        Ok(())
+    }
+
+    fn function_call(&mut self, name: &str) -> Result<(), String> {
+        let Some(function) = Function::from_name(name) else {
+            return Err(format!("unknown variable or function '{name}'"));
+        };
+
+        self.expect(&Token::OpenParenthesis)?;
+        for argument in 0..function.arity() {
+            if argument > 0 {
+                self.expect(&Token::Comma)?;
+            }
+            self.expression()?;
+        }
+        self.expect(&Token::CloseParenthesis)?;
+
+        self.ops.push(Op::Call(function));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn evaluate(source: &str) -> f32 {
+        let inputs = Inputs {
+            u: 0.25,
+            v: 0.5,
+            x: 1.0,
+            y: 2.0,
+            t: 0.0,
+        };
+        Program::compile(source).unwrap().evaluate(&inputs)[0]
+    }
+
+    #[test]
+    fn precedence() {
+        assert_eq!(evaluate("1 + 2 * 3"), 7.0);
+        assert_eq!(evaluate("2 ^ 3 ^ 2"), 512.0);
+        assert_eq!(evaluate("-2 ^ 2"), -4.0);
+        assert_eq!(evaluate("(u + v) * 4"), 3.0);
+    }
+
+    #[test]
+    fn reasonable_nesting_compiles() {
+        let source = format!("{}1{}", "(".repeat(200), ")".repeat(200));
+        assert_eq!(evaluate(&source), 1.0);
+        assert_eq!(evaluate(&format!("{}1", "-".repeat(100))), 1.0);
+    }
+
+    #[test]
+    fn deep_nesting_is_an_error() {
+        let depth = 200_000;
+        let parentheses = format!("{}1{}", "(".repeat(depth), ")".repeat(depth));
+        assert!(Program::compile(&parentheses).is_err());
+        assert!(Program::compile(&format!("{}1", "-".repeat(depth))).is_err());
+        let calls = format!("{}1{}", "sin(".repeat(depth), ")".repeat(depth));
+        assert!(Program::compile(&calls).is_err());
+    }
+}
@@ -0,0 +1,81 @@
+//! Snapshots of the last completed renders, shown as thumbnails to go back to
+//! one of them along with the settings that produced it
+use std::collections::VecDeque;
+
+use iced::widget::image;
+
+use crate::color_pipeline::scene_to_display;
+use crate::preset::Preset;
+use crate::render::Framebuffer;
+use crate::resample::{downscale_rgba, subsample_rgba};
+
+/// How many renders are kept around. Each keeps its full scene linear buffer.
+const GALLERY_LENGTH: usize = 12;
+pub const THUMBNAIL_WIDTH: usize = 96;
+
+pub struct Snapshot {
+    pub settings: Preset,
+    pub framebuffer: Framebuffer,
+    pub thumbnail: image::Handle,
+}
+
+impl Snapshot {
+    pub fn new(settings: Preset, framebuffer: Framebuffer) -> Snapshot {
+        let (width, height) = (framebuffer.width(), framebuffer.height());
+
+        // Converting every pixel would be wasted on a thumbnail, but a
+        // few more than it has keep the box filter smooth
+        let factor = (width / (THUMBNAIL_WIDTH * 2)).max(1);
+        let (subsampled, subsampled_width, subsampled_height) =
+            subsample_rgba(framebuffer.pixels(), width, height, factor);
+        let subsampled = Framebuffer::from_pixels(subsampled_width, subsampled_height, subsampled);
+        let display_pixels = scene_to_display(&subsampled, &settings.display_settings);
+        let thumbnail_width = THUMBNAIL_WIDTH.min(subsampled_width);
+        let thumbnail_height = (subsampled_height * thumbnail_width / subsampled_width).max(1);
+        let thumbnail_pixels = downscale_rgba(
+            &display_pixels,
+            subsampled_width,
+            subsampled_height,
+            thumbnail_width,
+            thumbnail_height,
+        );
+
+        Snapshot {
+            settings,
+            framebuffer,
+            thumbnail: image::Handle::from_pixels(
+                thumbnail_width as u32,
+                thumbnail_height as u32,
+                thumbnail_pixels,
+            ),
+        }
+    }
+}
+
+/// The last few renders, most recent first
+pub struct Gallery {
+    snapshots: VecDeque<Snapshot>,
+}
+
+impl Gallery {
+    pub fn new() -> Self {
+        Gallery {
+            snapshots: VecDeque::with_capacity(GALLERY_LENGTH),
+        }
+    }
+
+    pub fn record(&mut self, snapshot: Snapshot) {
+        if self.snapshots.len() == GALLERY_LENGTH {
+            self.snapshots.pop_back();
+        }
+        self.snapshots.push_front(snapshot);
+    }
+
+    pub fn get(&self, index: usize) -> Option<&Snapshot> {
+        self.snapshots.get(index)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Snapshot> {
+        self.snapshots.iter()
+    }
+}
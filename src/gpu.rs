@@ -0,0 +1,217 @@
+//! Render the simple patterns with a compute shader, and read them back.
+//! Only built with the `gpu` feature.
+use std::borrow::Cow;
+use std::sync::OnceLock;
+
+use iced::futures::executor::block_on;
+
+use crate::console;
+
+use crate::pattern::{BlendSpace, MAX_GRADIENT_STOPS};
+use crate::render::{
+    Framebuffer, Region, RenderBuffer, RenderSettings, SceneKind, CHECKERBOARD_SQUARES,
+};
+
+/// Must match the `@workgroup_size` of the shader
+const WORKGROUP_SIZE: u32 = 8;
+
+/// Whether the shader knows how to render `scene`
+pub fn supports(scene: SceneKind) -> bool {
+    shader_scene(scene).is_some()
+}
+
+fn shader_scene(scene: SceneKind) -> Option<u32> {
+    match scene {
+        SceneKind::Gradient => Some(0),
+        SceneKind::Radial => Some(1),
+        SceneKind::Checkerboard => Some(2),
+        SceneKind::UvRamp => Some(3),
+        _ => None,
+    }
+}
+
+struct GpuRenderer {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    pipeline: wgpu::ComputePipeline,
+}
+
+impl GpuRenderer {
+    fn new() -> Result<Self, String> {
+        let instance = wgpu::Instance::new(wgpu::Backends::all());
+        let adapter = block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            compatible_surface: None,
+            force_fallback_adapter: false,
+        }))
+        .ok_or("no GPU adapter found")?;
+
+        let (device, queue) = block_on(adapter.request_device(
+            &wgpu::DeviceDescriptor {
+                label: Some("render device"),
+                features: wgpu::Features::empty(),
+                limits: wgpu::Limits::downlevel_defaults(),
+            },
+            None,
+        ))
+        .map_err(|e| e.to_string())?;
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("patterns"),
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!("gpu.wgsl"))),
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("patterns"),
+            layout: None,
+            module: &shader,
+            entry_point: "main",
+        });
+
+        console::info(format!(
+            "Rendering on the GPU with {}",
+            adapter.get_info().name
+        ));
+        Ok(GpuRenderer {
+            device,
+            queue,
+            pipeline,
+        })
+    }
+
+    fn render_region(&self, settings: &RenderSettings, scene: u32, region: Region) -> RenderBuffer {
+        // Laid out like the `Params` of the shader
+        let mut params = vec![
+            settings.width as u32,
+            settings.height as u32,
+            region.x as u32,
+            region.y as u32,
+            region.width as u32,
+            region.height as u32,
+            scene,
+            CHECKERBOARD_SQUARES.to_bits(),
+            settings.bilinear_blend.to_bits(),
+            settings.radial_center.0.to_bits(),
+            settings.radial_center.1.to_bits(),
+            settings.radial_radius.to_bits(),
+            settings.time().to_bits(),
+        ];
+        let gradients = [settings.u_gradient.sorted(), settings.v_gradient.sorted()];
+        params.extend(gradients.iter().map(|gradient| gradient.stops.len() as u32));
+        // Up to the 16 bytes alignment of the stops
+        params.push(0);
+        for gradient in &gradients {
+            let mut slots = [[0.0; 4]; MAX_GRADIENT_STOPS];
+            for (slot, stop) in slots.iter_mut().zip(&gradient.stops) {
+                let [r, g, b] = stop.color;
+                *slot = [r, g, b, stop.position];
+            }
+            params.extend(slots.iter().flatten().map(|value: &f32| value.to_bits()));
+        }
+        let params_bytes: Vec<u8> = params.iter().flat_map(|p| p.to_ne_bytes()).collect();
+
+        let params_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("params"),
+            size: params_bytes.len() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        self.queue.write_buffer(&params_buffer, 0, &params_bytes);
+
+        let pixels_size = (region.pixel_count() * 4 * std::mem::size_of::<f32>()) as u64;
+        let pixels_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("pixels"),
+            size: pixels_size,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let readback_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("readback"),
+            size: pixels_size,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("patterns"),
+            layout: &self.pipeline.get_bind_group_layout(0),
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: params_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: pixels_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor { label: None });
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.dispatch_workgroups(
+                (region.width as u32).div_ceil(WORKGROUP_SIZE),
+                (region.height as u32).div_ceil(WORKGROUP_SIZE),
+                1,
+            );
+        }
+        encoder.copy_buffer_to_buffer(&pixels_buffer, 0, &readback_buffer, 0, pixels_size);
+        self.queue.submit(Some(encoder.finish()));
+
+        let readback = readback_buffer.slice(..);
+        readback.map_async(wgpu::MapMode::Read, |_| {});
+        self.device.poll(wgpu::Maintain::Wait);
+
+        let pixels = readback
+            .get_mapped_range()
+            .chunks_exact(4)
+            .map(|bytes| f32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+            .collect();
+        readback_buffer.unmap();
+
+        pixels
+    }
+}
+
+/// Set up on first use. None if there is no usable GPU, in which case we keep to the CPU.
+fn renderer() -> Option<&'static GpuRenderer> {
+    static RENDERER: OnceLock<Option<GpuRenderer>> = OnceLock::new();
+
+    RENDERER
+        .get_or_init(|| match GpuRenderer::new() {
+            Ok(renderer) => Some(renderer),
+            Err(e) => {
+                console::warning(format!(
+                    "GPU rendering is unavailable ({e}), using the CPU instead"
+                ));
+                None
+            }
+        })
+        .as_ref()
+}
+
+/// Render a single layer of `region` on the GPU.
+/// None when the scene isn't supported or there is no GPU, so that the caller can fall back.
+pub fn render_layer_region(
+    settings: &RenderSettings,
+    scene: SceneKind,
+    region: Region,
+) -> Option<Framebuffer> {
+    // The shader only blends the gradients in ACEScg, let the CPU handle the others
+    let is_gradient = matches!(scene, SceneKind::Gradient | SceneKind::Radial);
+    if is_gradient && settings.blend_space != BlendSpace::AcesCg {
+        return None;
+    }
+
+    let scene = shader_scene(scene)?;
+    let pixels = renderer()?.render_region(settings, scene, region);
+    Some(Framebuffer::from_pixels(
+        region.width,
+        region.height,
+        pixels,
+    ))
+}
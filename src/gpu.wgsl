@@ -0,0 +1,99 @@
+// GPU version of the simple patterns in pattern.rs, keep the two in sync
+
+struct Params {
+    width: u32,
+    height: u32,
+    region_x: u32,
+    region_y: u32,
+    region_width: u32,
+    region_height: u32,
+    scene: u32,
+    checkerboard_squares: f32,
+    bilinear_blend: f32,
+    radial_center_x: f32,
+    radial_center_y: f32,
+    radial_radius: f32,
+    // In seconds, see `RenderSettings::time`
+    time: f32,
+    u_stop_count: u32,
+    v_stop_count: u32,
+    // The sorted stops of the U gradient then of the V one, 8 slots each,
+    // with their colors in RGB and their positions in W
+    gradient_stops: array<vec4<f32>, 16>,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read_write> pixels: array<vec4<f32>>;
+
+// Like `triangle_wave` in pattern.rs
+fn triangle_wave(x: f32) -> f32 {
+    let wrapped = x - 2.0 * floor(x / 2.0);
+    return 1.0 - abs(wrapped - 1.0);
+}
+
+// Like `Gradient::sample`, but always blending in ACEScg
+fn sample_gradient(first: u32, count: u32, t: f32) -> vec3<f32> {
+    var previous = params.gradient_stops[first];
+    if (t < previous.w) {
+        return previous.rgb;
+    }
+    for (var i = 1u; i < count; i = i + 1u) {
+        let next = params.gradient_stops[first + i];
+        if (next.w > t) {
+            return mix(previous.rgb, next.rgb, (t - previous.w) / (next.w - previous.w));
+        }
+        previous = next;
+    }
+    return previous.rgb;
+}
+
+@compute @workgroup_size(8, 8)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (id.x >= params.region_width || id.y >= params.region_height) {
+        return;
+    }
+
+    // The first row of the region is the top one, V goes up
+    let x = params.region_x + id.x;
+    let y = params.height - 1u - (params.region_y + id.y);
+    let u = f32(x) / f32(params.width);
+    let v = f32(y) / f32(params.height);
+    let aspect_ratio = f32(params.width) / f32(params.height);
+
+    var color = vec3<f32>(0.0, 0.0, 0.0);
+    switch params.scene {
+        // Bilinear gradient
+        case 0u: {
+            let offset = params.time * 0.25;
+            let h_blended = sample_gradient(0u, params.u_stop_count, triangle_wave(u + offset));
+            let v_blended = sample_gradient(8u, params.v_stop_count, triangle_wave(v + offset));
+            color = mix(h_blended, v_blended, params.bilinear_blend);
+        }
+        // Radial gradient
+        case 1u: {
+            let offset = vec2<f32>(
+                (u - params.radial_center_x) * aspect_ratio,
+                v - params.radial_center_y,
+            );
+            let radius = params.radial_radius * (1.0 + 0.25 * sin(3.14159265 * params.time));
+            let t = clamp(length(offset) / radius, 0.0, 1.0);
+            color = sample_gradient(0u, params.u_stop_count, t);
+        }
+        // Checkerboard
+        case 2u: {
+            let column = i32(floor(u * aspect_ratio * params.checkerboard_squares));
+            let row = i32(floor(v * params.checkerboard_squares));
+            if ((column + row) % 2 == 0) {
+                color = vec3<f32>(0.02, 0.02, 0.02);
+            } else {
+                color = vec3<f32>(0.8, 0.8, 0.8);
+            }
+        }
+        // UV ramp
+        default: {
+            color = vec3<f32>(u, v, 0.0);
+        }
+    }
+
+    pixels[id.y * params.region_width + id.x] = vec4<f32>(color, 1.0);
+}
@@ -0,0 +1,261 @@
+//! Composition overlays of the viewer: pixel rulers along the edges of the image, the
+//! rule of thirds, a center cross, the safe areas, and guides placed by hand.
+//! They are drawn over the displayed pixels only, the framebuffer is left as it is.
+use iced::Point;
+
+/// How close to a guide, in normalized image coordinates, a drag has to start to move it
+pub const GRAB_DISTANCE: f32 = 0.02;
+
+/// Fractions of the image inside which the action and the titles should stay
+const SAFE_AREAS: [f32; 2] = [0.93, 0.9];
+
+const RULER_SIZE: usize = 16;
+const GUIDE_COLOR: [u8; 3] = [0, 255, 255];
+const COMPOSITION_COLOR: [u8; 3] = [255, 255, 255];
+const SAFE_AREA_COLOR: [u8; 3] = [255, 220, 0];
+
+/// 3x5 pixel digits of the ruler labels, a row per byte with the leftmost pixel as bit 2
+const DIGITS: [[u8; 5]; 10] = [
+    [0b111, 0b101, 0b101, 0b101, 0b111],
+    [0b010, 0b110, 0b010, 0b010, 0b111],
+    [0b111, 0b001, 0b111, 0b100, 0b111],
+    [0b111, 0b001, 0b011, 0b001, 0b111],
+    [0b101, 0b101, 0b111, 0b001, 0b001],
+    [0b111, 0b100, 0b111, 0b001, 0b111],
+    [0b111, 0b100, 0b111, 0b101, 0b111],
+    [0b111, 0b001, 0b010, 0b010, 0b010],
+    [0b111, 0b101, 0b111, 0b101, 0b111],
+    [0b111, 0b101, 0b111, 0b001, 0b111],
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Orientation {
+    Vertical,
+    Horizontal,
+}
+
+/// A guide placed by hand, as its orientation and index
+pub type GuideId = (Orientation, usize);
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Guides {
+    pub rulers: bool,
+    pub thirds: bool,
+    pub center: bool,
+    pub safe_areas: bool,
+    /// Positions of the vertical guides placed by hand, normalized to the image width
+    pub vertical: Vec<f32>,
+    /// Positions of the horizontal guides, normalized to the image height
+    pub horizontal: Vec<f32>,
+}
+
+impl Guides {
+    /// Place a new guide across the middle of the image
+    pub fn add(&mut self, orientation: Orientation) {
+        self.positions_mut(orientation).push(0.5);
+    }
+
+    /// The guide placed by hand closest to `position`, if one is within `GRAB_DISTANCE`
+    pub fn grab(&self, position: Point) -> Option<GuideId> {
+        let distances = |orientation: Orientation, target: f32| {
+            self.positions(orientation)
+                .iter()
+                .enumerate()
+                .map(move |(index, guide)| ((orientation, index), (guide - target).abs()))
+        };
+
+        distances(Orientation::Vertical, position.x)
+            .chain(distances(Orientation::Horizontal, position.y))
+            .filter(|(_, distance)| *distance <= GRAB_DISTANCE)
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(guide, _)| guide)
+    }
+
+    /// Move `guide` under `position`, which may be out of the image while dragging
+    pub fn move_to(&mut self, (orientation, index): GuideId, position: Point) {
+        let target = match orientation {
+            Orientation::Vertical => position.x,
+            Orientation::Horizontal => position.y,
+        };
+        if let Some(guide) = self.positions_mut(orientation).get_mut(index) {
+            *guide = target;
+        }
+    }
+
+    /// Remove the guides that were dragged out of the image
+    pub fn remove_outside(&mut self) {
+        for orientation in [Orientation::Vertical, Orientation::Horizontal] {
+            self.positions_mut(orientation)
+                .retain(|position| (0.0..=1.0).contains(position));
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.vertical.clear();
+        self.horizontal.clear();
+    }
+
+    fn positions(&self, orientation: Orientation) -> &Vec<f32> {
+        match orientation {
+            Orientation::Vertical => &self.vertical,
+            Orientation::Horizontal => &self.horizontal,
+        }
+    }
+
+    fn positions_mut(&mut self, orientation: Orientation) -> &mut Vec<f32> {
+        match orientation {
+            Orientation::Vertical => &mut self.vertical,
+            Orientation::Horizontal => &mut self.horizontal,
+        }
+    }
+}
+
+/// Draw the enabled `guides` over the RGBA `display_buffer` of a `width` x `height` image
+pub fn draw(display_buffer: &mut [u8], width: usize, height: usize, guides: &Guides) {
+    if width == 0 || height == 0 {
+        return;
+    }
+    let mut canvas = Canvas {
+        pixels: display_buffer,
+        width,
+        height,
+    };
+    let to_pixel = |position: f32, size: usize| {
+        (0.0..=1.0)
+            .contains(&position)
+            .then(|| ((position * size as f32) as usize).min(size - 1))
+    };
+
+    if guides.safe_areas {
+        for fraction in SAFE_AREAS {
+            let inset = |size: usize| (size as f32 * (1.0 - fraction) / 2.0).round() as usize;
+            let (left, top) = (inset(width), inset(height));
+            let (right, bottom) = (width - 1 - left, height - 1 - top);
+            canvas.horizontal_line(top, left, right, SAFE_AREA_COLOR, 0.6);
+            canvas.horizontal_line(bottom, left, right, SAFE_AREA_COLOR, 0.6);
+            canvas.vertical_line(left, top, bottom, SAFE_AREA_COLOR, 0.6);
+            canvas.vertical_line(right, top, bottom, SAFE_AREA_COLOR, 0.6);
+        }
+    }
+    if guides.thirds {
+        for third in [1.0 / 3.0, 2.0 / 3.0] {
+            if let Some(x) = to_pixel(third, width) {
+                canvas.vertical_line(x, 0, height - 1, COMPOSITION_COLOR, 0.5);
+            }
+            if let Some(y) = to_pixel(third, height) {
+                canvas.horizontal_line(y, 0, width - 1, COMPOSITION_COLOR, 0.5);
+            }
+        }
+    }
+    if guides.center {
+        let (x, y) = (width / 2, height / 2);
+        let arm = width.min(height) / 20;
+        canvas.horizontal_line(y, x.saturating_sub(arm), x + arm, COMPOSITION_COLOR, 0.8);
+        canvas.vertical_line(x, y.saturating_sub(arm), y + arm, COMPOSITION_COLOR, 0.8);
+    }
+    for x in guides.vertical.iter().filter_map(|&x| to_pixel(x, width)) {
+        canvas.vertical_line(x, 0, height - 1, GUIDE_COLOR, 1.0);
+    }
+    for y in guides
+        .horizontal
+        .iter()
+        .filter_map(|&y| to_pixel(y, height))
+    {
+        canvas.horizontal_line(y, 0, width - 1, GUIDE_COLOR, 1.0);
+    }
+    if guides.rulers {
+        canvas.rulers();
+    }
+}
+
+struct Canvas<'a> {
+    pixels: &'a mut [u8],
+    width: usize,
+    height: usize,
+}
+
+impl Canvas<'_> {
+    /// Mix `color` into the pixel at `x`, `y`, ignoring pixels out of the image.
+    /// Mixed on the encoded values, which is enough for an overlay.
+    fn blend(&mut self, x: usize, y: usize, color: [u8; 3], opacity: f32) {
+        if x >= self.width || y >= self.height {
+            return;
+        }
+        let index = (y * self.width + x) * 4;
+        let pixel = &mut self.pixels[index..index + 4];
+        for (channel, target) in pixel.iter_mut().zip(color) {
+            *channel =
+                (*channel as f32 + (target as f32 - *channel as f32) * opacity).round() as u8;
+        }
+        pixel[3] = 255;
+    }
+
+    fn horizontal_line(
+        &mut self,
+        y: usize,
+        left: usize,
+        right: usize,
+        color: [u8; 3],
+        opacity: f32,
+    ) {
+        for x in left..=right {
+            self.blend(x, y, color, opacity);
+        }
+    }
+
+    fn vertical_line(&mut self, x: usize, top: usize, bottom: usize, color: [u8; 3], opacity: f32) {
+        for y in top..=bottom {
+            self.blend(x, y, color, opacity);
+        }
+    }
+
+    /// Bands along the top and left edges, with a tick every 10 pixels, longer ones
+    /// every 50, and the position written next to every 100
+    fn rulers(&mut self) {
+        const BAND: [u8; 3] = [24, 24, 24];
+        const TICK: [u8; 3] = [230, 230, 230];
+        let tick_length = |position: usize| match position {
+            p if p % 100 == 0 => 10,
+            p if p % 50 == 0 => 6,
+            _ => 3,
+        };
+
+        for y in 0..self.height {
+            let band_width = if y < RULER_SIZE {
+                self.width
+            } else {
+                RULER_SIZE.min(self.width)
+            };
+            self.horizontal_line(y, 0, band_width - 1, BAND, 0.75);
+        }
+
+        for x in (RULER_SIZE..self.width).filter(|x| x % 10 == 0) {
+            let top = RULER_SIZE - tick_length(x);
+            self.vertical_line(x, top, RULER_SIZE - 1, TICK, 1.0);
+            if x % 100 == 0 {
+                self.label(x + 2, 2, x, TICK);
+            }
+        }
+        for y in (RULER_SIZE..self.height).filter(|y| y % 10 == 0) {
+            let left = RULER_SIZE - tick_length(y);
+            self.horizontal_line(y, left, RULER_SIZE - 1, TICK, 1.0);
+            if y % 100 == 0 {
+                self.label(1, y + 2, y, TICK);
+            }
+        }
+    }
+
+    /// Write `number` with its top left corner at `x`, `y`
+    fn label(&mut self, x: usize, y: usize, number: usize, color: [u8; 3]) {
+        for (digit_index, digit) in number.to_string().bytes().enumerate() {
+            let rows = DIGITS[(digit - b'0') as usize];
+            for (row_index, row) in rows.iter().enumerate() {
+                for column in 0..3 {
+                    if row & (0b100 >> column) != 0 {
+                        self.blend(x + digit_index * 4 + column, y + row_index, color, 1.0);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,169 @@
+//! Rendering without a window, for scripted and regression renders: `--headless` renders
+//! once with the settings given on the command line and writes the image, through the
+//! same render, display and save code as the application.
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::color_pipeline::DisplaySettings;
+use crate::console;
+use crate::expression::{Program, DEFAULT_EXPRESSION};
+use crate::io::OutputFormat;
+use crate::preset::{self, Preset};
+use crate::render::{parse_dimension, render_scene_linear, RenderSettings};
+use crate::save_worker::{self, SaveJob};
+
+const USAGE: &str = "\
+Usage: iced-framebuffer --headless [options]
+
+Options:
+    --preset <name>      start from the preset presets/<name>.toml
+    --scene <scene>      gradient, radial, checkerboard, uv-ramp, noise, fractal,
+                         path-traced, raymarched or expression
+    --expression <expr>  source of the expression scene, e.g. \"sin(u * 10), v, 0.5\"
+    --width <pixels>
+    --height <pixels>
+    --seed <seed>
+    --frame <frame>
+    --exposure <stops>
+    --output <path>      the extension picks the format: exr, png, tiff, dpx or hdr
+                         (default: render.exr)
+    --aovs               also write the AOV layers, for EXRs";
+
+struct Options {
+    preset: Preset,
+    output: String,
+    aovs: bool,
+}
+
+/// Render and save the image described by `args`, the arguments after `--headless`
+pub fn run(args: &[String]) -> Result<(), String> {
+    if args.iter().any(|arg| arg == "--help") {
+        println!("{USAGE}");
+        return Ok(());
+    }
+    let options = parse(args)?;
+    let format = OutputFormat::from_path(&options.output)
+        .ok_or_else(|| format!("unknown output format for {}", options.output))?;
+
+    let Preset {
+        mut render_settings,
+        display_settings,
+        expression,
+    } = options.preset;
+    render_settings.expression = Arc::new(Program::compile(&expression)?);
+
+    let start = Instant::now();
+    let framebuffer = render_scene_linear(&render_settings);
+    console::info(format!(
+        "Rendered {}x{} in {:.2}s",
+        render_settings.width,
+        render_settings.height,
+        start.elapsed().as_secs_f64()
+    ));
+
+    let job = SaveJob {
+        path: options.output,
+        format,
+        framebuffer,
+        metadata: save_worker::render_metadata(&render_settings, &display_settings),
+        display_settings,
+        aov_settings: (options.aovs && format == OutputFormat::Exr).then_some(render_settings),
+    };
+    let path = job.write()?;
+    console::info(format!("Saved {path}"));
+    Ok(())
+}
+
+fn parse(args: &[String]) -> Result<Options, String> {
+    let mut options = Options {
+        preset: Preset {
+            render_settings: RenderSettings::default(),
+            display_settings: DisplaySettings::default(),
+            expression: String::from(DEFAULT_EXPRESSION),
+        },
+        output: String::from("render.exr"),
+        aovs: false,
+    };
+
+    // The preset comes first, so that the other options apply on top of it
+    if let Some(name) = value_of(args, "--preset") {
+        options.preset = preset::load(name, options.preset)?;
+    }
+
+    let mut args = args.iter();
+    while let Some(option) = args.next() {
+        if option == "--aovs" {
+            options.aovs = true;
+            continue;
+        }
+        let value = args
+            .next()
+            .ok_or_else(|| format!("{option} needs a value\n\n{USAGE}"))?;
+        let invalid = |e: &dyn std::fmt::Display| format!("invalid {option} {value:?} ({e})");
+        let render = &mut options.preset.render_settings;
+        match option.as_str() {
+            "--preset" => {}
+            "--scene" => render.scene = value.parse().map_err(|e| invalid(&e))?,
+            "--width" => render.width = parse_dimension(value).map_err(|e| invalid(&e))?,
+            "--height" => render.height = parse_dimension(value).map_err(|e| invalid(&e))?,
+            "--seed" => render.seed = value.parse().map_err(|e| invalid(&e))?,
+            "--frame" => render.frame = value.parse::<u32>().map_err(|e| invalid(&e))?.max(1),
+            "--exposure" => {
+                options.preset.display_settings.exposure = value.parse().map_err(|e| invalid(&e))?
+            }
+            "--expression" => options.preset.expression = value.clone(),
+            "--output" => options.output = value.clone(),
+            _ => return Err(format!("unknown option {option}\n\n{USAGE}")),
+        }
+    }
+
+    Ok(options)
+}
+
+/// The value following `option` in `args`, if it's there
+fn value_of<'a>(args: &'a [String], option: &str) -> Option<&'a str> {
+    let index = args.iter().position(|arg| arg == option)?;
+    args.get(index + 1).map(String::as_str)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::{SceneKind, MAX_DIMENSION};
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn options_override_the_defaults() {
+        let options = parse(&args(&[
+            "--scene",
+            "expression",
+            "--expression",
+            "u, v, 0.5",
+            "--width",
+            "64",
+            "--output",
+            "out.png",
+            "--aovs",
+        ]))
+        .unwrap();
+        let render = &options.preset.render_settings;
+        assert_eq!(render.scene, SceneKind::Expression);
+        assert_eq!(render.width, 64);
+        assert_eq!(options.preset.expression, "u, v, 0.5");
+        assert_eq!(options.output, "out.png");
+        assert!(options.aovs);
+    }
+
+    #[test]
+    fn invalid_options_are_errors() {
+        let too_wide = (MAX_DIMENSION + 1).to_string();
+        assert!(parse(&args(&["--width", &too_wide])).is_err());
+        assert!(parse(&args(&["--height", "0"])).is_err());
+        assert!(parse(&args(&["--scene", "teapot"])).is_err());
+        assert!(parse(&args(&["--seed"])).is_err());
+        assert!(parse(&args(&["--bogus", "1"])).is_err());
+    }
+}
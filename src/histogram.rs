@@ -0,0 +1,122 @@
+//! RGB and luminance histograms of the image, drawn into a small RGBA image
+//! for the panel below the viewer (iced is built without its canvas here)
+use colstodian::{color, Scene};
+
+use crate::color_pipeline::acescg_luminance;
+
+pub const BINS: usize = 256;
+/// Range of the log axis of the linear histogram, in stops around middle gray
+const LINEAR_STOPS: (f32, f32) = (-8.0, 8.0);
+const MIDDLE_GRAY: f32 = 0.18;
+
+/// Which values the histogram counts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistogramSource {
+    /// The 8bit values shown in the viewer
+    Display,
+    /// The scene linear values, on a log axis
+    Linear,
+}
+
+impl HistogramSource {
+    pub const ALL: [HistogramSource; 2] = [HistogramSource::Display, HistogramSource::Linear];
+}
+
+impl std::fmt::Display for HistogramSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            HistogramSource::Display => "Display",
+            HistogramSource::Linear => "Linear (log)",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Counts of the red, green, blue and luminance values in each bin
+pub struct Histogram {
+    bins: [[u32; BINS]; 4],
+}
+
+impl Histogram {
+    /// Of display referred RGBA pixels, luminance being the Rec. 709 luma
+    pub fn of_display(pixels: &[u8]) -> Histogram {
+        let mut bins = [[0; BINS]; 4];
+        for pixel in pixels.chunks_exact(4) {
+            let [r, g, b] = [pixel[0], pixel[1], pixel[2]];
+            let luma = 0.2126 * r as f32 + 0.7152 * g as f32 + 0.0722 * b as f32;
+            for (channel, value) in [r as usize, g as usize, b as usize, luma.round() as usize]
+                .into_iter()
+                .enumerate()
+            {
+                bins[channel][value.min(BINS - 1)] += 1;
+            }
+        }
+        Histogram { bins }
+    }
+
+    /// Of scene linear ACEScg RGBA pixels, binned by stops from middle gray.
+    /// Values below the range, including zero and negatives, land in the first bin.
+    pub fn of_linear(pixels: &[f32]) -> Histogram {
+        let (low, high) = LINEAR_STOPS;
+        let bin = |value: f32| {
+            let stops = (value / MIDDLE_GRAY).log2();
+            let position = (stops - low) / (high - low) * BINS as f32;
+            // NaN and -inf (for zero) saturate to 0
+            (position as usize).min(BINS - 1)
+        };
+
+        let mut bins = [[0; BINS]; 4];
+        for pixel in pixels.chunks_exact(4) {
+            let luminance = acescg_luminance(color::acescg::<Scene>(pixel[0], pixel[1], pixel[2]));
+            for (channel, value) in [pixel[0], pixel[1], pixel[2], luminance]
+                .into_iter()
+                .enumerate()
+            {
+                bins[channel][bin(value)] += 1;
+            }
+        }
+        Histogram { bins }
+    }
+
+    /// Draw it as RGBA pixels: luminance in gray behind the additively blended channels.
+    /// The counts are on a square root scale, so that a few big spikes don't flatten the rest.
+    pub fn draw(&self, width: usize, height: usize) -> Vec<u8> {
+        let peak = self
+            .bins
+            .iter()
+            .flatten()
+            .copied()
+            .max()
+            .unwrap_or(0)
+            .max(1) as f32;
+        let bar_height = |channel: usize, x: usize| {
+            let count = self.bins[channel][x * BINS / width];
+            ((count as f32 / peak).sqrt() * height as f32).round() as usize
+        };
+
+        let mut pixels = vec![0; width * height * 4];
+        for x in 0..width {
+            let heights = [0, 1, 2, 3].map(|channel| bar_height(channel, x));
+            for y in 0..height {
+                let from_bottom = height - y;
+                let [r, g, b, luminance] = heights.map(|bar| from_bottom <= bar);
+                let base: u8 = if luminance { 90 } else { 25 };
+                let channel = |covered: bool| {
+                    if covered {
+                        base.saturating_add(150)
+                    } else {
+                        base
+                    }
+                };
+                let index = (y * width + x) * 4;
+                pixels[index..index + 4].copy_from_slice(&[
+                    channel(r),
+                    channel(g),
+                    channel(b),
+                    255,
+                ]);
+            }
+        }
+        pixels
+    }
+}
@@ -0,0 +1,788 @@
+//! Translations of the UI strings. Each string is a [`Text`], which [`tr`] spells
+//! in the current language, so adding a language is adding a function like
+//! [`french`]; the compiler then points at every string it's missing. The names
+//! of the patterns, tonemaps and other picked values are still only in English.
+//!
+//! The language is global, like the console, so that the workers' error messages
+//! are translated too.
+use std::fmt::Display;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    English,
+    French,
+}
+
+impl Language {
+    pub const ALL: [Language; 2] = [Language::English, Language::French];
+
+    /// The language of the `LANG` environment variable if there's a translation
+    /// for it, English otherwise
+    pub fn from_env() -> Language {
+        match std::env::var("LANG") {
+            Ok(lang) if lang.starts_with("fr") => Language::French,
+            _ => Language::English,
+        }
+    }
+}
+
+impl std::fmt::Display for Language {
+    // In the language itself, to be found by those who can't read the current one
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Language::English => "English",
+            Language::French => "Français",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Index in `Language::ALL`
+static LANGUAGE: AtomicUsize = AtomicUsize::new(0);
+
+pub fn language() -> Language {
+    Language::ALL[LANGUAGE.load(Ordering::Relaxed)]
+}
+
+pub fn set_language(language: Language) {
+    let index = Language::ALL
+        .iter()
+        .position(|l| *l == language)
+        .unwrap_or(0);
+    LANGUAGE.store(index, Ordering::Relaxed);
+}
+
+/// A string of the UI. Those with `{0}`, `{1}`... in them are completed by [`tr_args`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Text {
+    Render,
+    Cancel,
+    Undo,
+    Redo,
+    Save,
+    FileNamePlaceholder,
+    WillSave,
+    AlreadyExists,
+    OverwriteQuestion,
+    Overwrite,
+    SaveAsNewVersion,
+    AutoVersion,
+    Aovs,
+    Copy,
+    Saving,
+    Saved,
+    CopiedImage,
+    Dismiss,
+    Preset,
+    PresetPlaceholder,
+    PresetNamePlaceholder,
+    SavePreset,
+    Open,
+    OpenPlaceholder,
+    Recent,
+    RecentPlaceholder,
+    Reference,
+    Difference,
+    ReferencePlaceholder,
+    Load,
+    Heatmap,
+    Gain,
+    ReferenceSizeMismatch,
+    DifferingPixels,
+    Exposure,
+    Alpha,
+    Temperature,
+    Tint,
+    Gamma,
+    Display,
+    GamutMapping,
+    Clear,
+    IccProfile,
+    IccPlaceholder,
+    EncodingWith,
+    NoIccProfile,
+    Lut,
+    LutPlaceholder,
+    UsingLut,
+    NoLut,
+    Tonemap,
+    PostProcessing,
+    AddPass,
+    ResetCurve,
+    CurveInput,
+    CurveOutput,
+    CurveHelp,
+    Apply,
+    LivePreview,
+    ShowingProxy,
+    Resolution,
+    Custom,
+    Width,
+    Height,
+    Resize,
+    InvalidWidth,
+    InvalidHeight,
+    MustBeGreaterThanZero,
+    MustBeAtMost,
+    Backend,
+    CpuFallback,
+    AntiAliasing,
+    BucketOrder,
+    Pattern,
+    BlendSpace,
+    Blend,
+    AddStop,
+    RadialCenter,
+    Radius,
+    Frequency,
+    Octaves,
+    Lacunarity,
+    ResetView,
+    Iterations,
+    JuliaConstant,
+    FractalHelp,
+    Samples,
+    Bounces,
+    Operation,
+    Orbit,
+    Distance,
+    ExpressionError,
+    ExpressionHelp,
+    Overlay,
+    Opacity,
+    Seed,
+    Randomize,
+    Theme,
+    Background,
+    Accent,
+    Language,
+    Zoom,
+    Fit,
+    Present,
+    Clipped,
+    OutOfGamut,
+    PinAsA,
+    UnpinA,
+    SwapAB,
+    WipeHelp,
+    Rulers,
+    PixelGrid,
+    Thirds,
+    Center,
+    SafeAreas,
+    AddVerticalGuide,
+    AddHorizontalGuide,
+    MoveGuides,
+    ClearGuides,
+    Histogram,
+    View,
+    ClippingWarning,
+    FalseColorRange,
+    FalseColorClipping,
+    BracketStops,
+    NonFiniteLegend,
+    Scopes,
+    Statistics,
+    StatisticsValues,
+    Console,
+    TimesAreUtc,
+    History,
+    ShowPanel,
+    HidePanel,
+    RenderingFor,
+    RenderedIn,
+    ReducedQuality,
+    RenderingProgress,
+    RecentRenders,
+    Play,
+    Pause,
+    FrameOf,
+    Frames,
+    RenderSequence,
+    AbortSequence,
+    SequenceProgress,
+    InspectPixels,
+    Sample,
+    StagePixel,
+    AveragedOver,
+    StageLinear,
+    StageWhiteBalanced,
+    StageExposed,
+    StageTonemapped,
+    StageGamutMapped,
+    StageEncoded,
+    StageRgba8,
+    StageWarnings,
+    Skipped,
+    NoWarnings,
+    WarningClipped,
+    WarningOutOfGamut,
+    WarningClippedOutOfGamut,
+    DrawRegion,
+    ClearRegion,
+    RenderingRegion,
+    RenderingWholeImage,
+    Select,
+    ClearSelection,
+    ExportSelection,
+    SelectionAt,
+    LastSaved,
+    NotSavedYet,
+    NonFinitePixels,
+    Minimum,
+    Maximum,
+    Mean,
+    StandardDeviation,
+    CouldNotSave,
+    CouldNotOpen,
+    CouldNotRead,
+    CouldNotLoad,
+    CouldNotCopy,
+    SaveInterrupted,
+    PresetNameMissing,
+    UnsupportedImage,
+    DegenerateChromaticities,
+    BufferSizeMismatch,
+    TipRender,
+    TipCancel,
+    TipUndo,
+    TipRedo,
+    TipSave,
+    TipAovs,
+    TipSavePreset,
+    TipOpen,
+    TipExposure,
+    TipGamma,
+    TipDisplay,
+    TipTonemap,
+    TipPostProcessing,
+    TipApply,
+    TipResize,
+    TipPresent,
+    TipPanel,
+    TipExportSelection,
+    TipMoveGuides,
+    TipPixelGrid,
+    KeyboardShortcuts,
+    Close,
+    ActionRender,
+    ActionSave,
+    ActionUndo,
+    ActionRedo,
+    ActionCopy,
+    ActionEscape,
+    ActionTogglePresentation,
+    ActionToggleParameterPanel,
+    ActionToggleHelp,
+    ActionExposureUp,
+    ActionExposureDown,
+    ActionShowRed,
+    ActionShowGreen,
+    ActionShowBlue,
+    ActionShowAlpha,
+    ActionShowLuma,
+    ActionZoomToFit,
+    ActionZoom100,
+    ActionZoom200,
+    ActionZoomIn,
+    ActionZoomOut,
+    ActionSwapAB,
+}
+
+/// `text` in the current language
+pub fn tr(text: Text) -> &'static str {
+    translate(language(), text)
+}
+
+/// `text` in the current language, with its `{0}`, `{1}`... replaced by `args`
+pub fn tr_args(text: Text, args: &[&dyn Display]) -> String {
+    args.iter()
+        .enumerate()
+        .fold(tr(text).to_string(), |translated, (i, arg)| {
+            translated.replace(&format!("{{{i}}}"), &arg.to_string())
+        })
+}
+
+fn translate(language: Language, text: Text) -> &'static str {
+    match language {
+        Language::English => english(text),
+        Language::French => french(text),
+    }
+}
+
+fn english(text: Text) -> &'static str {
+    match text {
+        Text::Render => "Render",
+        Text::Cancel => "Cancel",
+        Text::Undo => "Undo",
+        Text::Redo => "Redo",
+        Text::Save => "Save",
+        Text::FileNamePlaceholder => "Your file name",
+        Text::WillSave => "Will save {0}",
+        Text::AlreadyExists => "{0} already exists",
+        Text::OverwriteQuestion => "{0} already exists. Overwrite it?",
+        Text::Overwrite => "Overwrite",
+        Text::SaveAsNewVersion => "Save as new version",
+        Text::AutoVersion => "Auto version",
+        Text::Aovs => "AOVs",
+        Text::Copy => "Copy",
+        Text::Saving => "Saving {0} file(s)...",
+        Text::Saved => "Saved {0}",
+        Text::CopiedImage => "Copied the image to the clipboard",
+        Text::Dismiss => "Dismiss",
+        Text::Preset => "Preset",
+        Text::PresetPlaceholder => "Load a preset",
+        Text::PresetNamePlaceholder => "Name of the preset to save",
+        Text::SavePreset => "Save preset",
+        Text::Open => "Open",
+        Text::OpenPlaceholder => "Path to an .exr, .png or .hdr file, or drop one onto the window",
+        Text::Recent => "Recent",
+        Text::RecentPlaceholder => "Reopen a recently opened or saved file",
+        Text::Reference => "Reference",
+        Text::Difference => "Difference",
+        Text::ReferencePlaceholder => "Image to compare the render to",
+        Text::Load => "Load",
+        Text::Heatmap => "Heatmap",
+        Text::Gain => "Gain {0}x",
+        Text::ReferenceSizeMismatch => "The reference is {0}, the render {1}",
+        Text::DifferingPixels => "{0} of {1} pixels differ ({2}%), by up to {3} ({4} on average)",
+        Text::Exposure => "Exposure",
+        Text::Alpha => "Alpha: {0}",
+        Text::Temperature => "Temperature: {0}K",
+        Text::Tint => "Tint: {0}",
+        Text::Gamma => "Gamma: {0}",
+        Text::Display => "Display",
+        Text::GamutMapping => "Gamut mapping",
+        Text::Clear => "Clear",
+        Text::IccProfile => "ICC profile",
+        Text::IccPlaceholder => "Path to the .icc/.icm profile of the monitor",
+        Text::EncodingWith => "Encoding with {0}",
+        Text::NoIccProfile => "No profile loaded, encoding for {0}",
+        Text::Lut => "LUT",
+        Text::LutPlaceholder => "Path to a .cube file",
+        Text::UsingLut => "Using {0}",
+        Text::NoLut => "No LUT loaded",
+        Text::Tonemap => "Tonemap",
+        Text::PostProcessing => "Post-processing",
+        Text::AddPass => "Add a pass",
+        Text::ResetCurve => "Reset curve",
+        Text::CurveInput => "Input: {0} to {1} stops around mid gray (the vertical line)",
+        Text::CurveOutput => "Output: display value, with a 2.2 gamma",
+        Text::CurveHelp => "Click to add a point, drag to move it, right click to remove it",
+        Text::Apply => "Apply",
+        Text::LivePreview => "Live preview",
+        Text::ShowingProxy => "Showing a low quality proxy of the pending changes",
+        Text::Resolution => "Resolution",
+        Text::Custom => "Custom",
+        Text::Width => "Width",
+        Text::Height => "Height",
+        Text::Resize => "Resize",
+        Text::InvalidWidth => "Width {0}",
+        Text::InvalidHeight => "Height {0}",
+        Text::MustBeGreaterThanZero => "must be greater than zero",
+        Text::MustBeAtMost => "must be at most {0}",
+        Text::Backend => "Backend",
+        Text::CpuFallback => "{0} can't render this pattern, using the CPU",
+        Text::AntiAliasing => "Anti-aliasing",
+        Text::BucketOrder => "Bucket order",
+        Text::Pattern => "Pattern",
+        Text::BlendSpace => "Blend space",
+        Text::Blend => "Blend: {0}",
+        Text::AddStop => "Add stop",
+        Text::RadialCenter => "Center: {0}, {1}",
+        Text::Radius => "Radius: {0}",
+        Text::Frequency => "Frequency: {0}",
+        Text::Octaves => "Octaves: {0}",
+        Text::Lacunarity => "Lacunarity: {0}",
+        Text::ResetView => "Reset view",
+        Text::Iterations => "Iterations: {0}",
+        Text::JuliaConstant => "Constant: {0}, {1}",
+        Text::FractalHelp => "Click to zoom in, right click to zoom out, drag to pan, scroll to zoom",
+        Text::Samples => "Samples",
+        Text::Bounces => "Bounces: {0}",
+        Text::Operation => "Operation",
+        Text::Orbit => "Orbit: {0}°, {1}°",
+        Text::Distance => "Distance: {0}",
+        Text::ExpressionError => "Error: {0}",
+        Text::ExpressionHelp => "Variables: u, v, x, y, t, pi, e. Functions: sin, cos, tan, abs, sqrt, exp, ln, floor, fract, pow, min, max, clamp, mix. Use r, g, b for colors.",
+        Text::Overlay => "Overlay a second scene",
+        Text::Opacity => "Opacity: {0}",
+        Text::Seed => "Seed",
+        Text::Randomize => "Randomize",
+        Text::Theme => "Theme",
+        Text::Background => "Background",
+        Text::Accent => "Accent",
+        Text::Language => "Language",
+        Text::Zoom => "Zoom {0}%",
+        Text::Fit => "Fit",
+        Text::Present => "Present",
+        Text::Clipped => "Clipped",
+        Text::OutOfGamut => "Out of sRGB gamut",
+        Text::PinAsA => "Pin as A",
+        Text::UnpinA => "Unpin A",
+        Text::SwapAB => "Swap A/B",
+        Text::WipeHelp => "A on the left, B on the right: click or drag over the image to move the wipe",
+        Text::Rulers => "Rulers",
+        Text::PixelGrid => "Pixel grid",
+        Text::Thirds => "Thirds",
+        Text::Center => "Center",
+        Text::SafeAreas => "Safe areas",
+        Text::AddVerticalGuide => "Add vertical guide",
+        Text::AddHorizontalGuide => "Add horizontal guide",
+        Text::MoveGuides => "Move guides",
+        Text::ClearGuides => "Clear guides",
+        Text::Histogram => "Histogram",
+        Text::View => "View",
+        Text::ClippingWarning => "Clipping warning",
+        Text::FalseColorRange => "{0} to {1}",
+        Text::FalseColorClipping => "Clipping (> {0})",
+        Text::BracketStops => "Stops from the exposure, left to right and top to bottom: {0}",
+        Text::NonFiniteLegend => "■ NaN or infinite ({0} pixels)",
+        Text::Scopes => "Scopes",
+        Text::Statistics => "Statistics",
+        Text::StatisticsValues => "Scene linear ACEScg values",
+        Text::Console => "Console",
+        Text::TimesAreUtc => "Times are UTC",
+        Text::History => "History",
+        Text::ShowPanel => "Show panel",
+        Text::HidePanel => "Hide panel",
+        Text::RenderingFor => "Rendering for {0}s",
+        Text::RenderedIn => "Rendered in {0}s",
+        Text::ReducedQuality => "Reduced quality while interacting",
+        Text::RenderingProgress => "Rendering... {0}%",
+        Text::RecentRenders => "Recent renders",
+        Text::Play => "Play",
+        Text::Pause => "Pause",
+        Text::FrameOf => "Frame {0}/{1}",
+        Text::Frames => "Frames",
+        Text::RenderSequence => "Render & save sequence",
+        Text::AbortSequence => "Abort sequence",
+        Text::SequenceProgress => "Frame {0} of {1}, {2} saved",
+        Text::InspectPixels => "Inspect pixels",
+        Text::Sample => "Sample",
+        Text::StagePixel => "Pixel",
+        Text::AveragedOver => "{0}, averaged over {1}",
+        Text::StageLinear => "Linear ACEScg",
+        Text::StageWhiteBalanced => "White balanced",
+        Text::StageExposed => "Exposed",
+        Text::StageTonemapped => "Tonemapped",
+        Text::StageGamutMapped => "Gamut mapped ({0})",
+        Text::StageEncoded => "Encoded {0}",
+        Text::StageRgba8 => "8bit RGBA",
+        Text::StageWarnings => "Warnings",
+        Text::Skipped => "skipped",
+        Text::NoWarnings => "none",
+        Text::WarningClipped => "clipped",
+        Text::WarningOutOfGamut => "out of sRGB gamut",
+        Text::WarningClippedOutOfGamut => "clipped, out of sRGB gamut",
+        Text::DrawRegion => "Draw region",
+        Text::ClearRegion => "Clear region",
+        Text::RenderingRegion => "Rendering only {0} at {1}",
+        Text::RenderingWholeImage => "Rendering the whole image",
+        Text::Select => "Select",
+        Text::ClearSelection => "Clear selection",
+        Text::ExportSelection => "Export selection",
+        Text::SelectionAt => "{0} at {1}",
+        Text::LastSaved => "Last saved: {0}",
+        Text::NotSavedYet => "Not saved yet",
+        Text::NonFinitePixels => "{0} pixels are NaN or infinite",
+        Text::Minimum => "Min",
+        Text::Maximum => "Max",
+        Text::Mean => "Mean",
+        Text::StandardDeviation => "Std dev",
+        Text::CouldNotSave => "Could not save {0}: {1}",
+        Text::CouldNotOpen => "Could not open {0}: {1}",
+        Text::CouldNotRead => "Could not read {0}: {1}",
+        Text::CouldNotLoad => "Could not load {0}: {1}",
+        Text::CouldNotCopy => "Could not copy the image: {0}",
+        Text::SaveInterrupted => "Could not save {0}: the save was interrupted",
+        Text::PresetNameMissing => "Type a name for the preset first",
+        Text::UnsupportedImage => "only .exr, .png and .hdr files can be opened",
+        Text::DegenerateChromaticities => "the chromaticities are degenerate",
+        Text::BufferSizeMismatch => "the buffer doesn't match the resolution",
+        Text::TipRender => "Render the pattern again with the current settings",
+        Text::TipCancel => "Stop the render in progress",
+        Text::TipUndo => "Go back to the settings before the last change",
+        Text::TipRedo => "Bring back the last undone change",
+        Text::TipSave => "Write the render to the file. EXR and HDR keep the scene linear values,\nPNG, TIFF and DPX the display referred pixels shown in the viewer.",
+        Text::TipAovs => "Also write the extra layers of the render (UVs, depth...) into the EXR",
+        Text::TipSavePreset => "Keep the render and display settings under this name",
+        Text::TipOpen => "Look at an image through the display pipeline instead of the render",
+        Text::TipExposure => "Brighten or darken the image by stops, before the tonemap",
+        Text::TipGamma => "Extra gamma applied after the tonemap",
+        Text::TipDisplay => "The color space of the monitor the image is encoded for",
+        Text::TipTonemap => "How the unbounded scene values are compressed into the display range",
+        Text::TipPostProcessing => "Passes run from top to bottom on the scene linear image, once it's fully rendered",
+        Text::TipApply => "Apply the pending display changes at full quality",
+        Text::TipResize => "Render at the width and height entered",
+        Text::TipPresent => "Show only the image, full screen. Escape leaves.",
+        Text::TipPanel => "Hide or show the parameters",
+        Text::TipExportSelection => "Save the selected pixels alone, in the output format, as <file name>_selection",
+        Text::TipMoveGuides => "Drag the guides over the viewer, out of the image to remove them",
+        Text::TipPixelGrid => "Outline the pixels when zoomed in to 800% or more",
+        Text::KeyboardShortcuts => "Keyboard shortcuts",
+        Text::Close => "Close",
+        Text::ActionRender => "Render",
+        Text::ActionSave => "Save",
+        Text::ActionUndo => "Undo",
+        Text::ActionRedo => "Redo",
+        Text::ActionCopy => "Copy the image",
+        Text::ActionEscape => "Leave the presentation, or cancel the render",
+        Text::ActionTogglePresentation => "Present the image full screen",
+        Text::ActionToggleParameterPanel => "Hide or show the parameters",
+        Text::ActionToggleHelp => "Show or hide these shortcuts",
+        Text::ActionExposureUp => "Half a stop brighter",
+        Text::ActionExposureDown => "Half a stop darker",
+        Text::ActionShowRed => "Show the red channel",
+        Text::ActionShowGreen => "Show the green channel",
+        Text::ActionShowBlue => "Show the blue channel",
+        Text::ActionShowAlpha => "Show the alpha channel",
+        Text::ActionShowLuma => "Show the luma",
+        Text::ActionZoomToFit => "Fit the image",
+        Text::ActionZoom100 => "Zoom to 100%",
+        Text::ActionZoom200 => "Zoom to 200%",
+        Text::ActionZoomIn => "Zoom in",
+        Text::ActionZoomOut => "Zoom out",
+        Text::ActionSwapAB => "Swap the A and B images",
+    }
+}
+
+fn french(text: Text) -> &'static str {
+    match text {
+        Text::Render => "Rendu",
+        Text::Cancel => "Annuler",
+        Text::Undo => "Défaire",
+        Text::Redo => "Refaire",
+        Text::Save => "Enregistrer",
+        Text::FileNamePlaceholder => "Nom du fichier",
+        Text::WillSave => "Enregistrera {0}",
+        Text::AlreadyExists => "{0} existe déjà",
+        Text::OverwriteQuestion => "{0} existe déjà. L'écraser ?",
+        Text::Overwrite => "Écraser",
+        Text::SaveAsNewVersion => "Enregistrer une nouvelle version",
+        Text::AutoVersion => "Version automatique",
+        Text::Aovs => "AOV",
+        Text::Copy => "Copier",
+        Text::Saving => "Enregistrement de {0} fichier(s)...",
+        Text::Saved => "{0} enregistré",
+        Text::CopiedImage => "Image copiée dans le presse-papiers",
+        Text::Dismiss => "Fermer",
+        Text::Preset => "Préréglage",
+        Text::PresetPlaceholder => "Charger un préréglage",
+        Text::PresetNamePlaceholder => "Nom du préréglage à enregistrer",
+        Text::SavePreset => "Enregistrer le préréglage",
+        Text::Open => "Ouvrir",
+        Text::OpenPlaceholder => {
+            "Chemin d'un fichier .exr, .png ou .hdr, ou déposez-en un sur la fenêtre"
+        }
+        Text::Recent => "Récents",
+        Text::RecentPlaceholder => "Rouvrir un fichier récemment ouvert ou enregistré",
+        Text::Reference => "Référence",
+        Text::Difference => "Différence",
+        Text::ReferencePlaceholder => "Image à comparer au rendu",
+        Text::Load => "Charger",
+        Text::Heatmap => "Carte de chaleur",
+        Text::Gain => "Gain {0}x",
+        Text::ReferenceSizeMismatch => "La référence fait {0}, le rendu {1}",
+        Text::DifferingPixels => "{0} pixels sur {1} diffèrent ({2} %), jusqu'à {3} ({4} en moyenne)",
+        Text::Exposure => "Exposition",
+        Text::Alpha => "Alpha : {0}",
+        Text::Temperature => "Température : {0} K",
+        Text::Tint => "Teinte : {0}",
+        Text::Gamma => "Gamma : {0}",
+        Text::Display => "Affichage",
+        Text::GamutMapping => "Mappage du gamut",
+        Text::Clear => "Effacer",
+        Text::IccProfile => "Profil ICC",
+        Text::IccPlaceholder => "Chemin du profil .icc/.icm du moniteur",
+        Text::EncodingWith => "Encodage avec {0}",
+        Text::NoIccProfile => "Aucun profil chargé, encodage pour {0}",
+        Text::Lut => "LUT",
+        Text::LutPlaceholder => "Chemin d'un fichier .cube",
+        Text::UsingLut => "Utilise {0}",
+        Text::NoLut => "Aucune LUT chargée",
+        Text::Tonemap => "Mappage tonal",
+        Text::PostProcessing => "Post-traitement",
+        Text::AddPass => "Ajouter une passe",
+        Text::ResetCurve => "Réinitialiser la courbe",
+        Text::CurveInput => "Entrée : de {0} à {1} diaphragmes autour du gris moyen (la ligne verticale)",
+        Text::CurveOutput => "Sortie : valeur de l'affichage, avec un gamma de 2,2",
+        Text::CurveHelp => "Cliquer pour ajouter un point, le faire glisser pour le déplacer, clic droit pour le supprimer",
+        Text::Apply => "Appliquer",
+        Text::LivePreview => "Aperçu en direct",
+        Text::ShowingProxy => "Aperçu en basse qualité des modifications en attente",
+        Text::Resolution => "Résolution",
+        Text::Custom => "Personnalisée",
+        Text::Width => "Largeur",
+        Text::Height => "Hauteur",
+        Text::Resize => "Redimensionner",
+        Text::InvalidWidth => "Largeur : {0}",
+        Text::InvalidHeight => "Hauteur : {0}",
+        Text::MustBeGreaterThanZero => "doit être supérieure à zéro",
+        Text::MustBeAtMost => "doit être au plus {0}",
+        Text::Backend => "Moteur",
+        Text::CpuFallback => "{0} ne peut pas rendre ce motif, rendu sur le CPU",
+        Text::AntiAliasing => "Anticrénelage",
+        Text::BucketOrder => "Ordre des tuiles",
+        Text::Pattern => "Motif",
+        Text::BlendSpace => "Espace de mélange",
+        Text::Blend => "Mélange : {0}",
+        Text::AddStop => "Ajouter un arrêt",
+        Text::RadialCenter => "Centre : {0}, {1}",
+        Text::Radius => "Rayon : {0}",
+        Text::Frequency => "Fréquence : {0}",
+        Text::Octaves => "Octaves : {0}",
+        Text::Lacunarity => "Lacunarité : {0}",
+        Text::ResetView => "Réinitialiser la vue",
+        Text::Iterations => "Itérations : {0}",
+        Text::JuliaConstant => "Constante : {0}, {1}",
+        Text::FractalHelp => "Cliquer pour zoomer, clic droit pour dézoomer, glisser pour se déplacer, molette pour zoomer",
+        Text::Samples => "Échantillons",
+        Text::Bounces => "Rebonds : {0}",
+        Text::Operation => "Opération",
+        Text::Orbit => "Orbite : {0}°, {1}°",
+        Text::Distance => "Distance : {0}",
+        Text::ExpressionError => "Erreur : {0}",
+        Text::ExpressionHelp => "Variables : u, v, x, y, t, pi, e. Fonctions : sin, cos, tan, abs, sqrt, exp, ln, floor, fract, pow, min, max, clamp, mix. r, g, b pour les couleurs.",
+        Text::Overlay => "Superposer une deuxième scène",
+        Text::Opacity => "Opacité : {0}",
+        Text::Seed => "Graine",
+        Text::Randomize => "Au hasard",
+        Text::Theme => "Thème",
+        Text::Background => "Fond",
+        Text::Accent => "Accent",
+        Text::Language => "Langue",
+        Text::Zoom => "Zoom {0} %",
+        Text::Fit => "Ajuster",
+        Text::Present => "Présenter",
+        Text::Clipped => "Écrêté",
+        Text::OutOfGamut => "Hors du gamut sRGB",
+        Text::PinAsA => "Épingler comme A",
+        Text::UnpinA => "Détacher A",
+        Text::SwapAB => "Échanger A/B",
+        Text::WipeHelp => "A à gauche, B à droite : cliquer ou glisser sur l'image pour déplacer le volet",
+        Text::Rulers => "Règles",
+        Text::PixelGrid => "Grille des pixels",
+        Text::Thirds => "Tiers",
+        Text::Center => "Centre",
+        Text::SafeAreas => "Zones de sécurité",
+        Text::AddVerticalGuide => "Ajouter un repère vertical",
+        Text::AddHorizontalGuide => "Ajouter un repère horizontal",
+        Text::MoveGuides => "Déplacer les repères",
+        Text::ClearGuides => "Effacer les repères",
+        Text::Histogram => "Histogramme",
+        Text::View => "Vue",
+        Text::ClippingWarning => "Alerte d'écrêtage",
+        Text::FalseColorRange => "{0} à {1}",
+        Text::FalseColorClipping => "Écrêtage (> {0})",
+        Text::BracketStops => "Diaphragmes depuis l'exposition, de gauche à droite et de haut en bas : {0}",
+        Text::NonFiniteLegend => "■ NaN ou infini ({0} pixels)",
+        Text::Scopes => "Oscilloscopes",
+        Text::Statistics => "Statistiques",
+        Text::StatisticsValues => "Valeurs ACEScg linéaires de la scène",
+        Text::Console => "Console",
+        Text::TimesAreUtc => "Heures en UTC",
+        Text::History => "Historique",
+        Text::ShowPanel => "Afficher le panneau",
+        Text::HidePanel => "Masquer le panneau",
+        Text::RenderingFor => "Rendu depuis {0} s",
+        Text::RenderedIn => "Rendu en {0} s",
+        Text::ReducedQuality => "Qualité réduite pendant l'interaction",
+        Text::RenderingProgress => "Rendu... {0} %",
+        Text::RecentRenders => "Rendus récents",
+        Text::Play => "Lecture",
+        Text::Pause => "Pause",
+        Text::FrameOf => "Image {0}/{1}",
+        Text::Frames => "Images",
+        Text::RenderSequence => "Rendre et enregistrer la séquence",
+        Text::AbortSequence => "Interrompre la séquence",
+        Text::SequenceProgress => "Image {0} sur {1}, {2} enregistrées",
+        Text::InspectPixels => "Inspecter les pixels",
+        Text::Sample => "Échantillon",
+        Text::StagePixel => "Pixel",
+        Text::AveragedOver => "{0}, moyenne sur {1}",
+        Text::StageLinear => "ACEScg linéaire",
+        Text::StageWhiteBalanced => "Balance des blancs",
+        Text::StageExposed => "Exposé",
+        Text::StageTonemapped => "Mappage tonal",
+        Text::StageGamutMapped => "Gamut mappé ({0})",
+        Text::StageEncoded => "Encodé {0}",
+        Text::StageRgba8 => "RGBA 8 bits",
+        Text::StageWarnings => "Alertes",
+        Text::Skipped => "ignoré",
+        Text::NoWarnings => "aucune",
+        Text::WarningClipped => "écrêté",
+        Text::WarningOutOfGamut => "hors du gamut sRGB",
+        Text::WarningClippedOutOfGamut => "écrêté, hors du gamut sRGB",
+        Text::DrawRegion => "Tracer une région",
+        Text::ClearRegion => "Effacer la région",
+        Text::RenderingRegion => "Rendu de {0} seulement, à {1}",
+        Text::RenderingWholeImage => "Rendu de toute l'image",
+        Text::Select => "Sélectionner",
+        Text::ClearSelection => "Effacer la sélection",
+        Text::ExportSelection => "Exporter la sélection",
+        Text::SelectionAt => "{0} à {1}",
+        Text::LastSaved => "Dernier enregistrement : {0}",
+        Text::NotSavedYet => "Pas encore enregistré",
+        Text::NonFinitePixels => "{0} pixels sont NaN ou infinis",
+        Text::Minimum => "Min",
+        Text::Maximum => "Max",
+        Text::Mean => "Moyenne",
+        Text::StandardDeviation => "Écart type",
+        Text::CouldNotSave => "Impossible d'enregistrer {0} : {1}",
+        Text::CouldNotOpen => "Impossible d'ouvrir {0} : {1}",
+        Text::CouldNotRead => "Impossible de lire {0} : {1}",
+        Text::CouldNotLoad => "Impossible de charger {0} : {1}",
+        Text::CouldNotCopy => "Impossible de copier l'image : {0}",
+        Text::SaveInterrupted => "Impossible d'enregistrer {0} : l'enregistrement a été interrompu",
+        Text::PresetNameMissing => "Saisissez d'abord un nom pour le préréglage",
+        Text::UnsupportedImage => "seuls les fichiers .exr, .png et .hdr peuvent être ouverts",
+        Text::DegenerateChromaticities => "les chromaticités sont dégénérées",
+        Text::BufferSizeMismatch => "le tampon ne correspond pas à la résolution",
+        Text::TipRender => "Refaire le rendu du motif avec les réglages actuels",
+        Text::TipCancel => "Arrêter le rendu en cours",
+        Text::TipUndo => "Revenir aux réglages d'avant la dernière modification",
+        Text::TipRedo => "Rétablir la dernière modification défaite",
+        Text::TipSave => "Écrire le rendu dans le fichier. EXR et HDR gardent les valeurs linéaires de la scène,\nPNG, TIFF et DPX les pixels de l'affichage montrés dans la visionneuse.",
+        Text::TipAovs => "Écrire aussi les calques supplémentaires du rendu (UV, profondeur...) dans l'EXR",
+        Text::TipSavePreset => "Garder les réglages du rendu et de l'affichage sous ce nom",
+        Text::TipOpen => "Voir une image à travers la chaîne d'affichage à la place du rendu",
+        Text::TipExposure => "Éclaircir ou assombrir l'image par diaphragmes, avant le mappage tonal",
+        Text::TipGamma => "Gamma supplémentaire appliqué après le mappage tonal",
+        Text::TipDisplay => "L'espace colorimétrique du moniteur pour lequel l'image est encodée",
+        Text::TipTonemap => "Comment les valeurs illimitées de la scène sont ramenées dans la plage de l'affichage",
+        Text::TipPostProcessing => "Passes appliquées de haut en bas à l'image linéaire de la scène, une fois entièrement rendue",
+        Text::TipApply => "Appliquer en pleine qualité les modifications de l'affichage en attente",
+        Text::TipResize => "Faire le rendu à la largeur et la hauteur saisies",
+        Text::TipPresent => "Ne montrer que l'image, en plein écran. Échap pour sortir.",
+        Text::TipPanel => "Masquer ou afficher les paramètres",
+        Text::TipExportSelection => "Enregistrer les pixels sélectionnés seuls, au format de sortie, sous <nom du fichier>_selection",
+        Text::TipMoveGuides => "Déplacer les repères dans la visionneuse, hors de l'image pour les supprimer",
+        Text::TipPixelGrid => "Délimiter les pixels à partir d'un zoom de 800%",
+        Text::KeyboardShortcuts => "Raccourcis clavier",
+        Text::Close => "Fermer",
+        Text::ActionRender => "Rendu",
+        Text::ActionSave => "Enregistrer",
+        Text::ActionUndo => "Défaire",
+        Text::ActionRedo => "Refaire",
+        Text::ActionCopy => "Copier l'image",
+        Text::ActionEscape => "Quitter la présentation, ou annuler le rendu",
+        Text::ActionTogglePresentation => "Présenter l'image en plein écran",
+        Text::ActionToggleParameterPanel => "Masquer ou afficher les paramètres",
+        Text::ActionToggleHelp => "Afficher ou masquer ces raccourcis",
+        Text::ActionExposureUp => "Un demi-diaphragme plus clair",
+        Text::ActionExposureDown => "Un demi-diaphragme plus sombre",
+        Text::ActionShowRed => "Montrer le canal rouge",
+        Text::ActionShowGreen => "Montrer le canal vert",
+        Text::ActionShowBlue => "Montrer le canal bleu",
+        Text::ActionShowAlpha => "Montrer le canal alpha",
+        Text::ActionShowLuma => "Montrer la luminance",
+        Text::ActionZoomToFit => "Ajuster l'image",
+        Text::ActionZoom100 => "Zoom à 100 %",
+        Text::ActionZoom200 => "Zoom à 200 %",
+        Text::ActionZoomIn => "Zoom avant",
+        Text::ActionZoomOut => "Zoom arrière",
+        Text::ActionSwapAB => "Échanger les images A et B",
+    }
+}
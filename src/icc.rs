@@ -0,0 +1,305 @@
+//! Matrix/TRC ICC display profiles, the kind that monitor calibration tools produce.
+//! LUT based profiles (A2B/B2A tags) are not supported.
+//! See https://www.color.org/specification/ICC.1-2022-05.pdf
+use std::path::Path;
+
+use crate::i18n::{tr_args, Text};
+use crate::white_balance::{chromatic_adaptation, invert, multiply, multiply_vector, Matrix};
+
+/// White of the profile connection space, that the colorants are adapted to
+const D50: [f32; 3] = [0.9642, 1.0, 0.8249];
+/// White of the CIE XYZ values coming out of the display pipeline
+const D65: [f32; 3] = [0.950_47, 1.0, 1.088_83];
+/// Number of entries of the inverted tone curves
+const INVERSE_CURVE_SIZE: usize = 4096;
+
+/// Tone curve of a channel, from encoded to linear values
+enum ToneCurve {
+    Gamma(f32),
+    /// Evenly spaced samples over 0..1
+    Table(Vec<f32>),
+    /// The function types of the `para` tag, with their parameters
+    /// g, a, b, c, d, e, f (missing ones are zero)
+    Parametric(u16, [f32; 7]),
+}
+
+impl ToneCurve {
+    fn evaluate(&self, x: f32) -> f32 {
+        match self {
+            ToneCurve::Gamma(gamma) => x.powf(*gamma),
+            ToneCurve::Table(table) => {
+                let position = x * (table.len() - 1) as f32;
+                let index = (position.floor() as usize).min(table.len() - 2);
+                let fraction = position - index as f32;
+                table[index] + fraction * (table[index + 1] - table[index])
+            }
+            ToneCurve::Parametric(function, [g, a, b, c, d, e, f]) => {
+                // Clamping also takes care of types 1 and 2 being 0 (plus c) below -b/a
+                let power = |x: f32| (a * x + b).max(0.0).powf(*g);
+                match function {
+                    0 => x.powf(*g),
+                    1 => power(x),
+                    2 => power(x) + c,
+                    3 if x >= *d => power(x),
+                    3 => c * x,
+                    _ if x >= *d => power(x) + e,
+                    _ => c * x + f,
+                }
+            }
+        }
+    }
+
+    /// Sample the inverse of the curve (from linear to encoded) over 0..1,
+    /// assuming that it's increasing
+    fn inverse_table(&self) -> Vec<f32> {
+        (0..INVERSE_CURVE_SIZE)
+            .map(|i| {
+                let target = i as f32 / (INVERSE_CURVE_SIZE - 1) as f32;
+                let (mut low, mut high) = (0.0_f32, 1.0_f32);
+                for _ in 0..24 {
+                    let middle = (low + high) / 2.0;
+                    if self.evaluate(middle) < target {
+                        low = middle;
+                    } else {
+                        high = middle;
+                    }
+                }
+                (low + high) / 2.0
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct IccProfile {
+    /// Name of the file it was loaded from, for display
+    pub name: String,
+    /// From D65 CIE XYZ to the linear values of the display
+    from_xyz: Matrix,
+    /// From linear to encoded values, for each channel
+    inverse_curves: [Vec<f32>; 3],
+}
+
+impl IccProfile {
+    pub fn load(path: &Path) -> Result<IccProfile, String> {
+        let bytes =
+            std::fs::read(path).map_err(|e| tr_args(Text::CouldNotRead, &[&path.display(), &e]))?;
+        let name = path.file_name().map_or_else(
+            || path.display().to_string(),
+            |name| name.to_string_lossy().into(),
+        );
+
+        IccProfile::parse(&name, &bytes).map_err(|e| tr_args(Text::CouldNotLoad, &[&name, &e]))
+    }
+
+    pub fn parse(name: &str, bytes: &[u8]) -> Result<IccProfile, String> {
+        if bytes.get(36..40) != Some(b"acsp") {
+            return Err(String::from("not an ICC profile"));
+        }
+        if bytes.get(16..20) != Some(b"RGB ") {
+            return Err(String::from("only RGB display profiles are supported"));
+        }
+        if bytes.get(20..24) != Some(b"XYZ ") {
+            return Err(String::from(
+                "only profiles with an XYZ connection space are supported",
+            ));
+        }
+
+        let tag = |signature: &[u8; 4]| -> Result<&[u8], String> {
+            let tag_count = read_u32(bytes, 128)? as usize;
+            for index in 0..tag_count {
+                let entry = 132 + index * 12;
+                if bytes.get(entry..entry + 4) == Some(signature) {
+                    let offset = read_u32(bytes, entry + 4)? as usize;
+                    let size = read_u32(bytes, entry + 8)? as usize;
+                    return bytes
+                        .get(offset..offset + size)
+                        .ok_or_else(|| format!("truncated {} tag", tag_name(signature)));
+                }
+            }
+            Err(format!(
+                "missing {} tag, only matrix/TRC profiles are supported",
+                tag_name(signature)
+            ))
+        };
+
+        // The colorants are the columns of the matrix from linear values to D50 CIE XYZ
+        let mut to_xyz = [[0.0; 3]; 3];
+        for (column, signature) in [b"rXYZ", b"gXYZ", b"bXYZ"].into_iter().enumerate() {
+            let xyz = parse_xyz(tag(signature)?)
+                .map_err(|e| format!("{} tag: {e}", tag_name(signature)))?;
+            for (row, value) in xyz.into_iter().enumerate() {
+                to_xyz[row][column] = value;
+            }
+        }
+        let from_xyz = multiply(
+            &invert(&to_xyz).ok_or("the colorants are not linearly independent")?,
+            &chromatic_adaptation(D65, D50),
+        );
+
+        let mut inverse_curves = [Vec::new(), Vec::new(), Vec::new()];
+        for (inverse_curve, signature) in inverse_curves.iter_mut().zip([b"rTRC", b"gTRC", b"bTRC"])
+        {
+            let curve = parse_curve(tag(signature)?)
+                .map_err(|e| format!("{} tag: {e}", tag_name(signature)))?;
+            *inverse_curve = curve.inverse_table();
+        }
+
+        Ok(IccProfile {
+            name: String::from(name),
+            from_xyz,
+            inverse_curves,
+        })
+    }
+
+    /// Encode D65 CIE XYZ values for the display
+    pub fn encode(&self, xyz: [f32; 3]) -> [f32; 3] {
+        let linear = multiply_vector(&self.from_xyz, xyz);
+        let mut encoded = [0.0; 3];
+        for (channel, value) in encoded.iter_mut().enumerate() {
+            let table = &self.inverse_curves[channel];
+            let position = linear[channel].clamp(0.0, 1.0) * (table.len() - 1) as f32;
+            let index = (position.floor() as usize).min(table.len() - 2);
+            let fraction = position - index as f32;
+            *value = table[index] + fraction * (table[index + 1] - table[index]);
+        }
+        encoded
+    }
+}
+
+fn tag_name(signature: &[u8; 4]) -> String {
+    String::from_utf8_lossy(signature).into()
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Result<u32, String> {
+    bytes
+        .get(offset..offset + 4)
+        .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or_else(|| String::from("unexpected end of file"))
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> Result<u16, String> {
+    bytes
+        .get(offset..offset + 2)
+        .map(|b| u16::from_be_bytes([b[0], b[1]]))
+        .ok_or_else(|| String::from("unexpected end of file"))
+}
+
+fn read_s15_fixed16(bytes: &[u8], offset: usize) -> Result<f32, String> {
+    Ok(read_u32(bytes, offset)? as i32 as f32 / 65536.0)
+}
+
+fn parse_xyz(tag: &[u8]) -> Result<[f32; 3], String> {
+    if tag.get(0..4) != Some(b"XYZ ") {
+        return Err(String::from("expected an XYZ type"));
+    }
+    Ok([
+        read_s15_fixed16(tag, 8)?,
+        read_s15_fixed16(tag, 12)?,
+        read_s15_fixed16(tag, 16)?,
+    ])
+}
+
+fn parse_curve(tag: &[u8]) -> Result<ToneCurve, String> {
+    match tag.get(0..4) {
+        Some(b"curv") => {
+            let count = read_u32(tag, 8)? as usize;
+            match count {
+                0 => Ok(ToneCurve::Gamma(1.0)),
+                // A single u8Fixed8Number
+                1 => Ok(ToneCurve::Gamma(read_u16(tag, 12)? as f32 / 256.0)),
+                _ => (0..count)
+                    .map(|i| Ok(read_u16(tag, 12 + i * 2)? as f32 / 65535.0))
+                    .collect::<Result<_, String>>()
+                    .map(ToneCurve::Table),
+            }
+        }
+        Some(b"para") => {
+            let function = read_u16(tag, 8)?;
+            let parameter_count = match function {
+                0 => 1,
+                1 => 3,
+                2 => 4,
+                3 => 5,
+                4 => 7,
+                _ => return Err(format!("unknown parametric curve type {function}")),
+            };
+            let mut parameters = [0.0; 7];
+            for (i, parameter) in parameters.iter_mut().take(parameter_count).enumerate() {
+                *parameter = read_s15_fixed16(tag, 12 + i * 4)?;
+            }
+            Ok(ToneCurve::Parametric(function, parameters))
+        }
+        _ => Err(String::from("expected a curv or para type")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use colstodian::color;
+    use colstodian::spaces::{CieXYZ, EncodedSrgb};
+
+    use super::*;
+
+    /// A minimal matrix/TRC profile, with the D50 adapted sRGB colorants and the sRGB curve
+    fn srgb_icc_bytes() -> Vec<u8> {
+        let fixed = |value: f32| ((value * 65536.0).round() as i32).to_be_bytes();
+        let xyz_tag = |xyz: [f32; 3]| {
+            let mut tag = b"XYZ \0\0\0\0".to_vec();
+            xyz.into_iter().for_each(|value| tag.extend(fixed(value)));
+            tag
+        };
+        let mut curve = b"para\0\0\0\0\0\x03\0\0".to_vec();
+        for parameter in [2.4, 1.0 / 1.055, 0.055 / 1.055, 1.0 / 12.92, 0.04045] {
+            curve.extend(fixed(parameter));
+        }
+        let tags = [
+            (b"rXYZ", xyz_tag([0.4361, 0.2225, 0.0139])),
+            (b"gXYZ", xyz_tag([0.3851, 0.7169, 0.0971])),
+            (b"bXYZ", xyz_tag([0.1431, 0.0606, 0.7141])),
+            (b"rTRC", curve.clone()),
+            (b"gTRC", curve.clone()),
+            (b"bTRC", curve),
+        ];
+
+        let mut header = vec![0; 128];
+        header[16..20].copy_from_slice(b"RGB ");
+        header[20..24].copy_from_slice(b"XYZ ");
+        header[36..40].copy_from_slice(b"acsp");
+        header.extend((tags.len() as u32).to_be_bytes());
+
+        let mut data = Vec::new();
+        let data_start = header.len() + tags.len() * 12;
+        for (signature, tag) in tags {
+            header.extend(signature);
+            header.extend(((data_start + data.len()) as u32).to_be_bytes());
+            header.extend((tag.len() as u32).to_be_bytes());
+            data.extend(tag);
+        }
+        header.extend(data);
+        header
+    }
+
+    #[test]
+    fn srgb_profile_matches_the_builtin_encoding() {
+        let profile = IccProfile::parse("sRGB.icc", &srgb_icc_bytes()).unwrap();
+        for [r, g, b] in [[0.18, 0.18, 0.18], [0.8, 0.2, 0.1], [0.05, 0.4, 0.9]] {
+            let linear = color::linear_srgb::<colstodian::Display>(r, g, b);
+            let expected = linear.convert::<EncodedSrgb>();
+            let xyz = linear.convert::<CieXYZ>();
+            let encoded = profile.encode([xyz.x, xyz.y, xyz.z]);
+            for (channel, expected) in encoded
+                .into_iter()
+                .zip([expected.r, expected.g, expected.b])
+            {
+                assert!((channel - expected).abs() < 2e-3, "{channel} != {expected}");
+            }
+        }
+    }
+
+    #[test]
+    fn truncated_profiles_are_errors() {
+        let bytes = srgb_icc_bytes();
+        assert!(IccProfile::parse("truncated.icc", &bytes[..100]).is_err());
+    }
+}
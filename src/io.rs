@@ -0,0 +1,436 @@
+//! Reading and writing images on disk
+use std::path::Path;
+
+use colstodian::spaces::{AcesCg, CieXYZ, LinearSrgb};
+use colstodian::{color, Color, Scene};
+
+use exr::meta::attribute::{AttributeValue, Chromaticities, Text};
+use exr::prelude::{
+    AnyChannel, AnyChannels, Encoding, FlatSamples, Image, ImageAttributes, IntegerBounds, Layer,
+    LayerAttributes, SpecificChannels, Vec2, WritableImage,
+};
+
+use crate::i18n::{self, tr, tr_args};
+use crate::render::Framebuffer;
+use crate::white_balance::{chromatic_adaptation, invert, multiply, multiply_vector, Matrix};
+
+/// Primaries and white point of ACEScg, so that other applications know how
+/// to interpret the scene linear values
+/// See https://docs.acescentral.com/specifications/acescg/
+const ACESCG_CHROMATICITIES: Chromaticities = Chromaticities {
+    red: Vec2(0.713, 0.293),
+    green: Vec2(0.165, 0.830),
+    blue: Vec2(0.128, 0.044),
+    white: Vec2(0.32168, 0.33767),
+};
+
+/// Name and version of the application, as stored in the files it writes
+const SOFTWARE: &str = concat!(env!("CARGO_PKG_NAME"), " ", env!("CARGO_PKG_VERSION"));
+
+/// EXR text attributes only support Latin-1, anything else becomes a '?'
+fn exr_text(value: &str) -> Text {
+    let latin1: String = value
+        .chars()
+        .map(|c| if (c as u32) < 256 { c } else { '?' })
+        .collect();
+    Text::new_or_panic(latin1)
+}
+
+/// Write the scene linear ACEScg RGBA `framebuffer` to an OpenEXR file, as 32 bit floats.
+/// The `metadata` is stored as text attributes of the header, e.g. to record
+/// the settings the image was rendered with.
+pub fn write_exr(
+    path: &Path,
+    framebuffer: &Framebuffer,
+    metadata: &[(String, String)],
+) -> Result<(), String> {
+    let (width, height) = (framebuffer.width(), framebuffer.height());
+    let channels = SpecificChannels::rgba(|Vec2(x, y)| {
+        let [r, g, b, a] = framebuffer.pixel(x, y);
+        (r, g, b, a)
+    });
+
+    let mut image = Image::from_channels((width, height), channels);
+    image.attributes = image_attributes(width, height, metadata);
+    image.layer_data.attributes.software_name = Some(exr_text(SOFTWARE));
+
+    image
+        .write()
+        .to_file(path)
+        .map_err(|e| tr_args(i18n::Text::CouldNotSave, &[&path.display(), &e]))
+}
+
+/// Header attributes shared by all the layers of the EXRs we write
+fn image_attributes(width: usize, height: usize, metadata: &[(String, String)]) -> ImageAttributes {
+    let mut attributes = ImageAttributes::new(IntegerBounds::from_dimensions((width, height)));
+    attributes.chromaticities = Some(ACESCG_CHROMATICITIES);
+    for (name, value) in metadata {
+        attributes
+            .other
+            .insert(exr_text(name), AttributeValue::Text(exr_text(value)));
+    }
+    attributes
+}
+
+/// A layer of a multi-layer EXR, with a plane of samples for each channel
+pub struct ExrLayer {
+    pub name: &'static str,
+    pub channels: Vec<(&'static str, Vec<f32>)>,
+}
+
+impl ExrLayer {
+    /// Split interleaved `pixels`, with `stride` samples per pixel, into the planes
+    /// of the `channels`, which take the first samples of each pixel
+    pub fn from_interleaved(
+        name: &'static str,
+        pixels: &[f32],
+        stride: usize,
+        channels: &[&'static str],
+    ) -> ExrLayer {
+        let channels = channels
+            .iter()
+            .enumerate()
+            .map(|(index, &channel)| {
+                let plane = pixels.iter().skip(index).step_by(stride).copied().collect();
+                (channel, plane)
+            })
+            .collect();
+        ExrLayer { name, channels }
+    }
+}
+
+/// Write `layers` as the parts of a single OpenEXR file, as 32 bit floats.
+/// Readers that only look at one of them get the first, which should be the beauty.
+pub fn write_layered_exr(
+    path: &Path,
+    width: usize,
+    height: usize,
+    layers: Vec<ExrLayer>,
+    metadata: &[(String, String)],
+) -> Result<(), String> {
+    let layers: Vec<_> = layers
+        .into_iter()
+        .map(|layer| {
+            let channels = layer
+                .channels
+                .into_iter()
+                .map(|(name, samples)| AnyChannel::new(name, FlatSamples::F32(samples)))
+                .collect();
+            let mut attributes = LayerAttributes::named(layer.name);
+            attributes.software_name = Some(exr_text(SOFTWARE));
+
+            Layer::new(
+                (width, height),
+                attributes,
+                Encoding::FAST_LOSSLESS,
+                AnyChannels::sort(channels),
+            )
+        })
+        .collect();
+
+    Image::from_layers(image_attributes(width, height, metadata), layers)
+        .write()
+        .to_file(path)
+        .map_err(|e| tr_args(i18n::Text::CouldNotSave, &[&path.display(), &e]))
+}
+
+/// What files without a chromaticities attribute use, according to the OpenEXR spec
+pub const REC709_CHROMATICITIES: Chromaticities = Chromaticities {
+    red: Vec2(0.64, 0.33),
+    green: Vec2(0.30, 0.60),
+    blue: Vec2(0.15, 0.06),
+    white: Vec2(0.3127, 0.3290),
+};
+
+/// Read the first RGBA layer of an OpenEXR file, as scene linear ACEScg RGBA pixels.
+/// Missing alpha channels are opaque.
+pub fn read_exr(path: &Path) -> Result<Framebuffer, String> {
+    let image = exr::image::read::read_first_rgba_layer_from_file(
+        path,
+        |resolution, _| Framebuffer::new(resolution.width(), resolution.height()),
+        |framebuffer, Vec2(x, y), (r, g, b, a): (f32, f32, f32, f32)| {
+            framebuffer.set_pixel(x, y, [r, g, b, a]);
+        },
+    )
+    .map_err(|e| tr_args(i18n::Text::CouldNotOpen, &[&path.display(), &e]))?;
+
+    let mut framebuffer = image.layer_data.channel_data.pixels;
+
+    let chromaticities = image
+        .attributes
+        .chromaticities
+        .unwrap_or(REC709_CHROMATICITIES);
+    if chromaticities != ACESCG_CHROMATICITIES {
+        let to_acescg = to_acescg_matrix(&chromaticities).ok_or_else(|| {
+            tr_args(
+                i18n::Text::CouldNotOpen,
+                &[&path.display(), &tr(i18n::Text::DegenerateChromaticities)],
+            )
+        })?;
+        for pixel in framebuffer.pixels_mut().chunks_exact_mut(4) {
+            let rgb = multiply_vector(&to_acescg, [pixel[0], pixel[1], pixel[2]]);
+            pixel[..3].copy_from_slice(&rgb);
+        }
+    }
+
+    Ok(framebuffer)
+}
+
+/// Read an EXR, PNG or Radiance HDR file, picked by its extension, as scene linear
+/// ACEScg RGBA pixels. PNGs are display referred: their sRGB encoding is undone,
+/// but they won't look the same once tonemapped again.
+pub fn read_image(path: &Path) -> Result<Framebuffer, String> {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_ascii_lowercase);
+    let error =
+        |e: &dyn std::fmt::Display| tr_args(i18n::Text::CouldNotOpen, &[&path.display(), &e]);
+    match extension.as_deref() {
+        Some("exr") => return read_exr(path),
+        Some("png" | "hdr") => {}
+        _ => return Err(error(&tr(i18n::Text::UnsupportedImage))),
+    }
+
+    let to_acescg = |[r, g, b]: [f32; 3], alpha: f32| {
+        let acescg = color::linear_srgb::<Scene>(r, g, b).convert::<AcesCg>();
+        [acescg.r, acescg.g, acescg.b, alpha]
+    };
+    let file = std::fs::File::open(path).map_err(|e| error(&e))?;
+    let reader = std::io::BufReader::new(file);
+
+    match extension.as_deref() {
+        Some("png") => {
+            let decoded = image::load(reader, image::ImageFormat::Png)
+                .map_err(|e| error(&e))?
+                .into_rgba8();
+            let pixels = decoded
+                .pixels()
+                .flat_map(|&image::Rgba([r, g, b, a])| {
+                    let linear = color::srgb_u8(r, g, b).convert::<LinearSrgb>();
+                    to_acescg([linear.r, linear.g, linear.b], a as f32 / 255.0)
+                })
+                .collect();
+            let (width, height) = (decoded.width() as usize, decoded.height() as usize);
+            Ok(Framebuffer::from_pixels(width, height, pixels))
+        }
+        // The generic image decoder would tonemap it down to 8bit
+        _ => {
+            let decoder = image::codecs::hdr::HdrDecoder::new(reader).map_err(|e| error(&e))?;
+            let metadata = decoder.metadata();
+            let pixels = decoder
+                .read_image_hdr()
+                .map_err(|e| error(&e))?
+                .into_iter()
+                .flat_map(|pixel| to_acescg(pixel.0, 1.0))
+                .collect();
+            let (width, height) = (metadata.width as usize, metadata.height as usize);
+            Ok(Framebuffer::from_pixels(width, height, pixels))
+        }
+    }
+}
+
+/// Matrix from the RGB values of a file with these `chromaticities` to ACEScg.
+/// colstodian's CIE XYZ is relative to D65, so the white of the file is adapted to it.
+/// See http://www.brucelindbloom.com/index.html?Eqn_RGB_XYZ_Matrix.html
+pub fn to_acescg_matrix(chromaticities: &Chromaticities) -> Option<Matrix> {
+    let xyz = |Vec2(x, y): Vec2<f32>| [x / y, 1.0, (1.0 - x - y) / y];
+
+    // Scale the primaries so that they add up to the white point
+    let mut primaries = [[0.0; 3]; 3];
+    for (column, primary) in [
+        chromaticities.red,
+        chromaticities.green,
+        chromaticities.blue,
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        for (row, value) in xyz(primary).into_iter().enumerate() {
+            primaries[row][column] = value;
+        }
+    }
+    let scale = multiply_vector(&invert(&primaries)?, xyz(chromaticities.white));
+    let to_xyz = primaries.map(|row| [0, 1, 2].map(|column| row[column] * scale[column]));
+
+    // colstodian only gives us conversions of colors, so build the matrix column by column
+    let mut from_xyz = [[0.0; 3]; 3];
+    for column in 0..3 {
+        let mut basis = [0.0; 3];
+        basis[column] = 1.0;
+        let acescg = Color::<CieXYZ, Scene>::new(basis[0], basis[1], basis[2]).convert::<AcesCg>();
+        for (row, value) in [acescg.r, acescg.g, acescg.b].into_iter().enumerate() {
+            from_xyz[row][column] = value;
+        }
+    }
+
+    let adaptation =
+        chromatic_adaptation(xyz(chromaticities.white), xyz(REC709_CHROMATICITIES.white));
+    Some(multiply(&from_xyz, &multiply(&adaptation, &to_xyz)))
+}
+
+/// File formats the render can be saved to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// The scene linear values, in 32 bit floats
+    Exr,
+    /// The display referred 8bit values, exactly as shown in the viewer
+    Png,
+    /// The display referred values, in 16 bit
+    Tiff16,
+    /// The display referred values, log encoded to 10 bit Cineon code values
+    Dpx,
+    /// The scene linear values, in the compact shared exponent RGBE encoding
+    Hdr,
+}
+
+impl OutputFormat {
+    pub const ALL: [OutputFormat; 5] = [
+        OutputFormat::Exr,
+        OutputFormat::Png,
+        OutputFormat::Tiff16,
+        OutputFormat::Dpx,
+        OutputFormat::Hdr,
+    ];
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            OutputFormat::Exr => "exr",
+            OutputFormat::Png => "png",
+            OutputFormat::Tiff16 => "tiff",
+            OutputFormat::Dpx => "dpx",
+            OutputFormat::Hdr => "hdr",
+        }
+    }
+
+    /// The format matching the extension of `path`, if it's one we can write
+    pub fn from_path(path: &str) -> Option<OutputFormat> {
+        let extension = Path::new(path).extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "tif" => Some(OutputFormat::Tiff16),
+            extension => OutputFormat::ALL
+                .into_iter()
+                .find(|format| format.extension() == extension),
+        }
+    }
+
+    /// Whether it stores the scene linear values, rather than the display referred ones
+    pub fn is_scene_linear(&self) -> bool {
+        match self {
+            OutputFormat::Exr | OutputFormat::Hdr => true,
+            OutputFormat::Png | OutputFormat::Tiff16 | OutputFormat::Dpx => false,
+        }
+    }
+}
+
+impl std::fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            OutputFormat::Exr => "OpenEXR (scene linear)",
+            OutputFormat::Png => "PNG (display)",
+            OutputFormat::Tiff16 => "16 bit TIFF (display)",
+            OutputFormat::Dpx => "10 bit log DPX (display)",
+            OutputFormat::Hdr => "Radiance HDR (scene linear)",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// `stem` without its `_v001` style version suffix, if it has one
+fn strip_version(stem: &str) -> &str {
+    match stem.rsplit_once("_v") {
+        Some((base, version))
+            if version.len() >= 3 && version.bytes().all(|byte| byte.is_ascii_digit()) =>
+        {
+            base
+        }
+        _ => stem,
+    }
+}
+
+/// The first version of `stem` that isn't on disk yet, e.g. `render_v003` when
+/// `render_v001.exr` and the `render_v002.####.exr` frames of a sequence exist.
+/// A version already at the end of `stem` is replaced.
+pub fn next_versioned_stem(stem: &str, extension: &str) -> String {
+    let base = strip_version(stem);
+    let path = Path::new(base);
+    let directory = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let prefix = format!(
+        "{}_v",
+        path.file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or_default()
+    );
+    let suffix = format!(".{extension}");
+
+    let latest = std::fs::read_dir(directory)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            let rest = name.strip_prefix(&prefix)?;
+            rest.strip_suffix(&suffix)?;
+            rest.split('.').next()?.parse::<u32>().ok()
+        })
+        .max()
+        .unwrap_or(0);
+
+    format!("{base}_v{:03}", latest + 1)
+}
+
+/// Write the display referred RGBA `pixels` to an 8bit PNG file
+pub fn write_png(path: &Path, width: usize, height: usize, pixels: &[u8]) -> Result<(), String> {
+    image::save_buffer_with_format(
+        path,
+        pixels,
+        width as u32,
+        height as u32,
+        image::ColorType::Rgba8,
+        image::ImageFormat::Png,
+    )
+    .map_err(|e| tr_args(i18n::Text::CouldNotSave, &[&path.display(), &e]))
+}
+
+/// Write the scene linear ACEScg RGBA `framebuffer` to a Radiance .hdr file.
+/// Readers expect sRGB primaries, so the colors are converted, and the ones outside
+/// of its gamut lose their negative components, which RGBE can't store. RGBE has no
+/// alpha either, so the image is composited over black.
+pub fn write_hdr(path: &Path, framebuffer: &Framebuffer) -> Result<(), String> {
+    let error =
+        |e: &dyn std::fmt::Display| tr_args(i18n::Text::CouldNotSave, &[&path.display(), &e]);
+
+    let srgb_pixels: Vec<_> = framebuffer
+        .pixels()
+        .chunks_exact(4)
+        .map(|pixel| {
+            let srgb = color::acescg::<Scene>(pixel[0], pixel[1], pixel[2]).convert::<LinearSrgb>();
+            let alpha = pixel[3].clamp(0.0, 1.0);
+            image::Rgb([srgb.r, srgb.g, srgb.b].map(|channel| (channel * alpha).max(0.0)))
+        })
+        .collect();
+
+    let file = std::fs::File::create(path).map_err(|e| error(&e))?;
+    image::codecs::hdr::HdrEncoder::new(std::io::BufWriter::new(file))
+        .encode(&srgb_pixels, framebuffer.width(), framebuffer.height())
+        .map_err(|e| error(&e))
+}
+
+/// Write the display referred 16 bit RGBA `pixels` to a TIFF file
+pub fn write_tiff16(
+    path: &Path,
+    width: usize,
+    height: usize,
+    pixels: Vec<u16>,
+) -> Result<(), String> {
+    let buffer =
+        image::ImageBuffer::<image::Rgba<u16>, _>::from_raw(width as u32, height as u32, pixels)
+            .ok_or(tr(i18n::Text::BufferSizeMismatch))?;
+
+    buffer
+        .save_with_format(path, image::ImageFormat::Tiff)
+        .map_err(|e| tr_args(i18n::Text::CouldNotSave, &[&path.display(), &e]))
+}
@@ -0,0 +1,394 @@
+//! Keyboard shortcuts: which keys trigger which action. The defaults can be
+//! overridden from `keymap.toml`, with lines like `render = "R"` or
+//! `zoom_in = ["Plus", "NumpadAdd"]`; actions it doesn't mention keep their keys.
+use iced::keyboard::{KeyCode, Modifiers};
+use toml::value::Value;
+
+use crate::console;
+use crate::i18n::Text;
+
+pub const KEYMAP_PATH: &str = "keymap.toml";
+
+/// What a shortcut does
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Render,
+    Save,
+    Undo,
+    Redo,
+    Copy,
+    /// Leave the presentation mode, or cancel the render
+    Escape,
+    TogglePresentation,
+    /// Hide the parameters, leaving the window to the image
+    ToggleParameterPanel,
+    /// Show the list of the shortcuts
+    ToggleHelp,
+    ExposureUp,
+    ExposureDown,
+    ShowRed,
+    ShowGreen,
+    ShowBlue,
+    ShowAlpha,
+    ShowLuma,
+    ZoomToFit,
+    Zoom100,
+    Zoom200,
+    ZoomIn,
+    ZoomOut,
+    SwapAB,
+}
+
+impl Action {
+    pub const ALL: [Action; 22] = [
+        Action::Render,
+        Action::Save,
+        Action::Undo,
+        Action::Redo,
+        Action::Copy,
+        Action::Escape,
+        Action::TogglePresentation,
+        Action::ToggleParameterPanel,
+        Action::ToggleHelp,
+        Action::ExposureUp,
+        Action::ExposureDown,
+        Action::ShowRed,
+        Action::ShowGreen,
+        Action::ShowBlue,
+        Action::ShowAlpha,
+        Action::ShowLuma,
+        Action::ZoomToFit,
+        Action::Zoom100,
+        Action::Zoom200,
+        Action::ZoomIn,
+        Action::ZoomOut,
+        Action::SwapAB,
+    ];
+
+    /// Its name in the keymap file
+    fn name(&self) -> &'static str {
+        match self {
+            Action::Render => "render",
+            Action::Save => "save",
+            Action::Undo => "undo",
+            Action::Redo => "redo",
+            Action::Copy => "copy",
+            Action::Escape => "escape",
+            Action::TogglePresentation => "toggle_presentation",
+            Action::ToggleParameterPanel => "toggle_parameter_panel",
+            Action::ToggleHelp => "toggle_help",
+            Action::ExposureUp => "exposure_up",
+            Action::ExposureDown => "exposure_down",
+            Action::ShowRed => "show_red",
+            Action::ShowGreen => "show_green",
+            Action::ShowBlue => "show_blue",
+            Action::ShowAlpha => "show_alpha",
+            Action::ShowLuma => "show_luma",
+            Action::ZoomToFit => "zoom_to_fit",
+            Action::Zoom100 => "zoom_100",
+            Action::Zoom200 => "zoom_200",
+            Action::ZoomIn => "zoom_in",
+            Action::ZoomOut => "zoom_out",
+            Action::SwapAB => "swap_ab",
+        }
+    }
+
+    /// What it does, for the list of the shortcuts
+    pub fn description(&self) -> Text {
+        match self {
+            Action::Render => Text::ActionRender,
+            Action::Save => Text::ActionSave,
+            Action::Undo => Text::ActionUndo,
+            Action::Redo => Text::ActionRedo,
+            Action::Copy => Text::ActionCopy,
+            Action::Escape => Text::ActionEscape,
+            Action::TogglePresentation => Text::ActionTogglePresentation,
+            Action::ToggleParameterPanel => Text::ActionToggleParameterPanel,
+            Action::ToggleHelp => Text::ActionToggleHelp,
+            Action::ExposureUp => Text::ActionExposureUp,
+            Action::ExposureDown => Text::ActionExposureDown,
+            Action::ShowRed => Text::ActionShowRed,
+            Action::ShowGreen => Text::ActionShowGreen,
+            Action::ShowBlue => Text::ActionShowBlue,
+            Action::ShowAlpha => Text::ActionShowAlpha,
+            Action::ShowLuma => Text::ActionShowLuma,
+            Action::ZoomToFit => Text::ActionZoomToFit,
+            Action::Zoom100 => Text::ActionZoom100,
+            Action::Zoom200 => Text::ActionZoom200,
+            Action::ZoomIn => Text::ActionZoomIn,
+            Action::ZoomOut => Text::ActionZoomOut,
+            Action::SwapAB => Text::ActionSwapAB,
+        }
+    }
+
+    fn default_keys(&self) -> &'static [&'static str] {
+        match self {
+            Action::Render => &["R"],
+            Action::Save => &["S"],
+            Action::Undo => &["Cmd+Z"],
+            Action::Redo => &["Cmd+Shift+Z", "Cmd+Y"],
+            Action::Copy => &["Cmd+C"],
+            Action::Escape => &["Escape"],
+            Action::TogglePresentation => &["F11"],
+            Action::ToggleParameterPanel => &["Tab"],
+            Action::ToggleHelp => &["F1", "Shift+Slash"],
+            Action::ExposureUp => &["Up"],
+            Action::ExposureDown => &["Down"],
+            Action::ShowRed => &["1"],
+            Action::ShowGreen => &["2"],
+            Action::ShowBlue => &["3"],
+            Action::ShowAlpha => &["4"],
+            Action::ShowLuma => &["5"],
+            Action::ZoomToFit => &["F"],
+            Action::Zoom100 => &["Cmd+1"],
+            Action::Zoom200 => &["Cmd+2"],
+            Action::ZoomIn => &["Plus", "Equals", "NumpadAdd"],
+            Action::ZoomOut => &["Minus", "NumpadSubtract"],
+            Action::SwapAB => &["W"],
+        }
+    }
+}
+
+/// Names of the keys that can be bound, besides the letters and digits
+const KEY_NAMES: [(&str, KeyCode); 32] = [
+    ("Escape", KeyCode::Escape),
+    ("Space", KeyCode::Space),
+    ("Enter", KeyCode::Enter),
+    ("Tab", KeyCode::Tab),
+    ("Backspace", KeyCode::Backspace),
+    ("Delete", KeyCode::Delete),
+    ("Home", KeyCode::Home),
+    ("End", KeyCode::End),
+    ("PageUp", KeyCode::PageUp),
+    ("PageDown", KeyCode::PageDown),
+    ("Up", KeyCode::Up),
+    ("Down", KeyCode::Down),
+    ("Left", KeyCode::Left),
+    ("Right", KeyCode::Right),
+    ("Plus", KeyCode::Plus),
+    ("Minus", KeyCode::Minus),
+    ("Equals", KeyCode::Equals),
+    ("NumpadAdd", KeyCode::NumpadAdd),
+    ("NumpadSubtract", KeyCode::NumpadSubtract),
+    ("Slash", KeyCode::Slash),
+    ("F1", KeyCode::F1),
+    ("F2", KeyCode::F2),
+    ("F3", KeyCode::F3),
+    ("F4", KeyCode::F4),
+    ("F5", KeyCode::F5),
+    ("F6", KeyCode::F6),
+    ("F7", KeyCode::F7),
+    ("F8", KeyCode::F8),
+    ("F9", KeyCode::F9),
+    ("F10", KeyCode::F10),
+    ("F11", KeyCode::F11),
+    ("F12", KeyCode::F12),
+];
+
+const LETTERS: [KeyCode; 26] = [
+    KeyCode::A,
+    KeyCode::B,
+    KeyCode::C,
+    KeyCode::D,
+    KeyCode::E,
+    KeyCode::F,
+    KeyCode::G,
+    KeyCode::H,
+    KeyCode::I,
+    KeyCode::J,
+    KeyCode::K,
+    KeyCode::L,
+    KeyCode::M,
+    KeyCode::N,
+    KeyCode::O,
+    KeyCode::P,
+    KeyCode::Q,
+    KeyCode::R,
+    KeyCode::S,
+    KeyCode::T,
+    KeyCode::U,
+    KeyCode::V,
+    KeyCode::W,
+    KeyCode::X,
+    KeyCode::Y,
+    KeyCode::Z,
+];
+
+const DIGITS: [KeyCode; 10] = [
+    KeyCode::Key0,
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+    KeyCode::Key7,
+    KeyCode::Key8,
+    KeyCode::Key9,
+];
+
+/// A key, and the modifiers that have to be held with it (and no others)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Shortcut {
+    key: KeyCode,
+    modifiers: Modifiers,
+}
+
+impl Shortcut {
+    /// Parse e.g. `S`, `F11` or `Ctrl+Shift+S`. `Cmd` is Ctrl, or ⌘ on macOS.
+    fn parse(text: &str) -> Result<Shortcut, String> {
+        let mut parts: Vec<&str> = text.split('+').map(str::trim).collect();
+        let key_name = parts.pop().unwrap_or_default();
+
+        let mut modifiers = Modifiers::empty();
+        for part in parts {
+            modifiers |= match part.to_ascii_lowercase().as_str() {
+                "ctrl" => Modifiers::CTRL,
+                "shift" => Modifiers::SHIFT,
+                "alt" => Modifiers::ALT,
+                "cmd" => Modifiers::COMMAND,
+                _ => return Err(format!("unknown modifier \"{part}\" in \"{text}\"")),
+            };
+        }
+
+        let key = match key_name.as_bytes() {
+            [letter] if letter.is_ascii_alphabetic() => {
+                Some(LETTERS[(letter.to_ascii_uppercase() - b'A') as usize])
+            }
+            [digit] if digit.is_ascii_digit() => Some(DIGITS[(digit - b'0') as usize]),
+            _ => KEY_NAMES
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(key_name))
+                .map(|(_, key)| *key),
+        };
+        match key {
+            Some(key) => Ok(Shortcut { key, modifiers }),
+            None => Err(format!("unknown key \"{key_name}\" in \"{text}\"")),
+        }
+    }
+}
+
+impl std::fmt::Display for Shortcut {
+    // The way it's written in the keymap file, except for `Cmd` which is shown as
+    // the key it stands for
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (modifier, name) in [
+            (Modifiers::CTRL, "Ctrl"),
+            (Modifiers::SHIFT, "Shift"),
+            (Modifiers::ALT, "Alt"),
+            (Modifiers::LOGO, "⌘"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                write!(f, "{name}+")?;
+            }
+        }
+
+        if let Some(i) = LETTERS.iter().position(|key| *key == self.key) {
+            write!(f, "{}", (b'A' + i as u8) as char)
+        } else if let Some(i) = DIGITS.iter().position(|key| *key == self.key) {
+            write!(f, "{i}")
+        } else {
+            match KEY_NAMES.iter().find(|(_, key)| *key == self.key) {
+                Some((name, _)) => write!(f, "{name}"),
+                None => write!(f, "{:?}", self.key),
+            }
+        }
+    }
+}
+
+pub struct Keymap {
+    bindings: Vec<(Shortcut, Action)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let bindings = Action::ALL
+            .iter()
+            .flat_map(|action| {
+                action.default_keys().iter().map(|text| {
+                    let shortcut = Shortcut::parse(text).expect("default shortcuts are valid");
+                    (shortcut, *action)
+                })
+            })
+            .collect();
+        Keymap { bindings }
+    }
+}
+
+impl Keymap {
+    /// The default keymap, with the bindings of `KEYMAP_PATH` on top if there's one.
+    /// Mistakes in it are reported, and leave the defaults of those actions.
+    pub fn load() -> Keymap {
+        let mut keymap = Keymap::default();
+        let Ok(contents) = std::fs::read_to_string(KEYMAP_PATH) else {
+            return keymap;
+        };
+        let table = match contents.parse::<Value>() {
+            Ok(Value::Table(table)) => table,
+            Ok(_) => return keymap,
+            Err(e) => {
+                console::warning(format!("could not read {KEYMAP_PATH}: {e}"));
+                return keymap;
+            }
+        };
+
+        for (name, keys) in &table {
+            let Some(action) = Action::ALL.iter().find(|action| action.name() == name) else {
+                console::warning(format!("unknown action \"{name}\" in {KEYMAP_PATH}"));
+                continue;
+            };
+            match keymap.bind(*action, keys) {
+                Ok(()) => (),
+                Err(e) => {
+                    console::warning(format!("{e} in {KEYMAP_PATH}, keeping its default keys"))
+                }
+            }
+        }
+        keymap
+    }
+
+    /// Replace the keys of `action` with `keys`, a string or an array of strings.
+    /// The keys are taken away from whatever other action had them.
+    fn bind(&mut self, action: Action, keys: &Value) -> Result<(), String> {
+        let texts: Vec<&str> = match keys {
+            Value::String(text) => vec![text.as_str()],
+            Value::Array(values) => values
+                .iter()
+                .map(|value| {
+                    value
+                        .as_str()
+                        .ok_or_else(|| format!("{value} isn't a key for {}", action.name()))
+                })
+                .collect::<Result<_, _>>()?,
+            other => return Err(format!("{other} isn't a key for {}", action.name())),
+        };
+        let shortcuts = texts
+            .into_iter()
+            .map(Shortcut::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        self.bindings.retain(|(shortcut, bound_action)| {
+            *bound_action != action && !shortcuts.contains(shortcut)
+        });
+        self.bindings
+            .extend(shortcuts.into_iter().map(|shortcut| (shortcut, action)));
+        Ok(())
+    }
+
+    /// The keys bound to `action`, e.g. `["Ctrl+Shift+Z", "Ctrl+Y"]`
+    pub fn keys(&self, action: Action) -> Vec<String> {
+        self.bindings
+            .iter()
+            .filter(|(_, bound_action)| *bound_action == action)
+            .map(|(shortcut, _)| shortcut.to_string())
+            .collect()
+    }
+
+    /// What pressing `key` with `modifiers` does
+    pub fn action(&self, key: KeyCode, modifiers: Modifiers) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(shortcut, _)| shortcut.key == key && shortcut.modifiers == modifiers)
+            .map(|(_, action)| *action)
+    }
+}
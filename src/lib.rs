@@ -0,0 +1,63 @@
+//! Procedural images rendered to a scene linear framebuffer, and shown through a display
+//! pipeline of tonemapping, color transforms and view modes. The application is the `ui`
+//! module; rendering, the display conversion and image files don't depend on it.
+
+// Rendering
+pub mod expression;
+#[cfg(feature = "gpu")]
+mod gpu;
+pub mod noise;
+pub mod path_tracer;
+pub mod pattern;
+pub mod raymarcher;
+pub mod render;
+pub mod vec3;
+
+mod adaptive_quality;
+mod render_stats;
+mod render_worker;
+
+// Display
+pub mod color_blindness;
+pub mod color_pipeline;
+pub mod display_lanes;
+pub mod icc;
+pub mod lut;
+pub mod post_process;
+pub mod tone_curve;
+pub mod white_balance;
+
+// Files
+pub mod dpx;
+pub mod io;
+
+mod preset;
+mod recent_files;
+mod save_worker;
+
+// Interface
+pub mod headless;
+pub mod ui;
+
+mod appearance;
+mod background;
+mod bracket;
+mod clipboard;
+mod color_picker;
+mod console;
+mod curve_editor;
+mod difference;
+mod gallery;
+mod guides;
+mod histogram;
+mod i18n;
+mod keymap;
+mod number_field;
+mod resample;
+mod scopes;
+mod selection;
+mod statistics;
+mod undo;
+mod viewer;
+
+pub mod self_test;
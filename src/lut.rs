@@ -0,0 +1,266 @@
+//! 3D LUTs in the Resolve/Adobe .cube format, applied with tetrahedral interpolation
+//! See https://resolve.cafe/developers/luts/
+use std::path::Path;
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use crate::console;
+use crate::i18n::{tr_args, Text};
+
+/// Largest LUT_3D_SIZE accepted, already 16.7M entries, so that the size of the table
+/// can't overflow
+const MAX_SIZE: usize = 256;
+
+/// Where the LUT goes in the display pipeline
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LutMode {
+    /// Applied to the encoded display values, like a show LUT on top of the viewer
+    AfterTonemap,
+    /// Takes the scene linear values and produces the encoded display values
+    InsteadOfTonemap,
+}
+
+impl LutMode {
+    pub const ALL: [LutMode; 2] = [LutMode::AfterTonemap, LutMode::InsteadOfTonemap];
+}
+
+impl std::fmt::Display for LutMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            LutMode::AfterTonemap => "After tonemap",
+            LutMode::InsteadOfTonemap => "Instead of tonemap",
+        };
+        write!(f, "{name}")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lut3d {
+    /// Name of the file it was loaded from, for display
+    pub name: String,
+    /// Number of entries along each axis
+    size: usize,
+    domain_min: [f32; 3],
+    domain_max: [f32; 3],
+    /// Red changes fastest, then green, then blue
+    table: Vec<[f32; 3]>,
+}
+
+impl Lut3d {
+    pub fn load(path: &Path) -> Result<Lut3d, String> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| tr_args(Text::CouldNotRead, &[&path.display(), &e]))?;
+        let name = path.file_name().map_or_else(
+            || path.display().to_string(),
+            |name| name.to_string_lossy().into(),
+        );
+
+        Lut3d::parse(&name, &contents).map_err(|e| tr_args(Text::CouldNotLoad, &[&name, &e]))
+    }
+
+    pub fn parse(name: &str, contents: &str) -> Result<Lut3d, String> {
+        let mut size = None;
+        let mut domain_min = [0.0; 3];
+        let mut domain_max = [1.0; 3];
+        let mut table = Vec::new();
+
+        for (index, line) in contents.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut words = line.split_whitespace();
+            let keyword = words.next().unwrap_or_default();
+            let triplet = |words: std::str::SplitWhitespace| -> Result<[f32; 3], String> {
+                let values = words
+                    .map(str::parse)
+                    .collect::<Result<Vec<f32>, _>>()
+                    .map_err(|e| format!("line {line_number}: {e}"))?;
+                values
+                    .try_into()
+                    .map_err(|_| format!("line {line_number}: expected 3 values"))
+            };
+
+            match keyword {
+                "TITLE" => {}
+                "LUT_1D_SIZE" => return Err(String::from("1D LUTs are not supported")),
+                "LUT_3D_SIZE" => {
+                    let parsed = words
+                        .next()
+                        .and_then(|word| word.parse::<usize>().ok())
+                        .filter(|size| (2..=MAX_SIZE).contains(size))
+                        .ok_or_else(|| {
+                            format!(
+                                "line {line_number}: LUT_3D_SIZE should be from 2 to {MAX_SIZE}"
+                            )
+                        })?;
+                    size = Some(parsed);
+                }
+                "DOMAIN_MIN" => domain_min = triplet(words)?,
+                "DOMAIN_MAX" => domain_max = triplet(words)?,
+                // Anything else is either a table entry, or a keyword we don't know about
+                _ => match triplet(line.split_whitespace()) {
+                    Ok(entry) => table.push(entry),
+                    Err(_) if keyword.starts_with(|c: char| c.is_ascii_alphabetic()) => {
+                        console::warning(format!(
+                            "ignoring the unknown {keyword} keyword of {name}"
+                        ));
+                    }
+                    Err(e) => return Err(e),
+                },
+            }
+        }
+
+        let size = size.ok_or("missing LUT_3D_SIZE")?;
+        if table.len() != size * size * size {
+            return Err(format!(
+                "expected {} entries for a size of {size}, found {}",
+                size * size * size,
+                table.len()
+            ));
+        }
+        if (0..3).any(|i| domain_max[i] <= domain_min[i]) {
+            return Err(String::from("DOMAIN_MAX must be greater than DOMAIN_MIN"));
+        }
+
+        Ok(Lut3d {
+            name: String::from(name),
+            size,
+            domain_min,
+            domain_max,
+            table,
+        })
+    }
+
+    /// Sample `function` over the domain, to bake a part of the pipeline into a LUT
+    pub fn bake(
+        name: &str,
+        size: usize,
+        domain_min: [f32; 3],
+        domain_max: [f32; 3],
+        function: impl Fn([f32; 3]) -> [f32; 3] + Sync,
+    ) -> Lut3d {
+        let input = |index: usize| {
+            let position = [index % size, (index / size) % size, index / (size * size)];
+            let mut color = [0.0; 3];
+            for (channel, value) in color.iter_mut().enumerate() {
+                let fraction = position[channel] as f32 / (size - 1) as f32;
+                *value =
+                    domain_min[channel] + fraction * (domain_max[channel] - domain_min[channel]);
+            }
+            function(color)
+        };
+
+        #[cfg(feature = "parallel")]
+        let table = (0..size * size * size).into_par_iter().map(input).collect();
+        #[cfg(not(feature = "parallel"))]
+        let table = (0..size * size * size).map(input).collect();
+
+        Lut3d {
+            name: String::from(name),
+            size,
+            domain_min,
+            domain_max,
+            table,
+        }
+    }
+
+    fn entry(&self, r: usize, g: usize, b: usize) -> [f32; 3] {
+        self.table[r + self.size * (g + self.size * b)]
+    }
+
+    /// Look up `color`, interpolating between the 4 entries of the tetrahedron it falls in
+    pub fn apply(&self, color: [f32; 3]) -> [f32; 3] {
+        let last = (self.size - 1) as f32;
+        let mut index = [0; 3];
+        let mut fraction = [0.0; 3];
+
+        for channel in 0..3 {
+            let (min, max) = (self.domain_min[channel], self.domain_max[channel]);
+            let position = ((color[channel] - min) / (max - min)).clamp(0.0, 1.0) * last;
+            // Stay one entry away from the end, so that there's always a next one
+            let floor = position.floor().min(last - 1.0);
+            index[channel] = floor as usize;
+            fraction[channel] = position - floor;
+        }
+
+        let [r, g, b] = index;
+        let [fr, fg, fb] = fraction;
+        let corner = |dr: usize, dg: usize, db: usize| self.entry(r + dr, g + dg, b + db);
+
+        // Walk from the first to the last corner of the cube, along the largest
+        // fraction first, then the second largest, then the smallest
+        let (c000, c111) = (corner(0, 0, 0), corner(1, 1, 1));
+        let (first, second, weights) = if fr > fg {
+            if fg > fb {
+                (corner(1, 0, 0), corner(1, 1, 0), [fr, fg, fb])
+            } else if fr > fb {
+                (corner(1, 0, 0), corner(1, 0, 1), [fr, fb, fg])
+            } else {
+                (corner(0, 0, 1), corner(1, 0, 1), [fb, fr, fg])
+            }
+        } else if fb > fg {
+            (corner(0, 0, 1), corner(0, 1, 1), [fb, fg, fr])
+        } else if fb > fr {
+            (corner(0, 1, 0), corner(0, 1, 1), [fg, fb, fr])
+        } else {
+            (corner(0, 1, 0), corner(1, 1, 0), [fg, fr, fb])
+        };
+
+        let mut result = [0.0; 3];
+        for (channel, value) in result.iter_mut().enumerate() {
+            *value = c000[channel]
+                + weights[0] * (first[channel] - c000[channel])
+                + weights[1] * (second[channel] - first[channel])
+                + weights[2] * (c111[channel] - second[channel]);
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identity_lut_leaves_colors_untouched() {
+        let size = 5;
+        let mut cube = format!("TITLE \"Identity\"\nLUT_3D_SIZE {size}\n");
+        for b in 0..size {
+            for g in 0..size {
+                for r in 0..size {
+                    let value = |i: usize| i as f32 / (size - 1) as f32;
+                    cube += &format!("{} {} {}\n", value(r), value(g), value(b));
+                }
+            }
+        }
+
+        let lut = Lut3d::parse("identity.cube", &cube).unwrap();
+        for color in [
+            [0.0, 0.0, 0.0],
+            [0.1, 0.5, 0.9],
+            [0.77, 0.33, 0.51],
+            [1.0, 0.2, 1.0],
+        ] {
+            let applied = lut.apply(color);
+            for (channel, expected) in applied.into_iter().zip(color) {
+                assert!(
+                    (channel - expected).abs() < 1e-5,
+                    "{applied:?} != {color:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn invalid_sizes_are_errors() {
+        for size in ["1", "257", "18446744073709551615", "-4", "four"] {
+            let contents = format!("LUT_3D_SIZE {size}\n0 0 0\n");
+            assert!(Lut3d::parse("invalid", &contents).is_err(), "{size}");
+        }
+        assert!(Lut3d::parse("missing entries", "LUT_3D_SIZE 2\n0 0 0\n").is_err());
+    }
+}
@@ -0,0 +1,73 @@
+//! Seeded 2D Perlin noise
+//! See https://mrl.cs.nyu.edu/~perlin/paper445.pdf
+
+/// Hash the lattice point (x, y), so that every seed gets its own gradients
+pub fn hash(x: i32, y: i32, seed: u32) -> u32 {
+    let mut h = (x as u32).wrapping_mul(0x8da6_b343)
+        ^ (y as u32).wrapping_mul(0xd816_3841)
+        ^ seed.wrapping_mul(0xcb1a_b31f);
+    h ^= h >> 13;
+    h = h.wrapping_mul(0x5bd1_e995);
+    h ^ (h >> 15)
+}
+
+/// Dot product between the gradient of a lattice point and the offset (dx, dy) from it
+fn gradient_dot(hash: u32, dx: f32, dy: f32) -> f32 {
+    const DIAGONAL: f32 = std::f32::consts::FRAC_1_SQRT_2;
+
+    let (gx, gy) = match hash & 7 {
+        0 => (1.0, 0.0),
+        1 => (-1.0, 0.0),
+        2 => (0.0, 1.0),
+        3 => (0.0, -1.0),
+        4 => (DIAGONAL, DIAGONAL),
+        5 => (-DIAGONAL, DIAGONAL),
+        6 => (DIAGONAL, -DIAGONAL),
+        _ => (-DIAGONAL, -DIAGONAL),
+    };
+    gx * dx + gy * dy
+}
+
+// 6t^5 - 15t^4 + 10t^3, so that the noise is smooth across lattice cells
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+/// Perlin noise at (x, y), roughly within -1..1
+pub fn perlin(x: f32, y: f32, seed: u32) -> f32 {
+    let (x0, y0) = (x.floor(), y.floor());
+    let (dx, dy) = (x - x0, y - y0);
+    let (ix, iy) = (x0 as i32, y0 as i32);
+
+    let n00 = gradient_dot(hash(ix, iy, seed), dx, dy);
+    let n10 = gradient_dot(hash(ix + 1, iy, seed), dx - 1.0, dy);
+    let n01 = gradient_dot(hash(ix, iy + 1, seed), dx, dy - 1.0);
+    let n11 = gradient_dot(hash(ix + 1, iy + 1, seed), dx - 1.0, dy - 1.0);
+
+    let (u, v) = (fade(dx), fade(dy));
+    let noise = lerp(lerp(n00, n10, u), lerp(n01, n11, u), v);
+
+    // Unit gradients peak at 1/sqrt(2)
+    noise * std::f32::consts::SQRT_2
+}
+
+/// Sum of `octaves` layers of Perlin noise, each one `lacunarity` times finer than
+/// the previous one and with half its amplitude. Stays roughly within -1..1.
+pub fn fractal_noise(x: f32, y: f32, octaves: u32, lacunarity: f32, seed: u32) -> f32 {
+    let mut sum = 0.0;
+    let mut total_amplitude = 0.0;
+    let (mut frequency, mut amplitude) = (1.0, 1.0);
+
+    for octave in 0..octaves {
+        sum += amplitude * perlin(x * frequency, y * frequency, seed.wrapping_add(octave));
+        total_amplitude += amplitude;
+        frequency *= lacunarity;
+        amplitude *= 0.5;
+    }
+
+    sum / total_amplitude
+}
@@ -0,0 +1,352 @@
+//! A text field for numbers: dragging it sideways scrubs the value, clicking it types one.
+//! While it's focused the up and down arrows step the value, ten steps at a time with shift.
+//! Values are rounded to the shown decimals and clamped to the range of the field, and
+//! typed text that isn't a number is dropped when the field loses focus.
+use iced_native::event::{self, Event};
+use iced_native::keyboard;
+use iced_native::layout::{self, Layout};
+use iced_native::mouse;
+use iced_native::renderer;
+use iced_native::widget::{text_input, tree, TextInput, Tree};
+use iced_native::{Clipboard, Element, Length, Point, Rectangle, Shell, Widget};
+use std::ops::RangeInclusive;
+
+type Renderer = iced::Renderer;
+
+/// How far the cursor has to move, in logical pixels, for a press to become a scrub
+const DRAG_THRESHOLD: f32 = 3.0;
+/// Cursor movement for one step while scrubbing
+const PIXELS_PER_STEP: f32 = 4.0;
+/// Steps per arrow press, or per `PIXELS_PER_STEP` of scrubbing, while shift is held
+const COARSE_STEPS: f64 = 10.0;
+
+/// Messages of the inner text input, handled by the field itself
+#[derive(Debug, Clone)]
+enum InputEvent {
+    Changed(String),
+    Submitted,
+}
+
+#[derive(Default)]
+struct State {
+    /// What was typed since the field got focus, shown instead of the value
+    text: Option<String>,
+    /// Where the left button went down on the unfocused field, and the value then
+    press: Option<(Point, f64)>,
+    is_scrubbing: bool,
+    modifiers: keyboard::Modifiers,
+}
+
+pub struct NumberField<'a, Message> {
+    value: f64,
+    placeholder: &'a str,
+    on_change: Box<dyn Fn(f64) -> Message + 'a>,
+    on_submit: Option<Message>,
+    on_release: Option<Message>,
+    range: RangeInclusive<f64>,
+    step: f64,
+    decimals: usize,
+    width: Length,
+}
+
+impl<'a, Message: Clone> NumberField<'a, Message> {
+    pub fn new(value: f64, on_change: impl Fn(f64) -> Message + 'a) -> Self {
+        NumberField {
+            value,
+            placeholder: "",
+            on_change: Box::new(on_change),
+            on_submit: None,
+            on_release: None,
+            range: f64::MIN..=f64::MAX,
+            step: 1.0,
+            decimals: 0,
+            width: Length::Fill,
+        }
+    }
+
+    /// Shown while the field is empty
+    pub fn placeholder(mut self, placeholder: &'a str) -> Self {
+        self.placeholder = placeholder;
+        self
+    }
+
+    pub fn range(mut self, range: RangeInclusive<f64>) -> Self {
+        self.range = range;
+        self
+    }
+
+    /// Change of an arrow press, or of scrubbing by `PIXELS_PER_STEP`
+    pub fn step(mut self, step: f64) -> Self {
+        self.step = step;
+        self
+    }
+
+    pub fn decimals(mut self, decimals: usize) -> Self {
+        self.decimals = decimals;
+        self
+    }
+
+    /// Sent when enter is pressed in the field
+    pub fn on_submit(mut self, message: Message) -> Self {
+        self.on_submit = Some(message);
+        self
+    }
+
+    /// Sent at the end of a scrub, and after each typed or stepped change, like the
+    /// `on_release` of a slider
+    pub fn on_release(mut self, message: Message) -> Self {
+        self.on_release = Some(message);
+        self
+    }
+
+    pub fn width(mut self, width: impl Into<Length>) -> Self {
+        self.width = width.into();
+        self
+    }
+
+    fn format(&self, value: f64) -> String {
+        format!("{value:.*}", self.decimals)
+    }
+
+    fn text_input(&self, text: &str) -> TextInput<'static, InputEvent, Renderer> {
+        TextInput::new(self.placeholder, text, InputEvent::Changed)
+            .on_submit(InputEvent::Submitted)
+            .padding(10)
+            .width(self.width)
+    }
+
+    /// Round and clamp `value`, and publish it if it differs from the current one
+    fn change(&self, value: f64, shell: &mut Shell<'_, Message>) -> f64 {
+        let scale = 10f64.powi(self.decimals as i32);
+        let value = ((value * scale).round() / scale).clamp(*self.range.start(), *self.range.end());
+        if value != self.value {
+            shell.publish((self.on_change)(value));
+        }
+        value
+    }
+
+    fn release(&self, shell: &mut Shell<'_, Message>) {
+        if let Some(message) = &self.on_release {
+            shell.publish(message.clone());
+        }
+    }
+}
+
+fn input_is_focused(tree: &Tree) -> bool {
+    tree.children[0]
+        .state
+        .downcast_ref::<text_input::State>()
+        .is_focused()
+}
+
+impl<'a, Message: Clone> Widget<Message, Renderer> for NumberField<'a, Message> {
+    fn tag(&self) -> tree::Tag {
+        tree::Tag::of::<State>()
+    }
+
+    fn state(&self) -> tree::State {
+        tree::State::new(State::default())
+    }
+
+    fn children(&self) -> Vec<Tree> {
+        vec![Tree::new(
+            &self.text_input("") as &dyn Widget<InputEvent, Renderer>
+        )]
+    }
+
+    fn width(&self) -> Length {
+        self.width
+    }
+
+    fn height(&self) -> Length {
+        Length::Shrink
+    }
+
+    fn layout(&self, renderer: &Renderer, limits: &layout::Limits) -> layout::Node {
+        self.text_input("").layout(renderer, limits)
+    }
+
+    fn on_event(
+        &mut self,
+        tree: &mut Tree,
+        event: Event,
+        layout: Layout<'_>,
+        cursor_position: Point,
+        renderer: &Renderer,
+        clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message>,
+    ) -> event::Status {
+        let is_focused = input_is_focused(tree);
+        let state = tree.state.downcast_mut::<State>();
+        let steps = if state.modifiers.shift() {
+            COARSE_STEPS
+        } else {
+            1.0
+        };
+
+        match event {
+            Event::Keyboard(keyboard::Event::ModifiersChanged(modifiers)) => {
+                state.modifiers = modifiers;
+            }
+            Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left))
+                if !is_focused && layout.bounds().contains(cursor_position) =>
+            {
+                state.press = Some((cursor_position, self.value));
+                return event::Status::Captured;
+            }
+            Event::Mouse(mouse::Event::CursorMoved { .. }) => {
+                if let Some((origin, start)) = state.press {
+                    let distance = cursor_position.x - origin.x;
+                    if state.is_scrubbing || distance.abs() >= DRAG_THRESHOLD {
+                        state.is_scrubbing = true;
+                        let scrubbed = (distance / PIXELS_PER_STEP).round() as f64;
+                        self.change(start + scrubbed * steps * self.step, shell);
+                    }
+                    return event::Status::Captured;
+                }
+            }
+            Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left))
+                if state.press.is_some() =>
+            {
+                state.press = None;
+                if std::mem::take(&mut state.is_scrubbing) {
+                    self.release(shell);
+                } else {
+                    // Not dragged: a click, which selects the value to type over it
+                    let input = tree.children[0].state.downcast_mut::<text_input::State>();
+                    input.focus();
+                    input.select_all();
+                }
+                return event::Status::Captured;
+            }
+            Event::Keyboard(keyboard::Event::KeyPressed {
+                key_code: key_code @ (keyboard::KeyCode::Up | keyboard::KeyCode::Down),
+                ..
+            }) if is_focused => {
+                let current = state
+                    .text
+                    .as_deref()
+                    .and_then(|text| text.trim().parse().ok())
+                    .unwrap_or(self.value);
+                let direction = if key_code == keyboard::KeyCode::Up {
+                    1.0
+                } else {
+                    -1.0
+                };
+                let value = self.change(current + direction * steps * self.step, shell);
+                self.release(shell);
+                state.text = Some(self.format(value));
+                return event::Status::Captured;
+            }
+            _ => {}
+        }
+
+        let text = state
+            .text
+            .clone()
+            .unwrap_or_else(|| self.format(self.value));
+        let mut messages = Vec::new();
+        let mut input_shell = Shell::new(&mut messages);
+        let status = self.text_input(&text).on_event(
+            &mut tree.children[0],
+            event,
+            layout,
+            cursor_position,
+            renderer,
+            clipboard,
+            &mut input_shell,
+        );
+        if let Some(request) = input_shell.redraw_request() {
+            shell.request_redraw(request);
+        }
+        if input_shell.is_layout_invalid() {
+            shell.invalidate_layout();
+        }
+
+        let is_focused = input_is_focused(tree);
+        let state = tree.state.downcast_mut::<State>();
+        for message in messages {
+            match message {
+                InputEvent::Changed(text) => {
+                    if let Ok(value) = text.trim().parse::<f64>() {
+                        if value.is_finite() {
+                            self.change(value, shell);
+                            self.release(shell);
+                        }
+                    }
+                    state.text = Some(text);
+                }
+                InputEvent::Submitted => {
+                    state.text = None;
+                    if let Some(message) = &self.on_submit {
+                        shell.publish(message.clone());
+                    }
+                }
+            }
+        }
+        if !is_focused {
+            state.text = None;
+        }
+        status
+    }
+
+    fn draw(
+        &self,
+        tree: &Tree,
+        renderer: &mut Renderer,
+        theme: &iced::Theme,
+        style: &renderer::Style,
+        layout: Layout<'_>,
+        cursor_position: Point,
+        viewport: &Rectangle,
+    ) {
+        let state = tree.state.downcast_ref::<State>();
+        let text = state
+            .text
+            .clone()
+            .unwrap_or_else(|| self.format(self.value));
+        Widget::draw(
+            &self.text_input(&text),
+            &tree.children[0],
+            renderer,
+            theme,
+            style,
+            layout,
+            cursor_position,
+            viewport,
+        );
+    }
+
+    fn mouse_interaction(
+        &self,
+        tree: &Tree,
+        layout: Layout<'_>,
+        cursor_position: Point,
+        viewport: &Rectangle,
+        renderer: &Renderer,
+    ) -> mouse::Interaction {
+        let state = tree.state.downcast_ref::<State>();
+        let is_over = layout.bounds().contains(cursor_position);
+
+        if state.is_scrubbing || (is_over && !input_is_focused(tree)) {
+            mouse::Interaction::ResizingHorizontally
+        } else {
+            self.text_input("").mouse_interaction(
+                &tree.children[0],
+                layout,
+                cursor_position,
+                viewport,
+                renderer,
+            )
+        }
+    }
+}
+
+impl<'a, Message> From<NumberField<'a, Message>> for Element<'a, Message, Renderer>
+where
+    Message: Clone + 'a,
+{
+    fn from(field: NumberField<'a, Message>) -> Self {
+        Element::new(field)
+    }
+}
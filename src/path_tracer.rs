@@ -0,0 +1,206 @@
+//! A tiny path tracer: a few diffuse spheres lit by a spherical area light.
+//! Unlike the other patterns, it produces real HDR values for the tonemapper to deal with.
+use colstodian::spaces::AcesCg;
+use colstodian::{color, Color, Scene};
+
+use std::ops::RangeInclusive;
+
+use crate::pattern::RenderPattern;
+use crate::vec3::{vec3, Camera, Vec3};
+
+/// Most samples per pixel the path tracer accepts
+pub const MAX_SAMPLES_PER_PIXEL: u32 = 4096;
+pub const BOUNCE_RANGE: RangeInclusive<u32> = 1..=8;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PathTracerSettings {
+    pub samples_per_pixel: u32,
+    /// How many times a path can bounce off a surface before we give up on it
+    pub max_bounces: u32,
+}
+
+impl Default for PathTracerSettings {
+    fn default() -> Self {
+        PathTracerSettings {
+            samples_per_pixel: 16,
+            max_bounces: 4,
+        }
+    }
+}
+
+struct Sphere {
+    center: Vec3,
+    radius: f32,
+    /// Diffuse reflectance, in ACEScg
+    albedo: Vec3,
+    /// Emitted radiance, in ACEScg. Only the light has some.
+    emission: Vec3,
+}
+
+const BLACK: Vec3 = vec3(0.0, 0.0, 0.0);
+/// Dim ambient light, so that the shadows aren't completely black
+const SKY: Vec3 = vec3(0.02, 0.02, 0.03);
+
+const SPHERES: [Sphere; 5] = [
+    // Ground
+    Sphere {
+        center: vec3(0.0, -1000.0, 0.0),
+        radius: 1000.0,
+        albedo: vec3(0.5, 0.5, 0.5),
+        emission: BLACK,
+    },
+    Sphere {
+        center: vec3(-1.1, 0.5, 0.0),
+        radius: 0.5,
+        albedo: vec3(0.7, 0.1, 0.1),
+        emission: BLACK,
+    },
+    Sphere {
+        center: vec3(0.0, 0.5, 0.0),
+        radius: 0.5,
+        albedo: vec3(0.8, 0.8, 0.8),
+        emission: BLACK,
+    },
+    Sphere {
+        center: vec3(1.1, 0.5, 0.0),
+        radius: 0.5,
+        albedo: vec3(0.1, 0.2, 0.7),
+        emission: BLACK,
+    },
+    // Area light
+    Sphere {
+        center: vec3(0.0, 3.5, 1.0),
+        radius: 1.0,
+        albedo: BLACK,
+        emission: vec3(10.0, 9.0, 8.0),
+    },
+];
+
+/// Distance along the ray to the closest sphere, and the sphere
+fn intersect(origin: Vec3, direction: Vec3) -> Option<(f32, &'static Sphere)> {
+    let mut closest: Option<(f32, &Sphere)> = None;
+
+    for sphere in &SPHERES {
+        // Solve |origin + t * direction - center|^2 = radius^2, with a normalized direction
+        let offset = origin - sphere.center;
+        let b = offset.dot(direction);
+        let c = offset.dot(offset) - sphere.radius * sphere.radius;
+        let discriminant = b * b - c;
+        if discriminant < 0.0 {
+            continue;
+        }
+
+        let t = -b - discriminant.sqrt();
+        if t > 0.0 && closest.is_none_or(|(closest_t, _)| t < closest_t) {
+            closest = Some((t, sphere));
+        }
+    }
+
+    closest
+}
+
+/// Small PCG random number generator, seeded per pixel so that renders are repeatable
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        let mut rng = Rng(seed.wrapping_mul(0x9e37_79b9_7f4a_7c15));
+        rng.next_f32();
+        rng
+    }
+
+    /// Uniform random number in 0..1
+    fn next_f32(&mut self) -> f32 {
+        self.0 = self
+            .0
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        let xorshifted = (((self.0 >> 18) ^ self.0) >> 27) as u32;
+        let rotated = xorshifted.rotate_right((self.0 >> 59) as u32);
+        (rotated >> 8) as f32 / (1 << 24) as f32
+    }
+}
+
+/// Random direction around `normal`, more likely towards the normal, to match diffuse surfaces
+fn cosine_sample_hemisphere(normal: Vec3, rng: &mut Rng) -> Vec3 {
+    let (r1, r2) = (rng.next_f32(), rng.next_f32());
+    let phi = 2.0 * std::f32::consts::PI * r1;
+    let radius = r2.sqrt();
+
+    // Any two axes perpendicular to the normal will do
+    let helper = if normal.x.abs() > 0.9 {
+        vec3(0.0, 1.0, 0.0)
+    } else {
+        vec3(1.0, 0.0, 0.0)
+    };
+    let tangent = helper.cross(normal).normalized();
+    let bitangent = normal.cross(tangent);
+
+    (tangent * (radius * phi.cos()) + bitangent * (radius * phi.sin()) + normal * (1.0 - r2).sqrt())
+        .normalized()
+}
+
+/// Radiance coming back along the ray
+fn trace(mut origin: Vec3, mut direction: Vec3, max_bounces: u32, rng: &mut Rng) -> Vec3 {
+    let mut radiance = BLACK;
+    let mut throughput = vec3(1.0, 1.0, 1.0);
+
+    for _ in 0..=max_bounces {
+        let Some((t, sphere)) = intersect(origin, direction) else {
+            radiance = radiance + throughput * SKY;
+            break;
+        };
+
+        radiance = radiance + throughput * sphere.emission;
+
+        let point = origin + direction * t;
+        let normal = (point - sphere.center) * (1.0 / sphere.radius);
+        throughput = throughput * sphere.albedo;
+
+        // With cosine weighted sampling, the cosine and the pdf cancel out
+        origin = point + normal * 1e-4;
+        direction = cosine_sample_hemisphere(normal, rng);
+    }
+
+    radiance
+}
+
+pub struct PathTracer {
+    pub settings: PathTracerSettings,
+    pub aspect_ratio: f32,
+    /// Size of a pixel in U,V coordinates, to jitter the samples within it
+    pub pixel_size: (f32, f32),
+    pub seed: u32,
+}
+
+impl RenderPattern for PathTracer {
+    fn color(&self, u: f32, v: f32) -> Color<AcesCg, Scene> {
+        let camera = Camera::look_at(vec3(0.0, 1.2, 4.5), vec3(0.0, 0.5, 0.0), 40.0);
+
+        let pixel = ((u.to_bits() as u64) << 32) | v.to_bits() as u64;
+        let mut rng =
+            Rng::new(pixel.wrapping_add((self.seed as u64).wrapping_mul(0xd1b5_4a32_d192_ed03)));
+        let mut sum = BLACK;
+
+        for _ in 0..self.settings.samples_per_pixel {
+            let sample_u = u + rng.next_f32() * self.pixel_size.0;
+            let sample_v = v + rng.next_f32() * self.pixel_size.1;
+
+            let direction = camera.ray_direction(sample_u, sample_v, self.aspect_ratio);
+
+            sum = sum + trace(camera.eye(), direction, self.settings.max_bounces, &mut rng);
+        }
+
+        let average = sum * (1.0 / self.settings.samples_per_pixel as f32);
+        color::acescg::<Scene>(average.x, average.y, average.z)
+    }
+
+    /// The albedo of the first sphere the camera ray hits, black for the sky
+    fn albedo(&self, u: f32, v: f32) -> Color<AcesCg, Scene> {
+        let camera = Camera::look_at(vec3(0.0, 1.2, 4.5), vec3(0.0, 0.5, 0.0), 40.0);
+        let direction = camera.ray_direction(u, v, self.aspect_ratio);
+
+        let albedo = intersect(camera.eye(), direction).map_or(BLACK, |(_, sphere)| sphere.albedo);
+        color::acescg::<Scene>(albedo.x, albedo.y, albedo.z)
+    }
+}
@@ -0,0 +1,538 @@
+//! Procedural patterns that can be rendered as a scene
+use colstodian::spaces::{AcesCg, LinearSrgb, Oklab};
+use colstodian::{color, Color, Scene};
+
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+
+use crate::expression::{Inputs, Program};
+use crate::noise::fractal_noise;
+
+/// Something that can be rendered, one pixel at a time
+pub trait RenderPattern: Sync {
+    /// Scene linear color at the normalized U,V coordinates, with V going up
+    fn color(&self, u: f32, v: f32) -> Color<AcesCg, Scene>;
+
+    /// Diffuse reflectance of what is seen at U,V, for the albedo AOV.
+    /// The flat patterns aren't lit, so their color is their albedo.
+    fn albedo(&self, u: f32, v: f32) -> Color<AcesCg, Scene> {
+        self.color(u, v)
+    }
+}
+
+/// Goes from 0 to 1 and back down to 0 every 2 units, so that scrolling
+/// patterns bounce back instead of jumping
+fn triangle_wave(x: f32) -> f32 {
+    1.0 - (x.rem_euclid(2.0) - 1.0).abs()
+}
+
+/// Color space the gradients are blended in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlendSpace {
+    AcesCg,
+    /// Perceptually uniform, so the gradients look more even
+    OkLab,
+    /// Still linear, so this looks the same as ACEScg: only the primaries differ
+    LinearSrgb,
+}
+
+impl BlendSpace {
+    pub const ALL: [BlendSpace; 3] = [
+        BlendSpace::AcesCg,
+        BlendSpace::OkLab,
+        BlendSpace::LinearSrgb,
+    ];
+
+    /// Blend from `a` to `b` in this space, and come back to ACEScg
+    pub fn blend(
+        &self,
+        a: Color<AcesCg, Scene>,
+        b: Color<AcesCg, Scene>,
+        factor: f32,
+    ) -> Color<AcesCg, Scene> {
+        match self {
+            BlendSpace::AcesCg => a.blend(b, factor),
+            BlendSpace::OkLab => a
+                .convert::<Oklab>()
+                .blend(b.convert::<Oklab>(), factor)
+                .convert::<AcesCg>(),
+            BlendSpace::LinearSrgb => a
+                .convert::<LinearSrgb>()
+                .blend(b.convert::<LinearSrgb>(), factor)
+                .convert::<AcesCg>(),
+        }
+    }
+}
+
+impl std::fmt::Display for BlendSpace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            BlendSpace::AcesCg => "ACEScg",
+            BlendSpace::OkLab => "OkLab",
+            BlendSpace::LinearSrgb => "Linear sRGB",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// A color at a position along a gradient
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColorStop {
+    /// From 0 to 1
+    pub position: f32,
+    /// Scene linear ACEScg
+    pub color: [f32; 3],
+}
+
+/// Colors blended between stops, holding the first and last ones past the ends
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gradient {
+    /// In the order they were added, which isn't necessarily that of their positions
+    pub stops: Vec<ColorStop>,
+}
+
+/// The most stops a gradient can have, as many as the GPU renderer takes
+pub const MAX_GRADIENT_STOPS: usize = 8;
+
+impl Gradient {
+    /// From `start` at 0 to `end` at 1
+    pub fn new(start: [f32; 3], end: [f32; 3]) -> Self {
+        Gradient {
+            stops: vec![
+                ColorStop {
+                    position: 0.0,
+                    color: start,
+                },
+                ColorStop {
+                    position: 1.0,
+                    color: end,
+                },
+            ],
+        }
+    }
+
+    /// The same stops, ordered by position as `sample` expects
+    pub fn sorted(&self) -> Gradient {
+        let mut stops = self.stops.clone();
+        stops.sort_by(|a, b| a.position.total_cmp(&b.position));
+        Gradient { stops }
+    }
+
+    /// Color at `t`, blended in `space`. The stops have to be sorted.
+    pub fn sample(&self, t: f32, space: BlendSpace) -> Color<AcesCg, Scene> {
+        let acescg = |stop: &ColorStop| {
+            let [r, g, b] = stop.color;
+            color::acescg::<Scene>(r, g, b)
+        };
+        let (Some(first), Some(last)) = (self.stops.first(), self.stops.last()) else {
+            return color::acescg::<Scene>(0.0, 0.0, 0.0);
+        };
+
+        // First stop past `t`, so that we blend between it and the previous one
+        match self.stops.iter().position(|stop| stop.position > t) {
+            None => acescg(last),
+            Some(0) => acescg(first),
+            Some(next) => {
+                let (start, end) = (&self.stops[next - 1], &self.stops[next]);
+                let local_t = (t - start.position) / (end.position - start.position);
+                space.blend(acescg(start), acescg(end), local_t)
+            }
+        }
+    }
+}
+
+/// The directions the bilinear gradient blends along
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GradientAxis {
+    U,
+    V,
+}
+
+impl GradientAxis {
+    pub const ALL: [GradientAxis; 2] = [GradientAxis::U, GradientAxis::V];
+}
+
+impl std::fmt::Display for GradientAxis {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            GradientAxis::U => "Horizontal (U)",
+            GradientAxis::V => "Vertical (V)",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Mix of a gradient from left to right and one from bottom to top, by default
+/// red to green and red to blue
+pub struct BilinearGradient {
+    /// Mix between the horizontal and vertical blends
+    pub blend: f32,
+    /// Both sorted
+    pub u_gradient: Gradient,
+    pub v_gradient: Gradient,
+    pub space: BlendSpace,
+    /// In seconds, the colors slide across the image over time
+    pub time: f32,
+}
+
+impl RenderPattern for BilinearGradient {
+    fn color(&self, u: f32, v: f32) -> Color<AcesCg, Scene> {
+        let offset = self.time * 0.25;
+        let h_blended = self
+            .u_gradient
+            .sample(triangle_wave(u + offset), self.space);
+        let v_blended = self
+            .v_gradient
+            .sample(triangle_wave(v + offset), self.space);
+        self.space.blend(h_blended, v_blended, self.blend)
+    }
+}
+
+/// Gradient from a center point, at its start, outwards to the edge color, at its end
+pub struct RadialGradient {
+    /// Sorted
+    pub gradient: Gradient,
+    pub center: (f32, f32),
+    /// Distance from the center at which the edge color is reached, relative to the height
+    pub radius: f32,
+    /// Width over height of the image, so that the gradient stays circular
+    pub aspect_ratio: f32,
+    pub space: BlendSpace,
+    /// In seconds, the radius pulses over time
+    pub time: f32,
+}
+
+impl RenderPattern for RadialGradient {
+    fn color(&self, u: f32, v: f32) -> Color<AcesCg, Scene> {
+        let du = (u - self.center.0) * self.aspect_ratio;
+        let dv = v - self.center.1;
+        let distance = (du * du + dv * dv).sqrt();
+        let radius = self.radius * (1.0 + 0.25 * (std::f32::consts::PI * self.time).sin());
+        let t = (distance / radius).clamp(0.0, 1.0);
+        self.gradient.sample(t, self.space)
+    }
+}
+
+/// Alternating dark and light gray squares
+pub struct Checkerboard {
+    /// Number of squares along the height of the image
+    pub squares: f32,
+    pub aspect_ratio: f32,
+}
+
+impl RenderPattern for Checkerboard {
+    fn color(&self, u: f32, v: f32) -> Color<AcesCg, Scene> {
+        let column = (u * self.aspect_ratio * self.squares).floor() as i32;
+        let row = (v * self.squares).floor() as i32;
+
+        if (column + row) % 2 == 0 {
+            color::acescg::<Scene>(0.02, 0.02, 0.02)
+        } else {
+            color::acescg::<Scene>(0.8, 0.8, 0.8)
+        }
+    }
+}
+
+/// U in the red channel and V in the green channel, handy to check orientation
+pub struct UvRamp;
+
+impl RenderPattern for UvRamp {
+    fn color(&self, u: f32, v: f32) -> Color<AcesCg, Scene> {
+        color::acescg::<Scene>(u, v, 0.0)
+    }
+}
+
+pub const OCTAVE_RANGE: RangeInclusive<u32> = 1..=8;
+pub const FREQUENCY_RANGE: RangeInclusive<f32> = 0.5..=32.0;
+pub const LACUNARITY_RANGE: RangeInclusive<f32> = 1.0..=4.0;
+/// Of the radial gradient, relative to the height
+pub const RADIUS_RANGE: RangeInclusive<f32> = 0.01..=1.5;
+pub const ITERATION_RANGE: RangeInclusive<u32> = 16..=2048;
+
+/// Parameters of the fractal noise pattern
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NoiseSettings {
+    /// Number of noise cells along the height of the image, for the first octave
+    pub frequency: f32,
+    pub octaves: u32,
+    /// How much finer each octave is than the previous one
+    pub lacunarity: f32,
+}
+
+impl Default for NoiseSettings {
+    fn default() -> Self {
+        NoiseSettings {
+            frequency: 4.0,
+            octaves: 4,
+            lacunarity: 2.0,
+        }
+    }
+}
+
+/// Grayscale fractal Perlin noise, spanning a few stops around mid gray
+pub struct Noise {
+    pub settings: NoiseSettings,
+    pub aspect_ratio: f32,
+    /// In seconds, the noise scrolls diagonally over time
+    pub time: f32,
+    pub seed: u32,
+}
+
+impl RenderPattern for Noise {
+    fn color(&self, u: f32, v: f32) -> Color<AcesCg, Scene> {
+        let NoiseSettings {
+            frequency,
+            octaves,
+            lacunarity,
+        } = self.settings;
+        let offset = self.time * 0.5;
+        let noise = fractal_noise(
+            u * self.aspect_ratio * frequency + offset,
+            v * frequency + offset,
+            octaves,
+            lacunarity,
+            self.seed,
+        );
+
+        // +/- 4 stops, so that the highlights have something for the tonemapper to do
+        let value = 0.18 * 2.0_f32.powf(noise * 4.0);
+        color::acescg::<Scene>(value, value, value)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FractalKind {
+    Mandelbrot,
+    Julia,
+}
+
+impl FractalKind {
+    pub const ALL: [FractalKind; 2] = [FractalKind::Mandelbrot, FractalKind::Julia];
+}
+
+impl std::fmt::Display for FractalKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            FractalKind::Mandelbrot => "Mandelbrot",
+            FractalKind::Julia => "Julia",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Colors the iteration counts get mapped through
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorRamp {
+    Fire,
+    Ocean,
+    Grayscale,
+}
+
+impl ColorRamp {
+    pub const ALL: [ColorRamp; 3] = [ColorRamp::Fire, ColorRamp::Ocean, ColorRamp::Grayscale];
+
+    /// Positions and scene linear ACEScg colors of the ramp. The last stops go
+    /// above 1.0, so that the edges of the set glow through the tonemapper.
+    fn stops(&self) -> &'static [(f32, [f32; 3])] {
+        match self {
+            ColorRamp::Fire => &[
+                (0.0, [0.0, 0.0, 0.0]),
+                (0.3, [0.6, 0.02, 0.0]),
+                (0.6, [1.0, 0.4, 0.0]),
+                (0.85, [1.0, 0.9, 0.3]),
+                (1.0, [2.0, 2.0, 1.6]),
+            ],
+            ColorRamp::Ocean => &[
+                (0.0, [0.0, 0.0, 0.02]),
+                (0.4, [0.0, 0.1, 0.4]),
+                (0.7, [0.1, 0.6, 0.8]),
+                (1.0, [1.5, 1.8, 2.0]),
+            ],
+            ColorRamp::Grayscale => &[(0.0, [0.0, 0.0, 0.0]), (1.0, [1.0, 1.0, 1.0])],
+        }
+    }
+
+    /// Color of the ramp at `t`, between 0 and 1
+    pub fn sample(&self, t: f32) -> Color<AcesCg, Scene> {
+        let stops = self.stops();
+        let t = t.clamp(0.0, 1.0);
+
+        // First stop past `t`, so that we blend between it and the previous one
+        let next = stops
+            .iter()
+            .position(|(position, _)| *position >= t)
+            .unwrap_or(stops.len() - 1)
+            .max(1);
+        let (start, start_color) = stops[next - 1];
+        let (end, end_color) = stops[next];
+
+        let local_t = ((t - start) / (end - start)).clamp(0.0, 1.0);
+        let start_color = color::acescg::<Scene>(start_color[0], start_color[1], start_color[2]);
+        let end_color = color::acescg::<Scene>(end_color[0], end_color[1], end_color[2]);
+        start_color.blend(end_color, local_t)
+    }
+}
+
+impl std::fmt::Display for ColorRamp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ColorRamp::Fire => "Fire",
+            ColorRamp::Ocean => "Ocean",
+            ColorRamp::Grayscale => "Grayscale",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Parameters of the fractal pattern. The view is kept in f64,
+/// so that we can zoom in much further than the pixel coordinates would allow.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FractalSettings {
+    pub kind: FractalKind,
+    /// Point of the complex plane at the center of the image
+    pub center: (f64, f64),
+    /// Height of the view, in the complex plane
+    pub span: f64,
+    pub max_iterations: u32,
+    /// The constant added at every iteration of the Julia set
+    pub julia_constant: (f64, f64),
+    pub ramp: ColorRamp,
+}
+
+impl FractalSettings {
+    /// The default view of `kind`, showing the whole set
+    pub fn new(kind: FractalKind) -> Self {
+        let center = match kind {
+            FractalKind::Mandelbrot => (-0.5, 0.0),
+            FractalKind::Julia => (0.0, 0.0),
+        };
+
+        FractalSettings {
+            kind,
+            center,
+            span: 3.0,
+            max_iterations: 256,
+            julia_constant: (-0.8, 0.156),
+            ramp: ColorRamp::Fire,
+        }
+    }
+
+    /// Point of the complex plane under `position`, normalized from the top left of the image
+    pub fn point_at(&self, position: (f32, f32), aspect_ratio: f32) -> (f64, f64) {
+        let (x, y) = (position.0 as f64, position.1 as f64);
+        (
+            self.center.0 + (x - 0.5) * self.span * aspect_ratio as f64,
+            self.center.1 + (0.5 - y) * self.span,
+        )
+    }
+
+    /// Scale the view by `factor` (below 1 zooms in), keeping `anchor` in place
+    pub fn zoom_around(&mut self, anchor: (f64, f64), factor: f64) {
+        self.center = (
+            anchor.0 + (self.center.0 - anchor.0) * factor,
+            anchor.1 + (self.center.1 - anchor.1) * factor,
+        );
+        self.span *= factor;
+    }
+}
+
+impl Default for FractalSettings {
+    fn default() -> Self {
+        FractalSettings::new(FractalKind::Mandelbrot)
+    }
+}
+
+/// Escape time fractals, colored by their smoothed iteration count
+pub struct Fractal {
+    pub settings: FractalSettings,
+    pub aspect_ratio: f32,
+}
+
+impl Fractal {
+    /// Smoothed number of iterations before `z` escapes, None if it never does
+    fn escape_time(&self, z: (f64, f64)) -> Option<f64> {
+        // A large bailout radius makes the smoothing more accurate
+        const BAILOUT: f64 = 256.0;
+
+        let c = match self.settings.kind {
+            FractalKind::Mandelbrot => z,
+            FractalKind::Julia => self.settings.julia_constant,
+        };
+        let (mut x, mut y) = match self.settings.kind {
+            FractalKind::Mandelbrot => (0.0, 0.0),
+            FractalKind::Julia => z,
+        };
+
+        for iteration in 0..self.settings.max_iterations {
+            let (x2, y2) = (x * x, y * y);
+            if x2 + y2 > BAILOUT * BAILOUT {
+                // See https://iquilezles.org/articles/msetsmooth/
+                let smoothing = ((x2 + y2).ln() / 2.0).ln() / std::f64::consts::LN_2;
+                return Some(iteration as f64 + 1.0 - smoothing);
+            }
+            y = 2.0 * x * y + c.1;
+            x = x2 - y2 + c.0;
+        }
+
+        None
+    }
+}
+
+impl RenderPattern for Fractal {
+    fn color(&self, u: f32, v: f32) -> Color<AcesCg, Scene> {
+        // U,V have V going up, the view has it going down
+        let z = self.settings.point_at((u, 1.0 - v), self.aspect_ratio);
+
+        match self.escape_time(z) {
+            Some(iterations) => {
+                // Square root spreads the colors out more evenly than a linear mapping
+                let t = (iterations.max(0.0) / self.settings.max_iterations as f64).sqrt();
+                self.settings.ramp.sample(t as f32)
+            }
+            None => color::acescg::<Scene>(0.0, 0.0, 0.0),
+        }
+    }
+}
+
+/// Evaluates a user typed expression for every pixel
+pub struct ExpressionPattern {
+    pub program: Arc<Program>,
+    pub width: usize,
+    pub height: usize,
+    pub time: f32,
+}
+
+impl RenderPattern for ExpressionPattern {
+    fn color(&self, u: f32, v: f32) -> Color<AcesCg, Scene> {
+        let inputs = Inputs {
+            u,
+            v,
+            x: u * self.width as f32,
+            y: v * self.height as f32,
+            t: self.time,
+        };
+        let [r, g, b] = self.program.evaluate(&inputs);
+        color::acescg::<Scene>(r, g, b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn radial_gradient_goes_from_the_first_to_the_last_stop() {
+        let (center, edge) = ([1.0, 0.5, 0.0], [0.0, 0.2, 1.0]);
+        let gradient = RadialGradient {
+            gradient: Gradient::new(center, edge),
+            center: (0.5, 0.5),
+            radius: 0.25,
+            aspect_ratio: 1.0,
+            space: BlendSpace::AcesCg,
+            time: 0.0,
+        };
+        let channels = |color: Color<AcesCg, Scene>| [color.r, color.g, color.b];
+        assert_eq!(channels(gradient.color(0.5, 0.5)), center);
+        assert_eq!(channels(gradient.color(0.9, 0.5)), edge);
+    }
+}
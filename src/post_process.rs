@@ -0,0 +1,272 @@
+//! Post-processing of the scene linear image, before the display transform: a stack of
+//! passes run in order over the whole image. Tiles are shown as they are rendered, the
+//! passes only once the image is complete, as a blur needs the pixels around each one.
+use std::borrow::Cow;
+use std::ops::RangeInclusive;
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use crate::noise::hash;
+use crate::render::{Framebuffer, RenderBuffer};
+
+const GRAIN_SEED: u32 = 0x6772_6169;
+
+/// A step of post-processing, from scene linear pixels to scene linear pixels
+pub trait RenderPass {
+    fn apply(&self, framebuffer: Framebuffer) -> Framebuffer;
+}
+
+/// Scales the colors by 2^`stops`, e.g. to make up for a pass darkening the image
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Exposure {
+    pub stops: f32,
+}
+
+impl RenderPass for Exposure {
+    fn apply(&self, mut framebuffer: Framebuffer) -> Framebuffer {
+        let scale = self.stops.exp2();
+        for pixel in framebuffer.pixels_mut().chunks_exact_mut(4) {
+            for channel in &mut pixel[..3] {
+                *channel *= scale;
+            }
+        }
+        framebuffer
+    }
+}
+
+/// Gaussian blur of the four channels, with a standard deviation of `radius` pixels.
+/// The pixels along the edges are repeated outwards.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Blur {
+    pub radius: f32,
+}
+
+impl RenderPass for Blur {
+    fn apply(&self, framebuffer: Framebuffer) -> Framebuffer {
+        let (width, height) = (framebuffer.width(), framebuffer.height());
+        if self.radius <= 0.0 || width == 0 || height == 0 {
+            return framebuffer;
+        }
+
+        let reach = (3.0 * self.radius).ceil() as usize;
+        let weights: Vec<f32> = (0..=2 * reach)
+            .map(|tap| {
+                let distance = tap as f32 - reach as f32;
+                (-distance * distance / (2.0 * self.radius * self.radius)).exp()
+            })
+            .collect();
+        let total: f32 = weights.iter().sum();
+        let kernel: Vec<f32> = weights.iter().map(|weight| weight / total).collect();
+
+        // Separable: along the rows, then along the columns
+        let rows = convolve(framebuffer.pixels(), width, height, &kernel, true);
+        let blurred = convolve(&rows, width, height, &kernel, false);
+        Framebuffer::from_pixels(width, height, blurred)
+    }
+}
+
+/// Convolve the RGBA `pixels` with `kernel`, centered on each pixel, along the rows when
+/// `horizontal` or along the columns otherwise
+fn convolve(
+    pixels: &[f32],
+    width: usize,
+    height: usize,
+    kernel: &[f32],
+    horizontal: bool,
+) -> RenderBuffer {
+    let reach = (kernel.len() / 2) as isize;
+    let convolve_row = |(y, output): (usize, &mut [f32])| {
+        for (x, output_pixel) in output.chunks_exact_mut(4).enumerate() {
+            let mut sum = [0.0; 4];
+            for (tap, weight) in kernel.iter().enumerate() {
+                let offset = tap as isize - reach;
+                let (source_x, source_y) = if horizontal {
+                    (
+                        (x as isize + offset).clamp(0, width as isize - 1) as usize,
+                        y,
+                    )
+                } else {
+                    (
+                        x,
+                        (y as isize + offset).clamp(0, height as isize - 1) as usize,
+                    )
+                };
+                let index = (source_y * width + source_x) * 4;
+                for (channel, sum) in sum.iter_mut().enumerate() {
+                    *sum += weight * pixels[index + channel];
+                }
+            }
+            output_pixel.copy_from_slice(&sum);
+        }
+    };
+
+    let mut output = vec![0.0; pixels.len()];
+    #[cfg(feature = "parallel")]
+    output
+        .par_chunks_exact_mut(width * 4)
+        .enumerate()
+        .for_each(convolve_row);
+    #[cfg(not(feature = "parallel"))]
+    output
+        .chunks_exact_mut(width * 4)
+        .enumerate()
+        .for_each(convolve_row);
+    output
+}
+
+/// Monochrome noise scaling each pixel by up to 1 ± `amount`, the same on every render
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Grain {
+    pub amount: f32,
+}
+
+impl RenderPass for Grain {
+    fn apply(&self, mut framebuffer: Framebuffer) -> Framebuffer {
+        let width = framebuffer.width().max(1);
+        let uniform =
+            |x: usize, y: usize, seed: u32| hash(x as i32, y as i32, seed) as f32 / u32::MAX as f32;
+        for (index, pixel) in framebuffer.pixels_mut().chunks_exact_mut(4).enumerate() {
+            let (x, y) = (index % width, index / width);
+            // The sum of two uniform values, which is denser around 0 than a single one
+            let noise = uniform(x, y, GRAIN_SEED) + uniform(x, y, GRAIN_SEED + 1) - 1.0;
+            let scale = 1.0 + self.amount * noise;
+            for channel in &mut pixel[..3] {
+                *channel *= scale;
+            }
+        }
+        framebuffer
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PassKind {
+    Exposure,
+    Blur,
+    Grain,
+}
+
+impl PassKind {
+    pub const ALL: [PassKind; 3] = [PassKind::Exposure, PassKind::Blur, PassKind::Grain];
+
+    /// The pass of this kind with the given `amount` of its parameter
+    pub fn with_amount(self, amount: f32) -> PostPass {
+        match self {
+            PassKind::Exposure => PostPass::Exposure(Exposure { stops: amount }),
+            PassKind::Blur => PostPass::Blur(Blur { radius: amount }),
+            PassKind::Grain => PostPass::Grain(Grain { amount }),
+        }
+    }
+
+    /// The pass as added to the stack, which already changes the image a little
+    pub fn default_pass(self) -> PostPass {
+        self.with_amount(match self {
+            PassKind::Exposure => 0.0,
+            PassKind::Blur => 2.0,
+            PassKind::Grain => 0.1,
+        })
+    }
+
+    /// Name of the parameter of the pass, as written in presets
+    pub fn parameter(self) -> &'static str {
+        match self {
+            PassKind::Exposure => "stops",
+            PassKind::Blur => "radius",
+            PassKind::Grain => "amount",
+        }
+    }
+
+    pub fn range(self) -> RangeInclusive<f32> {
+        match self {
+            PassKind::Exposure => -8.0..=8.0,
+            PassKind::Blur => 0.0..=16.0,
+            PassKind::Grain => 0.0..=1.0,
+        }
+    }
+}
+
+impl std::fmt::Display for PassKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            PassKind::Exposure => "Exposure",
+            PassKind::Blur => "Blur",
+            PassKind::Grain => "Grain",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// A pass of the stack, as edited in the UI and saved in the presets
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PostPass {
+    Exposure(Exposure),
+    Blur(Blur),
+    Grain(Grain),
+}
+
+impl PostPass {
+    pub fn kind(&self) -> PassKind {
+        match self {
+            PostPass::Exposure(_) => PassKind::Exposure,
+            PostPass::Blur(_) => PassKind::Blur,
+            PostPass::Grain(_) => PassKind::Grain,
+        }
+    }
+
+    /// Value of the parameter of the pass, see `PassKind::parameter`
+    pub fn amount(&self) -> f32 {
+        match self {
+            PostPass::Exposure(exposure) => exposure.stops,
+            PostPass::Blur(blur) => blur.radius,
+            PostPass::Grain(grain) => grain.amount,
+        }
+    }
+
+    fn pass(&self) -> &dyn RenderPass {
+        match self {
+            PostPass::Exposure(exposure) => exposure,
+            PostPass::Blur(blur) => blur,
+            PostPass::Grain(grain) => grain,
+        }
+    }
+}
+
+/// Run the `passes` in order over `framebuffer`, which is only copied when there is
+/// at least one pass
+pub fn apply_stack<'a>(passes: &[PostPass], framebuffer: &'a Framebuffer) -> Cow<'a, Framebuffer> {
+    if passes.is_empty() {
+        return Cow::Borrowed(framebuffer);
+    }
+    let processed = passes
+        .iter()
+        .fold(framebuffer.clone(), |framebuffer, pass| {
+            pass.pass().apply(framebuffer)
+        });
+    Cow::Owned(processed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blur_preserves_energy() {
+        // A single bright pixel, far enough from the edges for none of it to be repeated
+        let size = 32;
+        let mut framebuffer = Framebuffer::new(size, size);
+        let total = [1.0, 2.0, 4.0, 1.0];
+        framebuffer.set_pixel(size / 2, size / 2, total);
+
+        let blurred = apply_stack(&[PassKind::Blur.with_amount(3.0)], &framebuffer);
+        for (channel, total) in total.into_iter().enumerate() {
+            let sum: f32 = blurred.pixels().iter().skip(channel).step_by(4).sum();
+            assert!((sum - total).abs() < 1e-5 * total, "{sum} != {total}");
+        }
+    }
+
+    #[test]
+    fn no_passes_borrow_the_pixels() {
+        let framebuffer = Framebuffer::from_pixels(2, 2, vec![0.5; 4 * 4]);
+        assert!(matches!(apply_stack(&[], &framebuffer), Cow::Borrowed(_)));
+    }
+}
@@ -0,0 +1,598 @@
+//! Presets of the render and display settings, saved as TOML files so that
+//! favorite looks can be recalled across sessions
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+
+use toml::value::{Table, Value};
+
+use crate::color_pipeline::{
+    DisplaySettings, DisplayTransform, Dithering, GamutMapping, TonemapKind, EXPOSURE_RANGE,
+    GAMMA_RANGE,
+};
+use crate::i18n::{tr_args, Text};
+use crate::path_tracer::{BOUNCE_RANGE, MAX_SAMPLES_PER_PIXEL};
+use crate::pattern::{
+    BlendSpace, ColorRamp, ColorStop, FractalKind, Gradient, FREQUENCY_RANGE, ITERATION_RANGE,
+    LACUNARITY_RANGE, MAX_GRADIENT_STOPS, OCTAVE_RANGE, RADIUS_RANGE,
+};
+use crate::post_process::{PassKind, PostPass};
+use crate::raymarcher::SdfOperation;
+use crate::render::{AntiAliasing, BlendMode, RenderSettings, SceneKind, MAX_DIMENSION};
+use crate::tone_curve::{ToneCurve, MAX_CURVE_POINTS};
+use crate::white_balance::{TEMPERATURE_RANGE, TINT_RANGE};
+
+/// Where presets are saved, and listed from
+pub const PRESETS_DIRECTORY: &str = "presets";
+const EXTENSION: &str = "toml";
+
+/// Everything a preset restores
+#[derive(Clone)]
+pub struct Preset {
+    pub render_settings: RenderSettings,
+    pub display_settings: DisplaySettings,
+    /// Source of the expression scene, which isn't kept once compiled
+    pub expression: String,
+}
+
+/// Names of the presets found in `PRESETS_DIRECTORY`, sorted
+pub fn list() -> Vec<String> {
+    let mut names: Vec<String> = std::fs::read_dir(PRESETS_DIRECTORY)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            if path.extension()? != EXTENSION {
+                return None;
+            }
+            Some(path.file_stem()?.to_str()?.to_owned())
+        })
+        .collect();
+    names.sort();
+    names
+}
+
+pub fn path(name: &str) -> PathBuf {
+    Path::new(PRESETS_DIRECTORY).join(format!("{name}.{EXTENSION}"))
+}
+
+pub fn save(name: &str, preset: &Preset) -> Result<(), String> {
+    let path = path(name);
+    let error = |e: &dyn std::fmt::Display| tr_args(Text::CouldNotSave, &[&path.display(), &e]);
+
+    let contents = toml::to_string(&Value::Table(to_table(preset))).map_err(|e| error(&e))?;
+    std::fs::create_dir_all(PRESETS_DIRECTORY).map_err(|e| error(&e))?;
+    std::fs::write(&path, contents).map_err(|e| error(&e))
+}
+
+/// Load the preset `name` on top of `current`, so that whatever it doesn't
+/// mention (e.g. because it was saved by an older version) is left as is
+pub fn load(name: &str, current: Preset) -> Result<Preset, String> {
+    let path = path(name);
+    let error = |e: &dyn std::fmt::Display| tr_args(Text::CouldNotLoad, &[&path.display(), &e]);
+
+    let contents = std::fs::read_to_string(&path).map_err(|e| error(&e))?;
+    let table: Table = toml::from_str(&contents).map_err(|e| error(&e))?;
+    let mut preset = current;
+    apply_table(&table, &mut preset).map_err(|e| error(&e))?;
+    Ok(preset)
+}
+
+fn to_table(preset: &Preset) -> Table {
+    let render = &preset.render_settings;
+    let display = &preset.display_settings;
+    let float = |value: f32| Value::Float(value as f64);
+    let integer = |value: u32| Value::Integer(value as i64);
+    let name = |value: &dyn std::fmt::Display| Value::String(value.to_string());
+    let pair = |(x, y): (f64, f64)| Value::Array(vec![Value::Float(x), Value::Float(y)]);
+    let gradient = |gradient: &Gradient| {
+        let stops = gradient.stops.iter().map(|stop| {
+            let mut table = Table::new();
+            table.insert(String::from("position"), float(stop.position));
+            let color = stop.color.iter().map(|&channel| float(channel)).collect();
+            table.insert(String::from("color"), Value::Array(color));
+            Value::Table(table)
+        });
+        Value::Array(stops.collect())
+    };
+    let section = |entries: Vec<(&str, Value)>| {
+        Value::Table(
+            entries
+                .into_iter()
+                .map(|(key, value)| (String::from(key), value))
+                .collect(),
+        )
+    };
+
+    let render_section = section(vec![
+        ("width", Value::Integer(render.width as i64)),
+        ("height", Value::Integer(render.height as i64)),
+        ("scene", name(&render.scene)),
+        ("anti_aliasing", name(&render.anti_aliasing)),
+        ("seed", integer(render.seed)),
+        ("frame", integer(render.frame)),
+        ("bilinear_blend", float(render.bilinear_blend)),
+        ("u_gradient", gradient(&render.u_gradient)),
+        ("v_gradient", gradient(&render.v_gradient)),
+        ("blend_space", name(&render.blend_space)),
+        (
+            "radial_center",
+            pair((render.radial_center.0 as f64, render.radial_center.1 as f64)),
+        ),
+        ("radial_radius", float(render.radial_radius)),
+        ("expression", Value::String(preset.expression.clone())),
+        (
+            "noise",
+            section(vec![
+                ("frequency", float(render.noise.frequency)),
+                ("octaves", integer(render.noise.octaves)),
+                ("lacunarity", float(render.noise.lacunarity)),
+            ]),
+        ),
+        (
+            "fractal",
+            section(vec![
+                ("kind", name(&render.fractal.kind)),
+                ("center", pair(render.fractal.center)),
+                ("span", Value::Float(render.fractal.span)),
+                ("max_iterations", integer(render.fractal.max_iterations)),
+                ("julia_constant", pair(render.fractal.julia_constant)),
+                ("ramp", name(&render.fractal.ramp)),
+            ]),
+        ),
+        (
+            "path_tracer",
+            section(vec![
+                (
+                    "samples_per_pixel",
+                    integer(render.path_tracer.samples_per_pixel),
+                ),
+                ("max_bounces", integer(render.path_tracer.max_bounces)),
+            ]),
+        ),
+        (
+            "sdf",
+            section(vec![
+                ("operation", name(&render.sdf.operation)),
+                ("orbit_yaw", float(render.sdf.orbit_yaw)),
+                ("orbit_pitch", float(render.sdf.orbit_pitch)),
+                ("orbit_distance", float(render.sdf.orbit_distance)),
+            ]),
+        ),
+        (
+            "overlay",
+            section(vec![
+                ("enabled", Value::Boolean(render.overlay.enabled)),
+                ("scene", name(&render.overlay.scene)),
+                ("blend_mode", name(&render.overlay.blend_mode)),
+                ("opacity", float(render.overlay.opacity)),
+            ]),
+        ),
+    ]);
+
+    let display_section = section(vec![
+        ("global_alpha", float(display.global_alpha)),
+        ("temperature", float(display.white_balance.temperature)),
+        ("tint", float(display.white_balance.tint)),
+        ("exposure", float(display.exposure)),
+        ("tonemap", name(&display.tonemap)),
+        (
+            "tone_curve",
+            Value::Array(
+                display
+                    .tone_curve
+                    .points
+                    .iter()
+                    .map(|&[x, y]| pair((x as f64, y as f64)))
+                    .collect(),
+            ),
+        ),
+        (
+            "post_passes",
+            Value::Array(
+                display
+                    .post_passes
+                    .iter()
+                    .map(|pass| {
+                        let mut table = Table::new();
+                        table.insert(String::from("pass"), name(&pass.kind()));
+                        table.insert(String::from(pass.kind().parameter()), float(pass.amount()));
+                        Value::Table(table)
+                    })
+                    .collect(),
+            ),
+        ),
+        ("gamma", float(display.gamma)),
+        ("display", name(&display.display)),
+        ("gamut_mapping", name(&display.gamut_mapping)),
+        ("dithering", name(&display.dithering)),
+    ]);
+
+    Table::from_iter([
+        (String::from("render"), render_section),
+        (String::from("display"), display_section),
+    ])
+}
+
+/// Typed access to the entries of a section, which are all optional
+struct Section<'a> {
+    name: &'a str,
+    table: Option<&'a Table>,
+}
+
+impl<'a> Section<'a> {
+    fn new(parent: Option<&'a Table>, name: &'a str) -> Result<Section<'a>, String> {
+        let table = match parent.and_then(|parent| parent.get(name)) {
+            Some(Value::Table(table)) => Some(table),
+            Some(_) => return Err(format!("{name} should be a table")),
+            None => None,
+        };
+        Ok(Section { name, table })
+    }
+
+    fn section(&self, name: &'a str) -> Result<Section<'a>, String> {
+        Section::new(self.table, name)
+    }
+
+    fn read<T>(
+        &self,
+        key: &str,
+        target: &mut T,
+        expected: &str,
+        convert: impl Fn(&Value) -> Option<T>,
+    ) -> Result<(), String> {
+        if let Some(value) = self.table.and_then(|table| table.get(key)) {
+            *target = convert(value)
+                .ok_or_else(|| format!("{}.{key} should be {expected}", self.name))?;
+        }
+        Ok(())
+    }
+
+    fn f32(&self, key: &str, target: &mut f32) -> Result<(), String> {
+        self.read(key, target, "a number", |value| {
+            float(value).map(|x| x as f32)
+        })
+    }
+
+    fn f64(&self, key: &str, target: &mut f64) -> Result<(), String> {
+        self.read(key, target, "a number", float)
+    }
+
+    fn u32(&self, key: &str, target: &mut u32) -> Result<(), String> {
+        self.read(key, target, "a positive integer", |value| {
+            value.as_integer()?.try_into().ok()
+        })
+    }
+
+    fn u32_in(
+        &self,
+        key: &str,
+        target: &mut u32,
+        range: RangeInclusive<u32>,
+    ) -> Result<(), String> {
+        let expected = format!("an integer from {} to {}", range.start(), range.end());
+        self.read(key, target, &expected, |value| {
+            value
+                .as_integer()?
+                .try_into()
+                .ok()
+                .filter(|integer| range.contains(integer))
+        })
+    }
+
+    fn usize_in(
+        &self,
+        key: &str,
+        target: &mut usize,
+        range: RangeInclusive<usize>,
+    ) -> Result<(), String> {
+        let expected = format!("an integer from {} to {}", range.start(), range.end());
+        self.read(key, target, &expected, |value| {
+            value
+                .as_integer()?
+                .try_into()
+                .ok()
+                .filter(|integer| range.contains(integer))
+        })
+    }
+
+    fn f32_in(
+        &self,
+        key: &str,
+        target: &mut f32,
+        range: RangeInclusive<f32>,
+    ) -> Result<(), String> {
+        let expected = format!("a number from {} to {}", range.start(), range.end());
+        self.read(key, target, &expected, |value| {
+            float(value)
+                .map(|x| x as f32)
+                .filter(|number| range.contains(number))
+        })
+    }
+
+    fn bool(&self, key: &str, target: &mut bool) -> Result<(), String> {
+        self.read(key, target, "true or false", Value::as_bool)
+    }
+
+    fn string(&self, key: &str, target: &mut String) -> Result<(), String> {
+        self.read(key, target, "a string", |value| {
+            value.as_str().map(String::from)
+        })
+    }
+
+    fn pair(&self, key: &str, target: &mut (f64, f64)) -> Result<(), String> {
+        self.read(key, target, "a pair of numbers", |value| {
+            match value.as_array()?.as_slice() {
+                [x, y] => Some((float(x)?, float(y)?)),
+                _ => None,
+            }
+        })
+    }
+
+    /// Stops like `{ position = 0.5, color = [1.0, 0.0, 0.0] }`, at least two of them
+    fn gradient(&self, key: &str, target: &mut Gradient) -> Result<(), String> {
+        let expected =
+            format!("2 to {MAX_GRADIENT_STOPS} stops with a position from 0 to 1 and an RGB color");
+        self.read(key, target, &expected, |value| {
+            let stops = value
+                .as_array()?
+                .iter()
+                .map(|stop| {
+                    let position = float(stop.get("position")?)? as f32;
+                    if !(0.0..=1.0).contains(&position) {
+                        return None;
+                    }
+                    let color = match stop.get("color")?.as_array()?.as_slice() {
+                        [r, g, b] => [float(r)? as f32, float(g)? as f32, float(b)? as f32],
+                        _ => return None,
+                    };
+                    Some(ColorStop { position, color })
+                })
+                .collect::<Option<Vec<_>>>()?;
+            (2..=MAX_GRADIENT_STOPS)
+                .contains(&stops.len())
+                .then_some(Gradient { stops })
+        })
+    }
+
+    /// Points like `[0.5, 0.46]`, from `[0.0, y]` to `[1.0, y]` in increasing order
+    fn tone_curve(&self, key: &str, target: &mut ToneCurve) -> Result<(), String> {
+        let expected = format!(
+            "2 to {MAX_CURVE_POINTS} points from 0 to 1, starting at x = 0 and ending at x = 1"
+        );
+        self.read(key, target, &expected, |value| {
+            let points = value
+                .as_array()?
+                .iter()
+                .map(|point| match point.as_array()?.as_slice() {
+                    [x, y] => Some([float(x)? as f32, float(y)? as f32]),
+                    _ => None,
+                })
+                .collect::<Option<Vec<_>>>()?;
+            ToneCurve::from_points(points)
+        })
+    }
+
+    /// Passes like `{ pass = "Blur", radius = 2.0 }`, in the order they are run
+    fn post_passes(&self, key: &str, target: &mut Vec<PostPass>) -> Result<(), String> {
+        let expected = PassKind::ALL
+            .iter()
+            .map(|kind| {
+                format!(
+                    "\"{kind}\" with a {} in {:?}",
+                    kind.parameter(),
+                    kind.range()
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        self.read(key, target, &format!("passes among {expected}"), |value| {
+            value
+                .as_array()?
+                .iter()
+                .map(|pass| {
+                    let name = pass.get("pass")?.as_str()?;
+                    let kind = PassKind::ALL
+                        .into_iter()
+                        .find(|kind| kind.to_string() == name)?;
+                    let amount = float(pass.get(kind.parameter())?)? as f32;
+                    kind.range()
+                        .contains(&amount)
+                        .then(|| kind.with_amount(amount))
+                })
+                .collect()
+        })
+    }
+
+    /// One of `all`, by the name it's displayed with in the UI
+    fn choice<T: Copy + std::fmt::Display>(
+        &self,
+        key: &str,
+        target: &mut T,
+        all: &[T],
+    ) -> Result<(), String> {
+        let expected = all
+            .iter()
+            .map(|choice| format!("\"{choice}\""))
+            .collect::<Vec<_>>()
+            .join(", ");
+        self.read(key, target, &format!("one of {expected}"), |value| {
+            let name = value.as_str()?;
+            all.iter()
+                .find(|choice| choice.to_string() == name)
+                .copied()
+        })
+    }
+}
+
+/// Integers are fine where floats are expected, TOML doesn't let `1` be a float
+fn float(value: &Value) -> Option<f64> {
+    value
+        .as_float()
+        .or_else(|| value.as_integer().map(|integer| integer as f64))
+}
+
+fn apply_table(table: &Table, preset: &mut Preset) -> Result<(), String> {
+    let render = &mut preset.render_settings;
+    let section = Section::new(Some(table), "render")?;
+    section.usize_in("width", &mut render.width, 1..=MAX_DIMENSION)?;
+    section.usize_in("height", &mut render.height, 1..=MAX_DIMENSION)?;
+    section.choice("scene", &mut render.scene, &SceneKind::ALL)?;
+    section.choice(
+        "anti_aliasing",
+        &mut render.anti_aliasing,
+        &AntiAliasing::ALL,
+    )?;
+    section.u32("seed", &mut render.seed)?;
+    section.u32("frame", &mut render.frame)?;
+    render.frame = render.frame.max(1);
+    section.f32_in("bilinear_blend", &mut render.bilinear_blend, 0.0..=1.0)?;
+    section.gradient("u_gradient", &mut render.u_gradient)?;
+    section.gradient("v_gradient", &mut render.v_gradient)?;
+    section.choice("blend_space", &mut render.blend_space, &BlendSpace::ALL)?;
+    let mut radial_center = (render.radial_center.0 as f64, render.radial_center.1 as f64);
+    section.pair("radial_center", &mut radial_center)?;
+    render.radial_center = (radial_center.0 as f32, radial_center.1 as f32);
+    section.f32_in("radial_radius", &mut render.radial_radius, RADIUS_RANGE)?;
+    section.string("expression", &mut preset.expression)?;
+
+    let noise = section.section("noise")?;
+    noise.f32_in("frequency", &mut render.noise.frequency, FREQUENCY_RANGE)?;
+    noise.u32_in("octaves", &mut render.noise.octaves, OCTAVE_RANGE)?;
+    noise.f32_in("lacunarity", &mut render.noise.lacunarity, LACUNARITY_RANGE)?;
+
+    let fractal = section.section("fractal")?;
+    fractal.choice("kind", &mut render.fractal.kind, &FractalKind::ALL)?;
+    fractal.pair("center", &mut render.fractal.center)?;
+    fractal.f64("span", &mut render.fractal.span)?;
+    fractal.u32_in(
+        "max_iterations",
+        &mut render.fractal.max_iterations,
+        ITERATION_RANGE,
+    )?;
+    fractal.pair("julia_constant", &mut render.fractal.julia_constant)?;
+    fractal.choice("ramp", &mut render.fractal.ramp, &ColorRamp::ALL)?;
+
+    let path_tracer = section.section("path_tracer")?;
+    path_tracer.u32_in(
+        "samples_per_pixel",
+        &mut render.path_tracer.samples_per_pixel,
+        1..=MAX_SAMPLES_PER_PIXEL,
+    )?;
+    path_tracer.u32_in(
+        "max_bounces",
+        &mut render.path_tracer.max_bounces,
+        BOUNCE_RANGE,
+    )?;
+
+    let sdf = section.section("sdf")?;
+    sdf.choice("operation", &mut render.sdf.operation, &SdfOperation::ALL)?;
+    sdf.f32("orbit_yaw", &mut render.sdf.orbit_yaw)?;
+    sdf.f32("orbit_pitch", &mut render.sdf.orbit_pitch)?;
+    sdf.f32("orbit_distance", &mut render.sdf.orbit_distance)?;
+
+    let overlay = section.section("overlay")?;
+    overlay.bool("enabled", &mut render.overlay.enabled)?;
+    overlay.choice("scene", &mut render.overlay.scene, &SceneKind::ALL)?;
+    overlay.choice(
+        "blend_mode",
+        &mut render.overlay.blend_mode,
+        &BlendMode::ALL,
+    )?;
+    overlay.f32_in("opacity", &mut render.overlay.opacity, 0.0..=1.0)?;
+
+    let display = &mut preset.display_settings;
+    let section = Section::new(Some(table), "display")?;
+    section.f32_in("global_alpha", &mut display.global_alpha, 0.0..=1.0)?;
+    section.f32_in(
+        "temperature",
+        &mut display.white_balance.temperature,
+        TEMPERATURE_RANGE,
+    )?;
+    section.f32_in("tint", &mut display.white_balance.tint, TINT_RANGE)?;
+    section.f32_in("exposure", &mut display.exposure, EXPOSURE_RANGE)?;
+    section.choice("tonemap", &mut display.tonemap, &TonemapKind::ALL)?;
+    section.tone_curve("tone_curve", &mut display.tone_curve)?;
+    section.post_passes("post_passes", &mut display.post_passes)?;
+    section.f32_in("gamma", &mut display.gamma, GAMMA_RANGE)?;
+    section.choice("display", &mut display.display, &DisplayTransform::ALL)?;
+    section.choice(
+        "gamut_mapping",
+        &mut display.gamut_mapping,
+        &GamutMapping::ALL,
+    )?;
+    section.choice("dithering", &mut display.dithering, &Dithering::ALL)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expression::DEFAULT_EXPRESSION;
+
+    fn default_preset() -> Preset {
+        Preset {
+            render_settings: RenderSettings::default(),
+            display_settings: DisplaySettings::default(),
+            expression: String::from(DEFAULT_EXPRESSION),
+        }
+    }
+
+    fn apply(source: &str) -> Result<Preset, String> {
+        let table: Table = toml::from_str(source).unwrap();
+        let mut preset = default_preset();
+        apply_table(&table, &mut preset)?;
+        Ok(preset)
+    }
+
+    #[test]
+    fn saved_presets_load_back() {
+        let mut preset = default_preset();
+        preset.render_settings.width = 640;
+        preset.display_settings.gamma = 2.2;
+        let mut loaded = default_preset();
+        apply_table(&to_table(&preset), &mut loaded).unwrap();
+        assert_eq!(loaded.render_settings.width, 640);
+        assert_eq!(loaded.display_settings.gamma, 2.2);
+    }
+
+    #[test]
+    fn out_of_range_settings_are_errors() {
+        let too_wide = format!("[render]\nwidth = {}", MAX_DIMENSION + 1);
+        assert!(apply(&too_wide).is_err());
+        assert!(apply("[render]\nheight = 0").is_err());
+        assert!(apply("[render.noise]\noctaves = 100").is_err());
+        assert!(apply("[render.fractal]\nmax_iterations = 1000000000").is_err());
+        assert!(apply("[render.path_tracer]\nsamples_per_pixel = 100000").is_err());
+        assert!(apply("[render.path_tracer]\nmax_bounces = 0").is_err());
+        assert!(apply("[display]\ngamma = 0.0").is_err());
+        assert!(apply("[display]\ngamma = -1.0").is_err());
+
+        let preset = apply("[render]\nwidth = 800\n[display]\ngamma = 2.2").unwrap();
+        assert_eq!(preset.render_settings.width, 800);
+    }
+
+    #[test]
+    fn out_of_range_amounts_are_errors() {
+        for source in [
+            "[render]\nradial_radius = 0",
+            "[render]\nbilinear_blend = 2",
+            "[render.noise]\nfrequency = -1",
+            "[render.noise]\nlacunarity = 0",
+            "[render.overlay]\nopacity = -0.5",
+            "[display]\nglobal_alpha = -1",
+            "[display]\ntemperature = 0",
+            "[display]\ntint = 5",
+            "[display]\nexposure = 100",
+            "[render]\nu_gradient = [\n    { position = 0.0, color = [1, 0, 0] },\n    \
+             { position = 1.5, color = [0, 0, 1] },\n]",
+        ] {
+            assert!(apply(source).is_err(), "{source}");
+        }
+
+        let preset = apply("[render]\nradial_radius = 0.5\n[display]\nglobal_alpha = 0").unwrap();
+        assert_eq!(preset.render_settings.radial_radius, 0.5);
+        assert_eq!(preset.display_settings.global_alpha, 0.0);
+    }
+}
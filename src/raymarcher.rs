@@ -0,0 +1,222 @@
+//! Sphere tracing of a signed distance field: a box and a sphere combined with a
+//! boolean operation, and a torus around them, sitting on a ground plane.
+//! See https://iquilezles.org/articles/distfunctions/
+use colstodian::spaces::AcesCg;
+use colstodian::{color, Color, Scene};
+
+use crate::pattern::RenderPattern;
+use crate::vec3::{vec3, Camera, Vec3};
+
+/// How the sphere gets combined with the box
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SdfOperation {
+    Union,
+    /// Carve the sphere out of the box
+    Subtract,
+    Intersect,
+}
+
+impl SdfOperation {
+    pub const ALL: [SdfOperation; 3] = [
+        SdfOperation::Union,
+        SdfOperation::Subtract,
+        SdfOperation::Intersect,
+    ];
+}
+
+impl std::fmt::Display for SdfOperation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            SdfOperation::Union => "Union",
+            SdfOperation::Subtract => "Subtract",
+            SdfOperation::Intersect => "Intersect",
+        };
+        write!(f, "{name}")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SdfSettings {
+    pub operation: SdfOperation,
+    /// Rotation of the camera around the vertical axis, in degrees
+    pub orbit_yaw: f32,
+    /// Height of the camera above the horizon, in degrees
+    pub orbit_pitch: f32,
+    /// Distance between the camera and the center of the scene
+    pub orbit_distance: f32,
+}
+
+impl Default for SdfSettings {
+    fn default() -> Self {
+        SdfSettings {
+            operation: SdfOperation::Subtract,
+            orbit_yaw: 30.0,
+            orbit_pitch: 20.0,
+            orbit_distance: 5.0,
+        }
+    }
+}
+
+const MAX_STEPS: usize = 128;
+const MAX_DISTANCE: f32 = 50.0;
+/// Close enough to a surface to call it a hit
+const HIT_DISTANCE: f32 = 1e-3;
+
+/// Scene linear ACEScg, above 1.0 so that the tonemapper has something to do
+const SUN_COLOR: Vec3 = vec3(3.0, 2.8, 2.5);
+const SKY_COLOR: Vec3 = vec3(0.15, 0.2, 0.3);
+
+#[derive(Debug, Clone, Copy)]
+enum Material {
+    Ground,
+    Solid,
+    Torus,
+}
+
+impl Material {
+    fn albedo(&self) -> Vec3 {
+        match self {
+            Material::Ground => vec3(0.4, 0.4, 0.4),
+            Material::Solid => vec3(0.8, 0.3, 0.1),
+            Material::Torus => vec3(0.1, 0.5, 0.8),
+        }
+    }
+}
+
+fn sphere_distance(point: Vec3, radius: f32) -> f32 {
+    point.length() - radius
+}
+
+fn box_distance(point: Vec3, half_size: Vec3) -> f32 {
+    let q = vec3(
+        point.x.abs() - half_size.x,
+        point.y.abs() - half_size.y,
+        point.z.abs() - half_size.z,
+    );
+    let outside = vec3(q.x.max(0.0), q.y.max(0.0), q.z.max(0.0)).length();
+    let inside = q.x.max(q.y).max(q.z).min(0.0);
+    outside + inside
+}
+
+fn torus_distance(point: Vec3, major_radius: f32, minor_radius: f32) -> f32 {
+    let ring = (point.x * point.x + point.z * point.z).sqrt() - major_radius;
+    (ring * ring + point.y * point.y).sqrt() - minor_radius
+}
+
+pub struct Raymarcher {
+    pub settings: SdfSettings,
+    pub aspect_ratio: f32,
+}
+
+impl Raymarcher {
+    /// Distance to the closest surface, and its material
+    fn scene_distance(&self, point: Vec3) -> (f32, Material) {
+        let center = point - vec3(0.0, 1.0, 0.0);
+
+        let box_ = box_distance(center, vec3(0.75, 0.75, 0.75));
+        let sphere = sphere_distance(center, 1.0);
+        let solid = match self.settings.operation {
+            SdfOperation::Union => box_.min(sphere),
+            SdfOperation::Subtract => box_.max(-sphere),
+            SdfOperation::Intersect => box_.max(sphere),
+        };
+        let torus = torus_distance(center, 1.6, 0.15);
+        let ground = point.y;
+
+        [
+            (solid, Material::Solid),
+            (torus, Material::Torus),
+            (ground, Material::Ground),
+        ]
+        .into_iter()
+        .min_by(|a, b| a.0.total_cmp(&b.0))
+        .unwrap()
+    }
+
+    /// Distance along the ray to the first surface it hits
+    fn march(&self, origin: Vec3, direction: Vec3) -> Option<(f32, Material)> {
+        let mut t = 0.0;
+
+        for _ in 0..MAX_STEPS {
+            let (distance, material) = self.scene_distance(origin + direction * t);
+            if distance < HIT_DISTANCE {
+                return Some((t, material));
+            }
+            t += distance;
+            if t > MAX_DISTANCE {
+                break;
+            }
+        }
+
+        None
+    }
+
+    // Gradient of the distance field, from central differences
+    fn normal(&self, point: Vec3) -> Vec3 {
+        let epsilon = 1e-3;
+        let distance = |offset: Vec3| self.scene_distance(point + offset).0;
+
+        vec3(
+            distance(vec3(epsilon, 0.0, 0.0)) - distance(vec3(-epsilon, 0.0, 0.0)),
+            distance(vec3(0.0, epsilon, 0.0)) - distance(vec3(0.0, -epsilon, 0.0)),
+            distance(vec3(0.0, 0.0, epsilon)) - distance(vec3(0.0, 0.0, -epsilon)),
+        )
+        .normalized()
+    }
+
+    fn camera(&self) -> Camera {
+        let yaw = self.settings.orbit_yaw.to_radians();
+        let pitch = self.settings.orbit_pitch.to_radians();
+        let distance = self.settings.orbit_distance;
+        let eye = vec3(
+            distance * pitch.cos() * yaw.sin(),
+            1.0 + distance * pitch.sin(),
+            distance * pitch.cos() * yaw.cos(),
+        );
+
+        Camera::look_at(eye, vec3(0.0, 1.0, 0.0), 45.0)
+    }
+}
+
+impl RenderPattern for Raymarcher {
+    fn color(&self, u: f32, v: f32) -> Color<AcesCg, Scene> {
+        let sun_direction = vec3(0.5, 0.8, 0.3).normalized();
+        let camera = self.camera();
+        let direction = camera.ray_direction(u, v, self.aspect_ratio);
+
+        let shade = match self.march(camera.eye(), direction) {
+            Some((t, material)) => {
+                let point = camera.eye() + direction * t;
+                let normal = self.normal(point);
+
+                // Hard shadows: is there anything between us and the sun?
+                let shadow_origin = point + normal * (HIT_DISTANCE * 10.0);
+                let lit = self.march(shadow_origin, sun_direction).is_none();
+
+                let diffuse = if lit {
+                    normal.dot(sun_direction).max(0.0)
+                } else {
+                    0.0
+                };
+                // Light from the sky reaches upward facing surfaces the most
+                let sky = 0.5 + 0.5 * normal.y;
+
+                material.albedo() * (SUN_COLOR * diffuse + SKY_COLOR * sky)
+            }
+            None => SKY_COLOR,
+        };
+
+        color::acescg::<Scene>(shade.x, shade.y, shade.z)
+    }
+
+    /// The albedo of the material the camera ray hits, black for the sky
+    fn albedo(&self, u: f32, v: f32) -> Color<AcesCg, Scene> {
+        let camera = self.camera();
+        let direction = camera.ray_direction(u, v, self.aspect_ratio);
+
+        let albedo = self
+            .march(camera.eye(), direction)
+            .map_or(vec3(0.0, 0.0, 0.0), |(_, material)| material.albedo());
+        color::acescg::<Scene>(albedo.x, albedo.y, albedo.z)
+    }
+}
@@ -0,0 +1,43 @@
+//! The files recently opened or saved, kept across sessions in a plain text file
+//! with one path per line, most recent first
+use std::path::Path;
+
+use crate::console;
+use crate::i18n::{tr_args, Text};
+
+/// Where the list is persisted, next to the presets
+pub const RECENT_FILES_PATH: &str = "recent_files.txt";
+const MAX_RECENT_FILES: usize = 10;
+
+/// The persisted list, empty if there's none yet
+pub fn load() -> Vec<String> {
+    std::fs::read_to_string(RECENT_FILES_PATH)
+        .map(|contents| {
+            contents
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .take(MAX_RECENT_FILES)
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Move `path` to the front of `recent_files` and persist the list.
+/// Failing to persist it only costs the history, so it's just logged.
+pub fn add(recent_files: &mut Vec<String>, path: &Path) {
+    let path = std::fs::canonicalize(path)
+        .unwrap_or_else(|_| path.to_path_buf())
+        .display()
+        .to_string();
+    recent_files.retain(|recent| *recent != path);
+    recent_files.insert(0, path);
+    recent_files.truncate(MAX_RECENT_FILES);
+
+    let mut contents = recent_files.join("\n");
+    contents.push('\n');
+    if let Err(e) = std::fs::write(RECENT_FILES_PATH, contents) {
+        console::error(tr_args(Text::CouldNotSave, &[&RECENT_FILES_PATH, &e]));
+    }
+}
@@ -0,0 +1,763 @@
+//! Scene linear rendering of the procedural scenes
+use std::sync::Arc;
+
+use crate::expression::{Program, DEFAULT_EXPRESSION};
+use crate::noise::hash;
+use crate::path_tracer::{PathTracer, PathTracerSettings};
+use crate::pattern::{
+    BilinearGradient, BlendSpace, Checkerboard, ExpressionPattern, Fractal, FractalSettings,
+    Gradient, GradientAxis, Noise, NoiseSettings, RadialGradient, RenderPattern, UvRamp,
+};
+use crate::raymarcher::{Raymarcher, SdfSettings};
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+pub const DEFAULT_RENDER_WIDTH: usize = 1024;
+pub const DEFAULT_RENDER_HEIGHT: usize = 1024;
+/// Largest width or height accepted from the UI, the environment, presets and the
+/// command line
+pub const MAX_DIMENSION: usize = 16384;
+
+/// Frames per second of the timeline
+pub const FRAME_RATE: f32 = 24.0;
+
+/// Number of squares along the height of the checkerboard
+pub const CHECKERBOARD_SQUARES: f32 = 8.0;
+
+/// Common render resolutions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResolutionPreset {
+    Square512,
+    Square1024,
+    Square2048,
+    Uhd4k,
+}
+
+impl ResolutionPreset {
+    pub const ALL: [ResolutionPreset; 4] = [
+        ResolutionPreset::Square512,
+        ResolutionPreset::Square1024,
+        ResolutionPreset::Square2048,
+        ResolutionPreset::Uhd4k,
+    ];
+
+    /// Width and height, in pixels
+    pub fn size(&self) -> (usize, usize) {
+        match self {
+            ResolutionPreset::Square512 => (512, 512),
+            ResolutionPreset::Square1024 => (1024, 1024),
+            ResolutionPreset::Square2048 => (2048, 2048),
+            ResolutionPreset::Uhd4k => (3840, 2160),
+        }
+    }
+
+    /// The preset matching a resolution, if any
+    pub fn from_size(width: usize, height: usize) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|preset| preset.size() == (width, height))
+    }
+}
+
+impl std::fmt::Display for ResolutionPreset {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (width, height) = self.size();
+        match self {
+            ResolutionPreset::Uhd4k => write!(f, "4K UHD ({width} x {height})"),
+            _ => write!(f, "{width} x {height}"),
+        }
+    }
+}
+
+/// Where the scene gets rendered
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderBackend {
+    Cpu,
+    /// Compute shader, only for the simpler patterns. Needs the `gpu` feature.
+    Gpu,
+}
+
+impl RenderBackend {
+    pub const ALL: [RenderBackend; 2] = [RenderBackend::Cpu, RenderBackend::Gpu];
+
+    /// Whether `scene` can be rendered on this backend, otherwise it falls back to the CPU
+    pub fn supports(&self, scene: SceneKind) -> bool {
+        match self {
+            RenderBackend::Cpu => true,
+            #[cfg(feature = "gpu")]
+            RenderBackend::Gpu => crate::gpu::supports(scene),
+            #[cfg(not(feature = "gpu"))]
+            RenderBackend::Gpu => {
+                let _ = scene;
+                false
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for RenderBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            RenderBackend::Cpu => "CPU",
+            RenderBackend::Gpu => "GPU",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Which pattern gets rendered
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SceneKind {
+    /// Bilinear blend between red, green and blue corners
+    Gradient,
+    /// Blend from a center point outwards to an edge color
+    Radial,
+    Checkerboard,
+    /// U and V coordinates as colors
+    UvRamp,
+    /// Fractal Perlin noise
+    Noise,
+    /// Mandelbrot or Julia set
+    Fractal,
+    /// Spheres lit by an area light
+    PathTraced,
+    /// Signed distance field primitives, raymarched
+    Raymarched,
+    /// A user typed expression, evaluated for every pixel
+    Expression,
+}
+
+impl SceneKind {
+    pub const ALL: [SceneKind; 9] = [
+        SceneKind::Gradient,
+        SceneKind::Radial,
+        SceneKind::Checkerboard,
+        SceneKind::UvRamp,
+        SceneKind::Noise,
+        SceneKind::Fractal,
+        SceneKind::PathTraced,
+        SceneKind::Raymarched,
+        SceneKind::Expression,
+    ];
+
+    /// The pattern of this scene, configured from `settings`
+    pub fn pattern(&self, settings: &RenderSettings) -> Box<dyn RenderPattern> {
+        let aspect_ratio = settings.width as f32 / settings.height as f32;
+        let time = settings.time();
+
+        match self {
+            SceneKind::Gradient => Box::new(BilinearGradient {
+                blend: settings.bilinear_blend,
+                u_gradient: settings.u_gradient.sorted(),
+                v_gradient: settings.v_gradient.sorted(),
+                space: settings.blend_space,
+                time,
+            }),
+            SceneKind::Radial => Box::new(RadialGradient {
+                gradient: settings.u_gradient.sorted(),
+                center: settings.radial_center,
+                radius: settings.radial_radius,
+                aspect_ratio,
+                space: settings.blend_space,
+                time,
+            }),
+            SceneKind::Checkerboard => Box::new(Checkerboard {
+                squares: CHECKERBOARD_SQUARES,
+                aspect_ratio,
+            }),
+            SceneKind::UvRamp => Box::new(UvRamp),
+            SceneKind::Noise => Box::new(Noise {
+                settings: settings.noise,
+                aspect_ratio,
+                time,
+                seed: settings.seed,
+            }),
+            SceneKind::Fractal => Box::new(Fractal {
+                settings: settings.fractal,
+                aspect_ratio,
+            }),
+            SceneKind::PathTraced => Box::new(PathTracer {
+                settings: settings.path_tracer,
+                aspect_ratio,
+                pixel_size: (1.0 / settings.width as f32, 1.0 / settings.height as f32),
+                seed: settings.seed,
+            }),
+            SceneKind::Raymarched => Box::new(Raymarcher {
+                settings: settings.sdf,
+                aspect_ratio,
+            }),
+            SceneKind::Expression => Box::new(ExpressionPattern {
+                program: Arc::clone(&settings.expression),
+                width: settings.width,
+                height: settings.height,
+                time,
+            }),
+        }
+    }
+}
+
+/// Why a width or height can't be used
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DimensionError {
+    Zero,
+    TooLarge,
+    /// Not a positive integer, with the reason
+    Invalid(String),
+}
+
+impl std::fmt::Display for DimensionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DimensionError::Zero => write!(f, "must be greater than zero"),
+            DimensionError::TooLarge => write!(f, "must be at most {MAX_DIMENSION}"),
+            DimensionError::Invalid(reason) => write!(f, "{reason}"),
+        }
+    }
+}
+
+/// Parse a width or height, from 1 to `MAX_DIMENSION`
+pub fn parse_dimension(value: &str) -> Result<usize, DimensionError> {
+    match value.trim().parse::<usize>() {
+        Ok(0) => Err(DimensionError::Zero),
+        Ok(dimension) if dimension > MAX_DIMENSION => Err(DimensionError::TooLarge),
+        Ok(dimension) => Ok(dimension),
+        Err(e) => Err(DimensionError::Invalid(e.to_string())),
+    }
+}
+
+impl std::str::FromStr for SceneKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "gradient" => Ok(SceneKind::Gradient),
+            "radial" => Ok(SceneKind::Radial),
+            "checkerboard" => Ok(SceneKind::Checkerboard),
+            "uv-ramp" => Ok(SceneKind::UvRamp),
+            "noise" => Ok(SceneKind::Noise),
+            "fractal" => Ok(SceneKind::Fractal),
+            "path-traced" => Ok(SceneKind::PathTraced),
+            "raymarched" => Ok(SceneKind::Raymarched),
+            "expression" => Ok(SceneKind::Expression),
+            _ => Err(format!("unknown scene '{s}'")),
+        }
+    }
+}
+
+impl std::fmt::Display for SceneKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            SceneKind::Gradient => "Gradient",
+            SceneKind::Radial => "Radial",
+            SceneKind::Checkerboard => "Checkerboard",
+            SceneKind::UvRamp => "UV ramp",
+            SceneKind::Noise => "Noise",
+            SceneKind::Fractal => "Fractal",
+            SceneKind::PathTraced => "Path traced",
+            SceneKind::Raymarched => "Raymarched SDF",
+            SceneKind::Expression => "Expression",
+        };
+        write!(f, "{name}")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlendMode {
+    Normal,
+    Add,
+    Multiply,
+    Screen,
+}
+
+impl BlendMode {
+    pub const ALL: [BlendMode; 4] = [
+        BlendMode::Normal,
+        BlendMode::Add,
+        BlendMode::Multiply,
+        BlendMode::Screen,
+    ];
+
+    /// Blend a single channel of `top` over `bottom`
+    fn blend(&self, bottom: f32, top: f32) -> f32 {
+        match self {
+            BlendMode::Normal => top,
+            BlendMode::Add => bottom + top,
+            BlendMode::Multiply => bottom * top,
+            BlendMode::Screen => bottom + top - bottom * top,
+        }
+    }
+}
+
+impl std::fmt::Display for BlendMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            BlendMode::Normal => "Normal",
+            BlendMode::Add => "Add",
+            BlendMode::Multiply => "Multiply",
+            BlendMode::Screen => "Screen",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Number of samples taken within every pixel, to smooth out aliasing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AntiAliasing {
+    X1,
+    X4,
+    X16,
+}
+
+impl AntiAliasing {
+    pub const ALL: [AntiAliasing; 3] = [AntiAliasing::X1, AntiAliasing::X4, AntiAliasing::X16];
+
+    /// The samples are spread over a grid of `grid_size` x `grid_size` cells
+    fn grid_size(&self) -> usize {
+        match self {
+            AntiAliasing::X1 => 1,
+            AntiAliasing::X4 => 2,
+            AntiAliasing::X16 => 4,
+        }
+    }
+}
+
+impl std::fmt::Display for AntiAliasing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            AntiAliasing::X1 => "1x",
+            AntiAliasing::X4 => "4x",
+            AntiAliasing::X16 => "16x",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// A second scene composited on top of the main one
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OverlaySettings {
+    pub enabled: bool,
+    pub scene: SceneKind,
+    pub blend_mode: BlendMode,
+    pub opacity: f32,
+}
+
+impl Default for OverlaySettings {
+    fn default() -> Self {
+        OverlaySettings {
+            enabled: false,
+            scene: SceneKind::Radial,
+            blend_mode: BlendMode::Screen,
+            opacity: 0.5,
+        }
+    }
+}
+
+/// Parameters of the scene linear render
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderSettings {
+    pub width: usize,
+    pub height: usize,
+    pub backend: RenderBackend,
+    pub scene: SceneKind,
+    pub anti_aliasing: AntiAliasing,
+    /// Shared by all the stochastic patterns, so that renders can be reproduced exactly
+    pub seed: u32,
+    /// Frame of the animation, starting at 1
+    pub frame: u32,
+    /// Mix between the horizontal and vertical blends of the bilinear gradient
+    pub bilinear_blend: f32,
+    /// Colors of the bilinear gradient from left to right, and from bottom to top
+    pub u_gradient: Gradient,
+    pub v_gradient: Gradient,
+    /// Color space both gradients are blended in
+    pub blend_space: BlendSpace,
+    /// Center of the radial gradient, in normalized U,V coordinates
+    pub radial_center: (f32, f32),
+    /// Distance from the center at which the radial gradient reaches the edge color,
+    /// relative to the image height
+    pub radial_radius: f32,
+    pub noise: NoiseSettings,
+    pub fractal: FractalSettings,
+    pub path_tracer: PathTracerSettings,
+    pub sdf: SdfSettings,
+    /// Shared, so that cloning the settings for every render stays cheap
+    pub expression: Arc<Program>,
+    pub overlay: OverlaySettings,
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        RenderSettings {
+            width: DEFAULT_RENDER_WIDTH,
+            height: DEFAULT_RENDER_HEIGHT,
+            backend: RenderBackend::Cpu,
+            scene: SceneKind::Gradient,
+            anti_aliasing: AntiAliasing::X1,
+            seed: 0,
+            frame: 1,
+            bilinear_blend: 0.5,
+            u_gradient: Gradient::new([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+            v_gradient: Gradient::new([1.0, 0.0, 0.0], [0.0, 0.0, 1.0]),
+            blend_space: BlendSpace::AcesCg,
+            radial_center: (0.5, 0.5),
+            radial_radius: 0.5,
+            noise: NoiseSettings::default(),
+            fractal: FractalSettings::default(),
+            path_tracer: PathTracerSettings::default(),
+            sdf: SdfSettings::default(),
+            expression: Arc::new(
+                Program::compile(DEFAULT_EXPRESSION).expect("the default expression is valid"),
+            ),
+            overlay: OverlaySettings::default(),
+        }
+    }
+}
+
+impl RenderSettings {
+    pub fn gradient(&self, axis: GradientAxis) -> &Gradient {
+        match axis {
+            GradientAxis::U => &self.u_gradient,
+            GradientAxis::V => &self.v_gradient,
+        }
+    }
+
+    pub fn gradient_mut(&mut self, axis: GradientAxis) -> &mut Gradient {
+        match axis {
+            GradientAxis::U => &mut self.u_gradient,
+            GradientAxis::V => &mut self.v_gradient,
+        }
+    }
+
+    /// Time of the current frame, in seconds
+    pub fn time(&self) -> f32 {
+        (self.frame - 1) as f32 / FRAME_RATE
+    }
+}
+
+/// Scene linear RGBA pixels, in ACEScg
+pub type RenderBuffer = Vec<f32>;
+
+/// A rectangle of pixels, with y going down from the top of the image
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Region {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Region {
+    pub fn full(width: usize, height: usize) -> Self {
+        Region {
+            x: 0,
+            y: 0,
+            width,
+            height,
+        }
+    }
+
+    pub fn pixel_count(&self) -> usize {
+        self.width * self.height
+    }
+
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        (self.x..self.x + self.width).contains(&x) && (self.y..self.y + self.height).contains(&y)
+    }
+
+    /// Copy the RGBA `pixels` of this region into the RGBA `buffer` of a whole image
+    pub fn copy_into<T: Copy>(&self, pixels: &[T], buffer: &mut [T], buffer_width: usize) {
+        let row_length = self.width * 4;
+
+        for (row, row_pixels) in pixels.chunks_exact(row_length).enumerate() {
+            let start = ((self.y + row) * buffer_width + self.x) * 4;
+            buffer[start..start + row_length].copy_from_slice(row_pixels);
+        }
+    }
+
+    /// The RGBA pixels of this region, out of the RGBA `buffer` of a whole image
+    pub fn copy_from<T: Copy>(&self, buffer: &[T], buffer_width: usize) -> Vec<T> {
+        let row_length = self.width * 4;
+
+        (self.y..self.y + self.height)
+            .flat_map(|y| {
+                let start = (y * buffer_width + self.x) * 4;
+                &buffer[start..start + row_length]
+            })
+            .copied()
+            .collect()
+    }
+}
+
+/// A whole scene linear image: its size and its RGBA pixels, row by row from the top
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Framebuffer {
+    width: usize,
+    height: usize,
+    pixels: RenderBuffer,
+}
+
+impl Framebuffer {
+    /// A transparent black image
+    pub fn new(width: usize, height: usize) -> Self {
+        Framebuffer::from_pixels(width, height, vec![0.0; width * height * 4])
+    }
+
+    /// Panics if there aren't four channels for each of the `width` x `height` pixels
+    pub fn from_pixels(width: usize, height: usize, pixels: RenderBuffer) -> Self {
+        assert_eq!(
+            pixels.len(),
+            width * height * 4,
+            "{width}x{height} RGBA pixels expected"
+        );
+        Framebuffer {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The whole image, as converted by the display functions
+    pub fn region(&self) -> Region {
+        Region::full(self.width, self.height)
+    }
+
+    pub fn pixels(&self) -> &[f32] {
+        &self.pixels
+    }
+
+    pub fn pixels_mut(&mut self) -> &mut [f32] {
+        &mut self.pixels
+    }
+
+    pub fn into_pixels(self) -> RenderBuffer {
+        self.pixels
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> [f32; 4] {
+        let index = (y * self.width + x) * 4;
+        self.pixels[index..index + 4].try_into().unwrap()
+    }
+
+    pub fn set_pixel(&mut self, x: usize, y: usize, pixel: [f32; 4]) {
+        let index = (y * self.width + x) * 4;
+        self.pixels[index..index + 4].copy_from_slice(&pixel);
+    }
+
+    /// The pixels of `region`, as an image of their own
+    pub fn crop(&self, region: Region) -> Framebuffer {
+        let pixels = region.copy_from(&self.pixels, self.width);
+        Framebuffer::from_pixels(region.width, region.height, pixels)
+    }
+
+    /// Copy `tile` over the pixels of `region`, which has the size of the tile
+    pub fn paste(&mut self, tile: &Framebuffer, region: Region) {
+        debug_assert_eq!((tile.width, tile.height), (region.width, region.height));
+        region.copy_into(&tile.pixels, &mut self.pixels, self.width);
+    }
+}
+
+/// Linear remap a value in one range into another range (no clamping)
+pub fn fit_range(x: f32, imin: f32, imax: f32, omin: f32, omax: f32) -> f32 {
+    (omax - omin) * (x - imin) / (imax - imin) + omin
+}
+
+/// Composite `top` over `bottom` in linear space, using `mode` for the colors.
+/// `opacity` fades the contribution of `top` in and out.
+fn blend_buffers(
+    bottom: &Framebuffer,
+    top: &Framebuffer,
+    mode: BlendMode,
+    opacity: f32,
+) -> Framebuffer {
+    let mut blended = Framebuffer::new(bottom.width, bottom.height);
+
+    let it = std::iter::zip(bottom.pixels.chunks_exact(4), top.pixels.chunks_exact(4));
+    for ((bottom_pixel, top_pixel), blended_pixel) in it.zip(blended.pixels.chunks_exact_mut(4)) {
+        let coverage = top_pixel[3] * opacity;
+
+        for channel in 0..3 {
+            let color = mode.blend(bottom_pixel[channel], top_pixel[channel]);
+            blended_pixel[channel] =
+                bottom_pixel[channel] + (color - bottom_pixel[channel]) * coverage;
+        }
+        blended_pixel[3] = bottom_pixel[3] + coverage * (1.0 - bottom_pixel[3]);
+    }
+
+    blended
+}
+
+/// What gets rendered for each pixel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Aov {
+    /// The final lit color
+    Beauty,
+    /// The diffuse reflectance of the surfaces, without any lighting
+    Albedo,
+}
+
+/// Render the whole scene, including the overlay when enabled
+pub fn render_scene_linear(settings: &RenderSettings) -> Framebuffer {
+    render_scene_region(settings, Region::full(settings.width, settings.height))
+}
+
+/// Render only `region` of the scene. The returned image has the size of the region.
+pub fn render_scene_region(settings: &RenderSettings, region: Region) -> Framebuffer {
+    render_aov_region(settings, Aov::Beauty, region)
+}
+
+/// Render `aov` for the whole image, e.g. to write it as an extra layer of an EXR
+pub fn render_aov(settings: &RenderSettings, aov: Aov) -> Framebuffer {
+    render_aov_region(settings, aov, Region::full(settings.width, settings.height))
+}
+
+/// Normalized U,V coordinates of every pixel, as U,V pairs, with V going up.
+/// These are where the patterns get sampled, without anti-aliasing.
+pub fn uv_coordinates(width: usize, height: usize) -> Vec<f32> {
+    (0..height)
+        .flat_map(|row| {
+            let v = (height - 1 - row) as f32 / height as f32;
+            (0..width).flat_map(move |x| [x as f32 / width as f32, v])
+        })
+        .collect()
+}
+
+/// The overlay gets blended over the AOVs like over the beauty, so that they line up
+fn render_aov_region(settings: &RenderSettings, aov: Aov, region: Region) -> Framebuffer {
+    let base = render_layer_region(settings, settings.scene, aov, region);
+
+    if !settings.overlay.enabled {
+        return base;
+    }
+
+    let overlay = render_layer_region(settings, settings.overlay.scene, aov, region);
+
+    blend_buffers(
+        &base,
+        &overlay,
+        settings.overlay.blend_mode,
+        settings.overlay.opacity,
+    )
+}
+
+/// Position of a sample within the pixel (x, y), between 0 and 1.
+/// Every sample is jittered within its own cell of the grid, so that they are spread
+/// evenly without lining up. A single sample stays on the corner of the pixel.
+fn sample_offset(x: usize, y: usize, sample: usize, grid_size: usize) -> (f32, f32) {
+    if grid_size == 1 {
+        return (0.0, 0.0);
+    }
+
+    let jitter = |seed: usize| {
+        let h = hash(x as i32, y as i32, seed as u32);
+        (h >> 8) as f32 / (1 << 24) as f32
+    };
+    let (cell_x, cell_y) = (sample % grid_size, sample / grid_size);
+    (
+        (cell_x as f32 + jitter(sample * 2)) / grid_size as f32,
+        (cell_y as f32 + jitter(sample * 2 + 1)) / grid_size as f32,
+    )
+}
+
+// Sample function demostrating how to render a custom image
+fn render_layer_region(
+    settings: &RenderSettings,
+    scene: SceneKind,
+    aov: Aov,
+    region: Region,
+) -> Framebuffer {
+    // The compute shader only takes a single sample per pixel, and only renders the beauty
+    #[cfg(feature = "gpu")]
+    if settings.backend == RenderBackend::Gpu
+        && settings.anti_aliasing == AntiAliasing::X1
+        && aov == Aov::Beauty
+    {
+        if let Some(framebuffer) = crate::gpu::render_layer_region(settings, scene, region) {
+            return framebuffer;
+        }
+    }
+
+    let (width, height) = (settings.width, settings.height);
+    let pattern = scene.pattern(settings);
+    let grid_size = settings.anti_aliasing.grid_size();
+    let sample_weight = 1.0 / (grid_size * grid_size) as f32;
+    let mut framebuffer = Framebuffer::new(region.width, region.height);
+
+    // Render a in linear color space, one scanline at a time.
+    // The first scanline is the top of the region.
+    let render_scanline = |(row, scanline): (usize, &mut [f32])| {
+        let y = height - 1 - (region.y + row);
+
+        for (column, pixel) in scanline.chunks_exact_mut(4).enumerate() {
+            let x = region.x + column;
+
+            // Average the samples in linear light, before any tonemapping happens
+            let mut rendered_color = [0.0; 3];
+            for sample in 0..grid_size * grid_size {
+                let (offset_x, offset_y) = sample_offset(x, y, sample, grid_size);
+
+                // Get normalized U,V coordinates as we move through the image
+                let u = fit_range(x as f32 + offset_x, 0.0, width as f32, 0.0, 1.0);
+                let v = fit_range(y as f32 + offset_y, 0.0, height as f32, 0.0, 1.0);
+
+                let color = match aov {
+                    Aov::Beauty => pattern.color(u, v),
+                    Aov::Albedo => pattern.albedo(u, v),
+                };
+                rendered_color[0] += color.r * sample_weight;
+                rendered_color[1] += color.g * sample_weight;
+                rendered_color[2] += color.b * sample_weight;
+            }
+
+            // R, G, B, A
+            pixel[..3].copy_from_slice(&rendered_color);
+            pixel[3] = 1.0;
+        }
+    };
+
+    #[cfg(feature = "parallel")]
+    framebuffer
+        .pixels
+        .par_chunks_exact_mut(region.width * 4)
+        .enumerate()
+        .for_each(render_scanline);
+
+    #[cfg(not(feature = "parallel"))]
+    framebuffer
+        .pixels
+        .chunks_exact_mut(region.width * 4)
+        .enumerate()
+        .for_each(render_scanline);
+
+    framebuffer
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dimensions_are_bounded() {
+        assert_eq!(parse_dimension("1"), Ok(1));
+        assert_eq!(parse_dimension(" 1920 "), Ok(1920));
+        assert_eq!(
+            parse_dimension(&MAX_DIMENSION.to_string()),
+            Ok(MAX_DIMENSION)
+        );
+        assert_eq!(parse_dimension("0"), Err(DimensionError::Zero));
+        assert_eq!(
+            parse_dimension(&(MAX_DIMENSION + 1).to_string()),
+            Err(DimensionError::TooLarge)
+        );
+        assert_eq!(parse_dimension("200000"), Err(DimensionError::TooLarge));
+        assert!(parse_dimension("wide").is_err());
+    }
+}
@@ -0,0 +1,61 @@
+//! Timings of the recent renders, to compare before/after when tweaking the render code
+use std::collections::VecDeque;
+use std::fmt;
+use std::time::Duration;
+
+/// How many renders are kept around
+const RENDER_HISTORY_LENGTH: usize = 8;
+
+#[derive(Debug, Clone, Copy)]
+pub struct RenderStats {
+    pub width: usize,
+    pub height: usize,
+    /// Wall-clock time, from starting the job to receiving its last tile
+    pub render_time: Duration,
+    /// Memory taken by the framebuffers while the render was in flight
+    pub peak_buffer_bytes: usize,
+}
+
+impl RenderStats {
+    pub fn megapixels_per_second(&self) -> f64 {
+        (self.width * self.height) as f64 / self.render_time.as_secs_f64() / 1e6
+    }
+}
+
+impl fmt::Display for RenderStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}x{}: {:.1}ms, {:.1} Mpx/s, {:.1} MB",
+            self.width,
+            self.height,
+            self.render_time.as_secs_f64() * 1000.0,
+            self.megapixels_per_second(),
+            self.peak_buffer_bytes as f64 / (1024.0 * 1024.0)
+        )
+    }
+}
+
+/// The last few renders, most recent first
+pub struct RenderHistory {
+    renders: VecDeque<RenderStats>,
+}
+
+impl RenderHistory {
+    pub fn new() -> Self {
+        RenderHistory {
+            renders: VecDeque::with_capacity(RENDER_HISTORY_LENGTH),
+        }
+    }
+
+    pub fn record(&mut self, stats: RenderStats) {
+        if self.renders.len() == RENDER_HISTORY_LENGTH {
+            self.renders.pop_back();
+        }
+        self.renders.push_front(stats);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &RenderStats> {
+        self.renders.iter()
+    }
+}
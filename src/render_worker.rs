@@ -0,0 +1,350 @@
+//! Progressive rendering: the image is split into buckets that are rendered on
+//! worker threads, and streamed back to the UI as they complete
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use iced::futures::channel::mpsc;
+use iced::futures::StreamExt;
+use iced::{subscription, Subscription};
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use crate::adaptive_quality::Quality;
+use crate::color_pipeline::{scene_to_display, scene_to_display_tile, DisplaySettings};
+use crate::render::{
+    render_scene_linear, render_scene_region, AntiAliasing, Framebuffer, Region, RenderBackend,
+    RenderSettings,
+};
+use crate::resample::upscale_nearest_rgba;
+
+/// Size of the square buckets the image is split into
+const TILE_SIZE: usize = 64;
+/// The GPU is better off with fewer, bigger dispatches
+const GPU_TILE_SIZE: usize = 1024;
+/// Proxy renders only look at one pixel out of PROXY_SCALE x PROXY_SCALE
+pub const PROXY_SCALE: usize = 4;
+
+/// Order in which the buckets are rendered
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BucketOrder {
+    /// Row by row, from the top
+    Scanline,
+    /// From the center outwards, nice for centered subjects
+    Spiral,
+    /// Along a Hilbert curve, which keeps consecutive buckets close to each other
+    Hilbert,
+}
+
+impl BucketOrder {
+    pub const ALL: [BucketOrder; 3] = [
+        BucketOrder::Scanline,
+        BucketOrder::Spiral,
+        BucketOrder::Hilbert,
+    ];
+
+    /// Reorder the buckets of `area`, as returned by `split_into_tiles`
+    fn sort(&self, tiles: &mut [Region], area: Region, tile_size: usize) {
+        // Position of the buckets, in bucket units relative to the top left of the area
+        let column = |tile: &Region| (tile.x - area.x) / tile_size;
+        let row = |tile: &Region| (tile.y - area.y) / tile_size;
+
+        match self {
+            BucketOrder::Scanline => {}
+            BucketOrder::Spiral => {
+                let center_column = (area.width as f32 / tile_size as f32 - 1.0) / 2.0;
+                let center_row = (area.height as f32 / tile_size as f32 - 1.0) / 2.0;
+                // Ring by ring, going around each ring by angle
+                let key = |tile: &Region| {
+                    let dx = column(tile) as f32 - center_column;
+                    let dy = row(tile) as f32 - center_row;
+                    (dx.abs().max(dy.abs()).round() as usize, dy.atan2(dx))
+                };
+                tiles.sort_by(|a, b| {
+                    let (a, b) = (key(a), key(b));
+                    a.0.cmp(&b.0).then(a.1.total_cmp(&b.1))
+                });
+            }
+            BucketOrder::Hilbert => {
+                let columns = area.width.div_ceil(tile_size);
+                let rows = area.height.div_ceil(tile_size);
+                let curve_size = columns.max(rows).next_power_of_two();
+                tiles.sort_by_key(|tile| hilbert_index(curve_size, column(tile), row(tile)));
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for BucketOrder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            BucketOrder::Scanline => "Scanline",
+            BucketOrder::Spiral => "Spiral",
+            BucketOrder::Hilbert => "Hilbert",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Distance along the Hilbert curve filling a `size` x `size` grid (a power of two)
+/// See https://en.wikipedia.org/wiki/Hilbert_curve
+fn hilbert_index(size: usize, mut x: usize, mut y: usize) -> usize {
+    let mut index = 0;
+    let mut s = size / 2;
+
+    while s > 0 {
+        let rx = usize::from(x & s > 0);
+        let ry = usize::from(y & s > 0);
+        index += s * s * ((3 * rx) ^ ry);
+
+        // Rotate the quadrant, so that the curve stays continuous
+        if ry == 0 {
+            if rx == 1 {
+                x = size - 1 - x;
+                y = size - 1 - y;
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+        s /= 2;
+    }
+
+    index
+}
+
+/// Shared flag used to stop a render from the UI
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Everything needed to render a frame in the background
+#[derive(Debug, Clone)]
+pub struct RenderJob {
+    /// Incremented for every render, so that stale results can be ignored
+    pub generation: u64,
+    pub render_settings: RenderSettings,
+    pub display_settings: DisplaySettings,
+    pub quality: Quality,
+    /// Part of the image to render, the whole of it unless cropping to a region
+    /// of interest. Proxy renders always cover the whole image.
+    pub region: Region,
+    pub bucket_order: BucketOrder,
+    /// Whether this render is part of an interactive change
+    pub scrubbing: bool,
+    pub cancellation: CancellationToken,
+}
+
+/// A finished bucket, ready to be copied into the framebuffer
+#[derive(Clone)]
+pub struct RenderedTile {
+    pub region: Region,
+    /// Scene linear pixels of the region, None for proxy renders
+    pub linear_pixels: Option<Framebuffer>,
+    pub display_pixels: Vec<u8>,
+}
+
+// Skip the pixels, which would flood the output
+impl std::fmt::Debug for RenderedTile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RenderedTile")
+            .field("region", &self.region)
+            .field("proxy", &self.linear_pixels.is_none())
+            .finish()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum RenderEvent {
+    TilesRendered {
+        generation: u64,
+        tiles: Vec<RenderedTile>,
+    },
+    Finished {
+        generation: u64,
+        render_time: Duration,
+    },
+}
+
+/// Split `area` into buckets of (at most) `tile_size` x `tile_size`, in scanline order
+pub fn split_into_tiles(area: Region, tile_size: usize) -> Vec<Region> {
+    let mut tiles = Vec::new();
+    let (right, bottom) = (area.x + area.width, area.y + area.height);
+
+    for y in (area.y..bottom).step_by(tile_size) {
+        for x in (area.x..right).step_by(tile_size) {
+            tiles.push(Region {
+                x,
+                y,
+                width: tile_size.min(right - x),
+                height: tile_size.min(bottom - y),
+            });
+        }
+    }
+
+    tiles
+}
+
+fn render_tile(job: &RenderJob, region: Region) -> RenderedTile {
+    let linear_pixels = render_scene_region(&job.render_settings, region);
+    let display_pixels =
+        scene_to_display_tile(&linear_pixels, (region.x, region.y), &job.display_settings);
+
+    RenderedTile {
+        region,
+        linear_pixels: Some(linear_pixels),
+        display_pixels,
+    }
+}
+
+/// Render the scene at a fraction of the resolution, and scale it back up.
+/// This is cheap enough to be done in a single go.
+fn render_proxy(job: &RenderJob) -> RenderedTile {
+    let (width, height) = (job.render_settings.width, job.render_settings.height);
+    let proxy_settings = RenderSettings {
+        width: width.div_ceil(PROXY_SCALE),
+        height: height.div_ceil(PROXY_SCALE),
+        ..job.render_settings.clone()
+    };
+
+    let proxy_framebuffer = render_scene_linear(&proxy_settings);
+    let proxy_buffer = scene_to_display(&proxy_framebuffer, &job.display_settings);
+
+    RenderedTile {
+        region: Region::full(width, height),
+        linear_pixels: None,
+        display_pixels: upscale_nearest_rgba(
+            &proxy_buffer,
+            proxy_settings.width,
+            PROXY_SCALE,
+            width,
+            height,
+        ),
+    }
+}
+
+fn spawn_worker(job: RenderJob, sender: mpsc::UnboundedSender<RenderedTile>) {
+    std::thread::spawn(move || {
+        // Sending also fails once the subscription has been dropped, e.g. because
+        // a newer render replaced this one. That's our cue to stop early too.
+        let send = |tile| sender.unbounded_send(tile).map_err(|_| ());
+        let check_cancelled = || {
+            if job.cancellation.is_cancelled() {
+                Err(())
+            } else {
+                Ok(())
+            }
+        };
+
+        let _stopped_early = match job.quality {
+            Quality::Proxy => check_cancelled().and_then(|_| send(render_proxy(&job))),
+            Quality::Full => {
+                let settings = &job.render_settings;
+                let on_gpu = settings.backend == RenderBackend::Gpu
+                    && settings.anti_aliasing == AntiAliasing::X1
+                    && settings.backend.supports(settings.scene)
+                    && (!settings.overlay.enabled
+                        || settings.backend.supports(settings.overlay.scene));
+                let tile_size = if on_gpu { GPU_TILE_SIZE } else { TILE_SIZE };
+                let mut tiles = split_into_tiles(job.region, tile_size);
+                job.bucket_order.sort(&mut tiles, job.region, tile_size);
+
+                // Bridging hands out the buckets one at a time, in order, whereas
+                // a parallel iterator over the Vec would split it in big chunks
+                #[cfg(feature = "parallel")]
+                let tiles = tiles.into_iter().par_bridge();
+                #[cfg(not(feature = "parallel"))]
+                let mut tiles = tiles.into_iter();
+
+                tiles.try_for_each(|region| {
+                    check_cancelled()?;
+                    send(render_tile(&job, region))
+                })
+            }
+        };
+    });
+}
+
+enum WorkerState {
+    // Boxed, the job is much bigger than the other states
+    Starting(Box<RenderJob>),
+    Rendering {
+        generation: u64,
+        receiver: mpsc::UnboundedReceiver<RenderedTile>,
+        start: Instant,
+    },
+    Finished,
+}
+
+/// Render `job` progressively, emitting the tiles as they complete.
+/// Dropping the subscription stops the render.
+pub fn render_tiles(job: RenderJob) -> Subscription<RenderEvent> {
+    struct RenderWorker;
+    let id = (std::any::TypeId::of::<RenderWorker>(), job.generation);
+
+    subscription::unfold(
+        id,
+        WorkerState::Starting(Box::new(job)),
+        |state| async move {
+            match state {
+                WorkerState::Starting(job) => {
+                    let generation = job.generation;
+                    let (sender, receiver) = mpsc::unbounded();
+                    spawn_worker(*job, sender);
+
+                    let state = WorkerState::Rendering {
+                        generation,
+                        receiver,
+                        start: Instant::now(),
+                    };
+                    (None, state)
+                }
+                WorkerState::Rendering {
+                    generation,
+                    mut receiver,
+                    start,
+                } => match receiver.next().await {
+                    Some(tile) => {
+                        // Batch whatever else is already done, so that the UI
+                        // doesn't have to update the image for every single tile
+                        let mut tiles = vec![tile];
+                        while let Ok(Some(tile)) = receiver.try_next() {
+                            tiles.push(tile);
+                        }
+
+                        let state = WorkerState::Rendering {
+                            generation,
+                            receiver,
+                            start,
+                        };
+                        (
+                            Some(RenderEvent::TilesRendered { generation, tiles }),
+                            state,
+                        )
+                    }
+                    None => {
+                        let render_time = start.elapsed();
+                        let event = RenderEvent::Finished {
+                            generation,
+                            render_time,
+                        };
+                        (Some(event), WorkerState::Finished)
+                    }
+                },
+                WorkerState::Finished => {
+                    // Nothing left to do, wait for the subscription to be dropped
+                    iced::futures::future::pending::<()>().await;
+                    (None, WorkerState::Finished)
+                }
+            }
+        },
+    )
+}
@@ -0,0 +1,81 @@
+//! Resizing of RGBA pixel buffers
+
+/// Box filter an RGBA8 buffer down to `new_width` x `new_height`.
+/// Each destination pixel averages the source pixels that fall inside it.
+pub fn downscale_rgba(
+    pixels: &[u8],
+    width: usize,
+    height: usize,
+    new_width: usize,
+    new_height: usize,
+) -> Vec<u8> {
+    let mut downscaled = vec![0; new_width * new_height * 4];
+
+    for (index, pixel) in downscaled.chunks_exact_mut(4).enumerate() {
+        let (x, y) = (index % new_width, index / new_width);
+
+        // Source rectangle covered by this pixel, always at least one pixel wide
+        let x_start = x * width / new_width;
+        let x_end = ((x + 1) * width / new_width).max(x_start + 1);
+        let y_start = y * height / new_height;
+        let y_end = ((y + 1) * height / new_height).max(y_start + 1);
+
+        let mut sum = [0u32; 4];
+        for source_y in y_start..y_end {
+            for source_x in x_start..x_end {
+                let source_index = (source_y * width + source_x) * 4;
+                for channel in 0..4 {
+                    sum[channel] += pixels[source_index + channel] as u32;
+                }
+            }
+        }
+
+        let count = ((x_end - x_start) * (y_end - y_start)) as u32;
+        for channel in 0..4 {
+            pixel[channel] = (sum[channel] / count) as u8;
+        }
+    }
+
+    downscaled
+}
+
+/// Keep every `factor`-th pixel (in both directions) of an RGBA buffer.
+/// Returns the subsampled pixels with their width and height.
+pub fn subsample_rgba<T: Copy>(
+    pixels: &[T],
+    width: usize,
+    height: usize,
+    factor: usize,
+) -> (Vec<T>, usize, usize) {
+    let new_width = width.div_ceil(factor);
+    let new_height = height.div_ceil(factor);
+
+    let mut subsampled = Vec::with_capacity(new_width * new_height * 4);
+    for y in (0..height).step_by(factor) {
+        for x in (0..width).step_by(factor) {
+            let index = (y * width + x) * 4;
+            subsampled.extend_from_slice(&pixels[index..index + 4]);
+        }
+    }
+
+    (subsampled, new_width, new_height)
+}
+
+/// Nearest neighbour upscale of an RGBA8 buffer previously subsampled by `factor`
+pub fn upscale_nearest_rgba(
+    pixels: &[u8],
+    width: usize,
+    factor: usize,
+    new_width: usize,
+    new_height: usize,
+) -> Vec<u8> {
+    let mut upscaled = vec![0; new_width * new_height * 4];
+
+    for (index, pixel) in upscaled.chunks_exact_mut(4).enumerate() {
+        let (x, y) = (index % new_width, index / new_width);
+        let source_index = ((y / factor) * width + x / factor) * 4;
+        pixel.copy_from_slice(&pixels[source_index..source_index + 4]);
+    }
+
+    upscaled
+}
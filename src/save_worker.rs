@@ -0,0 +1,195 @@
+//! Saving frames on a background thread, so that writing a big EXR doesn't hitch the UI
+use std::borrow::Cow;
+use std::future::Future;
+use std::path::Path;
+
+use iced::futures::channel::oneshot;
+
+use crate::console;
+
+use crate::color_pipeline::{
+    luminance_plane, scene_to_display, scene_to_display_16, scene_to_display_log10, DisplaySettings,
+};
+use crate::dpx::write_dpx;
+use crate::i18n::{tr_args, Text};
+use crate::io::{self, ExrLayer, OutputFormat};
+use crate::post_process;
+use crate::render::{render_aov, uv_coordinates, Aov, Framebuffer, RenderSettings};
+
+/// Everything needed to write a frame, copied so that rendering can carry on meanwhile
+pub struct SaveJob {
+    pub path: String,
+    pub format: OutputFormat,
+    pub framebuffer: Framebuffer,
+    /// What PNGs, TIFFs and DPXs get converted and post-processed with
+    pub display_settings: DisplaySettings,
+    /// When set, EXRs also get the AOVs, rendered again with these settings
+    pub aov_settings: Option<RenderSettings>,
+    /// Header attributes of EXRs
+    pub metadata: Vec<(String, String)>,
+}
+
+impl SaveJob {
+    /// Write the frame, returning the full path of the file written
+    pub fn write(self) -> Result<String, String> {
+        console::info(format!("Saving {} to disk..", self.path));
+        let path = Path::new(&self.path);
+        let framebuffer = &self.framebuffer;
+        let (width, height) = (framebuffer.width(), framebuffer.height());
+        // Display referred formats also get the post-processing, EXRs and HDRs stay raw
+        let processed =
+            || post_process::apply_stack(&self.display_settings.post_passes, framebuffer);
+
+        // EXRs and HDRs don't go through the display conversion, which applies the
+        // global alpha for the other formats
+        let global_alpha = self.display_settings.global_alpha;
+        let scene_linear = || {
+            if global_alpha == 1.0 {
+                return Cow::Borrowed(framebuffer);
+            }
+            let mut faded = framebuffer.clone();
+            for pixel in faded.pixels_mut().chunks_exact_mut(4) {
+                pixel[3] *= global_alpha;
+            }
+            Cow::Owned(faded)
+        };
+
+        let result = match self.format {
+            OutputFormat::Exr => match &self.aov_settings {
+                Some(settings) => {
+                    let layers = self.aov_layers(&scene_linear(), settings);
+                    io::write_layered_exr(path, width, height, layers, &self.metadata)
+                }
+                None => io::write_exr(path, &scene_linear(), &self.metadata),
+            },
+            OutputFormat::Hdr => io::write_hdr(path, &scene_linear()),
+            OutputFormat::Png => {
+                let display_buffer = scene_to_display(&processed(), &self.display_settings);
+                io::write_png(path, width, height, &display_buffer)
+            }
+            OutputFormat::Tiff16 => {
+                let display_buffer = scene_to_display_16(&processed(), &self.display_settings);
+                io::write_tiff16(path, width, height, display_buffer)
+            }
+            OutputFormat::Dpx => {
+                let codes = scene_to_display_log10(&processed(), &self.display_settings);
+                write_dpx(path, width, height, &codes)
+            }
+        };
+
+        match result {
+            Ok(()) => Ok(std::fs::canonicalize(path)
+                .map_or(self.path.clone(), |path| path.display().to_string())),
+            Err(e) => {
+                console::error(&e);
+                Err(e)
+            }
+        }
+    }
+
+    /// Write the frame on its own thread, resolving once it's done
+    pub fn spawn(self) -> impl Future<Output = Result<String, String>> {
+        let path = self.path.clone();
+        let (sender, receiver) = oneshot::channel();
+        std::thread::spawn(move || {
+            // Nobody is waiting for the result anymore if the application was closed
+            let _ = sender.send(self.write());
+        });
+
+        async move {
+            receiver
+                .await
+                .unwrap_or_else(|_| Err(tr_args(Text::SaveInterrupted, &[&path])))
+        }
+    }
+
+    /// The `beauty` frame and its AOVs, as the layers of a multi-layer EXR
+    fn aov_layers(&self, beauty: &Framebuffer, settings: &RenderSettings) -> Vec<ExrLayer> {
+        let beauty = beauty.pixels();
+        let albedo = render_aov(settings, Aov::Albedo);
+        let uv = uv_coordinates(self.framebuffer.width(), self.framebuffer.height());
+        let luminance = luminance_plane(beauty);
+
+        vec![
+            ExrLayer::from_interleaved("beauty", beauty, 4, &["R", "G", "B", "A"]),
+            ExrLayer::from_interleaved("albedo", albedo.pixels(), 4, &["R", "G", "B"]),
+            ExrLayer::from_interleaved("uv", &uv, 2, &["U", "V"]),
+            ExrLayer::from_interleaved("luminance", &luminance, 1, &["Y"]),
+        ]
+    }
+}
+
+/// What the image was rendered and viewed with, saved along with it to reproduce it later
+pub fn render_metadata(
+    render: &RenderSettings,
+    display: &DisplaySettings,
+) -> Vec<(String, String)> {
+    [
+        (
+            "render/resolution",
+            format!("{}x{}", render.width, render.height),
+        ),
+        ("render/scene", render.scene.to_string()),
+        ("render/seed", render.seed.to_string()),
+        ("render/frame", render.frame.to_string()),
+        ("render/antiAliasing", render.anti_aliasing.to_string()),
+        ("display/globalAlpha", display.global_alpha.to_string()),
+        ("display/exposure", display.exposure.to_string()),
+        ("display/tonemap", display.tonemap.to_string()),
+        ("display/transform", display.display.to_string()),
+    ]
+    .into_iter()
+    .map(|(name, value)| (String::from(name), value))
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Write a mid gray frame through the given format with a global alpha of 0.5
+    fn save_faded(extension: &str) -> String {
+        let directory = std::env::temp_dir().join("iced-framebuffer-global-alpha");
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = directory.join(format!("faded.{extension}"));
+        let path = path.to_str().unwrap().to_string();
+
+        let pixels = [0.18, 0.18, 0.18, 1.0].repeat(4 * 4);
+        let display_settings = DisplaySettings {
+            global_alpha: 0.5,
+            ..DisplaySettings::default()
+        };
+        SaveJob {
+            path: path.clone(),
+            format: OutputFormat::from_path(&path).unwrap(),
+            framebuffer: Framebuffer::from_pixels(4, 4, pixels),
+            display_settings,
+            aov_settings: None,
+            metadata: Vec::new(),
+        }
+        .write()
+        .unwrap();
+        path
+    }
+
+    #[test]
+    fn global_alpha_is_saved() {
+        let exr = io::read_image(Path::new(&save_faded("exr"))).unwrap();
+        assert_eq!(exr.pixel(0, 0)[3], 0.5);
+
+        let png = io::read_image(Path::new(&save_faded("png"))).unwrap();
+        assert!((png.pixel(0, 0)[3] - 0.5).abs() < 1.0 / 255.0);
+
+        let tiff = image::open(save_faded("tiff")).unwrap().into_rgba16();
+        let alpha = tiff.get_pixel(0, 0).0[3] as f32 / u16::MAX as f32;
+        assert!((alpha - 0.5).abs() < 1.0 / 65535.0);
+
+        // Without an alpha channel, the image is composited over black
+        let hdr = io::read_image(Path::new(&save_faded("hdr"))).unwrap();
+        let [r, g, b, a] = hdr.pixel(0, 0);
+        assert_eq!(a, 1.0);
+        for channel in [r, g, b] {
+            assert!((channel - 0.09).abs() < 0.002, "{channel}");
+        }
+    }
+}
@@ -0,0 +1,176 @@
+//! Video style scopes of the display referred image: luma waveform, RGB parade
+//! and vectorscope, drawn into RGBA images like the histogram
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScopeKind {
+    /// Luma of each column of the image, from black at the bottom to white at the top
+    Waveform,
+    /// Same as the waveform, for the red, green and blue channels side by side
+    Parade,
+    /// Chroma of every pixel, on the BT.709 Cb/Cr plane
+    Vectorscope,
+}
+
+impl ScopeKind {
+    pub const ALL: [ScopeKind; 3] = [
+        ScopeKind::Waveform,
+        ScopeKind::Parade,
+        ScopeKind::Vectorscope,
+    ];
+}
+
+impl std::fmt::Display for ScopeKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ScopeKind::Waveform => "Luma waveform",
+            ScopeKind::Parade => "RGB parade",
+            ScopeKind::Vectorscope => "Vectorscope",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Height of the scopes, the waveforms being twice as wide
+pub const SCOPE_SIZE: usize = 200;
+const BACKGROUND: [u8; 3] = [20, 20, 20];
+const GRATICULE: [u8; 3] = [70, 70, 70];
+
+/// How many pixels land in each cell of the scope
+struct Counts {
+    width: usize,
+    height: usize,
+    cells: Vec<u32>,
+}
+
+impl Counts {
+    fn new(width: usize, height: usize) -> Counts {
+        Counts {
+            width,
+            height,
+            cells: vec![0; width * height],
+        }
+    }
+
+    /// Count a value from 0 (bottom) to 255 (top) in column `x`
+    fn add_level(&mut self, x: usize, level: u8) {
+        let y = (255 - level as usize) * (self.height - 1) / 255;
+        self.cells[y * self.width + x] += 1;
+    }
+
+    /// Draw the counts with `color`, `full` being the count that is shown at full intensity.
+    /// A square root keeps the sparse cells visible.
+    fn draw_into(&self, pixels: &mut [u8], full: f32, color: [u8; 3]) {
+        for (pixel, &count) in pixels.chunks_exact_mut(4).zip(&self.cells) {
+            if count == 0 {
+                continue;
+            }
+            let intensity = (count as f32 / full.max(1.0)).min(1.0).sqrt();
+            for (channel, color_channel) in pixel[..3].iter_mut().zip(color) {
+                let lit = *channel as f32 + intensity * color_channel as f32;
+                *channel = lit.min(255.0) as u8;
+            }
+        }
+    }
+}
+
+fn background(width: usize, height: usize) -> Vec<u8> {
+    let mut pixels = vec![255; width * height * 4];
+    for pixel in pixels.chunks_exact_mut(4) {
+        pixel[..3].copy_from_slice(&BACKGROUND);
+    }
+    pixels
+}
+
+fn set_pixel(pixels: &mut [u8], width: usize, x: usize, y: usize, color: [u8; 3]) {
+    let index = (y * width + x) * 4;
+    pixels[index..index + 3].copy_from_slice(&color);
+}
+
+/// Horizontal lines at 0, 25, 50, 75 and 100%
+fn draw_level_lines(pixels: &mut [u8], width: usize, height: usize) {
+    for quarter in 0..=4 {
+        let y = quarter * (height - 1) / 4;
+        for x in 0..width {
+            set_pixel(pixels, width, x, y, GRATICULE);
+        }
+    }
+}
+
+fn luma(pixel: &[u8]) -> u8 {
+    (0.2126 * pixel[0] as f32 + 0.7152 * pixel[1] as f32 + 0.0722 * pixel[2] as f32).round() as u8
+}
+
+/// Draw the `kind` scope of the display referred RGBA `pixels`, which are `width` wide.
+/// Returns the size of the scope and its RGBA pixels.
+pub fn draw(kind: ScopeKind, pixels: &[u8], width: usize) -> (usize, usize, Vec<u8>) {
+    let pixel_count = pixels.len() / 4;
+    let height = pixel_count / width.max(1);
+
+    match kind {
+        ScopeKind::Waveform | ScopeKind::Parade => {
+            let (scope_width, scope_height) = (SCOPE_SIZE * 2, SCOPE_SIZE);
+            let mut scope = background(scope_width, scope_height);
+            draw_level_lines(&mut scope, scope_width, scope_height);
+
+            // A column of the scope gathers a few columns of the image
+            let column = |x: usize, columns: usize| x * columns / width;
+            let full = (height as f32 / 8.0) * (width as f32 / scope_width as f32);
+
+            if kind == ScopeKind::Waveform {
+                let mut counts = Counts::new(scope_width, scope_height);
+                for (index, pixel) in pixels.chunks_exact(4).enumerate() {
+                    counts.add_level(column(index % width, scope_width), luma(pixel));
+                }
+                counts.draw_into(&mut scope, full, [180, 230, 180]);
+            } else {
+                let section_width = scope_width / 3;
+                for (channel, color) in [[230, 60, 60], [60, 230, 60], [80, 120, 255]]
+                    .into_iter()
+                    .enumerate()
+                {
+                    let mut counts = Counts::new(scope_width, scope_height);
+                    for (index, pixel) in pixels.chunks_exact(4).enumerate() {
+                        let x = channel * section_width + column(index % width, section_width);
+                        counts.add_level(x, pixel[channel]);
+                    }
+                    // Each column of a section gathers three times more of the image
+                    counts.draw_into(&mut scope, full * 3.0, color);
+                }
+            }
+            (scope_width, scope_height, scope)
+        }
+        ScopeKind::Vectorscope => {
+            let size = SCOPE_SIZE;
+            let mut scope = background(size, size);
+
+            // Graticule: the axes and the edge of the Cb/Cr range
+            let center = (size / 2) as f32;
+            for i in 0..size {
+                set_pixel(&mut scope, size, i, size / 2, GRATICULE);
+                set_pixel(&mut scope, size, size / 2, i, GRATICULE);
+            }
+            for step in 0..720 {
+                let angle = step as f32 / 720.0 * std::f32::consts::TAU;
+                let x = center + angle.cos() * (center - 1.0);
+                let y = center + angle.sin() * (center - 1.0);
+                set_pixel(&mut scope, size, x as usize, y as usize, GRATICULE);
+            }
+
+            let mut counts = Counts::new(size, size);
+            for pixel in pixels.chunks_exact(4) {
+                let [r, g, b] = [pixel[0], pixel[1], pixel[2]].map(|c| c as f32 / 255.0);
+                let y = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+                // Both between -0.5 and 0.5
+                let cb = (b - y) / 1.8556;
+                let cr = (r - y) / 1.5748;
+                let to_cell = |value: f32| {
+                    ((0.5 + value) * (size - 1) as f32)
+                        .round()
+                        .clamp(0.0, (size - 1) as f32) as usize
+                };
+                counts.cells[to_cell(-cr) * size + to_cell(cb)] += 1;
+            }
+            counts.draw_into(&mut scope, pixel_count as f32 / 2000.0, [200, 230, 200]);
+            (size, size, scope)
+        }
+    }
+}
@@ -0,0 +1,28 @@
+//! A rectangle selected over the image, to look at the statistics of its scene
+//! linear pixels or to export it on its own
+use crate::render::Region;
+
+/// Draw the edges of `region` over the RGBA `display_buffer` of an image `width`
+/// pixels wide, dashed in black and white so that they show over any color
+pub fn outline(display_buffer: &mut [u8], width: usize, region: Region) {
+    const DASH: usize = 4;
+    let (right, bottom) = (region.x + region.width - 1, region.y + region.height - 1);
+    let mut paint = |x: usize, y: usize| {
+        let level = if ((x + y) / DASH).is_multiple_of(2) {
+            255
+        } else {
+            0
+        };
+        let index = (y * width + x) * 4;
+        display_buffer[index..index + 4].copy_from_slice(&[level, level, level, 255]);
+    };
+
+    for x in region.x..=right {
+        paint(x, region.y);
+        paint(x, bottom);
+    }
+    for y in region.y..=bottom {
+        paint(region.x, y);
+        paint(right, y);
+    }
+}
@@ -0,0 +1,357 @@
+//! Numerical checks of the color pipeline, run with `--self-test`.
+//! Unlike unit tests, these are shipped in the binary so that users can
+//! confirm that the color math behaves on their own build.
+use colstodian::spaces::{AcesCg, CieXYZ, EncodedSrgb, LinearSrgb};
+use colstodian::{color, Display, Scene};
+
+use crate::background::{self, ViewerBackground};
+use crate::color_pipeline::{
+    acescg_luminance, cineon_encode, tonemap, DisplayPipeline, DisplaySettings, DisplayTransform,
+    GamutMapping, TonemapKind, CINEON_BLACK, CINEON_WHITE,
+};
+use crate::io::{to_acescg_matrix, REC709_CHROMATICITIES};
+use crate::tone_curve::ToneCurve;
+use crate::white_balance::{multiply_vector, white_xyz, WhiteBalance, REFERENCE_TEMPERATURE};
+
+/// Result of a single check: the largest error found, and how much is acceptable
+struct Check {
+    name: String,
+    delta: f32,
+    tolerance: f32,
+}
+
+impl Check {
+    fn passed(&self) -> bool {
+        // Written this way so that a NaN delta fails
+        self.delta <= self.tolerance
+    }
+}
+
+/// Run every check, printing pass/fail with their deltas.
+/// Returns true if all of them passed.
+pub fn run() -> bool {
+    let mut checks = vec![
+        mid_gray_round_trip(),
+        primaries_luminance(),
+        white_balance_adaptation(),
+        rec709_exr_primaries(),
+        cineon_reference_codes(),
+        linear_alpha_compositing(),
+        gamut_mapping_keeps_in_gamut_colors(),
+        gamut_mapping_removes_negatives(),
+        baked_tonemap_accuracy(),
+        tone_curve_through_points(),
+    ];
+    for kind in TonemapKind::ALL {
+        checks.push(white_stays_neutral(kind));
+        checks.push(tonemap_monotonicity(kind));
+        checks.push(tonemap_range(kind));
+    }
+
+    for check in &checks {
+        let status = if check.passed() { "PASS" } else { "FAIL" };
+        println!(
+            "[{status}] {} (delta: {:.2e}, tolerance: {:.2e})",
+            check.name, check.delta, check.tolerance
+        );
+    }
+
+    let failed = checks.iter().filter(|check| !check.passed()).count();
+    println!("{} checks, {} failed", checks.len(), failed);
+
+    failed == 0
+}
+
+fn max_abs_difference(a: [f32; 3], b: [f32; 3]) -> f32 {
+    (0..3).map(|i| (a[i] - b[i]).abs()).fold(0.0, f32::max)
+}
+
+/// 18% gray survives ACEScg -> linear sRGB -> encoded sRGB and back
+fn mid_gray_round_trip() -> Check {
+    let gray = color::acescg::<Display>(0.18, 0.18, 0.18);
+    let round_tripped = gray
+        .convert::<LinearSrgb>()
+        .convert::<EncodedSrgb>()
+        .convert::<LinearSrgb>()
+        .convert::<AcesCg>();
+
+    Check {
+        name: String::from("Mid gray round trip"),
+        delta: max_abs_difference(
+            [gray.r, gray.g, gray.b],
+            [round_tripped.r, round_tripped.g, round_tripped.b],
+        ),
+        tolerance: 1e-5,
+    }
+}
+
+/// The luminance weights we use match the Y of each ACEScg primary in CIE XYZ
+fn primaries_luminance() -> Check {
+    let primaries = [
+        color::acescg::<Scene>(1.0, 0.0, 0.0),
+        color::acescg::<Scene>(0.0, 1.0, 0.0),
+        color::acescg::<Scene>(0.0, 0.0, 1.0),
+    ];
+
+    let delta = primaries
+        .iter()
+        .map(|primary| (acescg_luminance(*primary) - primary.convert::<CieXYZ>().y).abs())
+        .fold(0.0, f32::max);
+
+    Check {
+        name: String::from("ACEScg primaries luminance"),
+        delta,
+        tolerance: 1e-4,
+    }
+}
+
+/// White balancing turns the white of the light into the reference white
+fn white_balance_adaptation() -> Check {
+    let reference_white = white_xyz(REFERENCE_TEMPERATURE, 0.0);
+    let delta = [(2700.0, 0.0), (4000.0, 0.5), (9000.0, -0.5)]
+        .iter()
+        .map(|&(temperature, tint)| {
+            let white_balance = WhiteBalance { temperature, tint };
+            let adapted = multiply_vector(
+                &white_balance.adaptation_matrix(),
+                white_xyz(temperature, tint),
+            );
+            max_abs_difference(adapted, reference_white)
+        })
+        .fold(0.0, f32::max);
+
+    Check {
+        name: String::from("White balance adapts the light to the reference white"),
+        delta,
+        tolerance: 1e-4,
+    }
+}
+
+/// Opening a Rec. 709 EXR converts its primaries like colstodian converts linear sRGB
+fn rec709_exr_primaries() -> Check {
+    let delta = match to_acescg_matrix(&REC709_CHROMATICITIES) {
+        Some(matrix) => [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0], [0.18; 3]]
+            .into_iter()
+            .map(|[r, g, b]| {
+                let expected = color::linear_srgb::<Scene>(r, g, b).convert::<AcesCg>();
+                max_abs_difference(
+                    multiply_vector(&matrix, [r, g, b]),
+                    [expected.r, expected.g, expected.b],
+                )
+            })
+            .fold(0.0, f32::max),
+        None => f32::NAN,
+    };
+
+    Check {
+        name: String::from("Rec. 709 EXR primaries to ACEScg"),
+        delta,
+        tolerance: 1e-3,
+    }
+}
+
+/// The DPX log encoding puts black and white at the reference Cineon code values
+fn cineon_reference_codes() -> Check {
+    let delta = [(0.0, CINEON_BLACK), (1.0, CINEON_WHITE)]
+        .into_iter()
+        .map(|(linear, code)| (cineon_encode(linear) as f32 - code as f32).abs())
+        .fold(0.0, f32::max);
+
+    Check {
+        name: String::from("Cineon black and white code values"),
+        delta,
+        tolerance: 0.0,
+    }
+}
+
+/// The viewer blends on display linear values, so half transparent white over black
+/// comes out with half of the light, not at half of the encoded range
+fn linear_alpha_compositing() -> Check {
+    let display = DisplayTransform::Srgb;
+    let mut pixel = [255, 255, 255, 128];
+    background::composite(&mut pixel, 1, ViewerBackground::Black, display);
+    let linear = display.decode_channel(pixel[0] as f32 / 255.0);
+
+    Check {
+        name: String::from("Alpha compositing over the viewer background is linear"),
+        delta: (linear - 128.0 / 255.0).abs(),
+        // One 8bit step around the middle
+        tolerance: 0.005,
+    }
+}
+
+/// Encoded sRGB of a scene linear ACEScg pixel, with the given gamut mapping
+fn encoded_srgb(color: [f32; 3], gamut_mapping: GamutMapping) -> [f32; 3] {
+    let settings = DisplaySettings {
+        gamut_mapping,
+        ..DisplaySettings::default()
+    };
+    DisplayPipeline::new(&settings)
+        .stages(&[color[0], color[1], color[2], 1.0], 0, 0)
+        .encoded
+}
+
+/// Colors that are well inside sRGB come out the same with every gamut mapping
+fn gamut_mapping_keeps_in_gamut_colors() -> Check {
+    let delta = [[0.18, 0.18, 0.18], [0.4, 0.3, 0.2], [0.05, 0.1, 0.08]]
+        .iter()
+        .map(|&[r, g, b]| {
+            let acescg = color::linear_srgb::<Scene>(r, g, b).convert::<AcesCg>();
+            [acescg.r, acescg.g, acescg.b]
+        })
+        .flat_map(|color| {
+            let clipped = encoded_srgb(color, GamutMapping::Clip);
+            GamutMapping::ALL
+                .map(|mapping| max_abs_difference(encoded_srgb(color, mapping), clipped))
+        })
+        .fold(0.0, f32::max);
+
+    Check {
+        name: String::from("Gamut mapping leaves in gamut colors untouched"),
+        delta,
+        tolerance: 1e-4,
+    }
+}
+
+/// Desaturating brings the ACEScg primaries inside sRGB
+fn gamut_mapping_removes_negatives() -> Check {
+    let delta = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]
+        .iter()
+        .flat_map(|&color| encoded_srgb(color, GamutMapping::Desaturate))
+        .fold(0.0, |delta: f32, channel| delta.max(-channel));
+
+    Check {
+        name: String::from("Desaturate gamut mapping leaves no negative sRGB components"),
+        delta,
+        tolerance: 1e-4,
+    }
+}
+
+/// The baked tonemap LUT of big images stays close to the full conversion
+fn baked_tonemap_accuracy() -> Check {
+    let settings = DisplaySettings::default();
+    let exact = DisplayPipeline::new(&settings);
+    let baked = DisplayPipeline::baked(&settings, usize::MAX);
+
+    // Every combination of these linear sRGB values, from the shadows to bright highlights.
+    // Outside of the sRGB gamut, the interpolation error of the most saturated colors
+    // can become two 8bit steps once they are clipped to it.
+    let in_gamut = combinations(&[0.0, 0.005, 0.02, 0.05, 0.18, 0.5, 1.0, 4.0, 16.0])
+        .into_iter()
+        .map(|[r, g, b]| {
+            let acescg = color::linear_srgb::<Scene>(r, g, b).convert::<AcesCg>();
+            [acescg.r, acescg.g, acescg.b]
+        });
+    // Both clamp negative ACEScg values to 0
+    let negative = combinations(&[-0.1, -0.01, 0.0, 0.18, 1.0, 16.0])
+        .into_iter()
+        .filter(|channels| channels.iter().any(|&channel| channel < 0.0));
+
+    let delta = in_gamut
+        .chain(negative)
+        .map(|[r, g, b]| {
+            let pixel = [r, g, b, 1.0];
+            let encoded = |pipeline: &DisplayPipeline| {
+                let [r, g, b, _] = pipeline.stages(&pixel, 0, 0).rgba;
+                [r, g, b].map(f32::from)
+            };
+            max_abs_difference(encoded(&exact), encoded(&baked))
+        })
+        .fold(0.0, f32::max);
+
+    Check {
+        name: String::from("Baked perceptual tonemap matches the full conversion"),
+        delta,
+        // In 8bit steps, off by one for some saturated colors
+        tolerance: 1.0,
+    }
+}
+
+/// Every combination of three of `values`
+fn combinations(values: &[f32]) -> Vec<[f32; 3]> {
+    values
+        .iter()
+        .flat_map(|&r| {
+            values
+                .iter()
+                .flat_map(move |&g| values.iter().map(move |&b| [r, g, b]))
+        })
+        .collect()
+}
+
+/// The spline of a tone curve goes through its points, and the default one keeps
+/// mid gray where it is
+fn tone_curve_through_points() -> Check {
+    let mut curve = ToneCurve::default();
+    curve.insert([0.8, 0.9]);
+    curve.insert([0.2, 0.05]);
+    let delta = curve
+        .points
+        .iter()
+        .map(|&[x, y]| (curve.sample(x) - y).abs())
+        .fold((ToneCurve::default().apply(0.18) - 0.18).abs(), f32::max);
+
+    Check {
+        name: String::from("Tone curve goes through its points"),
+        delta,
+        tolerance: 1e-5,
+    }
+}
+
+/// Achromatic inputs stay achromatic all the way to encoded sRGB
+fn white_stays_neutral(kind: TonemapKind) -> Check {
+    let delta = [0.18, 1.0, 4.0, 16.0]
+        .iter()
+        .map(|&intensity| {
+            let gray = color::acescg::<Scene>(intensity, intensity, intensity);
+            let encoded = tonemap(gray, kind, &ToneCurve::default()).convert::<EncodedSrgb>();
+            let max = encoded.r.max(encoded.g).max(encoded.b);
+            let min = encoded.r.min(encoded.g).min(encoded.b);
+            max - min
+        })
+        .fold(0.0, f32::max);
+
+    Check {
+        name: format!("{kind} tonemap keeps white neutral"),
+        delta,
+        tolerance: 1e-3,
+    }
+}
+
+/// Tonemapped luminance for increasing input luminances, from 0 to 64
+fn tonemapped_ramp(kind: TonemapKind) -> impl Iterator<Item = f32> {
+    (0..=1024).map(move |i| {
+        let intensity = 64.0 * (i as f32 / 1024.0).powi(3);
+        let gray = color::acescg::<Scene>(intensity, intensity, intensity);
+        acescg_luminance(tonemap(gray, kind, &ToneCurve::default()))
+    })
+}
+
+/// Brighter inputs never produce darker outputs
+fn tonemap_monotonicity(kind: TonemapKind) -> Check {
+    let luminances: Vec<f32> = tonemapped_ramp(kind).collect();
+    let delta = luminances
+        .windows(2)
+        .map(|pair| (pair[0] - pair[1]).max(0.0))
+        .fold(0.0, f32::max);
+
+    Check {
+        name: format!("{kind} tonemap is monotonic"),
+        delta,
+        // The PQ round trip inside the perceptual tonemapper is a bit noisy near white
+        tolerance: 1e-4,
+    }
+}
+
+/// Tonemapped values stay within the displayable 0..1 range
+fn tonemap_range(kind: TonemapKind) -> Check {
+    let delta = tonemapped_ramp(kind)
+        .map(|luminance| (-luminance).max(luminance - 1.0).max(0.0))
+        .fold(0.0, f32::max);
+
+    Check {
+        name: format!("{kind} tonemap stays within 0..1"),
+        delta,
+        tolerance: 1e-4,
+    }
+}
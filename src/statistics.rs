@@ -0,0 +1,110 @@
+//! Statistics of each channel of scene linear RGBA pixels, to check the range of
+//! values a pattern produces, over the whole image or a selection of it,
+//! and the NaNs and infinities that no pattern should produce
+use crate::render::Region;
+
+/// How many of the RGBA `pixels` have a NaN or infinite channel
+pub fn count_non_finite(pixels: &[f32]) -> usize {
+    pixels
+        .chunks_exact(4)
+        .filter(|pixel| pixel.iter().any(|channel| !channel.is_finite()))
+        .count()
+}
+
+/// Of each of the R, G, B and A channels
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChannelStats {
+    pub min: [f32; 4],
+    pub max: [f32; 4],
+    pub mean: [f32; 4],
+    /// Population standard deviation
+    pub std_dev: [f32; 4],
+}
+
+impl ChannelStats {
+    /// Statistics of RGBA `pixels`, none if there are no pixels
+    pub fn new(pixels: &[f32]) -> Option<ChannelStats> {
+        let count = pixels.len() / 4;
+        if count == 0 {
+            return None;
+        }
+
+        let mut min = [f32::INFINITY; 4];
+        let mut max = [f32::NEG_INFINITY; 4];
+        // Summed in f64, as a million pixels is enough for f32 sums to drift
+        let mut sums = [0.0f64; 4];
+        let mut squared_sums = [0.0f64; 4];
+        for pixel in pixels.chunks_exact(4) {
+            for channel in 0..4 {
+                let value = pixel[channel];
+                min[channel] = min[channel].min(value);
+                max[channel] = max[channel].max(value);
+                sums[channel] += value as f64;
+                squared_sums[channel] += value as f64 * value as f64;
+            }
+        }
+
+        let mean = sums.map(|sum| sum / count as f64);
+        let mut std_dev = [0.0; 4];
+        for channel in 0..4 {
+            let variance = squared_sums[channel] / count as f64 - mean[channel] * mean[channel];
+            // Rounding can take the variance of a constant channel just below 0
+            std_dev[channel] = variance.max(0.0).sqrt() as f32;
+        }
+        Some(ChannelStats {
+            min,
+            max,
+            mean: mean.map(|mean| mean as f32),
+            std_dev,
+        })
+    }
+}
+
+/// How many pixels around the inspected one are averaged, like the color samplers of
+/// paint and compositing applications, to probe noisy renders
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SampleSize {
+    Single,
+    ThreeByThree,
+    FiveByFive,
+    NineByNine,
+}
+
+impl SampleSize {
+    pub const ALL: [SampleSize; 4] = [
+        SampleSize::Single,
+        SampleSize::ThreeByThree,
+        SampleSize::FiveByFive,
+        SampleSize::NineByNine,
+    ];
+
+    /// How far the averaged square goes on each side of the inspected pixel
+    pub fn radius(&self) -> usize {
+        match self {
+            SampleSize::Single => 0,
+            SampleSize::ThreeByThree => 1,
+            SampleSize::FiveByFive => 2,
+            SampleSize::NineByNine => 4,
+        }
+    }
+
+    /// The square of pixels averaged around `x`, `y`, cut to the `width` x `height` image
+    pub fn region(&self, x: usize, y: usize, width: usize, height: usize) -> Region {
+        let radius = self.radius();
+        let (left, top) = (x.saturating_sub(radius), y.saturating_sub(radius));
+        let (right, bottom) = ((x + radius).min(width - 1), (y + radius).min(height - 1));
+        Region {
+            x: left,
+            y: top,
+            width: right - left + 1,
+            height: bottom - top + 1,
+        }
+    }
+}
+
+impl std::fmt::Display for SampleSize {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let size = 2 * self.radius() + 1;
+        write!(f, "{size}×{size}")
+    }
+}
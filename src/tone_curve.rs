@@ -0,0 +1,159 @@
+//! A custom tone curve, an alternative to the built-in tonemappers, edited with the
+//! draggable points of the curve editor. Its input is the exposed scene linear value
+//! in stops around mid gray, its output the display value with a 2.2 gamma, so that
+//! both axes are roughly perceptual. The points are joined by a monotone cubic spline,
+//! which never overshoots them.
+
+/// Scene linear value at the middle of the input axis
+const MID_GRAY: f32 = 0.18;
+/// Range of the input axis, in stops around mid gray
+pub const MIN_STOPS: f32 = -8.0;
+pub const MAX_STOPS: f32 = 6.0;
+/// The output axis is the display linear value to the power of 1 / DISPLAY_GAMMA
+const DISPLAY_GAMMA: f32 = 2.2;
+
+pub const MAX_CURVE_POINTS: usize = 12;
+/// Closest two points can get along the input axis
+const MIN_SPACING: f32 = 0.01;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToneCurve {
+    /// Positions on both axes from 0 to 1, sorted along the input axis.
+    /// The first one is always at 0, the last one at 1.
+    pub points: Vec<[f32; 2]>,
+}
+
+impl Default for ToneCurve {
+    /// Straight from black to white, through mid gray
+    fn default() -> Self {
+        ToneCurve {
+            points: vec![
+                [0.0, 0.0],
+                [
+                    Self::input_position(MID_GRAY),
+                    MID_GRAY.powf(1.0 / DISPLAY_GAMMA),
+                ],
+                [1.0, 1.0],
+            ],
+        }
+    }
+}
+
+impl ToneCurve {
+    /// Where the scene linear value `scene` is on the input axis, unclamped
+    pub fn input_position(scene: f32) -> f32 {
+        ((scene / MID_GRAY).log2() - MIN_STOPS) / (MAX_STOPS - MIN_STOPS)
+    }
+
+    /// Tonemap the exposed scene linear `scene` value to a display linear one.
+    /// Values below the input axis fade linearly to black, those above it are clipped.
+    pub fn apply(&self, scene: f32) -> f32 {
+        let lowest = MID_GRAY * MIN_STOPS.exp2();
+        let output = if scene < lowest {
+            self.sample(0.0) * scene.max(0.0) / lowest
+        } else {
+            self.sample(Self::input_position(scene))
+        };
+        output.clamp(0.0, 1.0).powf(DISPLAY_GAMMA)
+    }
+
+    /// Height of the curve at `x`, both on the axes of the editor
+    pub fn sample(&self, x: f32) -> f32 {
+        let points = &self.points;
+        let x = x.clamp(0.0, 1.0);
+        let segment = points
+            .windows(2)
+            .position(|pair| x <= pair[1][0])
+            .unwrap_or(points.len() - 2);
+        let ([x0, y0], [x1, y1]) = (points[segment], points[segment + 1]);
+        let width = x1 - x0;
+        if width <= 0.0 {
+            return y1;
+        }
+
+        // Cubic Hermite interpolation
+        let t = (x - x0) / width;
+        let (t2, t3) = (t * t, t * t * t);
+        let (m0, m1) = (self.tangent(segment), self.tangent(segment + 1));
+        (2.0 * t3 - 3.0 * t2 + 1.0) * y0
+            + (t3 - 2.0 * t2 + t) * width * m0
+            + (-2.0 * t3 + 3.0 * t2) * y1
+            + (t3 - t2) * width * m1
+    }
+
+    /// Slope of the straight line from the point `index` to the next one
+    fn secant(&self, index: usize) -> f32 {
+        let ([x0, y0], [x1, y1]) = (self.points[index], self.points[index + 1]);
+        (y1 - y0) / (x1 - x0)
+    }
+
+    /// Fritsch-Butland tangents: the harmonic mean of the slopes on each side,
+    /// flat at the peaks and valleys, which keeps monotonic stretches monotonic
+    fn tangent(&self, index: usize) -> f32 {
+        let last = self.points.len() - 1;
+        if index == 0 {
+            return self.secant(0);
+        }
+        if index == last {
+            return self.secant(last - 1);
+        }
+        let (before, after) = (self.secant(index - 1), self.secant(index));
+        if before * after <= 0.0 {
+            0.0
+        } else {
+            2.0 / (1.0 / before + 1.0 / after)
+        }
+    }
+
+    /// Add a point at `point`, unless there are already `MAX_CURVE_POINTS` or there is no
+    /// room left between its neighbors
+    pub fn insert(&mut self, [x, y]: [f32; 2]) {
+        if self.points.len() >= MAX_CURVE_POINTS {
+            return;
+        }
+        let Some(index) = self.points.iter().position(|point| point[0] > x) else {
+            return;
+        };
+        let index = index.max(1);
+        let (before, after) = (self.points[index - 1][0], self.points[index][0]);
+        if after - before >= 2.0 * MIN_SPACING {
+            let x = x.clamp(before + MIN_SPACING, after - MIN_SPACING);
+            self.points.insert(index, [x, y.clamp(0.0, 1.0)]);
+        }
+    }
+
+    /// Move the point `index` to `point`, between its neighbors.
+    /// The first and last points only move up and down.
+    pub fn move_point(&mut self, index: usize, [x, y]: [f32; 2]) {
+        let last = self.points.len() - 1;
+        if index > last {
+            return;
+        }
+        let x = match index {
+            0 => 0.0,
+            _ if index == last => 1.0,
+            _ => x.clamp(
+                self.points[index - 1][0] + MIN_SPACING,
+                self.points[index + 1][0] - MIN_SPACING,
+            ),
+        };
+        self.points[index] = [x, y.clamp(0.0, 1.0)];
+    }
+
+    /// Remove the point `index`, unless it's the first or the last one
+    pub fn remove(&mut self, index: usize) {
+        if index > 0 && index + 1 < self.points.len() {
+            self.points.remove(index);
+        }
+    }
+
+    /// Points as read from a preset, if they make a valid curve
+    pub fn from_points(points: Vec<[f32; 2]>) -> Option<ToneCurve> {
+        let valid = (2..=MAX_CURVE_POINTS).contains(&points.len())
+            && points.first()?[0] == 0.0
+            && points.last()?[0] == 1.0
+            && points.windows(2).all(|pair| pair[0][0] < pair[1][0])
+            && points.iter().all(|&[_, y]| (0.0..=1.0).contains(&y));
+        valid.then_some(ToneCurve { points })
+    }
+}
@@ -0,0 +1,2762 @@
+//! The application: its state and the messages changing it. The widgets showing it are
+//! built in `view`.
+use iced::theme::Theme;
+use iced::widget::{image, pane_grid};
+use iced::{
+    executor, keyboard, mouse, subscription, window, Application, Command, Element, Event, Point,
+    Settings, Subscription,
+};
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::adaptive_quality::{Quality, QualityController};
+use crate::appearance::{Appearance, ThemeKind};
+use crate::background::ViewerBackground;
+use crate::color_blindness::ColorBlindness;
+use crate::color_pipeline::{
+    scene_to_display, DisplayPipeline, DisplaySettings, DisplayTransform, Dithering, GamutMapping,
+    PipelineStages, TonemapKind, ViewMode, EXPOSURE_RANGE,
+};
+use crate::curve_editor::CurveEdit;
+use crate::difference::DifferenceStats;
+use crate::expression::{Program, DEFAULT_EXPRESSION};
+use crate::gallery::{Gallery, Snapshot};
+use crate::guides::{GuideId, Guides, Orientation};
+use crate::histogram::{Histogram, HistogramSource};
+use crate::i18n::{tr, tr_args, Language, Text};
+use crate::icc::IccProfile;
+use crate::io::{read_image, OutputFormat};
+use crate::keymap::{Action, Keymap};
+use crate::lut::{Lut3d, LutMode};
+use crate::pattern::{
+    BlendSpace, ColorRamp, ColorStop, FractalKind, FractalSettings, GradientAxis,
+};
+use crate::post_process::PassKind;
+use crate::preset::Preset;
+use crate::raymarcher::SdfOperation;
+use crate::render::{
+    parse_dimension, render_scene_linear, AntiAliasing, BlendMode, DimensionError, Framebuffer,
+    Region, RenderBackend, RenderSettings, ResolutionPreset, SceneKind, FRAME_RATE, MAX_DIMENSION,
+};
+use crate::render_stats::{RenderHistory, RenderStats};
+use crate::render_worker::{
+    BucketOrder, CancellationToken, RenderEvent, RenderJob, RenderedTile, PROXY_SCALE,
+};
+use crate::resample::{downscale_rgba, subsample_rgba, upscale_nearest_rgba};
+use crate::save_worker::SaveJob;
+use crate::scopes::ScopeKind;
+use crate::statistics::{count_non_finite, ChannelStats, SampleSize};
+use crate::tone_curve::ToneCurve;
+use crate::undo::UndoHistory;
+use crate::viewer::PointerEvent;
+use crate::{
+    appearance, background, bracket, clipboard, color_picker, console, difference, guides,
+    histogram, i18n, io, post_process, preset, recent_files, render_worker, save_worker, scopes,
+    selection, undo,
+};
+
+mod view;
+
+#[derive(Debug, Clone)]
+pub enum ApplicationMessage {
+    FileNameChanged(String),
+    OutputFormatChanged(OutputFormat),
+    AutoVersionToggled(bool),
+    AovsToggled(bool),
+    SaveFilePressed,
+    SaveFinished(Result<String, String>),
+    SaveNotificationDismissed,
+    CopyPressed,
+    PresetSelected(String),
+    PresetNameChanged(String),
+    SavePresetPressed,
+    OpenPathChanged(String),
+    OpenPressed,
+    FileDropped(PathBuf),
+    RecentFileSelected(String),
+    ConfirmOverwrite,
+    SaveAsNewVersion,
+    CancelSave,
+    RenderPressed,
+    CancelRenderPressed,
+    // Leaves the presentation mode first, then cancels the render
+    EscapePressed,
+    ThemeKindChanged(ThemeKind),
+    LanguageChanged(Language),
+    ThemeBackgroundChanged(f32),
+    AccentInputChanged(String),
+    // Any key that no widget used, looked up in the keymap
+    KeyPressed(keyboard::KeyCode, keyboard::Modifiers),
+    PresentationToggled,
+    HelpToggled,
+    PanesResized(pane_grid::ResizeEvent),
+    ParameterPanelToggled,
+    ResolutionPresetSelected(ResolutionPreset),
+    WidthInputChanged(String),
+    HeightInputChanged(String),
+    ResizePressed,
+    BackendChanged(RenderBackend),
+    GlobalAlphaChanged(f32),
+    ExposureChanged(f32),
+    TemperatureChanged(f32),
+    TintChanged(f32),
+    GammaChanged(f32),
+    DisplayTransformChanged(DisplayTransform),
+    GamutMappingChanged(GamutMapping),
+    ColorBlindnessChanged(ColorBlindness),
+    DitheringChanged(Dithering),
+    LutPathChanged(String),
+    LoadLutPressed,
+    LutCleared,
+    LutModeChanged(LutMode),
+    IccPathChanged(String),
+    LoadIccPressed,
+    IccCleared,
+    TonemapChanged(TonemapKind),
+    ToneCurveEdited(CurveEdit),
+    ToneCurveReset,
+    PostPassAdded(PassKind),
+    PostPassChanged(usize, f32),
+    /// From one place of the stack to another
+    PostPassMoved(usize, usize),
+    PostPassRemoved(usize),
+    LivePreviewToggled(bool),
+    ApplyPressed,
+    SceneChanged(SceneKind),
+    AntiAliasingChanged(AntiAliasing),
+    BucketOrderChanged(BucketOrder),
+    SeedChanged(u32),
+    SeedRandomized,
+    RadialCenterXChanged(f32),
+    RadialCenterYChanged(f32),
+    RadialRadiusChanged(f32),
+    BilinearBlendChanged(f32),
+    // A channel of a gradient stop was typed in: the stop, the channel and the text
+    GradientStopInputChanged(GradientAxis, usize, usize, String),
+    GradientStopPicked(GradientAxis, usize, [f32; 3]),
+    GradientStopMoved(GradientAxis, usize, f32),
+    GradientStopAdded(GradientAxis),
+    GradientStopRemoved(GradientAxis, usize),
+    BlendSpaceChanged(BlendSpace),
+    NoiseFrequencyChanged(f32),
+    NoiseOctavesChanged(u32),
+    NoiseLacunarityChanged(f32),
+    FractalKindChanged(FractalKind),
+    ColorRampChanged(ColorRamp),
+    FractalIterationsChanged(u32),
+    JuliaConstantXChanged(f64),
+    JuliaConstantYChanged(f64),
+    FractalViewReset,
+    FractalPointer(PointerEvent),
+    RegionDrawingToggled(bool),
+    RegionPointer(PointerEvent),
+    RegionCleared,
+    SelectingToggled(bool),
+    SelectionPointer(PointerEvent),
+    SelectionCleared,
+    ExportSelectionPressed,
+    RulersToggled(bool),
+    PixelGridToggled(bool),
+    ThirdsToggled(bool),
+    CenterCrossToggled(bool),
+    SafeAreasToggled(bool),
+    GuideAdded(Orientation),
+    GuidesCleared,
+    MovingGuidesToggled(bool),
+    GuidePointer(PointerEvent),
+    ReferencePathChanged(String),
+    LoadReferencePressed,
+    DifferenceToggled(bool),
+    DifferenceGainChanged(f32),
+    InspectorToggled(bool),
+    SampleSizeChanged(SampleSize),
+    ViewModeChanged(ViewMode),
+    // Shows the view when pressing its shortcut, or goes back to normal if it's already shown
+    ViewShortcutPressed(ViewMode),
+    ZoomSelected(Option<f32>),
+    ZoomStepped(f32),
+    ViewChanged(f32, Point),
+    PinToggled,
+    HistogramToggled(bool),
+    ScopesToggled,
+    StatisticsToggled,
+    ConsoleToggled,
+    TabSelected(usize),
+    UndoPressed,
+    RedoPressed,
+    SnapshotSelected(usize),
+    TabClosed(usize),
+    NewTabPressed,
+    // Normalized position of the cursor over the image, for the status bar
+    CursorMoved(Option<Point>),
+    ConsoleCopyPressed,
+    ConsoleCleared,
+    ViewerBackgroundChanged(ViewerBackground),
+    ScopeKindChanged(ScopeKind),
+    HistogramSourceChanged(HistogramSource),
+    SwapPressed,
+    WipePointer(PointerEvent),
+    ClippingWarningToggled(bool),
+    InspectorPointer(PointerEvent),
+    SamplesPerPixelChanged(u32),
+    MaxBouncesChanged(u32),
+    SdfOperationChanged(SdfOperation),
+    OrbitYawChanged(f32),
+    OrbitPitchChanged(f32),
+    OrbitDistanceChanged(f32),
+    ExpressionChanged(String),
+    PlayToggled,
+    FrameChanged(u32),
+    FrameCountChanged(u32),
+    // A new frame is about to be drawn, while playing
+    Tick(Instant),
+    RenderSequencePressed,
+    AbortSequencePressed,
+    OverlayToggled(bool),
+    OverlaySceneChanged(SceneKind),
+    BlendModeChanged(BlendMode),
+    OverlayOpacityChanged(f32),
+    // A slider that re-renders the scene while dragging was released
+    ScrubFinished,
+    RenderProgressed(RenderEvent),
+}
+
+impl RenderSettings {
+    /// Override the defaults with `RENDER_WIDTH`, `RENDER_HEIGHT`, `RENDER_SCENE`
+    /// and `RENDER_SEED` when they are set and valid
+    fn apply_env(&mut self) {
+        if let Some(width) = env_override("RENDER_WIDTH", parse_dimension) {
+            self.width = width;
+        }
+        if let Some(height) = env_override("RENDER_HEIGHT", parse_dimension) {
+            self.height = height;
+        }
+        if let Some(scene) = env_override("RENDER_SCENE", str::parse) {
+            self.scene = scene;
+        }
+        if let Some(seed) = env_override("RENDER_SEED", str::parse) {
+            self.seed = seed;
+        }
+    }
+}
+
+/// Read and parse the environment variable `name`.
+/// Invalid values are ignored with a warning, so the caller can keep its default.
+fn env_override<T, E: std::fmt::Display>(
+    name: &str,
+    parse: impl Fn(&str) -> Result<T, E>,
+) -> Option<T> {
+    let value = std::env::var(name).ok()?;
+    match parse(&value) {
+        Ok(parsed) => Some(parsed),
+        Err(e) => {
+            console::warning(format!("ignoring {name}={value:?} ({e})"));
+            None
+        }
+    }
+}
+
+/// Dialogs shown on top of everything else, the rest of the UI waits until they're answered
+enum Modal {
+    /// Saving would replace this existing file
+    ConfirmOverwrite { path: String },
+    /// The list of the keyboard shortcuts
+    Shortcuts,
+}
+
+// A framebuffer kept open in a tab, with what it was rendered and viewed with.
+// The active tab's are in the application state instead, these are only kept
+// up to date while another tab is shown.
+struct Tab {
+    name: String,
+    settings: Preset,
+    linear_render_buffer: Framebuffer,
+}
+
+// The two sides of the window
+enum Panel {
+    Viewer,
+    Parameters,
+}
+
+struct ApplicationState {
+    // As typed, the extension is optional
+    file_name: String,
+    output_format: OutputFormat,
+    file_name_with_ext: String,
+    // Whether `file_name_with_ext` already exists on disk
+    save_target_exists: bool,
+    // Whether EXRs also get the albedo, U,V and luminance AOVs, as extra layers
+    write_aovs: bool,
+    // When set, saving picks the next `_v001` style version instead of overwriting
+    auto_version: bool,
+    // The dialog currently open, if any
+    modal: Option<Modal>,
+    // Outcome of the last save, the full path that was written or why it failed,
+    // shown until dismissed
+    save_result: Option<Result<String, String>>,
+    // Saves still being written in the background
+    saves_in_flight: usize,
+    // Outcome of the last copy to the clipboard, until the next save or copy
+    copy_result: Option<Result<(), String>>,
+    rendered_image: image::Handle,
+    // The last completed render
+    linear_render_buffer: Framebuffer,
+    // Tiles of the render in flight, only copied over to `linear_render_buffer` once it
+    // completes, so that a cancelled render leaves the last complete one untouched
+    partial_linear_buffer: Framebuffer,
+    // What is currently shown in `rendered_image`, tiles get copied here as they complete
+    display_buffer: Vec<u8>,
+    render_settings: RenderSettings,
+    // Contents of the resolution fields, only applied when pressing Resize
+    width_input: String,
+    height_input: String,
+    // Contents of the R, G and B fields of each gradient stop, along U then V,
+    // applied as soon as they're valid
+    gradient_stop_inputs: [Vec<[String; 3]>; 2],
+    // The background render in flight, if any
+    render_job: Option<RenderJob>,
+    // How many pixels of the render in flight are done
+    rendered_pixel_count: usize,
+    // Incremented for every render, so that stale results can be ignored
+    render_generation: u64,
+    bucket_order: BucketOrder,
+    // When live preview is off, changes accumulate in `pending_display_settings`
+    // and are only copied over to `applied_display_settings` when pressing Apply
+    live_preview: bool,
+    pending_display_settings: DisplaySettings,
+    applied_display_settings: DisplaySettings,
+    quality_controller: QualityController,
+    // What is typed in the LUT field, and why it couldn't be loaded if it couldn't
+    lut_path_input: String,
+    lut_error: Option<String>,
+    icc_path_input: String,
+    icc_error: Option<String>,
+    // Presets found on disk, the one last loaded or saved, and what is typed as the name
+    // of the next one
+    presets: Vec<String>,
+    selected_preset: Option<String>,
+    preset_name_input: String,
+    preset_error: Option<String>,
+    // What is typed in the open field, and why it couldn't be opened if it couldn't
+    open_path_input: String,
+    open_error: Option<String>,
+    // Full paths of the files last opened or saved, most recent first
+    recent_files: Vec<String>,
+    // Every open framebuffer, the active one included, and how many were ever
+    // opened to name the next one
+    tabs: Vec<Tab>,
+    active_tab: usize,
+    tabs_opened: usize,
+    // The last completed renders, to go back to
+    gallery: Gallery,
+    undo_history: UndoHistory,
+    // Which message last changed the settings and when, to undo the steps of a slider at once
+    last_settings_change: Option<(std::mem::Discriminant<ApplicationMessage>, Instant)>,
+    // Length of the timeline, frames go from 1 to `frame_count`
+    frame_count: u32,
+    playing: bool,
+    // When the frame currently shown started playing
+    last_frame_time: Option<Instant>,
+    // Set while rendering all the frames of the timeline to disk
+    rendering_sequence: bool,
+    // What is typed in the expression field, and why it doesn't compile if it doesn't
+    expression_input: String,
+    expression_error: Option<String>,
+    // When set, renders only update this part of the image, and the rest is dimmed
+    region_of_interest: Option<Region>,
+    // Whether dragging over the image draws the region of interest
+    drawing_region: bool,
+    // A rectangle of the image to look at or export on its own, drawn by dragging while selecting
+    selection: Option<Region>,
+    selecting: bool,
+    // Of the scene linear pixels in the selection, kept up to date with the render
+    selection_stats: Option<ChannelStats>,
+    // Composition overlays, moving_guides to drag the guides placed by hand
+    guides: Guides,
+    // Outlines of the pixels, drawn by the viewer at high zoom
+    show_pixel_grid: bool,
+    moving_guides: bool,
+    dragged_guide: Option<GuideId>,
+    // Whether hovering the image shows the pipeline inspector, and for which pixel
+    inspecting: bool,
+    inspected_pixel: Option<(usize, usize)>,
+    // How many pixels around the inspected one are averaged
+    sample_size: SampleSize,
+    // Center of the fractal view when the current drag started
+    fractal_drag_origin: Option<(f64, f64)>,
+    // Scene linear render pinned as "A", to compare the current one ("B") against,
+    // its display referred pixels, and where the wipe between them is, from 0 to 1
+    pinned_render: Option<Framebuffer>,
+    pinned_display_buffer: Vec<u8>,
+    wipe_position: f32,
+    // Scene linear image to compare the render against, with
+    // the difference shown as a heatmap amplified by 2^difference_gain_stops
+    reference_path_input: String,
+    reference: Option<Framebuffer>,
+    reference_error: Option<String>,
+    show_difference: bool,
+    difference_gain_stops: f32,
+    difference_stats: Option<DifferenceStats>,
+    // The histogram panel, drawn again whenever the image changes while it's shown
+    show_histogram: bool,
+    histogram_source: HistogramSource,
+    histogram_image: Option<image::Handle>,
+    // The scopes panel, which is collapsed (and not computed) until opened
+    show_scopes: bool,
+    scope_kind: ScopeKind,
+    scope_image: Option<image::Handle>,
+    // Statistics of the scene linear image, only computed while their panel is open
+    show_statistics: bool,
+    image_stats: Option<ChannelStats>,
+    // Pixels of the image with a NaN or infinite channel, always counted
+    non_finite_pixels: usize,
+    // The pixel under the cursor, when it's over the image
+    cursor_pixel: Option<(usize, usize)>,
+    // When the render in flight started, and the full path of the last saved file
+    render_started: Instant,
+    last_saved_path: Option<String>,
+    // The log console, collapsed until opened
+    show_console: bool,
+    // Shown behind the transparent pixels
+    viewer_background: ViewerBackground,
+    // Fullscreen, with only the viewer shown
+    presenting: bool,
+    keymap: Keymap,
+    // The split between the viewer and the parameters, whose panel is hidden by
+    // maximizing the viewer
+    panes: pane_grid::State<Panel>,
+    appearance: Appearance,
+    // As typed, the accent color only changes once it's a valid #RRGGBB
+    accent_input: String,
+    // Scale of the image in the viewer, None to fit it, and the point of the image
+    // at the center of the viewer, None to center it
+    zoom: Option<f32>,
+    view_center: Option<Point>,
+    // Stats of the last few full quality renders
+    render_history: RenderHistory,
+}
+
+const FONT_BYTES: &[u8; 283684] = include_bytes!("../media/FiraCode-Medium.ttf");
+const WINDOW_ICON_SIZE: usize = 32;
+
+/// Largest size of the image viewer, in logical pixels
+const VIEWER_SIZE: (f32, f32) = (800.0, 512.0);
+/// Size of the histogram panel, in pixels
+const HISTOGRAM_SIZE: (usize, usize) = (histogram::BINS * 3, 120);
+/// Range of the viewer zoom, as a scale of the image
+const MIN_ZOOM: f32 = 1.0 / 16.0;
+const MAX_ZOOM: f32 = 32.0;
+/// Share of the window's width that the viewer starts with
+const PANEL_SPLIT: f32 = 0.62;
+const CONSOLE_HEIGHT: f32 = 200.0;
+/// How much the exposure shortcuts change it, in stops
+const EXPOSURE_STEP: f32 = 0.5;
+
+/// Render a small version of the startup image to use as the window icon.
+// iced doesn't support changing the icon at runtime, so this is only set once.
+fn render_window_icon() -> Option<window::Icon> {
+    let mut render_settings = RenderSettings::default();
+    render_settings.apply_env();
+
+    // No need to render the full framebuffer just to throw most of it away
+    let scale = 4;
+    render_settings.width = WINDOW_ICON_SIZE * scale;
+    render_settings.height = WINDOW_ICON_SIZE * scale;
+
+    let framebuffer = render_scene_linear(&render_settings);
+    let display_buffer = scene_to_display(&framebuffer, &DisplaySettings::default());
+    let icon_pixels = downscale_rgba(
+        &display_buffer,
+        render_settings.width,
+        render_settings.height,
+        WINDOW_ICON_SIZE,
+        WINDOW_ICON_SIZE,
+    );
+
+    match window::Icon::from_rgba(
+        icon_pixels,
+        WINDOW_ICON_SIZE as u32,
+        WINDOW_ICON_SIZE as u32,
+    ) {
+        Ok(icon) => Some(icon),
+        Err(e) => {
+            console::warning(format!("could not create the window icon ({e})"));
+            None
+        }
+    }
+}
+
+/// The text fields of the channels of the gradient stops, along U then V
+fn stop_inputs(settings: &RenderSettings) -> [Vec<[String; 3]>; 2] {
+    GradientAxis::ALL.map(|axis| {
+        settings
+            .gradient(axis)
+            .stops
+            .iter()
+            .map(|stop| stop.color.map(color_picker::format_channel))
+            .collect()
+    })
+}
+
+// The reason a typed width or height can't be used, in the language of the UI
+fn translate_dimension_error(e: DimensionError) -> String {
+    match e {
+        DimensionError::Zero => tr(Text::MustBeGreaterThanZero).to_string(),
+        DimensionError::TooLarge => tr_args(Text::MustBeAtMost, &[&MAX_DIMENSION]),
+        DimensionError::Invalid(reason) => reason,
+    }
+}
+
+// Creates an image Handle containing the image pixels directly.
+// This function expects the input data to be provided as a Vec<u8> of RGBA pixels.
+fn display_buffer_to_handle(width: usize, height: usize, display_buffer: Vec<u8>) -> image::Handle {
+    image::Handle::from_pixels(width as u32, height as u32, display_buffer)
+}
+
+// Darken the RGBA pixels outside of `region`, to show that they are stale
+fn dim_outside(display_buffer: &mut [u8], width: usize, region: Region) {
+    for (index, pixel) in display_buffer.chunks_exact_mut(4).enumerate() {
+        if !region.contains(index % width, index / width) {
+            for channel in &mut pixel[..3] {
+                *channel /= 3;
+            }
+        }
+    }
+}
+
+// Show `pinned` on the left of `position` (from 0 to 1), with a line at the boundary
+fn wipe(display_buffer: &mut [u8], pinned: &[u8], width: usize, position: f32) {
+    let boundary = (position.clamp(0.0, 1.0) * width as f32).round() as usize;
+    for (index, (pixel, pinned_pixel)) in display_buffer
+        .chunks_exact_mut(4)
+        .zip(pinned.chunks_exact(4))
+        .enumerate()
+    {
+        let x = index % width;
+        if x == boundary {
+            pixel.copy_from_slice(&[255, 255, 255, 255]);
+        } else if x < boundary {
+            pixel.copy_from_slice(pinned_pixel);
+        }
+    }
+}
+
+// The rectangle of pixels between two normalized positions, None if it's empty
+fn region_between(from: Point, to: Point, width: usize, height: usize) -> Option<Region> {
+    let to_pixels =
+        |position: f32, size: usize| (position.clamp(0.0, 1.0) * size as f32).round() as usize;
+    let (left, right) = (
+        to_pixels(from.x.min(to.x), width),
+        to_pixels(from.x.max(to.x), width),
+    );
+    let (top, bottom) = (
+        to_pixels(from.y.min(to.y), height),
+        to_pixels(from.y.max(to.y), height),
+    );
+
+    (right > left && bottom > top).then_some(Region {
+        x: left,
+        y: top,
+        width: right - left,
+        height: bottom - top,
+    })
+}
+
+impl ApplicationState {
+    fn update_image(&mut self) {
+        let (width, height) = (self.render_settings.width, self.render_settings.height);
+        let differences = match &self.reference {
+            Some(reference) if (reference.width(), reference.height()) == (width, height) => Some(
+                difference::per_pixel(self.linear_render_buffer.pixels(), reference.pixels()),
+            ),
+            _ => None,
+        };
+        self.difference_stats = differences.as_deref().map(DifferenceStats::new);
+        let bracketed = self.applied_display_settings.view_mode == ViewMode::Bracket;
+
+        let mut display_buffer = match differences {
+            Some(differences) if self.show_difference => {
+                difference::heatmap(&differences, self.difference_gain_stops.exp2())
+            }
+            _ if bracketed => {
+                bracket::contact_sheet(&self.linear_render_buffer, &self.applied_display_settings)
+            }
+            _ => self.display_buffer.clone(),
+        };
+        // Wiping between two contact sheets would only compare halves of their tiles
+        if self.pinned_render.is_some() && !bracketed {
+            wipe(
+                &mut display_buffer,
+                &self.pinned_display_buffer,
+                self.render_settings.width,
+                self.wipe_position,
+            );
+        }
+        background::composite(
+            &mut display_buffer,
+            self.render_settings.width,
+            self.viewer_background,
+            self.applied_display_settings.display,
+        );
+        if let Some(region) = self.region_of_interest {
+            dim_outside(&mut display_buffer, self.render_settings.width, region);
+        }
+        if let Some(selection) = self.selection {
+            selection::outline(&mut display_buffer, self.render_settings.width, selection);
+        }
+        guides::draw(
+            &mut display_buffer,
+            self.render_settings.width,
+            self.render_settings.height,
+            &self.guides,
+        );
+        self.selection_stats = self.selection.and_then(|selection| {
+            ChannelStats::new(self.linear_render_buffer.crop(selection).pixels())
+        });
+
+        self.rendered_image = display_buffer_to_handle(
+            self.render_settings.width,
+            self.render_settings.height,
+            display_buffer,
+        );
+        self.update_histogram();
+        self.update_scope();
+        self.update_statistics();
+    }
+
+    fn update_statistics(&mut self) {
+        self.non_finite_pixels = count_non_finite(self.linear_render_buffer.pixels());
+        self.image_stats = if self.show_statistics {
+            ChannelStats::new(self.linear_render_buffer.pixels())
+        } else {
+            None
+        };
+    }
+
+    fn update_scope(&mut self) {
+        self.scope_image = self.show_scopes.then(|| {
+            let (width, height, pixels) = scopes::draw(
+                self.scope_kind,
+                &self.display_buffer,
+                self.render_settings.width,
+            );
+            display_buffer_to_handle(width, height, pixels)
+        });
+    }
+
+    fn update_histogram(&mut self) {
+        if !self.show_histogram {
+            self.histogram_image = None;
+            return;
+        }
+
+        let histogram = match self.histogram_source {
+            HistogramSource::Display => Histogram::of_display(&self.display_buffer),
+            HistogramSource::Linear => Histogram::of_linear(self.linear_render_buffer.pixels()),
+        };
+        let (width, height) = HISTOGRAM_SIZE;
+        self.histogram_image = Some(display_buffer_to_handle(
+            width,
+            height,
+            histogram.draw(width, height),
+        ));
+    }
+
+    // Re-run only the display conversion, reusing the last scene linear render
+    fn refresh_display(&mut self) {
+        let settings = &self.applied_display_settings;
+        let processed =
+            post_process::apply_stack(&settings.post_passes, &self.linear_render_buffer);
+        self.display_buffer = scene_to_display(&processed, settings);
+        // Both sides of the wipe are viewed the same way
+        if let Some(pinned_render) = &self.pinned_render {
+            let processed = post_process::apply_stack(&settings.post_passes, pinned_render);
+            self.pinned_display_buffer = scene_to_display(&processed, settings);
+        }
+        self.update_image();
+    }
+
+    // Pin the current render as "A", or stop comparing
+    fn toggle_pin(&mut self) {
+        self.pinned_render = match self.pinned_render {
+            Some(_) => None,
+            None => Some(self.linear_render_buffer.clone()),
+        };
+        self.refresh_display();
+    }
+
+    // Exchange "A" and "B", the pinned render becomes the current one
+    fn swap_pinned(&mut self) {
+        let Some(pinned_render) = &mut self.pinned_render else {
+            return;
+        };
+        std::mem::swap(pinned_render, &mut self.linear_render_buffer);
+        self.refresh_display();
+    }
+
+    // Cheap preview of the pending display settings, at a fraction of the resolution
+    fn refresh_proxy_display(&mut self) {
+        let (width, height) = (self.render_settings.width, self.render_settings.height);
+        let processed = post_process::apply_stack(
+            &self.pending_display_settings.post_passes,
+            &self.linear_render_buffer,
+        );
+        let (proxy_linear_buffer, proxy_width, proxy_height) =
+            subsample_rgba(processed.pixels(), width, height, PROXY_SCALE);
+        let proxy_linear_buffer =
+            Framebuffer::from_pixels(proxy_width, proxy_height, proxy_linear_buffer);
+        let proxy_buffer = scene_to_display(&proxy_linear_buffer, &self.pending_display_settings);
+
+        // Scale it back up so that the viewer keeps the same zoom
+        self.display_buffer =
+            upscale_nearest_rgba(&proxy_buffer, proxy_width, PROXY_SCALE, width, height);
+        self.update_image();
+    }
+
+    fn apply_display_settings(&mut self) {
+        self.applied_display_settings
+            .clone_from(&self.pending_display_settings);
+        self.refresh_display();
+    }
+
+    fn change_display_settings(&mut self, change: impl FnOnce(&mut DisplaySettings)) {
+        change(&mut self.pending_display_settings);
+
+        if self.live_preview {
+            self.apply_display_settings();
+        } else {
+            self.refresh_proxy_display();
+        }
+    }
+
+    // Render the scene again in the background, the tiles show up as they complete
+    fn render(&mut self) {
+        self.start_render(Quality::Full, false);
+    }
+
+    // Replacing the job drops the subscription of the previous one, which stops it
+    fn start_render(&mut self, quality: Quality, scrubbing: bool) {
+        // A region only covers part of the image, which must not keep the tiles of a
+        // cancelled render around it
+        let replaced = self.render_job.take();
+        if let Some(previous_job) = &replaced {
+            previous_job.cancellation.cancel();
+        }
+        if replaced.is_none() || self.region_of_interest.is_some() {
+            self.partial_linear_buffer
+                .clone_from(&self.linear_render_buffer);
+        }
+
+        // A proxy would redraw the whole image, and the region should be cheap enough anyway
+        let (quality, region) = match self.region_of_interest {
+            Some(region) => (Quality::Full, region),
+            None => (
+                quality,
+                Region::full(self.render_settings.width, self.render_settings.height),
+            ),
+        };
+
+        self.render_generation += 1;
+        self.rendered_pixel_count = 0;
+        self.render_started = Instant::now();
+        self.render_job = Some(RenderJob {
+            generation: self.render_generation,
+            render_settings: self.render_settings.clone(),
+            // The pending settings are the ones currently shown
+            display_settings: self.pending_display_settings.clone(),
+            quality,
+            region,
+            bucket_order: self.bucket_order,
+            scrubbing,
+            cancellation: CancellationToken::default(),
+        });
+    }
+
+    // Abort the render in flight, and go back to showing the last completed one
+    fn cancel_render(&mut self) {
+        let Some(job) = self.render_job.take() else {
+            return;
+        };
+        job.cancellation.cancel();
+        console::info("Render cancelled");
+        self.rendering_sequence = false;
+
+        if self.has_pending_changes() {
+            self.refresh_proxy_display();
+        } else {
+            self.refresh_display();
+        }
+    }
+
+    fn receive_render_event(&mut self, event: RenderEvent) -> Command<ApplicationMessage> {
+        match event {
+            RenderEvent::TilesRendered { generation, tiles } => {
+                if generation == self.render_generation {
+                    self.receive_tiles(tiles);
+                }
+                Command::none()
+            }
+            RenderEvent::Finished {
+                generation,
+                render_time,
+            } if generation == self.render_generation => self.finish_render(render_time),
+            RenderEvent::Finished { .. } => Command::none(),
+        }
+    }
+
+    fn receive_tiles(&mut self, tiles: Vec<RenderedTile>) {
+        let width = self.render_settings.width;
+
+        for tile in tiles {
+            self.rendered_pixel_count += tile.region.pixel_count();
+            if let Some(linear_pixels) = &tile.linear_pixels {
+                self.partial_linear_buffer.paste(linear_pixels, tile.region);
+            }
+            tile.region
+                .copy_into(&tile.display_pixels, &mut self.display_buffer, width);
+        }
+
+        self.update_image();
+    }
+
+    fn finish_render(&mut self, render_time: Duration) -> Command<ApplicationMessage> {
+        let Some(job) = self.render_job.take() else {
+            return Command::none();
+        };
+
+        if job.scrubbing {
+            self.quality_controller.record_frame(render_time);
+        }
+
+        // Proxy renders only produce display pixels, so there's nothing new to keep
+        if job.quality == Quality::Full {
+            self.linear_render_buffer
+                .clone_from(&self.partial_linear_buffer);
+            self.render_history.record(RenderStats {
+                width: job.render_settings.width,
+                height: job.render_settings.height,
+                render_time,
+                peak_buffer_bytes: self.buffer_bytes(),
+            });
+            // The frames of a sequence would push everything else out
+            if !job.scrubbing && !self.rendering_sequence {
+                let settings = Preset {
+                    render_settings: job.render_settings.clone(),
+                    display_settings: job.display_settings.clone(),
+                    expression: self.expression_input.clone(),
+                };
+                self.gallery
+                    .record(Snapshot::new(settings, self.linear_render_buffer.clone()));
+            }
+            if !job.scrubbing {
+                console::info(format!(
+                    "Rendered {}x{} in {:.2}s",
+                    job.render_settings.width,
+                    job.render_settings.height,
+                    render_time.as_secs_f64()
+                ));
+                let non_finite_pixels = count_non_finite(self.linear_render_buffer.pixels());
+                if non_finite_pixels > 0 {
+                    console::warning(tr_args(Text::NonFinitePixels, &[&non_finite_pixels]));
+                }
+            }
+        }
+
+        // The display settings might have changed while we were rendering, and the tiles
+        // were shown without the post-processing passes, which need the whole image
+        let unprocessed =
+            job.quality == Quality::Full && !job.display_settings.post_passes.is_empty();
+        if job.display_settings != self.pending_display_settings || unprocessed {
+            if self.has_pending_changes() {
+                self.refresh_proxy_display();
+            } else {
+                self.refresh_display();
+            }
+        }
+
+        if self.rendering_sequence && job.quality == Quality::Full {
+            self.continue_sequence(job.render_settings.frame)
+        } else {
+            Command::none()
+        }
+    }
+
+    // Render all the frames of the timeline, and save each one once it's done
+    fn start_sequence(&mut self) {
+        self.playing = false;
+        self.rendering_sequence = true;
+        console::info(format!("Rendering frames 1 to {}...", self.frame_count));
+        self.change_render_settings(|settings| settings.frame = 1);
+    }
+
+    // Stop after the frames already saved, the frame in flight is dropped
+    fn abort_sequence(&mut self) {
+        let frame = self.render_settings.frame;
+        self.cancel_render();
+        self.rendering_sequence = false;
+        self.update_save_target();
+        console::info(format!("Sequence aborted, {} frames saved", frame - 1));
+    }
+
+    fn continue_sequence(&mut self, finished_frame: u32) -> Command<ApplicationMessage> {
+        let path = format!(
+            "{}.{finished_frame:04}.{}",
+            self.save_target_stem(),
+            self.output_format.extension()
+        );
+        let save = self.save_frame(path);
+
+        if finished_frame < self.frame_count {
+            self.change_render_settings(|settings| settings.frame = finished_frame + 1);
+        } else {
+            self.rendering_sequence = false;
+            self.update_save_target();
+            console::info(format!("Rendered {} frames", self.frame_count));
+        }
+        save
+    }
+
+    // Move on to the next frame when its time has come, and the current one is shown
+    fn advance_playback(&mut self, now: Instant) {
+        let frame_duration = Duration::from_secs_f32(1.0 / FRAME_RATE);
+        let too_early = self
+            .last_frame_time
+            .is_some_and(|last_frame_time| now - last_frame_time < frame_duration);
+        if self.render_job.is_some() || too_early {
+            return;
+        }
+
+        self.last_frame_time = Some(now);
+        let next_frame = self.render_settings.frame % self.frame_count + 1;
+        self.scrub_render_settings(|settings| settings.frame = next_frame);
+    }
+
+    // Reallocate all the framebuffers at the new size, and render into them
+    fn resize(&mut self, width: usize, height: usize) {
+        console::info(format!("Resizing the framebuffer to {width}x{height}"));
+        self.reset_framebuffer(width, height);
+        self.update_image();
+        self.render();
+    }
+
+    // Stop the render in flight and reallocate the buffers, which start out transparent
+    fn reset_framebuffer(&mut self, width: usize, height: usize) {
+        if let Some(job) = self.render_job.take() {
+            job.cancellation.cancel();
+        }
+
+        self.region_of_interest = None;
+        self.selection = None;
+        self.inspected_pixel = None;
+        self.view_center = None;
+        self.pinned_render = None;
+        self.render_settings.width = width;
+        self.render_settings.height = height;
+        self.width_input = width.to_string();
+        self.height_input = height.to_string();
+
+        self.linear_render_buffer = Framebuffer::new(width, height);
+        self.partial_linear_buffer = Framebuffer::new(width, height);
+        self.display_buffer = vec![0; width * height * 4];
+    }
+
+    fn current_preset(&self) -> Preset {
+        Preset {
+            render_settings: self.render_settings.clone(),
+            display_settings: self.pending_display_settings.clone(),
+            expression: self.expression_input.clone(),
+        }
+    }
+
+    fn save_preset(&mut self) {
+        let name = self.preset_name_input.trim().to_owned();
+        if name.is_empty() {
+            self.preset_error = Some(String::from(tr(Text::PresetNameMissing)));
+            return;
+        }
+
+        match preset::save(&name, &self.current_preset()) {
+            Ok(()) => {
+                console::info(format!("Saved the {name} preset"));
+                self.preset_error = None;
+                self.presets = preset::list();
+                self.selected_preset = Some(name);
+            }
+            Err(e) => {
+                console::error(&e);
+                self.preset_error = Some(e);
+            }
+        }
+    }
+
+    // Restore a preset on top of the current settings, and render it
+    fn load_preset(&mut self, name: String) {
+        let preset = match preset::load(&name, self.current_preset()) {
+            Ok(preset) => preset,
+            Err(e) => {
+                console::error(&e);
+                self.preset_error = Some(e);
+                return;
+            }
+        };
+        console::info(format!("Loaded the {name} preset"));
+        self.preset_error = None;
+        self.selected_preset = Some(name);
+
+        self.apply_settings(preset);
+    }
+
+    // Take over the settings of `preset`, rendering again only if it has to
+    fn apply_settings(&mut self, preset: Preset) {
+        let (width, height) = (preset.render_settings.width, preset.render_settings.height);
+        let resized = (width, height) != (self.render_settings.width, self.render_settings.height);
+        let render_changed = preset.render_settings != self.render_settings;
+        self.restore_settings(preset);
+        if resized {
+            self.resize(width, height);
+        } else if render_changed {
+            self.render();
+        } else {
+            self.refresh_display();
+        }
+    }
+
+    // Take over the settings of `preset`, without rendering them
+    fn restore_settings(&mut self, preset: Preset) {
+        let mut render_settings = preset.render_settings;
+        match Program::compile(&preset.expression) {
+            Ok(program) => {
+                self.expression_error = None;
+                render_settings.expression = Arc::new(program);
+            }
+            Err(e) => self.expression_error = Some(e),
+        }
+        self.expression_input = preset.expression;
+        self.gradient_stop_inputs = stop_inputs(&render_settings);
+        self.pending_display_settings = preset.display_settings;
+        self.applied_display_settings
+            .clone_from(&self.pending_display_settings);
+        self.render_settings = render_settings;
+    }
+
+    // Keep a copy of the current framebuffer in a new tab, and switch to it
+    fn new_tab(&mut self) {
+        self.tabs[self.active_tab].settings = self.current_preset();
+        self.tabs[self.active_tab]
+            .linear_render_buffer
+            .clone_from(&self.linear_render_buffer);
+
+        self.tabs_opened += 1;
+        self.tabs.push(Tab {
+            name: format!("Framebuffer {}", self.tabs_opened),
+            settings: self.current_preset(),
+            linear_render_buffer: Framebuffer::default(),
+        });
+        self.active_tab = self.tabs.len() - 1;
+    }
+
+    // Put the current framebuffer away in its tab, and show the one of `index`
+    fn select_tab(&mut self, index: usize) {
+        if index == self.active_tab || index >= self.tabs.len() {
+            return;
+        }
+        // A sequence or the playback would carry on with the other tab's settings
+        if self.rendering_sequence {
+            self.abort_sequence();
+        }
+        self.playing = false;
+
+        let settings = self.current_preset();
+        let active = &mut self.tabs[self.active_tab];
+        active.settings = settings;
+        active.linear_render_buffer = std::mem::take(&mut self.linear_render_buffer);
+
+        self.active_tab = index;
+        // The changes made in the other tab can't be undone in this one
+        self.undo_history.clear();
+        let tab = &mut self.tabs[index];
+        let (settings, linear_render_buffer) = (
+            tab.settings.clone(),
+            std::mem::take(&mut tab.linear_render_buffer),
+        );
+        self.show_framebuffer(settings, linear_render_buffer);
+    }
+
+    // Go back to a render from the gallery, in the current tab
+    fn restore_snapshot(&mut self, index: usize) {
+        let Some(snapshot) = self.gallery.get(index) else {
+            return;
+        };
+        let (settings, linear_render_buffer) =
+            (snapshot.settings.clone(), snapshot.framebuffer.clone());
+        if self.rendering_sequence {
+            self.abort_sequence();
+        }
+        self.playing = false;
+        self.show_framebuffer(settings, linear_render_buffer);
+    }
+
+    // Show an already rendered framebuffer, with the settings it was rendered with
+    fn show_framebuffer(&mut self, settings: Preset, linear_render_buffer: Framebuffer) {
+        let (width, height) = (
+            settings.render_settings.width,
+            settings.render_settings.height,
+        );
+        self.restore_settings(settings);
+        self.reset_framebuffer(width, height);
+        self.partial_linear_buffer.clone_from(&linear_render_buffer);
+        self.linear_render_buffer = linear_render_buffer;
+        self.refresh_display();
+    }
+
+    fn close_tab(&mut self, index: usize) {
+        if self.tabs.len() < 2 || index >= self.tabs.len() {
+            return;
+        }
+        if index == self.active_tab {
+            // Show its neighbor first, the one after it unless it's the last
+            self.select_tab(if index + 1 < self.tabs.len() {
+                index + 1
+            } else {
+                index - 1
+            });
+        }
+        self.tabs.remove(index);
+        if index < self.active_tab {
+            self.active_tab -= 1;
+        }
+    }
+
+    // Show an image from disk instead of the render, until the next render replaces it
+    fn open(&mut self, path: &Path) {
+        match read_image(path) {
+            Ok(framebuffer) => {
+                let (width, height) = (framebuffer.width(), framebuffer.height());
+                console::info(format!("Opened {} ({width}x{height})", path.display()));
+                recent_files::add(&mut self.recent_files, path);
+                self.playing = false;
+                self.reset_framebuffer(width, height);
+                self.linear_render_buffer = framebuffer;
+                self.open_error = None;
+                self.refresh_display();
+            }
+            Err(e) => {
+                console::error(&e);
+                self.open_error = Some(e);
+            }
+        }
+    }
+
+    // Load the image to compare the renders against, which has to be of the same resolution
+    fn load_reference(&mut self, path: &Path) {
+        match read_image(path) {
+            Ok(reference) => {
+                console::info(format!(
+                    "Loaded the reference {} ({}x{})",
+                    path.display(),
+                    reference.width(),
+                    reference.height()
+                ));
+                self.reference = Some(reference);
+                self.reference_error = None;
+                self.show_difference = true;
+            }
+            Err(e) => {
+                console::error(&e);
+                self.reference_error = Some(e);
+            }
+        }
+        self.update_image();
+    }
+
+    // The resolution typed in the width and height fields, if valid
+    fn input_resolution(&self) -> Result<(usize, usize), String> {
+        let width = parse_dimension(&self.width_input)
+            .map_err(|e| tr_args(Text::InvalidWidth, &[&translate_dimension_error(e)]))?;
+        let height = parse_dimension(&self.height_input)
+            .map_err(|e| tr_args(Text::InvalidHeight, &[&translate_dimension_error(e)]))?;
+        Ok((width, height))
+    }
+
+    fn change_render_settings(&mut self, change: impl FnOnce(&mut RenderSettings)) {
+        change(&mut self.render_settings);
+        self.render();
+    }
+
+    // Like `change_render_settings`, but for continuous changes (e.g. dragging a slider):
+    // if frames get too slow we switch to proxy renders until `finish_scrub`
+    fn scrub_render_settings(&mut self, change: impl FnOnce(&mut RenderSettings)) {
+        change(&mut self.render_settings);
+        self.start_render(self.quality_controller.quality(), true);
+    }
+
+    fn finish_scrub(&mut self) {
+        if self.quality_controller.pause() == Quality::Proxy {
+            self.render();
+        }
+    }
+
+    // Clicking zooms in on the clicked point (or out, with the right button),
+    // dragging pans the view and scrolling zooms around the cursor
+    fn handle_fractal_pointer(&mut self, event: PointerEvent) {
+        let aspect_ratio = self.render_settings.width as f32 / self.render_settings.height as f32;
+
+        match event {
+            PointerEvent::Clicked { position, button } => {
+                let factor = match button {
+                    mouse::Button::Left => 0.5,
+                    mouse::Button::Right => 2.0,
+                    _ => return,
+                };
+                self.change_render_settings(|settings| {
+                    let fractal = &mut settings.fractal;
+                    fractal.center = fractal.point_at((position.x, position.y), aspect_ratio);
+                    fractal.span *= factor;
+                });
+            }
+            PointerEvent::Dragged { from, to } => {
+                let origin = *self
+                    .fractal_drag_origin
+                    .get_or_insert(self.render_settings.fractal.center);
+                self.scrub_render_settings(|settings| {
+                    let fractal = &mut settings.fractal;
+                    let span = fractal.span;
+                    // The view moves along with the cursor
+                    fractal.center = (
+                        origin.0 - (to.x - from.x) as f64 * span * aspect_ratio as f64,
+                        origin.1 + (to.y - from.y) as f64 * span,
+                    );
+                });
+            }
+            PointerEvent::DragFinished { .. } => {
+                self.fractal_drag_origin = None;
+                self.finish_scrub();
+            }
+            PointerEvent::Scrolled { position, lines } => {
+                self.change_render_settings(|settings| {
+                    let fractal = &mut settings.fractal;
+                    let anchor = fractal.point_at((position.x, position.y), aspect_ratio);
+                    fractal.zoom_around(anchor, 0.8_f64.powf(lines as f64));
+                });
+            }
+            PointerEvent::Hovered { .. } | PointerEvent::Left => {}
+        }
+    }
+
+    // Dragging draws the region, which is rendered once the button is released
+    fn handle_region_pointer(&mut self, event: PointerEvent) {
+        let (width, height) = (self.render_settings.width, self.render_settings.height);
+
+        match event {
+            PointerEvent::Dragged { from, to } => {
+                self.region_of_interest = region_between(from, to, width, height);
+                self.update_image();
+            }
+            PointerEvent::DragFinished { from, to } => {
+                self.region_of_interest = region_between(from, to, width, height);
+                if let Some(region) = self.region_of_interest {
+                    console::info(format!(
+                        "Rendering the {}x{} region at {},{}",
+                        region.width, region.height, region.x, region.y
+                    ));
+                }
+                self.drawing_region = false;
+                self.render();
+            }
+            PointerEvent::Clicked { .. }
+            | PointerEvent::Scrolled { .. }
+            | PointerEvent::Hovered { .. }
+            | PointerEvent::Left => {}
+        }
+    }
+
+    // Like the region of interest, dragging draws the selection
+    fn handle_selection_pointer(&mut self, event: PointerEvent) {
+        let (width, height) = (self.render_settings.width, self.render_settings.height);
+
+        match event {
+            PointerEvent::Dragged { from, to } => {
+                self.selection = region_between(from, to, width, height);
+                self.update_image();
+            }
+            PointerEvent::DragFinished { from, to } => {
+                self.selection = region_between(from, to, width, height);
+                self.selecting = false;
+                self.update_image();
+            }
+            PointerEvent::Clicked { .. }
+            | PointerEvent::Scrolled { .. }
+            | PointerEvent::Hovered { .. }
+            | PointerEvent::Left => {}
+        }
+    }
+
+    // Dragging from near a guide moves it, dropping it out of the image removes it
+    fn handle_guide_pointer(&mut self, event: PointerEvent) {
+        match event {
+            PointerEvent::Dragged { from, to } => {
+                if self.dragged_guide.is_none() {
+                    self.dragged_guide = self.guides.grab(from);
+                }
+                if let Some(guide) = self.dragged_guide {
+                    self.guides.move_to(guide, to);
+                    self.update_image();
+                }
+            }
+            PointerEvent::DragFinished { to, .. } => {
+                if let Some(guide) = self.dragged_guide.take() {
+                    self.guides.move_to(guide, to);
+                    self.guides.remove_outside();
+                    self.update_image();
+                }
+            }
+            PointerEvent::Clicked { .. }
+            | PointerEvent::Scrolled { .. }
+            | PointerEvent::Hovered { .. }
+            | PointerEvent::Left => {}
+        }
+    }
+
+    // Write the selected pixels alone, next to where the whole image would be saved but
+    // never over an existing file. AOVs would have to be rendered again, so they're left out.
+    fn export_selection(&mut self) -> Command<ApplicationMessage> {
+        let Some(selection) = self.selection else {
+            return Command::none();
+        };
+        let extension = self.output_format.extension();
+        let stem = io::next_versioned_stem(&format!("{}_selection", self.output_stem()), extension);
+
+        let mut job = self.save_job(format!("{stem}.{extension}"));
+        job.framebuffer = self.linear_render_buffer.crop(selection);
+        job.aov_settings = None;
+        job.metadata.push((
+            String::from("selection"),
+            format!(
+                "{}x{} at {},{}",
+                selection.width, selection.height, selection.x, selection.y
+            ),
+        ));
+
+        self.saves_in_flight += 1;
+        Command::perform(job.spawn(), ApplicationMessage::SaveFinished)
+    }
+
+    fn save(&mut self) -> Command<ApplicationMessage> {
+        self.save_frame(self.file_name_with_ext.clone())
+    }
+
+    // The typed file name, without the extension of the output format if it has one
+    fn output_stem(&self) -> &str {
+        let extension = Path::new(&self.file_name).extension();
+        match (OutputFormat::from_path(&self.file_name), extension) {
+            (Some(_), Some(extension)) => {
+                &self.file_name[..self.file_name.len() - extension.len() - 1]
+            }
+            _ => &self.file_name,
+        }
+    }
+
+    // Typing an extension picks the matching format, otherwise the selected one is appended
+    fn set_file_name(&mut self, file_name: String) {
+        if let Some(format) = OutputFormat::from_path(&file_name) {
+            self.output_format = format;
+        }
+        self.file_name = file_name;
+        self.update_save_target();
+        self.save_result = None;
+    }
+
+    // Work out where the next save goes, which is never an existing file when auto versioning
+    fn update_save_target(&mut self) {
+        let extension = self.output_format.extension();
+        let stem = if self.auto_version {
+            io::next_versioned_stem(self.output_stem(), extension)
+        } else {
+            self.output_stem().to_owned()
+        };
+        self.file_name_with_ext = format!("{stem}.{extension}");
+        self.save_target_exists = Path::new(&self.file_name_with_ext).exists();
+    }
+
+    // `file_name_with_ext` without the extension, including the version if auto versioning
+    fn save_target_stem(&self) -> &str {
+        let extension = self.output_format.extension();
+        &self.file_name_with_ext[..self.file_name_with_ext.len() - extension.len() - 1]
+    }
+
+    // Everything needed to write the last completed render to `path`, either scene linear
+    // (before any tonemapping) or converted with the display settings currently shown
+    fn save_job(&self, path: String) -> SaveJob {
+        let aovs = self.write_aovs && self.output_format == OutputFormat::Exr;
+        SaveJob {
+            path,
+            format: self.output_format,
+            framebuffer: self.linear_render_buffer.clone(),
+            display_settings: self.pending_display_settings.clone(),
+            aov_settings: aovs.then(|| self.render_settings.clone()),
+            metadata: save_worker::render_metadata(
+                &self.render_settings,
+                &self.pending_display_settings,
+            ),
+        }
+    }
+
+    // Write the frame in the background, the outcome comes back as a `SaveFinished`
+    fn save_frame(&mut self, path: String) -> Command<ApplicationMessage> {
+        self.saves_in_flight += 1;
+        Command::perform(
+            self.save_job(path).spawn(),
+            ApplicationMessage::SaveFinished,
+        )
+    }
+
+    // Memory taken by all the framebuffers, including the copy in the image handle
+    fn buffer_bytes(&self) -> usize {
+        let linear_bytes = std::mem::size_of_val(self.linear_render_buffer.pixels())
+            + std::mem::size_of_val(self.partial_linear_buffer.pixels());
+        let display_bytes = self.display_buffer.len() * 2;
+        linear_bytes + display_bytes
+    }
+
+    // Fraction of the render in flight that is done, between 0 and 1
+    fn render_progress(&self) -> f32 {
+        let total_pixel_count = match &self.render_job {
+            Some(job) => job.region.pixel_count(),
+            None => self.render_settings.width * self.render_settings.height,
+        };
+        self.rendered_pixel_count as f32 / total_pixel_count as f32
+    }
+
+    // Scale of the image when fitted to the viewer, which never enlarges it
+    fn fit_scale(&self) -> f32 {
+        let (width, height) = (self.render_settings.width, self.render_settings.height);
+        (VIEWER_SIZE.0 / width as f32)
+            .min(VIEWER_SIZE.1 / height as f32)
+            .min(1.0)
+    }
+
+    // Every display stage of the pixel under the cursor, while inspecting
+    // The scene linear values around the inspected pixel are averaged before
+    // going through the display pipeline, as they would be in a smaller image
+    fn inspected_stages(&self) -> Option<(usize, usize, [f32; 4], PipelineStages)> {
+        let (x, y) = self.inspected_pixel.filter(|_| self.inspecting)?;
+        let (width, height) = (self.render_settings.width, self.render_settings.height);
+        let region = self.sample_size.region(x, y, width, height);
+        let sample = ChannelStats::new(self.linear_render_buffer.crop(region).pixels())?.mean;
+        let pipeline = DisplayPipeline::new(&self.pending_display_settings);
+        let stages = pipeline.stages(&sample, x, y);
+        Some((x, y, sample, stages))
+    }
+
+    fn change_appearance(&mut self, change: impl FnOnce(&mut Appearance)) {
+        change(&mut self.appearance);
+        self.appearance.save();
+    }
+
+    // What a keyboard shortcut does right now, if anything
+    fn shortcut_message(&self, action: Action) -> Option<ApplicationMessage> {
+        // The dialogs have their own buttons, only the list of shortcuts is closed with them
+        match (&self.modal, action) {
+            (Some(Modal::Shortcuts), Action::Escape | Action::ToggleHelp) => {
+                return Some(ApplicationMessage::HelpToggled)
+            }
+            (Some(_), _) => return None,
+            (None, _) => (),
+        }
+
+        let view = |view_mode| ApplicationMessage::ViewShortcutPressed(view_mode);
+        let exposure = |step: f32| {
+            let exposure = self.pending_display_settings.exposure + step;
+            ApplicationMessage::ExposureChanged(
+                exposure.clamp(*EXPOSURE_RANGE.start(), *EXPOSURE_RANGE.end()),
+            )
+        };
+        let message = match action {
+            Action::Render => ApplicationMessage::RenderPressed,
+            // Like the button, which waits for the current write
+            Action::Save if self.saves_in_flight > 0 => return None,
+            Action::Save => ApplicationMessage::SaveFilePressed,
+            Action::Undo => ApplicationMessage::UndoPressed,
+            Action::Redo => ApplicationMessage::RedoPressed,
+            Action::Copy => ApplicationMessage::CopyPressed,
+            Action::Escape => ApplicationMessage::EscapePressed,
+            Action::TogglePresentation => ApplicationMessage::PresentationToggled,
+            Action::ToggleParameterPanel => ApplicationMessage::ParameterPanelToggled,
+            Action::ToggleHelp => ApplicationMessage::HelpToggled,
+            Action::ExposureUp => exposure(EXPOSURE_STEP),
+            Action::ExposureDown => exposure(-EXPOSURE_STEP),
+            Action::ShowRed => view(ViewMode::Red),
+            Action::ShowGreen => view(ViewMode::Green),
+            Action::ShowBlue => view(ViewMode::Blue),
+            Action::ShowAlpha => view(ViewMode::Alpha),
+            Action::ShowLuma => view(ViewMode::Luma),
+            Action::ZoomToFit => ApplicationMessage::ZoomSelected(None),
+            Action::Zoom100 => ApplicationMessage::ZoomSelected(Some(1.0)),
+            Action::Zoom200 => ApplicationMessage::ZoomSelected(Some(2.0)),
+            Action::ZoomIn => ApplicationMessage::ZoomStepped(2.0),
+            Action::ZoomOut => ApplicationMessage::ZoomStepped(0.5),
+            Action::SwapAB => ApplicationMessage::SwapPressed,
+        };
+        Some(message)
+    }
+
+    // The state before the first render, a transparent image in a single tab
+    fn initial(
+        file_name: String,
+        render_settings: RenderSettings,
+        display_settings: DisplaySettings,
+    ) -> Self {
+        let (width, height) = (render_settings.width, render_settings.height);
+        let appearance = Appearance::load();
+        let mut state = ApplicationState {
+            // Filled in by `set_file_name`
+            save_target_exists: false,
+            file_name: String::new(),
+            output_format: OutputFormat::Exr,
+            file_name_with_ext: String::new(),
+            write_aovs: false,
+            auto_version: false,
+            modal: None,
+            save_result: None,
+            saves_in_flight: 0,
+            copy_result: None,
+            rendered_image: display_buffer_to_handle(width, height, vec![0; width * height * 4]),
+            linear_render_buffer: Framebuffer::new(width, height),
+            partial_linear_buffer: Framebuffer::new(width, height),
+            display_buffer: vec![0; width * height * 4],
+            width_input: width.to_string(),
+            height_input: height.to_string(),
+            gradient_stop_inputs: stop_inputs(&render_settings),
+            render_settings,
+            render_job: None,
+            rendered_pixel_count: 0,
+            render_generation: 0,
+            bucket_order: BucketOrder::Spiral,
+            live_preview: true,
+            pending_display_settings: display_settings.clone(),
+            applied_display_settings: display_settings,
+            quality_controller: QualityController::new(),
+            lut_path_input: String::new(),
+            lut_error: None,
+            icc_path_input: String::new(),
+            icc_error: None,
+            presets: preset::list(),
+            selected_preset: None,
+            preset_name_input: String::new(),
+            preset_error: None,
+            open_path_input: String::new(),
+            open_error: None,
+            recent_files: recent_files::load(),
+            tabs: Vec::new(),
+            active_tab: 0,
+            tabs_opened: 1,
+            gallery: Gallery::new(),
+            undo_history: UndoHistory::new(),
+            last_settings_change: None,
+            frame_count: 48,
+            playing: false,
+            last_frame_time: None,
+            rendering_sequence: false,
+            expression_input: String::from(DEFAULT_EXPRESSION),
+            expression_error: None,
+            region_of_interest: None,
+            drawing_region: false,
+            selection: None,
+            selecting: false,
+            selection_stats: None,
+            guides: Guides::default(),
+            show_pixel_grid: true,
+            moving_guides: false,
+            dragged_guide: None,
+            inspecting: false,
+            inspected_pixel: None,
+            sample_size: SampleSize::Single,
+            fractal_drag_origin: None,
+            pinned_render: None,
+            pinned_display_buffer: Vec::new(),
+            reference_path_input: String::new(),
+            reference: None,
+            reference_error: None,
+            show_difference: false,
+            difference_gain_stops: 0.0,
+            difference_stats: None,
+            wipe_position: 0.5,
+            show_histogram: false,
+            histogram_source: HistogramSource::Display,
+            histogram_image: None,
+            show_scopes: false,
+            show_statistics: false,
+            image_stats: None,
+            non_finite_pixels: 0,
+            scope_kind: ScopeKind::Waveform,
+            scope_image: None,
+            cursor_pixel: None,
+            render_started: Instant::now(),
+            last_saved_path: None,
+            show_console: false,
+            viewer_background: ViewerBackground::Checkerboard,
+            presenting: false,
+            keymap: Keymap::load(),
+            panes: pane_grid::State::with_configuration(pane_grid::Configuration::Split {
+                axis: pane_grid::Axis::Vertical,
+                ratio: PANEL_SPLIT,
+                a: Box::new(pane_grid::Configuration::Pane(Panel::Viewer)),
+                b: Box::new(pane_grid::Configuration::Pane(Panel::Parameters)),
+            }),
+            appearance,
+            accent_input: appearance::to_hex(appearance.custom.accent),
+            zoom: None,
+            view_center: None,
+            render_history: RenderHistory::new(),
+        };
+        state.set_file_name(file_name);
+        state.tabs.push(Tab {
+            name: String::from("Framebuffer 1"),
+            settings: state.current_preset(),
+            linear_render_buffer: Framebuffer::default(),
+        });
+        state
+    }
+
+    fn has_pending_changes(&self) -> bool {
+        self.pending_display_settings != self.applied_display_settings
+    }
+}
+
+impl Application for ApplicationState {
+    type Executor = executor::Default;
+    type Message = ApplicationMessage;
+    type Theme = Theme;
+    type Flags = ();
+
+    fn new(_flags: ()) -> (Self, Command<Self::Message>) {
+        i18n::set_language(Language::from_env());
+
+        // The environment can override the defaults, e.g. for containerized rendering
+        let file_name = env_override("RENDER_OUTPUT", |value: &str| {
+            if value.is_empty() {
+                Err("empty file name")
+            } else {
+                Ok(value.to_string())
+            }
+        })
+        .unwrap_or_else(|| String::from("sample_file"));
+
+        let mut render_settings = RenderSettings::default();
+        render_settings.apply_env();
+
+        let mut display_settings = DisplaySettings::default();
+
+        // The profile of the monitor can't be queried from the OS, but it can be given at startup
+        let icc_path_input = std::env::var("DISPLAY_ICC_PROFILE").unwrap_or_default();
+        let mut icc_error = None;
+        if !icc_path_input.is_empty() {
+            match IccProfile::load(Path::new(&icc_path_input)) {
+                Ok(profile) => display_settings.icc_profile = Some(Arc::new(profile)),
+                Err(e) => {
+                    console::error(&e);
+                    icc_error = Some(e);
+                }
+            }
+        }
+
+        let mut state = ApplicationState::initial(file_name, render_settings, display_settings);
+        state.icc_path_input = icc_path_input;
+        state.icc_error = icc_error;
+        state.render();
+
+        (state, Command::none())
+    }
+
+    fn title(&self) -> String {
+        String::from("Iced Sample Render image App")
+    }
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        self.layout()
+    }
+
+    // Handle `message`, remembering the settings it changes so they can be undone
+    fn update(&mut self, message: ApplicationMessage) -> Command<Self::Message> {
+        // The timeline playback, switching tabs and undoing itself aren't undoable changes.
+        // Shortcuts are, once they're turned into the message they stand for.
+        let undoable = !matches!(
+            message,
+            ApplicationMessage::Tick(_)
+                | ApplicationMessage::RenderProgressed(_)
+                | ApplicationMessage::KeyPressed(..)
+                | ApplicationMessage::UndoPressed
+                | ApplicationMessage::RedoPressed
+                | ApplicationMessage::TabSelected(_)
+                | ApplicationMessage::TabClosed(_)
+                | ApplicationMessage::NewTabPressed
+        );
+        if !undoable {
+            return self.handle_message(message);
+        }
+
+        let kind = std::mem::discriminant(&message);
+        let before = self.current_preset();
+        let command = self.handle_message(message);
+
+        let changed = before.render_settings != self.render_settings
+            || before.display_settings != self.pending_display_settings;
+        if changed {
+            let now = Instant::now();
+            let merged = self.last_settings_change.is_some_and(|(last_kind, at)| {
+                last_kind == kind && now - at < undo::MERGE_INTERVAL
+            });
+            self.undo_history.record(before, merged);
+            self.last_settings_change = Some((kind, now));
+        }
+        command
+    }
+
+    fn subscription(&self) -> Subscription<Self::Message> {
+        let render = match &self.render_job {
+            Some(job) => {
+                render_worker::render_tiles(job.clone()).map(ApplicationMessage::RenderProgressed)
+            }
+            None => Subscription::none(),
+        };
+
+        // Keys go through the keymap, unless a widget (e.g. the file name input) used them
+        let shortcuts = subscription::events_with(|event, status| match (event, status) {
+            (
+                Event::Keyboard(keyboard::Event::KeyPressed {
+                    key_code,
+                    modifiers,
+                }),
+                iced::event::Status::Ignored,
+            ) => Some(ApplicationMessage::KeyPressed(key_code, modifiers)),
+            // Files dropped onto the window are opened like with the Open button
+            (Event::Window(window::Event::FileDropped(path)), _) => {
+                Some(ApplicationMessage::FileDropped(path))
+            }
+            _ => None,
+        });
+
+        let playback = if self.playing {
+            window::frames().map(ApplicationMessage::Tick)
+        } else {
+            Subscription::none()
+        };
+
+        Subscription::batch([render, shortcuts, playback])
+    }
+
+    fn theme(&self) -> Theme {
+        self.appearance.theme()
+    }
+}
+
+impl ApplicationState {
+    fn handle_message(&mut self, message: ApplicationMessage) -> Command<ApplicationMessage> {
+        match message {
+            ApplicationMessage::RenderPressed => {
+                // Pick a new variation of the gradient, so that each render looks different
+                self.render_settings.bilinear_blend = rand::random();
+                console::info(format!(
+                    "Rendering with a blend factor of {:.2}...",
+                    self.render_settings.bilinear_blend
+                ));
+                self.render();
+                Command::none()
+            }
+            ApplicationMessage::CancelRenderPressed => {
+                self.cancel_render();
+                Command::none()
+            }
+            ApplicationMessage::EscapePressed => {
+                if self.presenting {
+                    self.presenting = false;
+                    window::change_mode(window::Mode::Windowed)
+                } else {
+                    self.cancel_render();
+                    Command::none()
+                }
+            }
+            ApplicationMessage::KeyPressed(key_code, modifiers) => {
+                let message = self
+                    .keymap
+                    .action(key_code, modifiers)
+                    .and_then(|action| self.shortcut_message(action));
+                match message {
+                    Some(message) => self.update(message),
+                    None => Command::none(),
+                }
+            }
+            ApplicationMessage::LanguageChanged(language) => {
+                i18n::set_language(language);
+                Command::none()
+            }
+            ApplicationMessage::ThemeKindChanged(kind) => {
+                self.change_appearance(|appearance| appearance.kind = kind);
+                Command::none()
+            }
+            ApplicationMessage::ThemeBackgroundChanged(background) => {
+                self.change_appearance(|appearance| appearance.custom.background = background);
+                Command::none()
+            }
+            ApplicationMessage::AccentInputChanged(accent) => {
+                if let Some(color) = appearance::parse_hex(&accent) {
+                    self.change_appearance(|appearance| appearance.custom.accent = color);
+                }
+                self.accent_input = accent;
+                Command::none()
+            }
+            ApplicationMessage::PanesResized(pane_grid::ResizeEvent { split, ratio }) => {
+                self.panes.resize(&split, ratio);
+                Command::none()
+            }
+            ApplicationMessage::ParameterPanelToggled => {
+                if self.panes.maximized().is_some() {
+                    self.panes.restore();
+                } else {
+                    let viewer = self
+                        .panes
+                        .iter()
+                        .find(|(_, panel)| matches!(panel, Panel::Viewer))
+                        .map(|(pane, _)| *pane);
+                    if let Some(viewer) = viewer {
+                        self.panes.maximize(&viewer);
+                    }
+                }
+                Command::none()
+            }
+            ApplicationMessage::HelpToggled => {
+                self.modal = match self.modal {
+                    Some(Modal::Shortcuts) => None,
+                    _ => Some(Modal::Shortcuts),
+                };
+                Command::none()
+            }
+            ApplicationMessage::PresentationToggled => {
+                self.presenting = !self.presenting;
+                window::change_mode(if self.presenting {
+                    window::Mode::Fullscreen
+                } else {
+                    window::Mode::Windowed
+                })
+            }
+            ApplicationMessage::ResolutionPresetSelected(preset) => {
+                let (width, height) = preset.size();
+                self.resize(width, height);
+                Command::none()
+            }
+            ApplicationMessage::WidthInputChanged(width) => {
+                self.width_input = width;
+                Command::none()
+            }
+            ApplicationMessage::HeightInputChanged(height) => {
+                self.height_input = height;
+                Command::none()
+            }
+            ApplicationMessage::ResizePressed => {
+                match self.input_resolution() {
+                    Ok((width, height)) => self.resize(width, height),
+                    Err(e) => console::warning(format!("Not resizing: {e}")),
+                }
+                Command::none()
+            }
+            ApplicationMessage::BackendChanged(backend) => {
+                self.change_render_settings(|settings| settings.backend = backend);
+                Command::none()
+            }
+            ApplicationMessage::PlayToggled => {
+                self.playing = !self.playing;
+                if !self.playing {
+                    self.last_frame_time = None;
+                    self.finish_scrub();
+                }
+                Command::none()
+            }
+            ApplicationMessage::FrameChanged(frame) => {
+                self.scrub_render_settings(|settings| settings.frame = frame);
+                Command::none()
+            }
+            ApplicationMessage::FrameCountChanged(frame_count) => {
+                self.frame_count = frame_count;
+                if self.render_settings.frame > frame_count {
+                    self.change_render_settings(|settings| settings.frame = frame_count);
+                }
+                Command::none()
+            }
+            ApplicationMessage::Tick(now) => {
+                if self.playing {
+                    self.advance_playback(now);
+                }
+                Command::none()
+            }
+            ApplicationMessage::AbortSequencePressed => {
+                self.abort_sequence();
+                Command::none()
+            }
+            ApplicationMessage::RenderSequencePressed => {
+                self.start_sequence();
+                Command::none()
+            }
+            ApplicationMessage::FileNameChanged(new_name) => {
+                console::info(format!("New name: {new_name}"));
+                self.set_file_name(new_name);
+                console::info(format!("New file name: {}", self.file_name_with_ext));
+                Command::none()
+            }
+            ApplicationMessage::OutputFormatChanged(format) => {
+                // Keep the typed extension in sync, so that it doesn't pick the old format back
+                let file_name = match OutputFormat::from_path(&self.file_name) {
+                    Some(_) => format!("{}.{}", self.output_stem(), format.extension()),
+                    None => self.file_name.clone(),
+                };
+                self.output_format = format;
+                self.set_file_name(file_name);
+                Command::none()
+            }
+            ApplicationMessage::AovsToggled(write_aovs) => {
+                self.write_aovs = write_aovs;
+                Command::none()
+            }
+            ApplicationMessage::AutoVersionToggled(auto_version) => {
+                self.auto_version = auto_version;
+                self.update_save_target();
+                Command::none()
+            }
+            ApplicationMessage::PresetSelected(name) => {
+                self.load_preset(name);
+                Command::none()
+            }
+            ApplicationMessage::PresetNameChanged(name) => {
+                self.preset_name_input = name;
+                Command::none()
+            }
+            ApplicationMessage::SavePresetPressed => {
+                self.save_preset();
+                Command::none()
+            }
+            ApplicationMessage::OpenPathChanged(path) => {
+                self.open_path_input = path;
+                Command::none()
+            }
+            ApplicationMessage::OpenPressed => {
+                let path = self.open_path_input.trim().to_owned();
+                self.open(Path::new(&path));
+                Command::none()
+            }
+            ApplicationMessage::FileDropped(path) => {
+                self.open_path_input = path.display().to_string();
+                self.open(&path);
+                Command::none()
+            }
+            ApplicationMessage::RecentFileSelected(path) => {
+                self.open(Path::new(&path));
+                self.open_path_input = path;
+                Command::none()
+            }
+            ApplicationMessage::SaveFilePressed => {
+                if Path::new(&self.file_name_with_ext).exists() {
+                    self.modal = Some(Modal::ConfirmOverwrite {
+                        path: self.file_name_with_ext.clone(),
+                    });
+                    Command::none()
+                } else {
+                    self.save()
+                }
+            }
+            ApplicationMessage::ConfirmOverwrite => {
+                self.modal = None;
+                self.save()
+            }
+            ApplicationMessage::SaveAsNewVersion => {
+                self.modal = None;
+                let extension = self.output_format.extension();
+                let stem = io::next_versioned_stem(self.save_target_stem(), extension);
+                self.save_frame(format!("{stem}.{extension}"))
+            }
+            ApplicationMessage::SaveFinished(result) => {
+                self.saves_in_flight -= 1;
+                if let Ok(path) = &result {
+                    console::info(format!("Saved {path}"));
+                    self.last_saved_path = Some(path.clone());
+                    recent_files::add(&mut self.recent_files, Path::new(path));
+                }
+                self.save_result = Some(result);
+                self.copy_result = None;
+                // The versions of a sequence stay the same until it's done
+                if !self.rendering_sequence {
+                    self.update_save_target();
+                }
+                Command::none()
+            }
+            ApplicationMessage::SaveNotificationDismissed => {
+                self.save_result = None;
+                self.copy_result = None;
+                Command::none()
+            }
+            ApplicationMessage::CopyPressed => {
+                let (width, height) = (self.render_settings.width, self.render_settings.height);
+                let result = clipboard::copy_image(width, height, &self.display_buffer);
+                if let Err(e) = &result {
+                    console::error(e);
+                }
+                self.copy_result = Some(result);
+                self.save_result = None;
+                Command::none()
+            }
+            ApplicationMessage::CancelSave => {
+                self.modal = None;
+                Command::none()
+            }
+            ApplicationMessage::GlobalAlphaChanged(alpha) => {
+                self.change_display_settings(|settings| settings.global_alpha = alpha);
+                Command::none()
+            }
+            ApplicationMessage::TemperatureChanged(temperature) => {
+                self.change_display_settings(|settings| {
+                    settings.white_balance.temperature = temperature
+                });
+                Command::none()
+            }
+            ApplicationMessage::TintChanged(tint) => {
+                self.change_display_settings(|settings| settings.white_balance.tint = tint);
+                Command::none()
+            }
+            ApplicationMessage::ExposureChanged(exposure) => {
+                self.change_display_settings(|settings| settings.exposure = exposure);
+                Command::none()
+            }
+            ApplicationMessage::GammaChanged(gamma) => {
+                self.change_display_settings(|settings| settings.gamma = gamma);
+                Command::none()
+            }
+            ApplicationMessage::DisplayTransformChanged(display) => {
+                self.change_display_settings(|settings| settings.display = display);
+                Command::none()
+            }
+            ApplicationMessage::DitheringChanged(dithering) => {
+                self.change_display_settings(|settings| settings.dithering = dithering);
+                Command::none()
+            }
+            ApplicationMessage::ColorBlindnessChanged(color_blindness) => {
+                self.change_display_settings(|settings| settings.color_blindness = color_blindness);
+                Command::none()
+            }
+            ApplicationMessage::GamutMappingChanged(gamut_mapping) => {
+                self.change_display_settings(|settings| settings.gamut_mapping = gamut_mapping);
+                Command::none()
+            }
+            ApplicationMessage::LutPathChanged(path) => {
+                self.lut_path_input = path;
+                Command::none()
+            }
+            ApplicationMessage::LoadLutPressed => {
+                match Lut3d::load(Path::new(self.lut_path_input.trim())) {
+                    Ok(lut) => {
+                        console::info(format!("Loaded the {} LUT", lut.name));
+                        self.lut_error = None;
+                        self.change_display_settings(|settings| settings.lut = Some(Arc::new(lut)));
+                    }
+                    Err(e) => {
+                        console::error(&e);
+                        self.lut_error = Some(e);
+                    }
+                }
+                Command::none()
+            }
+            ApplicationMessage::LutCleared => {
+                self.lut_error = None;
+                self.change_display_settings(|settings| settings.lut = None);
+                Command::none()
+            }
+            ApplicationMessage::IccPathChanged(path) => {
+                self.icc_path_input = path;
+                Command::none()
+            }
+            ApplicationMessage::LoadIccPressed => {
+                match IccProfile::load(Path::new(self.icc_path_input.trim())) {
+                    Ok(profile) => {
+                        console::info(format!("Loaded the {} ICC profile", profile.name));
+                        self.icc_error = None;
+                        self.change_display_settings(|settings| {
+                            settings.icc_profile = Some(Arc::new(profile))
+                        });
+                    }
+                    Err(e) => {
+                        console::error(&e);
+                        self.icc_error = Some(e);
+                    }
+                }
+                Command::none()
+            }
+            ApplicationMessage::IccCleared => {
+                self.icc_error = None;
+                self.change_display_settings(|settings| settings.icc_profile = None);
+                Command::none()
+            }
+            ApplicationMessage::LutModeChanged(lut_mode) => {
+                self.change_display_settings(|settings| settings.lut_mode = lut_mode);
+                Command::none()
+            }
+            ApplicationMessage::TonemapChanged(tonemap) => {
+                self.change_display_settings(|settings| settings.tonemap = tonemap);
+                Command::none()
+            }
+            ApplicationMessage::ToneCurveEdited(edit) => {
+                self.change_display_settings(|settings| {
+                    let curve = &mut settings.tone_curve;
+                    match edit {
+                        CurveEdit::Added(position) => curve.insert(position),
+                        CurveEdit::Moved { index, position } => curve.move_point(index, position),
+                        CurveEdit::Removed(index) => curve.remove(index),
+                    }
+                });
+                Command::none()
+            }
+            ApplicationMessage::ToneCurveReset => {
+                self.change_display_settings(|settings| settings.tone_curve = ToneCurve::default());
+                Command::none()
+            }
+            ApplicationMessage::PostPassAdded(kind) => {
+                self.change_display_settings(|settings| {
+                    settings.post_passes.push(kind.default_pass())
+                });
+                Command::none()
+            }
+            ApplicationMessage::PostPassChanged(index, amount) => {
+                self.change_display_settings(|settings| {
+                    if let Some(pass) = settings.post_passes.get_mut(index) {
+                        *pass = pass.kind().with_amount(amount);
+                    }
+                });
+                Command::none()
+            }
+            ApplicationMessage::PostPassMoved(from, to) => {
+                self.change_display_settings(|settings| {
+                    if from < settings.post_passes.len() && to < settings.post_passes.len() {
+                        let pass = settings.post_passes.remove(from);
+                        settings.post_passes.insert(to, pass);
+                    }
+                });
+                Command::none()
+            }
+            ApplicationMessage::PostPassRemoved(index) => {
+                self.change_display_settings(|settings| {
+                    if index < settings.post_passes.len() {
+                        settings.post_passes.remove(index);
+                    }
+                });
+                Command::none()
+            }
+            ApplicationMessage::LivePreviewToggled(live_preview) => {
+                self.live_preview = live_preview;
+                if live_preview && self.has_pending_changes() {
+                    self.apply_display_settings();
+                }
+                Command::none()
+            }
+            ApplicationMessage::ApplyPressed => {
+                self.apply_display_settings();
+                Command::none()
+            }
+            ApplicationMessage::SceneChanged(scene) => {
+                self.change_render_settings(|settings| settings.scene = scene);
+                Command::none()
+            }
+            ApplicationMessage::RadialCenterXChanged(x) => {
+                self.scrub_render_settings(|settings| settings.radial_center.0 = x);
+                Command::none()
+            }
+            ApplicationMessage::RadialCenterYChanged(y) => {
+                self.scrub_render_settings(|settings| settings.radial_center.1 = y);
+                Command::none()
+            }
+            ApplicationMessage::BilinearBlendChanged(blend) => {
+                self.scrub_render_settings(|settings| settings.bilinear_blend = blend);
+                Command::none()
+            }
+            ApplicationMessage::GradientStopInputChanged(axis, index, channel, input) => {
+                let value = color_picker::parse_channel(&input);
+                self.gradient_stop_inputs[axis as usize][index][channel] = input;
+                if let Ok(value) = value {
+                    self.change_render_settings(|settings| {
+                        settings.gradient_mut(axis).stops[index].color[channel] = value
+                    });
+                }
+                Command::none()
+            }
+            ApplicationMessage::GradientStopPicked(axis, index, color) => {
+                self.gradient_stop_inputs[axis as usize][index] =
+                    color.map(color_picker::format_channel);
+                self.change_render_settings(|settings| {
+                    settings.gradient_mut(axis).stops[index].color = color
+                });
+                Command::none()
+            }
+            ApplicationMessage::GradientStopMoved(axis, index, position) => {
+                self.scrub_render_settings(|settings| {
+                    settings.gradient_mut(axis).stops[index].position = position
+                });
+                Command::none()
+            }
+            ApplicationMessage::GradientStopAdded(axis) => {
+                // In the middle of the widest gap, with the color already shown there
+                let gradient = self.render_settings.gradient(axis).sorted();
+                let gap = |pair: &[ColorStop]| pair[1].position - pair[0].position;
+                let position = gradient
+                    .stops
+                    .windows(2)
+                    .max_by(|a, b| gap(a).total_cmp(&gap(b)))
+                    .map_or(0.5, |pair| (pair[0].position + pair[1].position) / 2.0);
+                let color = gradient.sample(position, self.render_settings.blend_space);
+                let stop = ColorStop {
+                    position,
+                    color: [color.r, color.g, color.b],
+                };
+
+                self.gradient_stop_inputs[axis as usize]
+                    .push(stop.color.map(color_picker::format_channel));
+                self.change_render_settings(|settings| {
+                    settings.gradient_mut(axis).stops.push(stop)
+                });
+                Command::none()
+            }
+            ApplicationMessage::GradientStopRemoved(axis, index) => {
+                self.gradient_stop_inputs[axis as usize].remove(index);
+                self.change_render_settings(|settings| {
+                    settings.gradient_mut(axis).stops.remove(index);
+                });
+                Command::none()
+            }
+            ApplicationMessage::BlendSpaceChanged(blend_space) => {
+                self.change_render_settings(|settings| settings.blend_space = blend_space);
+                Command::none()
+            }
+            ApplicationMessage::RadialRadiusChanged(radius) => {
+                self.scrub_render_settings(|settings| settings.radial_radius = radius);
+                Command::none()
+            }
+            ApplicationMessage::NoiseFrequencyChanged(frequency) => {
+                self.scrub_render_settings(|settings| settings.noise.frequency = frequency);
+                Command::none()
+            }
+            ApplicationMessage::NoiseOctavesChanged(octaves) => {
+                self.scrub_render_settings(|settings| settings.noise.octaves = octaves);
+                Command::none()
+            }
+            ApplicationMessage::NoiseLacunarityChanged(lacunarity) => {
+                self.scrub_render_settings(|settings| settings.noise.lacunarity = lacunarity);
+                Command::none()
+            }
+            ApplicationMessage::SeedChanged(seed) => {
+                self.change_render_settings(|settings| settings.seed = seed);
+                Command::none()
+            }
+            ApplicationMessage::SeedRandomized => {
+                let seed: u32 = rand::random();
+                console::info(format!("Rendering with seed {seed}..."));
+                self.change_render_settings(|settings| settings.seed = seed);
+                Command::none()
+            }
+            ApplicationMessage::FractalKindChanged(kind) => {
+                // Each set lives around a different point, start from its default view
+                self.change_render_settings(|settings| {
+                    settings.fractal = FractalSettings {
+                        ramp: settings.fractal.ramp,
+                        max_iterations: settings.fractal.max_iterations,
+                        ..FractalSettings::new(kind)
+                    }
+                });
+                Command::none()
+            }
+            ApplicationMessage::ColorRampChanged(ramp) => {
+                self.change_render_settings(|settings| settings.fractal.ramp = ramp);
+                Command::none()
+            }
+            ApplicationMessage::FractalIterationsChanged(iterations) => {
+                self.scrub_render_settings(|settings| settings.fractal.max_iterations = iterations);
+                Command::none()
+            }
+            ApplicationMessage::JuliaConstantXChanged(x) => {
+                self.scrub_render_settings(|settings| settings.fractal.julia_constant.0 = x);
+                Command::none()
+            }
+            ApplicationMessage::JuliaConstantYChanged(y) => {
+                self.scrub_render_settings(|settings| settings.fractal.julia_constant.1 = y);
+                Command::none()
+            }
+            ApplicationMessage::FractalViewReset => {
+                self.change_render_settings(|settings| {
+                    let fractal = &mut settings.fractal;
+                    let default_view = FractalSettings::new(fractal.kind);
+                    fractal.center = default_view.center;
+                    fractal.span = default_view.span;
+                });
+                Command::none()
+            }
+            ApplicationMessage::FractalPointer(event) => {
+                self.handle_fractal_pointer(event);
+                Command::none()
+            }
+            ApplicationMessage::RegionDrawingToggled(drawing) => {
+                self.drawing_region = drawing;
+                self.selecting &= !drawing;
+                self.moving_guides &= !drawing;
+                Command::none()
+            }
+            ApplicationMessage::SelectingToggled(selecting) => {
+                self.selecting = selecting;
+                self.drawing_region &= !selecting;
+                self.moving_guides &= !selecting;
+                Command::none()
+            }
+            ApplicationMessage::SelectionPointer(event) => {
+                self.handle_selection_pointer(event);
+                Command::none()
+            }
+            ApplicationMessage::SelectionCleared => {
+                self.selection = None;
+                self.update_image();
+                Command::none()
+            }
+            ApplicationMessage::ExportSelectionPressed => self.export_selection(),
+            ApplicationMessage::RulersToggled(shown) => {
+                self.guides.rulers = shown;
+                self.update_image();
+                Command::none()
+            }
+            ApplicationMessage::PixelGridToggled(shown) => {
+                self.show_pixel_grid = shown;
+                Command::none()
+            }
+            ApplicationMessage::ThirdsToggled(shown) => {
+                self.guides.thirds = shown;
+                self.update_image();
+                Command::none()
+            }
+            ApplicationMessage::CenterCrossToggled(shown) => {
+                self.guides.center = shown;
+                self.update_image();
+                Command::none()
+            }
+            ApplicationMessage::SafeAreasToggled(shown) => {
+                self.guides.safe_areas = shown;
+                self.update_image();
+                Command::none()
+            }
+            ApplicationMessage::GuideAdded(orientation) => {
+                self.guides.add(orientation);
+                self.update_image();
+                Command::none()
+            }
+            ApplicationMessage::GuidesCleared => {
+                self.guides.clear();
+                self.update_image();
+                Command::none()
+            }
+            ApplicationMessage::MovingGuidesToggled(moving) => {
+                self.moving_guides = moving;
+                self.drawing_region &= !moving;
+                self.selecting &= !moving;
+                Command::none()
+            }
+            ApplicationMessage::GuidePointer(event) => {
+                self.handle_guide_pointer(event);
+                Command::none()
+            }
+            ApplicationMessage::ReferencePathChanged(path) => {
+                self.reference_path_input = path;
+                Command::none()
+            }
+            ApplicationMessage::LoadReferencePressed => {
+                let path = self.reference_path_input.trim().to_owned();
+                self.load_reference(Path::new(&path));
+                Command::none()
+            }
+            ApplicationMessage::DifferenceToggled(shown) => {
+                self.show_difference = shown;
+                self.update_image();
+                Command::none()
+            }
+            ApplicationMessage::DifferenceGainChanged(stops) => {
+                self.difference_gain_stops = stops;
+                self.update_image();
+                Command::none()
+            }
+            ApplicationMessage::HistogramToggled(show_histogram) => {
+                self.show_histogram = show_histogram;
+                self.update_histogram();
+                Command::none()
+            }
+            ApplicationMessage::ScopesToggled => {
+                self.show_scopes = !self.show_scopes;
+                self.update_scope();
+                Command::none()
+            }
+            ApplicationMessage::StatisticsToggled => {
+                self.show_statistics = !self.show_statistics;
+                self.update_statistics();
+                Command::none()
+            }
+            ApplicationMessage::CursorMoved(position) => {
+                let (width, height) = (self.render_settings.width, self.render_settings.height);
+                self.cursor_pixel = position.map(|position| {
+                    (
+                        ((position.x * width as f32) as usize).min(width - 1),
+                        ((position.y * height as f32) as usize).min(height - 1),
+                    )
+                });
+                Command::none()
+            }
+            ApplicationMessage::SnapshotSelected(index) => {
+                self.restore_snapshot(index);
+                Command::none()
+            }
+            ApplicationMessage::UndoPressed => {
+                if let Some(settings) = self.undo_history.undo(self.current_preset()) {
+                    self.apply_settings(settings);
+                }
+                self.last_settings_change = None;
+                Command::none()
+            }
+            ApplicationMessage::RedoPressed => {
+                if let Some(settings) = self.undo_history.redo(self.current_preset()) {
+                    self.apply_settings(settings);
+                }
+                self.last_settings_change = None;
+                Command::none()
+            }
+            ApplicationMessage::TabSelected(index) => {
+                self.select_tab(index);
+                Command::none()
+            }
+            ApplicationMessage::TabClosed(index) => {
+                self.close_tab(index);
+                Command::none()
+            }
+            ApplicationMessage::NewTabPressed => {
+                self.new_tab();
+                Command::none()
+            }
+            ApplicationMessage::ConsoleToggled => {
+                self.show_console = !self.show_console;
+                Command::none()
+            }
+            ApplicationMessage::ConsoleCopyPressed => {
+                let lines: Vec<String> = console::entries()
+                    .iter()
+                    .map(|entry| entry.to_string())
+                    .collect();
+                iced::clipboard::write(lines.join("\n"))
+            }
+            ApplicationMessage::ConsoleCleared => {
+                console::clear();
+                Command::none()
+            }
+            ApplicationMessage::ViewerBackgroundChanged(background) => {
+                self.viewer_background = background;
+                self.update_image();
+                Command::none()
+            }
+            ApplicationMessage::ScopeKindChanged(kind) => {
+                self.scope_kind = kind;
+                self.update_scope();
+                Command::none()
+            }
+            ApplicationMessage::HistogramSourceChanged(source) => {
+                self.histogram_source = source;
+                self.update_histogram();
+                Command::none()
+            }
+            ApplicationMessage::PinToggled => {
+                self.toggle_pin();
+                Command::none()
+            }
+            ApplicationMessage::SwapPressed => {
+                self.swap_pinned();
+                Command::none()
+            }
+            ApplicationMessage::WipePointer(
+                PointerEvent::Clicked { position, .. } | PointerEvent::Dragged { to: position, .. },
+            ) => {
+                self.wipe_position = position.x.clamp(0.0, 1.0);
+                self.update_image();
+                Command::none()
+            }
+            ApplicationMessage::WipePointer(_) => Command::none(),
+            ApplicationMessage::RegionPointer(event) => {
+                self.handle_region_pointer(event);
+                Command::none()
+            }
+            ApplicationMessage::ClippingWarningToggled(clipping_warning) => {
+                self.change_display_settings(|settings| {
+                    settings.clipping_warning = clipping_warning
+                });
+                Command::none()
+            }
+            ApplicationMessage::ZoomSelected(zoom) => {
+                self.zoom = zoom;
+                if zoom.is_none() {
+                    self.view_center = None;
+                }
+                Command::none()
+            }
+            ApplicationMessage::ViewChanged(zoom, center) => {
+                self.zoom = Some(zoom);
+                self.view_center = Some(center);
+                Command::none()
+            }
+            ApplicationMessage::ZoomStepped(factor) => {
+                let zoom = self.zoom.unwrap_or_else(|| self.fit_scale()) * factor;
+                self.zoom = Some(zoom.clamp(MIN_ZOOM, MAX_ZOOM));
+                Command::none()
+            }
+            ApplicationMessage::ViewShortcutPressed(view_mode) => {
+                let view_mode = if self.pending_display_settings.view_mode == view_mode {
+                    ViewMode::Normal
+                } else {
+                    view_mode
+                };
+                self.change_display_settings(|settings| settings.view_mode = view_mode);
+                Command::none()
+            }
+            ApplicationMessage::ViewModeChanged(view_mode) => {
+                self.change_display_settings(|settings| settings.view_mode = view_mode);
+                Command::none()
+            }
+            ApplicationMessage::SampleSizeChanged(size) => {
+                self.sample_size = size;
+                Command::none()
+            }
+            ApplicationMessage::InspectorToggled(inspecting) => {
+                self.inspecting = inspecting;
+                Command::none()
+            }
+            ApplicationMessage::InspectorPointer(PointerEvent::Hovered { position }) => {
+                let (width, height) = (self.render_settings.width, self.render_settings.height);
+                let to_pixel =
+                    |position: f32, size: usize| ((position * size as f32) as usize).min(size - 1);
+                self.inspected_pixel =
+                    Some((to_pixel(position.x, width), to_pixel(position.y, height)));
+                Command::none()
+            }
+            ApplicationMessage::InspectorPointer(PointerEvent::Left) => {
+                self.inspected_pixel = None;
+                Command::none()
+            }
+            ApplicationMessage::InspectorPointer(_) => Command::none(),
+            ApplicationMessage::RegionCleared => {
+                self.region_of_interest = None;
+                self.render();
+                Command::none()
+            }
+            ApplicationMessage::SamplesPerPixelChanged(samples) => {
+                self.scrub_render_settings(|settings| {
+                    settings.path_tracer.samples_per_pixel = samples
+                });
+                Command::none()
+            }
+            ApplicationMessage::MaxBouncesChanged(bounces) => {
+                self.scrub_render_settings(|settings| settings.path_tracer.max_bounces = bounces);
+                Command::none()
+            }
+            ApplicationMessage::SdfOperationChanged(operation) => {
+                self.change_render_settings(|settings| settings.sdf.operation = operation);
+                Command::none()
+            }
+            ApplicationMessage::OrbitYawChanged(yaw) => {
+                self.scrub_render_settings(|settings| settings.sdf.orbit_yaw = yaw);
+                Command::none()
+            }
+            ApplicationMessage::OrbitPitchChanged(pitch) => {
+                self.scrub_render_settings(|settings| settings.sdf.orbit_pitch = pitch);
+                Command::none()
+            }
+            ApplicationMessage::OrbitDistanceChanged(distance) => {
+                self.scrub_render_settings(|settings| settings.sdf.orbit_distance = distance);
+                Command::none()
+            }
+            ApplicationMessage::ExpressionChanged(expression) => {
+                // Re-render as soon as what is typed makes sense
+                match Program::compile(&expression) {
+                    Ok(program) => {
+                        self.expression_error = None;
+                        if program != *self.render_settings.expression {
+                            self.change_render_settings(|settings| {
+                                settings.expression = Arc::new(program)
+                            });
+                        }
+                    }
+                    Err(e) => self.expression_error = Some(e),
+                }
+                self.expression_input = expression;
+                Command::none()
+            }
+            ApplicationMessage::OverlayToggled(enabled) => {
+                self.change_render_settings(|settings| settings.overlay.enabled = enabled);
+                Command::none()
+            }
+            ApplicationMessage::AntiAliasingChanged(anti_aliasing) => {
+                self.change_render_settings(|settings| settings.anti_aliasing = anti_aliasing);
+                Command::none()
+            }
+            ApplicationMessage::BucketOrderChanged(bucket_order) => {
+                // Only the order changes, so there's no need to render again
+                self.bucket_order = bucket_order;
+                Command::none()
+            }
+            ApplicationMessage::OverlaySceneChanged(scene) => {
+                self.change_render_settings(|settings| settings.overlay.scene = scene);
+                Command::none()
+            }
+            ApplicationMessage::BlendModeChanged(mode) => {
+                self.change_render_settings(|settings| settings.overlay.blend_mode = mode);
+                Command::none()
+            }
+            ApplicationMessage::OverlayOpacityChanged(opacity) => {
+                self.scrub_render_settings(|settings| settings.overlay.opacity = opacity);
+                Command::none()
+            }
+            ApplicationMessage::ScrubFinished => {
+                self.finish_scrub();
+                Command::none()
+            }
+            ApplicationMessage::RenderProgressed(event) => self.receive_render_event(event),
+        }
+    }
+}
+
+/// Open the application window, until it's closed
+pub fn run() -> iced::Result {
+    // There are no OpenColorIO bindings to build against yet, so at least
+    // don't let a config in the environment be silently ignored
+    if let Ok(config) = std::env::var("OCIO") {
+        console::warning(format!(
+            "OCIO={config:?} is set, but OpenColorIO configs aren't supported yet. \
+             Using the built-in colstodian display pipeline."
+        ));
+    }
+
+    let settings = Settings {
+        default_font: Some(FONT_BYTES),
+        window: window::Settings {
+            icon: render_window_icon(),
+            // Room for the viewer at its full size next to the parameters
+            size: (1400, 900),
+            ..window::Settings::default()
+        },
+        ..Settings::default()
+    };
+    ApplicationState::run(settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The state of a window that hasn't rendered anything yet, small so that each
+    /// refresh of the display is quick
+    fn state() -> ApplicationState {
+        let render_settings = RenderSettings {
+            width: 8,
+            height: 8,
+            ..RenderSettings::default()
+        };
+        ApplicationState::initial(
+            String::from("test"),
+            render_settings,
+            DisplaySettings::default(),
+        )
+    }
+
+    #[test]
+    fn display_changes_wait_for_apply_without_live_preview() {
+        let mut state = state();
+        let _ = state.update(ApplicationMessage::LivePreviewToggled(false));
+        let _ = state.update(ApplicationMessage::ExposureChanged(2.0));
+        assert!(state.has_pending_changes());
+        assert_eq!(state.applied_display_settings.exposure, 0.0);
+
+        let _ = state.update(ApplicationMessage::ApplyPressed);
+        assert!(!state.has_pending_changes());
+        assert_eq!(state.applied_display_settings.exposure, 2.0);
+    }
+
+    #[test]
+    fn undo_and_redo_restore_the_settings() {
+        let mut state = state();
+        let _ = state.update(ApplicationMessage::ExposureChanged(1.0));
+        let _ = state.update(ApplicationMessage::GammaChanged(2.0));
+
+        let _ = state.update(ApplicationMessage::UndoPressed);
+        assert_eq!(state.pending_display_settings.gamma, 1.0);
+        assert_eq!(state.pending_display_settings.exposure, 1.0);
+        let _ = state.update(ApplicationMessage::UndoPressed);
+        assert_eq!(state.pending_display_settings.exposure, 0.0);
+
+        let _ = state.update(ApplicationMessage::RedoPressed);
+        assert_eq!(state.pending_display_settings.exposure, 1.0);
+        assert_eq!(state.applied_display_settings.exposure, 1.0);
+    }
+
+    #[test]
+    fn quick_changes_of_a_setting_are_undone_together() {
+        let mut state = state();
+        for exposure in [0.5, 1.0, 1.5] {
+            let _ = state.update(ApplicationMessage::ExposureChanged(exposure));
+        }
+        let _ = state.update(ApplicationMessage::UndoPressed);
+        assert_eq!(state.pending_display_settings.exposure, 0.0);
+        assert!(!state.undo_history.can_undo());
+    }
+
+    #[test]
+    fn region_renders_drop_the_tiles_of_a_cancelled_render() {
+        let mut state = state();
+        state.render();
+        // Tiles of the full render, which is replaced before it completes
+        state.partial_linear_buffer.pixels_mut().fill(1.0);
+        state.region_of_interest = Some(Region {
+            x: 2,
+            y: 2,
+            width: 4,
+            height: 4,
+        });
+        state.render();
+        assert_eq!(state.partial_linear_buffer, state.linear_render_buffer);
+    }
+
+    #[test]
+    fn shortcuts_wait_for_the_dialogs() {
+        let mut state = state();
+        let message = state.shortcut_message(Action::ExposureUp).unwrap();
+        let _ = state.update(message);
+        assert_eq!(state.pending_display_settings.exposure, EXPOSURE_STEP);
+
+        state.modal = Some(Modal::Shortcuts);
+        assert!(state.shortcut_message(Action::Render).is_none());
+        assert!(matches!(
+            state.shortcut_message(Action::Escape),
+            Some(ApplicationMessage::HelpToggled)
+        ));
+    }
+
+    #[test]
+    fn regions_are_drawn_in_any_direction() {
+        let region = region_between(Point::new(0.75, 0.5), Point::new(0.25, -1.0), 8, 8);
+        assert_eq!(
+            region,
+            Some(Region {
+                x: 2,
+                y: 0,
+                width: 4,
+                height: 4,
+            })
+        );
+        assert_eq!(
+            region_between(Point::new(0.5, 0.0), Point::new(0.5, 1.0), 8, 8),
+            None
+        );
+    }
+}
@@ -0,0 +1,2149 @@
+//! The widgets showing the state of the application: the viewer and the panels around it
+//! on the left, the parameters on the right and the status bar along the bottom
+use iced::widget::{
+    button, checkbox, column, container, image, pane_grid, pick_list, progress_bar, row,
+    scrollable, slider, text, text_input, tooltip, Column, PaneGrid,
+};
+use iced::{Element, Length};
+
+use std::cell::Cell;
+
+use super::{
+    ApplicationMessage, ApplicationState, Modal, Panel, CONSOLE_HEIGHT, MAX_ZOOM, MIN_ZOOM,
+    VIEWER_SIZE,
+};
+use crate::adaptive_quality::Quality;
+use crate::appearance::ThemeKind;
+use crate::background::ViewerBackground;
+use crate::bracket::BRACKET_STOPS;
+use crate::color_blindness::ColorBlindness;
+use crate::color_pipeline::{
+    DisplayTransform, Dithering, GamutMapping, TonemapKind, ViewMode, CLIPPING_WARNING_COLOR,
+    EXPOSURE_RANGE, FALSE_COLOR_BANDS, FALSE_COLOR_CLIPPED, GAMMA_RANGE, GAMUT_WARNING_COLOR,
+    NON_FINITE_COLOR,
+};
+use crate::curve_editor::CurveEditor;
+use crate::guides::Orientation;
+use crate::histogram::HistogramSource;
+use crate::i18n::{tr, tr_args, Language, Text};
+use crate::io::OutputFormat;
+use crate::keymap::Action;
+use crate::lut::LutMode;
+use crate::number_field::NumberField;
+use crate::path_tracer::{BOUNCE_RANGE, MAX_SAMPLES_PER_PIXEL};
+use crate::pattern::{
+    BlendSpace, ColorRamp, FractalKind, GradientAxis, FREQUENCY_RANGE, ITERATION_RANGE,
+    LACUNARITY_RANGE, MAX_GRADIENT_STOPS, OCTAVE_RANGE, RADIUS_RANGE,
+};
+use crate::post_process::PassKind;
+use crate::raymarcher::SdfOperation;
+use crate::render::{parse_dimension, MAX_DIMENSION};
+use crate::render::{AntiAliasing, BlendMode, RenderBackend, ResolutionPreset, SceneKind};
+use crate::render_worker::BucketOrder;
+use crate::scopes::ScopeKind;
+use crate::statistics::{ChannelStats, SampleSize};
+use crate::tone_curve::ToneCurve;
+use crate::viewer::{PointerEvent, View, Viewer};
+use crate::white_balance::{TEMPERATURE_RANGE, TINT_RANGE};
+use crate::{color_picker, console, gallery, i18n, tone_curve};
+
+impl ApplicationState {
+    // The whole window, or only the image while presenting
+    pub(super) fn layout(&self) -> Element<'_, ApplicationMessage> {
+        if let Some(modal) = &self.modal {
+            return self.modal_view(modal);
+        }
+
+        let image_viewer = self.image_viewer();
+
+        // Nothing but the image, until F11 or Esc is pressed
+        if self.presenting {
+            return container(image_viewer)
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .into();
+        }
+
+        // The viewer and what's about the image on the left, the parameters on the right
+        let viewer_panel = self.viewer_panel(image_viewer);
+        let parameter_panel = self.parameter_panel();
+        // The pane grid asks for the content of each pane once
+        let viewer_panel = Cell::new(Some(viewer_panel));
+        let parameter_panel = Cell::new(Some(parameter_panel));
+        let panes = PaneGrid::new(&self.panes, |_, panel, _| {
+            let content = match panel {
+                Panel::Viewer => viewer_panel.take(),
+                Panel::Parameters => parameter_panel.take(),
+            };
+            pane_grid::Content::new(content.unwrap_or_else(|| row![].into()))
+        })
+        .spacing(10)
+        .on_resize(10, ApplicationMessage::PanesResized);
+
+        column![self.notification_banner(), panes, self.status_bar()].into()
+    }
+
+    // The image, taking the clicks and drags the current tool needs
+    fn image_viewer(&self) -> Viewer<'_, ApplicationMessage> {
+        // This stores the image after it has been rendered.
+        // Drawing the region of interest or the selection, moving the guides, inspecting and the fractal need the clicks for
+        // themselves. The middle button always pans the image, and the wheel zooms it
+        // (besides on the fractal, which it zooms instead).
+        type PointerMessage = fn(PointerEvent) -> ApplicationMessage;
+        let (pointer_message, forward_scroll): (Option<PointerMessage>, bool) =
+            if self.drawing_region {
+                (Some(ApplicationMessage::RegionPointer), false)
+            } else if self.selecting {
+                (Some(ApplicationMessage::SelectionPointer), false)
+            } else if self.moving_guides {
+                (Some(ApplicationMessage::GuidePointer), false)
+            } else if self.inspecting {
+                (Some(ApplicationMessage::InspectorPointer), false)
+            } else if self.pinned_render.is_some() {
+                (Some(ApplicationMessage::WipePointer), false)
+            } else if self.render_settings.scene == SceneKind::Fractal {
+                (Some(ApplicationMessage::FractalPointer), true)
+            } else {
+                (None, false)
+            };
+        let view = View {
+            zoom: self.zoom,
+            center: self.view_center,
+        };
+        let viewer_height = if self.presenting {
+            Length::Fill
+        } else {
+            Length::Fixed(VIEWER_SIZE.1)
+        };
+        let mut image_viewer = Viewer::new(self.rendered_image.clone(), view)
+            .height(viewer_height)
+            .zoom_range(MIN_ZOOM, MAX_ZOOM)
+            .on_view_change(ApplicationMessage::ViewChanged)
+            .on_hover(ApplicationMessage::CursorMoved)
+            .pixel_grid(self.show_pixel_grid);
+        if let Some(pointer_message) = pointer_message {
+            image_viewer = image_viewer
+                .on_pointer(pointer_message)
+                .forward_scroll(forward_scroll);
+        }
+
+        image_viewer
+    }
+
+    // The render, display and output settings, in the right pane
+    fn parameter_panel(&self) -> Element<'_, ApplicationMessage> {
+        // Render button
+        let render_button = button(
+            text(tr(Text::Render))
+                .width(Length::Fill)
+                .horizontal_alignment(iced::alignment::Horizontal::Center),
+        )
+        .on_press(ApplicationMessage::RenderPressed)
+        .padding(10)
+        .width(Length::Fill);
+
+        // Cancel button, only enabled while rendering
+        let mut cancel_render_button = button(
+            text(tr(Text::Cancel))
+                .width(Length::Fill)
+                .horizontal_alignment(iced::alignment::Horizontal::Center),
+        )
+        .padding(10)
+        .width(100);
+        if self.render_job.is_some() {
+            cancel_render_button =
+                cancel_render_button.on_press(ApplicationMessage::CancelRenderPressed);
+        }
+
+        // Undo and redo of the settings, only enabled when there's something to
+        let mut undo_button = button(text(tr(Text::Undo))).padding(10);
+        if self.undo_history.can_undo() {
+            undo_button = undo_button.on_press(ApplicationMessage::UndoPressed);
+        }
+        let mut redo_button = button(text(tr(Text::Redo))).padding(10);
+        if self.undo_history.can_redo() {
+            redo_button = redo_button.on_press(ApplicationMessage::RedoPressed);
+        }
+
+        // Save text field
+        let file_name_input = text_input(
+            tr(Text::FileNamePlaceholder),
+            &self.file_name,
+            ApplicationMessage::FileNameChanged,
+        )
+        .padding(10)
+        .size(20);
+
+        let mut save_button = button(
+            text(tr(Text::Save))
+                .width(Length::Fill)
+                .horizontal_alignment(iced::alignment::Horizontal::Center),
+        )
+        .padding(10)
+        .width(100);
+        // Until the current write is done, the next auto versioned name isn't known
+        if self.saves_in_flight == 0 {
+            save_button = save_button.on_press(ApplicationMessage::SaveFilePressed);
+        }
+
+        // Only EXRs can hold the extra layers
+        let aovs_checkbox: Element<'_, ApplicationMessage> =
+            if self.output_format == OutputFormat::Exr {
+                checkbox(
+                    tr(Text::Aovs),
+                    self.write_aovs,
+                    ApplicationMessage::AovsToggled,
+                )
+                .into()
+            } else {
+                row![].into()
+            };
+
+        // Presets of the render and display settings
+        let mut preset_controls = column![row![
+            text(tr(Text::Preset)).width(100),
+            pick_list(
+                &self.presets[..],
+                self.selected_preset.clone(),
+                ApplicationMessage::PresetSelected
+            )
+            .placeholder(tr(Text::PresetPlaceholder)),
+            text_input(
+                tr(Text::PresetNamePlaceholder),
+                &self.preset_name_input,
+                ApplicationMessage::PresetNameChanged
+            )
+            .on_submit(ApplicationMessage::SavePresetPressed)
+            .padding(10),
+            with_tooltip(
+                button(text(tr(Text::SavePreset)))
+                    .padding(10)
+                    .on_press(ApplicationMessage::SavePresetPressed),
+                Text::TipSavePreset
+            ),
+        ]
+        .padding(10)
+        .spacing(10)
+        .align_items(iced::Alignment::Center)];
+        if let Some(e) = &self.preset_error {
+            preset_controls = preset_controls
+                .push(row![text(e).style(iced::Color::from_rgb(0.8, 0.2, 0.2))].padding(10));
+        }
+
+        // Open an image, to look at it through the display pipeline
+        let mut open_controls = column![row![
+            text(tr(Text::Open)).width(100),
+            text_input(
+                tr(Text::OpenPlaceholder),
+                &self.open_path_input,
+                ApplicationMessage::OpenPathChanged
+            )
+            .on_submit(ApplicationMessage::OpenPressed)
+            .padding(10),
+            with_tooltip(
+                button(text(tr(Text::Open)))
+                    .padding(10)
+                    .on_press(ApplicationMessage::OpenPressed),
+                Text::TipOpen
+            ),
+        ]
+        .padding(10)
+        .spacing(10)
+        .align_items(iced::Alignment::Center)];
+        if !self.recent_files.is_empty() {
+            open_controls = open_controls.push(
+                row![
+                    text(tr(Text::Recent)).width(100),
+                    pick_list(
+                        &self.recent_files[..],
+                        None,
+                        ApplicationMessage::RecentFileSelected
+                    )
+                    .placeholder(tr(Text::RecentPlaceholder))
+                    .width(Length::Fill),
+                ]
+                .padding(10)
+                .spacing(10)
+                .align_items(iced::Alignment::Center),
+            );
+        }
+        if let Some(e) = &self.open_error {
+            open_controls = open_controls
+                .push(row![text(e).style(iced::Color::from_rgb(0.8, 0.2, 0.2))].padding(10));
+        }
+
+        // Difference to a reference image, for regression checks of the render code
+        let mut reference_controls = column![row![
+            text(tr(Text::Reference)).width(100),
+            text_input(
+                tr(Text::ReferencePlaceholder),
+                &self.reference_path_input,
+                ApplicationMessage::ReferencePathChanged
+            )
+            .on_submit(ApplicationMessage::LoadReferencePressed)
+            .padding(10),
+            button(text(tr(Text::Load)))
+                .padding(10)
+                .on_press(ApplicationMessage::LoadReferencePressed),
+        ]
+        .padding(10)
+        .spacing(10)
+        .align_items(iced::Alignment::Center)];
+        if let Some(reference) = &self.reference {
+            let (width, height) = (reference.width(), reference.height());
+            let mut difference_row = row![
+                text(tr(Text::Difference)).width(100),
+                checkbox(
+                    tr(Text::Heatmap),
+                    self.show_difference,
+                    ApplicationMessage::DifferenceToggled
+                ),
+                text(tr_args(
+                    Text::Gain,
+                    &[&format!("{:.0}", self.difference_gain_stops.exp2())]
+                )),
+                slider(
+                    0.0..=16.0,
+                    self.difference_gain_stops,
+                    ApplicationMessage::DifferenceGainChanged
+                )
+                .step(1.0),
+            ]
+            .padding(10)
+            .spacing(10)
+            .align_items(iced::Alignment::Center);
+            if self.difference_stats.is_none() {
+                difference_row = difference_row.push(
+                    text(tr_args(
+                        Text::ReferenceSizeMismatch,
+                        &[
+                            &format!("{width}x{height}"),
+                            &format!(
+                                "{}x{}",
+                                self.render_settings.width, self.render_settings.height
+                            ),
+                        ],
+                    ))
+                    .style(iced::Color::from_rgb(0.8, 0.2, 0.2)),
+                );
+            }
+            reference_controls = reference_controls.push(difference_row);
+        }
+        if let Some(stats) = self.difference_stats {
+            let pixel_count = self.render_settings.width * self.render_settings.height;
+            reference_controls = reference_controls.push(
+                row![text(tr_args(
+                    Text::DifferingPixels,
+                    &[
+                        &stats.differing_pixels,
+                        &pixel_count,
+                        &format!(
+                            "{:.2}",
+                            100.0 * stats.differing_pixels as f32 / pixel_count.max(1) as f32
+                        ),
+                        &format!("{:.6}", stats.max),
+                        &format!("{:.6}", stats.mean),
+                    ],
+                ))]
+                .padding(10),
+            );
+        }
+        if let Some(e) = &self.reference_error {
+            reference_controls = reference_controls
+                .push(row![text(e).style(iced::Color::from_rgb(0.8, 0.2, 0.2))].padding(10));
+        }
+
+        let save_status: Element<'_, ApplicationMessage> = if self.auto_version {
+            row![text(tr_args(Text::WillSave, &[&self.file_name_with_ext]))]
+                .padding(10)
+                .into()
+        } else if self.save_target_exists {
+            row![text(tr_args(
+                Text::AlreadyExists,
+                &[&self.file_name_with_ext]
+            ))]
+            .padding(10)
+            .into()
+        } else {
+            row![].into()
+        };
+
+        // Post-processing, run in order before the rest of the display conversion
+        let post_passes = &self.pending_display_settings.post_passes;
+        let add_pass_list = pick_list(&PassKind::ALL[..], None, ApplicationMessage::PostPassAdded)
+            .placeholder(tr(Text::AddPass));
+        let post_process_controls = post_passes.iter().enumerate().fold(
+            column![row![
+                with_tooltip(
+                    text(tr(Text::PostProcessing)).width(200),
+                    Text::TipPostProcessing
+                ),
+                add_pass_list,
+            ]
+            .padding(10)
+            .spacing(10)
+            .align_items(iced::Alignment::Center)],
+            |controls, (index, pass)| {
+                let kind = pass.kind();
+                let mut up_button = button(text("▲"));
+                if index > 0 {
+                    up_button =
+                        up_button.on_press(ApplicationMessage::PostPassMoved(index, index - 1));
+                }
+                let mut down_button = button(text("▼"));
+                if index + 1 < post_passes.len() {
+                    down_button =
+                        down_button.on_press(ApplicationMessage::PostPassMoved(index, index + 1));
+                }
+                controls.push(
+                    row![
+                        text(format!("{kind} {}: {:.2}", kind.parameter(), pass.amount()))
+                            .width(200),
+                        slider(kind.range(), pass.amount(), move |amount| {
+                            ApplicationMessage::PostPassChanged(index, amount)
+                        })
+                        .step(0.01),
+                        up_button,
+                        down_button,
+                        button(text("✕")).on_press(ApplicationMessage::PostPassRemoved(index)),
+                    ]
+                    .padding(10)
+                    .spacing(10)
+                    .align_items(iced::Alignment::Center),
+                )
+            },
+        );
+
+        // Global alpha
+        let alpha_label = text(tr_args(
+            Text::Alpha,
+            &[&format!(
+                "{:.2}",
+                self.pending_display_settings.global_alpha
+            )],
+        ))
+        .width(100);
+        let alpha_slider = slider(
+            0.0..=1.0,
+            self.pending_display_settings.global_alpha,
+            ApplicationMessage::GlobalAlphaChanged,
+        )
+        .step(0.01);
+
+        // White balance
+        let white_balance = self.pending_display_settings.white_balance;
+        let white_balance_controls = column![
+            row![
+                text(tr_args(
+                    Text::Temperature,
+                    &[&format!("{:.0}", white_balance.temperature)]
+                ))
+                .width(200),
+                slider(
+                    TEMPERATURE_RANGE,
+                    white_balance.temperature,
+                    ApplicationMessage::TemperatureChanged
+                )
+                .step(50.0),
+            ]
+            .padding(10)
+            .spacing(10),
+            row![
+                text(tr_args(
+                    Text::Tint,
+                    &[&format!("{:+.2}", white_balance.tint)]
+                ))
+                .width(200),
+                slider(
+                    TINT_RANGE,
+                    white_balance.tint,
+                    ApplicationMessage::TintChanged
+                )
+                .step(0.01),
+            ]
+            .padding(10)
+            .spacing(10),
+        ];
+
+        // Exposure
+        let exposure = self.pending_display_settings.exposure;
+        let exposure_label = text(tr(Text::Exposure)).width(100);
+        let exposure_field = NumberField::new(exposure as f64, |exposure| {
+            ApplicationMessage::ExposureChanged(exposure as f32)
+        })
+        .range(*EXPOSURE_RANGE.start() as f64..=*EXPOSURE_RANGE.end() as f64)
+        .step(0.1)
+        .decimals(1)
+        .width(80);
+        let exposure_slider = slider(
+            EXPOSURE_RANGE,
+            exposure,
+            ApplicationMessage::ExposureChanged,
+        )
+        .step(0.1);
+
+        // Gamma
+        let gamma = self.pending_display_settings.gamma;
+        let gamma_label = text(tr_args(Text::Gamma, &[&format!("{gamma:.2}")])).width(100);
+        let gamma_slider = slider(GAMMA_RANGE, gamma, ApplicationMessage::GammaChanged).step(0.01);
+
+        // Display colorspace
+        let display_label = text(tr(Text::Display)).width(100);
+        let display_list = pick_list(
+            &DisplayTransform::ALL[..],
+            Some(self.pending_display_settings.display),
+            ApplicationMessage::DisplayTransformChanged,
+        )
+        .width(Length::Fill);
+
+        // Gamut mapping
+        let gamut_mapping_label = text(tr(Text::GamutMapping)).width(100);
+        let gamut_mapping_list = pick_list(
+            &GamutMapping::ALL[..],
+            Some(self.pending_display_settings.gamut_mapping),
+            ApplicationMessage::GamutMappingChanged,
+        )
+        .width(Length::Fill);
+
+        // ICC display profile
+        let mut clear_icc_button = button(text(tr(Text::Clear))).padding(10);
+        if self.pending_display_settings.icc_profile.is_some() {
+            clear_icc_button = clear_icc_button.on_press(ApplicationMessage::IccCleared);
+        }
+        let icc_status = match (&self.icc_error, &self.pending_display_settings.icc_profile) {
+            (Some(e), _) => text(e),
+            (None, Some(profile)) => text(tr_args(Text::EncodingWith, &[&profile.name])),
+            (None, None) => text(tr_args(
+                Text::NoIccProfile,
+                &[&self.pending_display_settings.display],
+            )),
+        };
+        let icc_controls = column![
+            row![
+                text(tr(Text::IccProfile)).width(100),
+                text_input(
+                    tr(Text::IccPlaceholder),
+                    &self.icc_path_input,
+                    ApplicationMessage::IccPathChanged
+                )
+                .on_submit(ApplicationMessage::LoadIccPressed)
+                .padding(10),
+                button(text(tr(Text::Load)))
+                    .padding(10)
+                    .on_press(ApplicationMessage::LoadIccPressed),
+                clear_icc_button,
+            ]
+            .padding(10)
+            .spacing(10)
+            .align_items(iced::Alignment::Center),
+            row![icc_status].padding(10),
+        ];
+
+        // 3D LUT
+        let lut_settings = &self.pending_display_settings;
+        let mut clear_lut_button = button(text(tr(Text::Clear))).padding(10);
+        if lut_settings.lut.is_some() {
+            clear_lut_button = clear_lut_button.on_press(ApplicationMessage::LutCleared);
+        }
+        let lut_status = match (&self.lut_error, &lut_settings.lut) {
+            (Some(e), _) => text(e),
+            (None, Some(lut)) => text(tr_args(Text::UsingLut, &[&lut.name])),
+            (None, None) => text(tr(Text::NoLut)),
+        };
+        let lut_controls = column![
+            row![
+                text(tr(Text::Lut)).width(100),
+                text_input(
+                    tr(Text::LutPlaceholder),
+                    &self.lut_path_input,
+                    ApplicationMessage::LutPathChanged
+                )
+                .on_submit(ApplicationMessage::LoadLutPressed)
+                .padding(10),
+                button(text(tr(Text::Load)))
+                    .on_press(ApplicationMessage::LoadLutPressed)
+                    .padding(10),
+                clear_lut_button,
+                pick_list(
+                    &LutMode::ALL[..],
+                    Some(lut_settings.lut_mode),
+                    ApplicationMessage::LutModeChanged,
+                ),
+            ]
+            .padding(10)
+            .spacing(10)
+            .align_items(iced::Alignment::Center),
+            row![lut_status].padding(10),
+        ];
+
+        // Tonemap
+        let tonemap_label = text(tr(Text::Tonemap)).width(100);
+        let tonemap_list = pick_list(
+            &TonemapKind::ALL[..],
+            Some(self.pending_display_settings.tonemap),
+            ApplicationMessage::TonemapChanged,
+        )
+        .width(Length::Fill);
+        let curve_controls = if self.pending_display_settings.tonemap == TonemapKind::Curve {
+            let mut reset_curve_button = button(text(tr(Text::ResetCurve)));
+            if self.pending_display_settings.tone_curve != ToneCurve::default() {
+                reset_curve_button =
+                    reset_curve_button.on_press(ApplicationMessage::ToneCurveReset);
+            }
+            row![
+                text("").width(100),
+                CurveEditor::new(
+                    &self.pending_display_settings.tone_curve,
+                    ApplicationMessage::ToneCurveEdited
+                ),
+                column![
+                    text(tr_args(
+                        Text::CurveInput,
+                        &[
+                            &tone_curve::MIN_STOPS,
+                            &format!("{:+}", tone_curve::MAX_STOPS),
+                        ],
+                    ))
+                    .size(16),
+                    text(tr(Text::CurveOutput)).size(16),
+                    text(tr(Text::CurveHelp)).size(16),
+                    reset_curve_button,
+                ]
+                .spacing(10),
+            ]
+            .padding(10)
+            .spacing(10)
+        } else {
+            row![]
+        };
+
+        // Live preview vs explicit apply
+        let live_preview_checkbox = checkbox(
+            tr(Text::LivePreview),
+            self.live_preview,
+            ApplicationMessage::LivePreviewToggled,
+        );
+        let pending_label = if self.has_pending_changes() {
+            text(tr(Text::ShowingProxy))
+        } else {
+            text("")
+        };
+        let mut apply_button = button(
+            text(tr(Text::Apply))
+                .width(Length::Fill)
+                .horizontal_alignment(iced::alignment::Horizontal::Center),
+        )
+        .padding(10)
+        .width(100);
+        if self.has_pending_changes() {
+            apply_button = apply_button.on_press(ApplicationMessage::ApplyPressed);
+        }
+
+        // Resolution
+        let resolution_label = text(tr(Text::Resolution)).width(100);
+        let resolution_list = pick_list(
+            &ResolutionPreset::ALL[..],
+            ResolutionPreset::from_size(self.render_settings.width, self.render_settings.height),
+            ApplicationMessage::ResolutionPresetSelected,
+        )
+        .placeholder(tr(Text::Custom));
+        let dimension_input = |input: &str, current, placeholder, on_change: fn(String) -> _| {
+            let value = parse_dimension(input).unwrap_or(current);
+            NumberField::new(value as f64, move |value| on_change(value.to_string()))
+                .placeholder(tr(placeholder))
+                .range(1.0..=MAX_DIMENSION as f64)
+                .on_submit(ApplicationMessage::ResizePressed)
+                .width(100)
+        };
+        let width_input = dimension_input(
+            &self.width_input,
+            self.render_settings.width,
+            Text::Width,
+            ApplicationMessage::WidthInputChanged,
+        );
+        let height_input = dimension_input(
+            &self.height_input,
+            self.render_settings.height,
+            Text::Height,
+            ApplicationMessage::HeightInputChanged,
+        );
+        let mut resize_button = button(text(tr(Text::Resize))).padding(10);
+        let resolution_status = match self.input_resolution() {
+            Ok(resolution) => {
+                if resolution != (self.render_settings.width, self.render_settings.height) {
+                    resize_button = resize_button.on_press(ApplicationMessage::ResizePressed);
+                }
+                text("")
+            }
+            Err(e) => text(e),
+        };
+
+        // Backend, only worth showing when there is a choice
+        let backend_controls: Element<'_, ApplicationMessage> = if cfg!(feature = "gpu") {
+            let backend = self.render_settings.backend;
+            let fallback_label = if backend.supports(self.render_settings.scene) {
+                text("")
+            } else {
+                text(tr_args(Text::CpuFallback, &[&backend]))
+            };
+
+            row![
+                text(tr(Text::Backend)).width(100),
+                pick_list(
+                    &RenderBackend::ALL[..],
+                    Some(backend),
+                    ApplicationMessage::BackendChanged,
+                ),
+                fallback_label,
+            ]
+            .padding(10)
+            .spacing(10)
+            .align_items(iced::Alignment::Center)
+            .into()
+        } else {
+            row![].into()
+        };
+
+        let pattern_controls = self.pattern_controls();
+
+        // Overlay
+        let overlay = &self.render_settings.overlay;
+        let mut overlay_controls = column![row![checkbox(
+            tr(Text::Overlay),
+            overlay.enabled,
+            ApplicationMessage::OverlayToggled,
+        )]
+        .padding(10)];
+
+        if overlay.enabled {
+            overlay_controls = overlay_controls.push(
+                row![
+                    pick_list(
+                        &SceneKind::ALL[..],
+                        Some(overlay.scene),
+                        ApplicationMessage::OverlaySceneChanged,
+                    ),
+                    pick_list(
+                        &BlendMode::ALL[..],
+                        Some(overlay.blend_mode),
+                        ApplicationMessage::BlendModeChanged,
+                    ),
+                    text(tr_args(
+                        Text::Opacity,
+                        &[&format!("{:.2}", overlay.opacity)]
+                    )),
+                    slider(
+                        0.0..=1.0,
+                        overlay.opacity,
+                        ApplicationMessage::OverlayOpacityChanged
+                    )
+                    .step(0.01)
+                    .on_release(ApplicationMessage::ScrubFinished),
+                ]
+                .padding(10)
+                .spacing(10)
+                .align_items(iced::Alignment::Center),
+            );
+        }
+
+        let quality_label = match (self.render_job.is_some(), self.quality_controller.quality()) {
+            (_, Quality::Proxy) => text(tr(Text::ReducedQuality)),
+            (true, Quality::Full) => text(tr_args(
+                Text::RenderingProgress,
+                &[&format!("{:.0}", self.render_progress() * 100.0)],
+            )),
+            (false, Quality::Full) => text(""),
+        };
+
+        // Empty when idle
+        let render_progress_fraction = match self.render_job {
+            Some(_) => self.render_progress(),
+            None => 0.0,
+        };
+        let render_progress = progress_bar(0.0..=1.0, render_progress_fraction).height(10);
+
+        // Render stats, most recent first
+        let mut render_stats = column![text(tr(Text::RecentRenders))]
+            .padding(10)
+            .spacing(5);
+        for stats in self.render_history.iter() {
+            render_stats = render_stats.push(text(stats.to_string()).size(16));
+        }
+
+        // Timeline
+        let play_button = button(text(tr(if self.playing {
+            Text::Pause
+        } else {
+            Text::Play
+        })))
+        .on_press(ApplicationMessage::PlayToggled)
+        .padding(10)
+        .width(100);
+        let frame = self.render_settings.frame;
+        let timeline = row![
+            play_button,
+            text(tr_args(Text::FrameOf, &[&frame, &self.frame_count])).width(150),
+            slider(
+                1..=self.frame_count,
+                frame,
+                ApplicationMessage::FrameChanged
+            )
+            .on_release(ApplicationMessage::ScrubFinished),
+            text(tr(Text::Frames)),
+            slider(
+                2..=240,
+                self.frame_count,
+                ApplicationMessage::FrameCountChanged
+            )
+            .width(150),
+        ]
+        .padding(10)
+        .spacing(10)
+        .align_items(iced::Alignment::Center);
+
+        // While a sequence renders, the same button aborts it
+        let render_sequence_button: Element<'_, ApplicationMessage> = if self.rendering_sequence {
+            button(text(tr(Text::AbortSequence)))
+                .on_press(ApplicationMessage::AbortSequencePressed)
+                .padding(10)
+                .into()
+        } else {
+            button(text(tr(Text::RenderSequence)))
+                .on_press(ApplicationMessage::RenderSequencePressed)
+                .padding(10)
+                .into()
+        };
+        let sequence_progress: Element<'_, ApplicationMessage> = if self.rendering_sequence {
+            let completed_frames = frame - 1;
+            let progress =
+                (completed_frames as f32 + render_progress_fraction) / self.frame_count as f32;
+            row![
+                text(tr_args(
+                    Text::SequenceProgress,
+                    &[&frame, &self.frame_count, &completed_frames]
+                ))
+                .width(250),
+                progress_bar(0.0..=1.0, progress).height(10),
+            ]
+            .padding(10)
+            .spacing(10)
+            .align_items(iced::Alignment::Center)
+            .into()
+        } else {
+            row![].into()
+        };
+
+        // Seed of the stochastic patterns
+        let seed_input = NumberField::new(self.render_settings.seed as f64, |seed| {
+            ApplicationMessage::SeedChanged(seed as u32)
+        })
+        .range(0.0..=u32::MAX as f64)
+        .width(150);
+        let seed_controls = row![
+            text(tr(Text::Seed)).width(100),
+            seed_input,
+            button(text(tr(Text::Randomize)))
+                .on_press(ApplicationMessage::SeedRandomized)
+                .padding(10),
+        ]
+        .padding(10)
+        .spacing(10)
+        .align_items(iced::Alignment::Center);
+
+        // Pipeline inspector, for the pixel under the cursor
+        let mut inspector = column![row![
+            checkbox(
+                tr(Text::InspectPixels),
+                self.inspecting,
+                ApplicationMessage::InspectorToggled
+            ),
+            text(tr(Text::Sample)),
+            pick_list(
+                &SampleSize::ALL[..],
+                Some(self.sample_size),
+                ApplicationMessage::SampleSizeChanged
+            ),
+        ]
+        .spacing(10)
+        .align_items(iced::Alignment::Center)]
+        .padding(10)
+        .spacing(5);
+        if let Some((x, y, _, stages)) = self.inspected_stages() {
+            let format_rgb = |[r, g, b]: [f32; 3]| format!("{r:.4}, {g:.4}, {b:.4}");
+            let [r, g, b, a] = stages.rgba;
+
+            let lines = [
+                (
+                    String::from(tr(Text::StagePixel)),
+                    match self.sample_size {
+                        SampleSize::Single => format!("{x}, {y}"),
+                        size => tr_args(Text::AveragedOver, &[&format!("{x}, {y}"), &size]),
+                    },
+                ),
+                (
+                    String::from(tr(Text::StageLinear)),
+                    format_rgb(stages.linear),
+                ),
+                (
+                    String::from(tr(Text::StageWhiteBalanced)),
+                    format_rgb(stages.white_balanced),
+                ),
+                (
+                    String::from(tr(Text::StageExposed)),
+                    format_rgb(stages.exposed),
+                ),
+                (
+                    String::from(tr(Text::StageTonemapped)),
+                    stages
+                        .tonemapped
+                        .map_or_else(|| String::from(tr(Text::Skipped)), format_rgb),
+                ),
+                (
+                    tr_args(
+                        Text::StageGamutMapped,
+                        &[&self.pending_display_settings.gamut_mapping],
+                    ),
+                    stages
+                        .gamut_mapped
+                        .map_or_else(|| String::from(tr(Text::Skipped)), format_rgb),
+                ),
+                (
+                    tr_args(
+                        Text::StageEncoded,
+                        &[&self.pending_display_settings.display],
+                    ),
+                    format_rgb(stages.encoded),
+                ),
+                (
+                    String::from(tr(Text::StageRgba8)),
+                    format!("{r}, {g}, {b}, {a}"),
+                ),
+                (
+                    String::from(tr(Text::StageWarnings)),
+                    match (stages.clipped, stages.out_of_gamut) {
+                        (false, false) => String::from(tr(Text::NoWarnings)),
+                        (true, false) => String::from(tr(Text::WarningClipped)),
+                        (false, true) => String::from(tr(Text::WarningOutOfGamut)),
+                        (true, true) => String::from(tr(Text::WarningClippedOutOfGamut)),
+                    },
+                ),
+            ];
+            for (stage, value) in lines {
+                inspector = inspector.push(row![text(stage).width(200), text(value)].spacing(10));
+            }
+        }
+
+        // Region of interest
+        let mut clear_region_button = button(text(tr(Text::ClearRegion))).padding(10);
+        if self.region_of_interest.is_some() {
+            clear_region_button = clear_region_button.on_press(ApplicationMessage::RegionCleared);
+        }
+        let region_label = match self.region_of_interest {
+            Some(region) => text(tr_args(
+                Text::RenderingRegion,
+                &[
+                    &format!("{}x{}", region.width, region.height),
+                    &format!("{},{}", region.x, region.y),
+                ],
+            )),
+            None => text(tr(Text::RenderingWholeImage)),
+        };
+        let region_controls = row![
+            checkbox(
+                tr(Text::DrawRegion),
+                self.drawing_region,
+                ApplicationMessage::RegionDrawingToggled
+            ),
+            clear_region_button,
+            region_label,
+        ]
+        .padding(10)
+        .spacing(10)
+        .align_items(iced::Alignment::Center);
+
+        // Selection, with the statistics of its pixels
+        let mut clear_selection_button = button(text(tr(Text::ClearSelection))).padding(10);
+        let mut export_selection_button = button(text(tr(Text::ExportSelection))).padding(10);
+        if self.selection.is_some() {
+            clear_selection_button =
+                clear_selection_button.on_press(ApplicationMessage::SelectionCleared);
+            export_selection_button =
+                export_selection_button.on_press(ApplicationMessage::ExportSelectionPressed);
+        }
+        let mut selection_controls = column![row![
+            checkbox(
+                tr(Text::Select),
+                self.selecting,
+                ApplicationMessage::SelectingToggled
+            ),
+            clear_selection_button,
+            with_tooltip(export_selection_button, Text::TipExportSelection),
+        ]
+        .spacing(10)
+        .align_items(iced::Alignment::Center)]
+        .padding(10)
+        .spacing(5);
+        if let (Some(selection), Some(stats)) = (self.selection, self.selection_stats) {
+            selection_controls = selection_controls.push(text(tr_args(
+                Text::SelectionAt,
+                &[
+                    &format!("{}x{}", selection.width, selection.height),
+                    &format!("{},{}", selection.x, selection.y),
+                ],
+            )));
+            selection_controls = selection_controls.push(statistics_table(&stats));
+        }
+
+        // The custom theme's colors are only shown while it's picked
+        let mut theme_controls = row![
+            text(tr(Text::Theme)).width(100),
+            pick_list(
+                &ThemeKind::ALL[..],
+                Some(self.appearance.kind),
+                ApplicationMessage::ThemeKindChanged
+            ),
+        ]
+        .padding(10)
+        .spacing(10)
+        .align_items(iced::Alignment::Center);
+        if self.appearance.kind == ThemeKind::Custom {
+            theme_controls = theme_controls
+                .push(text(tr(Text::Background)))
+                .push(
+                    slider(
+                        0.0..=1.0,
+                        self.appearance.custom.background,
+                        ApplicationMessage::ThemeBackgroundChanged,
+                    )
+                    .step(0.01),
+                )
+                .push(text(tr(Text::Accent)))
+                .push(
+                    text_input(
+                        "#RRGGBB",
+                        &self.accent_input,
+                        ApplicationMessage::AccentInputChanged,
+                    )
+                    .padding(5)
+                    .width(100),
+                );
+        }
+
+        let language_controls = row![
+            text(tr(Text::Language)).width(100),
+            pick_list(
+                &Language::ALL[..],
+                Some(i18n::language()),
+                ApplicationMessage::LanguageChanged
+            ),
+        ]
+        .padding(10)
+        .spacing(10)
+        .align_items(iced::Alignment::Center);
+
+        let controls = column![
+            row![render_progress].padding(10),
+            row![
+                with_tooltip(render_button, Text::TipRender),
+                with_tooltip(cancel_render_button, Text::TipCancel),
+                with_tooltip(undo_button, Text::TipUndo),
+                with_tooltip(redo_button, Text::TipRedo),
+                quality_label
+            ]
+            .padding(10)
+            .spacing(10)
+            .align_items(iced::Alignment::Center),
+            timeline,
+            region_controls,
+            selection_controls,
+            inspector,
+            row![
+                resolution_label,
+                resolution_list,
+                width_input,
+                text("x"),
+                height_input,
+                with_tooltip(resize_button, Text::TipResize),
+                resolution_status,
+            ]
+            .padding(10)
+            .spacing(10)
+            .align_items(iced::Alignment::Center),
+            backend_controls,
+            row![
+                text(tr(Text::AntiAliasing)).width(100),
+                pick_list(
+                    &AntiAliasing::ALL[..],
+                    Some(self.render_settings.anti_aliasing),
+                    ApplicationMessage::AntiAliasingChanged,
+                ),
+                text(tr(Text::BucketOrder)),
+                pick_list(
+                    &BucketOrder::ALL[..],
+                    Some(self.bucket_order),
+                    ApplicationMessage::BucketOrderChanged,
+                ),
+            ]
+            .padding(10)
+            .spacing(10)
+            .align_items(iced::Alignment::Center),
+            seed_controls,
+            pattern_controls,
+            overlay_controls,
+            post_process_controls,
+            row![alpha_label, alpha_slider].padding(10).spacing(10),
+            white_balance_controls,
+            row![
+                with_tooltip(exposure_label, Text::TipExposure),
+                exposure_field,
+                exposure_slider
+            ]
+            .padding(10)
+            .spacing(10)
+            .align_items(iced::Alignment::Center),
+            row![with_tooltip(tonemap_label, Text::TipTonemap), tonemap_list]
+                .padding(10)
+                .spacing(10),
+            curve_controls,
+            row![with_tooltip(gamma_label, Text::TipGamma), gamma_slider]
+                .padding(10)
+                .spacing(10),
+            row![with_tooltip(display_label, Text::TipDisplay), display_list]
+                .padding(10)
+                .spacing(10),
+            row![gamut_mapping_label, gamut_mapping_list]
+                .padding(10)
+                .spacing(10),
+            icc_controls,
+            lut_controls,
+            row![
+                live_preview_checkbox,
+                pending_label,
+                with_tooltip(apply_button, Text::TipApply)
+            ]
+            .padding(10)
+            .spacing(10)
+            .align_items(iced::Alignment::Center),
+            row![
+                file_name_input,
+                pick_list(
+                    &OutputFormat::ALL[..],
+                    Some(self.output_format),
+                    ApplicationMessage::OutputFormatChanged
+                ),
+                with_tooltip(aovs_checkbox, Text::TipAovs),
+                checkbox(
+                    tr(Text::AutoVersion),
+                    self.auto_version,
+                    ApplicationMessage::AutoVersionToggled
+                ),
+                with_tooltip(save_button, Text::TipSave),
+                button(text(tr(Text::Copy)))
+                    .on_press(ApplicationMessage::CopyPressed)
+                    .padding(10),
+                render_sequence_button,
+            ]
+            .padding(10)
+            .spacing(10)
+            .align_items(iced::Alignment::Center),
+            save_status,
+            sequence_progress,
+            preset_controls,
+            open_controls,
+            reference_controls,
+            theme_controls,
+            language_controls,
+            render_stats,
+        ];
+
+        scrollable(controls).into()
+    }
+
+    // The pattern to render and the settings of the picked one
+    fn pattern_controls(&self) -> Column<'_, ApplicationMessage> {
+        let pattern_label = text(tr(Text::Pattern)).width(100);
+        let pattern_list = pick_list(
+            &SceneKind::ALL[..],
+            Some(self.render_settings.scene),
+            ApplicationMessage::SceneChanged,
+        )
+        .width(Length::Fill);
+        let mut pattern_controls =
+            column![row![pattern_label, pattern_list].padding(10).spacing(10)];
+
+        if matches!(
+            self.render_settings.scene,
+            SceneKind::Gradient | SceneKind::Radial
+        ) {
+            pattern_controls = pattern_controls.push(
+                row![
+                    text(tr(Text::BlendSpace)).width(200),
+                    pick_list(
+                        &BlendSpace::ALL[..],
+                        Some(self.render_settings.blend_space),
+                        ApplicationMessage::BlendSpaceChanged,
+                    ),
+                ]
+                .padding(10)
+                .spacing(10)
+                .align_items(iced::Alignment::Center),
+            );
+        }
+
+        if self.render_settings.scene == SceneKind::Gradient {
+            let blend = self.render_settings.bilinear_blend;
+            pattern_controls = pattern_controls.push(
+                row![
+                    text(tr_args(Text::Blend, &[&format!("{blend:.2}")])).width(200),
+                    slider(0.0..=1.0, blend, ApplicationMessage::BilinearBlendChanged)
+                        .step(0.01)
+                        .on_release(ApplicationMessage::ScrubFinished),
+                ]
+                .padding(10)
+                .spacing(10),
+            );
+        }
+
+        // The stops of the gradients, each with a picker: its scene linear
+        // channels, or one of the sRGB swatches. The radial one only uses the U stops.
+        let gradient_axes: &[GradientAxis] = match self.render_settings.scene {
+            SceneKind::Gradient => &GradientAxis::ALL,
+            SceneKind::Radial => &[GradientAxis::U],
+            _ => &[],
+        };
+        for &axis in gradient_axes {
+            let stops = &self.render_settings.gradient(axis).stops;
+            let mut add_button = button(text(tr(Text::AddStop))).padding(5);
+            if stops.len() < MAX_GRADIENT_STOPS {
+                add_button = add_button.on_press(ApplicationMessage::GradientStopAdded(axis));
+            }
+            pattern_controls = pattern_controls.push(
+                row![text(axis.to_string()).width(200), add_button]
+                    .padding(10)
+                    .spacing(10)
+                    .align_items(iced::Alignment::Center),
+            );
+
+            for (index, stop) in stops.iter().enumerate() {
+                let mut remove_button = button(text("×")).style(iced::theme::Button::Text);
+                if stops.len() > 2 {
+                    remove_button = remove_button
+                        .on_press(ApplicationMessage::GradientStopRemoved(axis, index));
+                }
+                let position_row = row![
+                    text("■")
+                        .size(30)
+                        .style(color_picker::to_ui_color(stop.color)),
+                    text(format!("{:.2}", stop.position)).width(50),
+                    slider(0.0..=1.0, stop.position, move |position| {
+                        ApplicationMessage::GradientStopMoved(axis, index, position)
+                    })
+                    .step(0.01)
+                    .on_release(ApplicationMessage::ScrubFinished),
+                    remove_button,
+                ]
+                .padding([0, 10])
+                .spacing(10)
+                .align_items(iced::Alignment::Center);
+
+                let mut color_row = row![]
+                    .padding([0, 10, 10, 40])
+                    .spacing(5)
+                    .align_items(iced::Alignment::Center);
+                let inputs = &self.gradient_stop_inputs[axis as usize][index];
+                for (channel, input) in inputs.iter().enumerate() {
+                    color_row = color_row.push(
+                        text_input(["R", "G", "B"][channel], input, move |input| {
+                            ApplicationMessage::GradientStopInputChanged(
+                                axis, index, channel, input,
+                            )
+                        })
+                        .padding(5)
+                        .width(70),
+                    );
+                }
+                for swatch in color_picker::SWATCHES {
+                    let swatch_color = color_picker::from_srgb(swatch);
+                    color_row = color_row.push(
+                        button(text("■").style(color_picker::to_ui_color(swatch_color)))
+                            .style(iced::theme::Button::Text)
+                            .padding(0)
+                            .on_press(ApplicationMessage::GradientStopPicked(
+                                axis,
+                                index,
+                                swatch_color,
+                            )),
+                    );
+                }
+                let error = inputs
+                    .iter()
+                    .zip(["R", "G", "B"])
+                    .find_map(|(input, name)| {
+                        color_picker::parse_channel(input)
+                            .err()
+                            .map(|e| format!("{name} {e}"))
+                    });
+                if let Some(e) = error {
+                    color_row = color_row.push(text(e).style(iced::Color::from_rgb(0.8, 0.2, 0.2)));
+                }
+
+                pattern_controls = pattern_controls.push(position_row).push(color_row);
+            }
+        }
+
+        if self.render_settings.scene == SceneKind::Radial {
+            let (center_x, center_y) = self.render_settings.radial_center;
+            let radius = self.render_settings.radial_radius;
+            pattern_controls = pattern_controls.push(
+                row![
+                    text(tr_args(
+                        Text::RadialCenter,
+                        &[&format!("{center_x:.2}"), &format!("{center_y:.2}")]
+                    ))
+                    .width(200),
+                    slider(
+                        0.0..=1.0,
+                        center_x,
+                        ApplicationMessage::RadialCenterXChanged
+                    )
+                    .step(0.01)
+                    .on_release(ApplicationMessage::ScrubFinished),
+                    slider(
+                        0.0..=1.0,
+                        center_y,
+                        ApplicationMessage::RadialCenterYChanged
+                    )
+                    .step(0.01)
+                    .on_release(ApplicationMessage::ScrubFinished),
+                ]
+                .padding(10)
+                .spacing(10),
+            );
+            pattern_controls = pattern_controls.push(
+                row![
+                    text(tr_args(Text::Radius, &[&format!("{radius:.2}")])).width(200),
+                    slider(
+                        RADIUS_RANGE,
+                        radius,
+                        ApplicationMessage::RadialRadiusChanged
+                    )
+                    .step(0.01)
+                    .on_release(ApplicationMessage::ScrubFinished),
+                ]
+                .padding(10)
+                .spacing(10),
+            );
+        }
+
+        if self.render_settings.scene == SceneKind::Noise {
+            let noise = self.render_settings.noise;
+            pattern_controls = pattern_controls.push(
+                row![
+                    text(tr_args(
+                        Text::Frequency,
+                        &[&format!("{:.1}", noise.frequency)]
+                    ))
+                    .width(200),
+                    slider(
+                        FREQUENCY_RANGE,
+                        noise.frequency,
+                        ApplicationMessage::NoiseFrequencyChanged
+                    )
+                    .step(0.1)
+                    .on_release(ApplicationMessage::ScrubFinished),
+                ]
+                .padding(10)
+                .spacing(10),
+            );
+            pattern_controls = pattern_controls.push(
+                row![
+                    text(tr_args(Text::Octaves, &[&noise.octaves])).width(200),
+                    slider(
+                        OCTAVE_RANGE,
+                        noise.octaves,
+                        ApplicationMessage::NoiseOctavesChanged
+                    )
+                    .on_release(ApplicationMessage::ScrubFinished),
+                ]
+                .padding(10)
+                .spacing(10),
+            );
+            pattern_controls = pattern_controls.push(
+                row![
+                    text(tr_args(
+                        Text::Lacunarity,
+                        &[&format!("{:.2}", noise.lacunarity)]
+                    ))
+                    .width(200),
+                    slider(
+                        LACUNARITY_RANGE,
+                        noise.lacunarity,
+                        ApplicationMessage::NoiseLacunarityChanged
+                    )
+                    .step(0.01)
+                    .on_release(ApplicationMessage::ScrubFinished),
+                ]
+                .padding(10)
+                .spacing(10),
+            );
+        }
+
+        if self.render_settings.scene == SceneKind::Fractal {
+            let fractal = self.render_settings.fractal;
+            pattern_controls = pattern_controls.push(
+                row![
+                    pick_list(
+                        &FractalKind::ALL[..],
+                        Some(fractal.kind),
+                        ApplicationMessage::FractalKindChanged,
+                    ),
+                    pick_list(
+                        &ColorRamp::ALL[..],
+                        Some(fractal.ramp),
+                        ApplicationMessage::ColorRampChanged,
+                    ),
+                    button(text(tr(Text::ResetView)))
+                        .on_press(ApplicationMessage::FractalViewReset)
+                        .padding(10),
+                ]
+                .padding(10)
+                .spacing(10)
+                .align_items(iced::Alignment::Center),
+            );
+            pattern_controls = pattern_controls.push(
+                row![
+                    text(tr_args(Text::Iterations, &[&fractal.max_iterations])).width(200),
+                    slider(
+                        ITERATION_RANGE,
+                        fractal.max_iterations,
+                        ApplicationMessage::FractalIterationsChanged
+                    )
+                    .on_release(ApplicationMessage::ScrubFinished),
+                ]
+                .padding(10)
+                .spacing(10),
+            );
+            if fractal.kind == FractalKind::Julia {
+                let (cx, cy) = fractal.julia_constant;
+                pattern_controls = pattern_controls.push(
+                    row![
+                        text(tr_args(
+                            Text::JuliaConstant,
+                            &[&format!("{cx:.3}"), &format!("{cy:.3}")]
+                        ))
+                        .width(200),
+                        slider(-1.0..=1.0, cx, ApplicationMessage::JuliaConstantXChanged)
+                            .step(0.001)
+                            .on_release(ApplicationMessage::ScrubFinished),
+                        slider(-1.0..=1.0, cy, ApplicationMessage::JuliaConstantYChanged)
+                            .step(0.001)
+                            .on_release(ApplicationMessage::ScrubFinished),
+                    ]
+                    .padding(10)
+                    .spacing(10),
+                );
+            }
+            pattern_controls = pattern_controls.push(row![text(tr(Text::FractalHelp))].padding(10));
+        }
+
+        if self.render_settings.scene == SceneKind::PathTraced {
+            let path_tracer = self.render_settings.path_tracer;
+            pattern_controls = pattern_controls.push(
+                row![
+                    text(tr(Text::Samples)).width(200),
+                    NumberField::new(path_tracer.samples_per_pixel as f64, |samples| {
+                        ApplicationMessage::SamplesPerPixelChanged(samples as u32)
+                    })
+                    .range(1.0..=MAX_SAMPLES_PER_PIXEL as f64)
+                    .on_release(ApplicationMessage::ScrubFinished)
+                    .width(100),
+                ]
+                .padding(10)
+                .spacing(10)
+                .align_items(iced::Alignment::Center),
+            );
+            pattern_controls = pattern_controls.push(
+                row![
+                    text(tr_args(Text::Bounces, &[&path_tracer.max_bounces])).width(200),
+                    slider(
+                        BOUNCE_RANGE,
+                        path_tracer.max_bounces,
+                        ApplicationMessage::MaxBouncesChanged
+                    )
+                    .on_release(ApplicationMessage::ScrubFinished),
+                ]
+                .padding(10)
+                .spacing(10),
+            );
+        }
+
+        if self.render_settings.scene == SceneKind::Raymarched {
+            let sdf = self.render_settings.sdf;
+            pattern_controls = pattern_controls.push(
+                row![
+                    text(tr(Text::Operation)).width(200),
+                    pick_list(
+                        &SdfOperation::ALL[..],
+                        Some(sdf.operation),
+                        ApplicationMessage::SdfOperationChanged,
+                    ),
+                ]
+                .padding(10)
+                .spacing(10),
+            );
+            pattern_controls = pattern_controls.push(
+                row![
+                    text(tr_args(
+                        Text::Orbit,
+                        &[
+                            &format!("{:.0}", sdf.orbit_yaw),
+                            &format!("{:.0}", sdf.orbit_pitch)
+                        ]
+                    ))
+                    .width(200),
+                    slider(
+                        -180.0..=180.0,
+                        sdf.orbit_yaw,
+                        ApplicationMessage::OrbitYawChanged
+                    )
+                    .on_release(ApplicationMessage::ScrubFinished),
+                    slider(
+                        -10.0..=85.0,
+                        sdf.orbit_pitch,
+                        ApplicationMessage::OrbitPitchChanged
+                    )
+                    .on_release(ApplicationMessage::ScrubFinished),
+                ]
+                .padding(10)
+                .spacing(10),
+            );
+            pattern_controls = pattern_controls.push(
+                row![
+                    text(tr_args(
+                        Text::Distance,
+                        &[&format!("{:.1}", sdf.orbit_distance)]
+                    ))
+                    .width(200),
+                    slider(
+                        2.0..=12.0,
+                        sdf.orbit_distance,
+                        ApplicationMessage::OrbitDistanceChanged
+                    )
+                    .step(0.1)
+                    .on_release(ApplicationMessage::ScrubFinished),
+                ]
+                .padding(10)
+                .spacing(10),
+            );
+        }
+
+        if self.render_settings.scene == SceneKind::Expression {
+            let expression_input = text_input(
+                "sin(u * 10) * cos(v * 10)",
+                &self.expression_input,
+                ApplicationMessage::ExpressionChanged,
+            )
+            .padding(10);
+            let expression_status = match &self.expression_error {
+                Some(e) => text(tr_args(Text::ExpressionError, &[e])),
+                None => text(tr(Text::ExpressionHelp)),
+            };
+
+            pattern_controls = pattern_controls.push(row![expression_input].padding(10));
+            pattern_controls = pattern_controls.push(row![expression_status].padding(10));
+        }
+
+        pattern_controls
+    }
+
+    // What's under the image: the tools to look at it and the panels about it
+    fn viewer_panel<'a>(
+        &'a self,
+        image_viewer: Viewer<'a, ApplicationMessage>,
+    ) -> Element<'a, ApplicationMessage> {
+        let rendered_image = container(image_viewer)
+            .width(Length::Fill)
+            .center_x()
+            .max_height(VIEWER_SIZE.1)
+            .max_width(VIEWER_SIZE.0);
+
+        // Toolbar
+        let clipping_legend = if self.pending_display_settings.clipping_warning {
+            let swatch = |[r, g, b]: [u8; 3], label| {
+                text(format!("■ {label}"))
+                    .size(16)
+                    .style(iced::Color::from_rgb8(r, g, b))
+            };
+            row![
+                swatch(CLIPPING_WARNING_COLOR, tr(Text::Clipped)),
+                swatch(GAMUT_WARNING_COLOR, tr(Text::OutOfGamut)),
+            ]
+            .spacing(10)
+        } else {
+            row![]
+        };
+        let view_mode = self.pending_display_settings.view_mode;
+        // Same as the keyboard shortcuts, pressing the shown channel again goes back to normal
+        let mut channel_buttons = row![].spacing(5);
+        for (label, channel) in [
+            ("R", ViewMode::Red),
+            ("G", ViewMode::Green),
+            ("B", ViewMode::Blue),
+            ("A", ViewMode::Alpha),
+            ("L", ViewMode::Luma),
+        ] {
+            let style = if view_mode == channel {
+                iced::theme::Button::Primary
+            } else {
+                iced::theme::Button::Secondary
+            };
+            channel_buttons = channel_buttons.push(
+                button(text(label))
+                    .style(style)
+                    .on_press(ApplicationMessage::ViewShortcutPressed(channel)),
+            );
+        }
+
+        let zoom_percent = 100.0 * self.zoom.unwrap_or_else(|| self.fit_scale());
+        let zoom_button = |label, zoom| {
+            let style = if self.zoom == zoom {
+                iced::theme::Button::Primary
+            } else {
+                iced::theme::Button::Secondary
+            };
+            button(text(label))
+                .style(style)
+                .on_press(ApplicationMessage::ZoomSelected(zoom))
+        };
+        let zoom_controls = row![
+            text(tr_args(Text::Zoom, &[&format!("{zoom_percent:.0}")])).width(100),
+            zoom_button(tr(Text::Fit), None),
+            zoom_button("100%", Some(1.0)),
+            zoom_button("200%", Some(2.0)),
+            button(text("-")).on_press(ApplicationMessage::ZoomStepped(0.5)),
+            button(text("+")).on_press(ApplicationMessage::ZoomStepped(2.0)),
+            with_tooltip(
+                button(text(tr(Text::Present))).on_press(ApplicationMessage::PresentationToggled),
+                Text::TipPresent
+            ),
+            with_tooltip(
+                button(text(if self.panes.maximized().is_some() {
+                    tr(Text::ShowPanel)
+                } else {
+                    tr(Text::HidePanel)
+                }))
+                .on_press(ApplicationMessage::ParameterPanelToggled),
+                Text::TipPanel
+            ),
+            with_tooltip(
+                button(text("?")).on_press(ApplicationMessage::HelpToggled),
+                Text::KeyboardShortcuts
+            ),
+        ]
+        .spacing(5)
+        .align_items(iced::Alignment::Center);
+
+        // A/B comparison
+        let mut swap_button = button(text(tr(Text::SwapAB)));
+        if self.pinned_render.is_some() {
+            swap_button = swap_button.on_press(ApplicationMessage::SwapPressed);
+        }
+        let compare_controls = row![
+            button(text(tr(if self.pinned_render.is_some() {
+                Text::UnpinA
+            } else {
+                Text::PinAsA
+            })))
+            .on_press(ApplicationMessage::PinToggled),
+            swap_button,
+            text(if self.pinned_render.is_some() {
+                tr(Text::WipeHelp)
+            } else {
+                ""
+            })
+            .size(16),
+        ]
+        .spacing(5)
+        .align_items(iced::Alignment::Center);
+
+        let mut clear_guides_button = button(text(tr(Text::ClearGuides)));
+        if !self.guides.vertical.is_empty() || !self.guides.horizontal.is_empty() {
+            clear_guides_button = clear_guides_button.on_press(ApplicationMessage::GuidesCleared);
+        }
+        let guide_controls = row![
+            checkbox(
+                tr(Text::Rulers),
+                self.guides.rulers,
+                ApplicationMessage::RulersToggled
+            ),
+            with_tooltip(
+                checkbox(
+                    tr(Text::PixelGrid),
+                    self.show_pixel_grid,
+                    ApplicationMessage::PixelGridToggled
+                ),
+                Text::TipPixelGrid
+            ),
+            checkbox(
+                tr(Text::Thirds),
+                self.guides.thirds,
+                ApplicationMessage::ThirdsToggled
+            ),
+            checkbox(
+                tr(Text::Center),
+                self.guides.center,
+                ApplicationMessage::CenterCrossToggled
+            ),
+            checkbox(
+                tr(Text::SafeAreas),
+                self.guides.safe_areas,
+                ApplicationMessage::SafeAreasToggled
+            ),
+            button(text(tr(Text::AddVerticalGuide)))
+                .on_press(ApplicationMessage::GuideAdded(Orientation::Vertical)),
+            button(text(tr(Text::AddHorizontalGuide)))
+                .on_press(ApplicationMessage::GuideAdded(Orientation::Horizontal)),
+            with_tooltip(
+                checkbox(
+                    tr(Text::MoveGuides),
+                    self.moving_guides,
+                    ApplicationMessage::MovingGuidesToggled
+                ),
+                Text::TipMoveGuides
+            ),
+            clear_guides_button,
+        ]
+        .spacing(10)
+        .align_items(iced::Alignment::Center);
+
+        let mut viewer_toolbar = column![
+            row![
+                zoom_controls,
+                compare_controls,
+                checkbox(
+                    tr(Text::Histogram),
+                    self.show_histogram,
+                    ApplicationMessage::HistogramToggled
+                ),
+                pick_list(
+                    &HistogramSource::ALL[..],
+                    Some(self.histogram_source),
+                    ApplicationMessage::HistogramSourceChanged
+                ),
+                pick_list(
+                    &ViewerBackground::ALL[..],
+                    Some(self.viewer_background),
+                    ApplicationMessage::ViewerBackgroundChanged
+                ),
+            ]
+            .spacing(20)
+            .align_items(iced::Alignment::Center),
+            row![
+                text(tr(Text::View)),
+                pick_list(
+                    &ViewMode::ALL[..],
+                    Some(view_mode),
+                    ApplicationMessage::ViewModeChanged
+                ),
+                channel_buttons,
+                pick_list(
+                    &ColorBlindness::ALL[..],
+                    Some(self.pending_display_settings.color_blindness),
+                    ApplicationMessage::ColorBlindnessChanged
+                ),
+                pick_list(
+                    &Dithering::ALL[..],
+                    Some(self.pending_display_settings.dithering),
+                    ApplicationMessage::DitheringChanged
+                ),
+                checkbox(
+                    tr(Text::ClippingWarning),
+                    self.pending_display_settings.clipping_warning,
+                    ApplicationMessage::ClippingWarningToggled
+                ),
+                clipping_legend,
+            ]
+            .spacing(10)
+            .align_items(iced::Alignment::Center),
+            guide_controls,
+        ]
+        .padding([0, 10])
+        .spacing(5);
+        if view_mode == ViewMode::FalseColor {
+            let swatch = |[r, g, b]: [f32; 3], label: String| {
+                text(format!("■ {label}"))
+                    .size(16)
+                    .style(iced::Color::from_rgb(r, g, b))
+            };
+            let mut legend = row![].spacing(10);
+            let mut lower_stops = None;
+            for (upper_stops, color, name) in FALSE_COLOR_BANDS {
+                let range = match lower_stops {
+                    None => format!("< {upper_stops:+}"),
+                    Some(lower_stops) => tr_args(
+                        Text::FalseColorRange,
+                        &[&format!("{lower_stops:+}"), &format!("{upper_stops:+}")],
+                    ),
+                };
+                legend = legend.push(swatch(color, format!("{name} ({range})")));
+                lower_stops = Some(upper_stops);
+            }
+            let last_stops = FALSE_COLOR_BANDS[FALSE_COLOR_BANDS.len() - 1].0;
+            legend = legend.push(swatch(
+                FALSE_COLOR_CLIPPED,
+                tr_args(Text::FalseColorClipping, &[&format!("{last_stops:+}")]),
+            ));
+            viewer_toolbar = viewer_toolbar.push(
+                scrollable(legend)
+                    .horizontal_scroll(iced::widget::scrollable::Properties::default()),
+            );
+        }
+        if view_mode == ViewMode::Bracket {
+            let stops: Vec<String> = BRACKET_STOPS
+                .iter()
+                .map(|stops| format!("{stops:+}"))
+                .collect();
+            viewer_toolbar =
+                viewer_toolbar.push(text(tr_args(Text::BracketStops, &[&stops.join(", ")])));
+        }
+        if view_mode == ViewMode::NonFinite {
+            let [r, g, b] = NON_FINITE_COLOR;
+            viewer_toolbar = viewer_toolbar.push(
+                text(tr_args(Text::NonFiniteLegend, &[&self.non_finite_pixels]))
+                    .size(16)
+                    .style(iced::Color::from_rgb8(r, g, b)),
+            );
+        }
+
+        let histogram_panel: Element<'_, ApplicationMessage> = match &self.histogram_image {
+            Some(histogram) => container(image(histogram.clone()))
+                .width(Length::Fill)
+                .center_x()
+                .padding([0, 10])
+                .into(),
+            None => row![].into(),
+        };
+
+        // Scopes, collapsible
+        let mut scopes_panel = column![row![
+            button(text(format!(
+                "{} {}",
+                if self.show_scopes { "▾" } else { "▸" },
+                tr(Text::Scopes)
+            )))
+            .style(iced::theme::Button::Text)
+            .on_press(ApplicationMessage::ScopesToggled),
+            pick_list(
+                &ScopeKind::ALL[..],
+                Some(self.scope_kind),
+                ApplicationMessage::ScopeKindChanged
+            ),
+        ]
+        .spacing(10)
+        .align_items(iced::Alignment::Center)]
+        .padding([0, 10])
+        .spacing(5);
+        if let Some(scope) = &self.scope_image {
+            scopes_panel = scopes_panel.push(
+                container(image(scope.clone()))
+                    .width(Length::Fill)
+                    .center_x(),
+            );
+        }
+
+        // Statistics of the whole image, collapsible like the scopes
+        let mut statistics_panel = column![row![
+            button(text(format!(
+                "{} {}",
+                if self.show_statistics { "▾" } else { "▸" },
+                tr(Text::Statistics)
+            )))
+            .style(iced::theme::Button::Text)
+            .on_press(ApplicationMessage::StatisticsToggled),
+            text(tr(Text::StatisticsValues)).size(16),
+        ]
+        .spacing(10)
+        .align_items(iced::Alignment::Center)]
+        .padding([0, 10])
+        .spacing(5);
+        if let Some(stats) = &self.image_stats {
+            statistics_panel = statistics_panel.push(statistics_table(stats));
+        }
+
+        // What happened during the session, collapsible like the scopes
+        let mut console_panel = column![row![
+            button(text(format!(
+                "{} {}",
+                if self.show_console { "▾" } else { "▸" },
+                tr(Text::Console)
+            )))
+            .style(iced::theme::Button::Text)
+            .on_press(ApplicationMessage::ConsoleToggled),
+            button(text(tr(Text::Copy))).on_press(ApplicationMessage::ConsoleCopyPressed),
+            button(text(tr(Text::Clear))).on_press(ApplicationMessage::ConsoleCleared),
+            text(tr(Text::TimesAreUtc)).size(16),
+        ]
+        .spacing(10)
+        .align_items(iced::Alignment::Center)]
+        .padding([0, 10])
+        .spacing(5);
+        if self.show_console {
+            // Most recent first, so that what just happened is at the top
+            let mut lines = column![].spacing(2);
+            for entry in console::entries().iter().rev() {
+                let line = text(entry.to_string()).size(14);
+                lines = lines.push(match entry.severity {
+                    console::Severity::Info => line,
+                    console::Severity::Warning => line.style(iced::Color::from_rgb(0.9, 0.6, 0.1)),
+                    console::Severity::Error => line.style(iced::Color::from_rgb(0.8, 0.2, 0.2)),
+                });
+            }
+            console_panel =
+                console_panel.push(scrollable(lines).height(Length::Fixed(CONSOLE_HEIGHT)));
+        }
+
+        // The values of the hovered pixel, right under the image so they can be
+        // followed while moving the cursor. All the stages are in the inspector section.
+        let pixel_readout: Element<'_, ApplicationMessage> = match self.inspected_stages() {
+            Some((x, y, sample, stages)) => {
+                let [r, g, b] = stages.linear;
+                let alpha = sample[3];
+                let [r8, g8, b8, a8] = stages.rgba;
+                row![text(format!(
+                    "{x}, {y}    ACEScg {r:.4} {g:.4} {b:.4} {alpha:.4}    \
+                     {} {r8} {g8} {b8} {a8}",
+                    self.pending_display_settings.display
+                ))
+                .size(16)]
+                .padding([0, 10])
+                .into()
+            }
+            None => row![].into(),
+        };
+
+        // Thumbnails of the last renders, most recent first
+        let mut thumbnails = row![].spacing(5);
+        for (index, snapshot) in self.gallery.iter().enumerate() {
+            thumbnails = thumbnails.push(
+                button(image(snapshot.thumbnail.clone()).width(gallery::THUMBNAIL_WIDTH as f32))
+                    .padding(2)
+                    .style(iced::theme::Button::Secondary)
+                    .on_press(ApplicationMessage::SnapshotSelected(index)),
+            );
+        }
+        let gallery_strip = column![
+            text(tr(Text::History)).size(16),
+            scrollable(thumbnails).horizontal_scroll(iced::widget::scrollable::Properties::new())
+        ]
+        .padding([0, 10])
+        .spacing(5);
+
+        // One tab per open framebuffer, the last one can't be closed
+        let mut tab_bar = row![].spacing(5).align_items(iced::Alignment::Center);
+        for (index, tab) in self.tabs.iter().enumerate() {
+            let style = if index == self.active_tab {
+                iced::theme::Button::Primary
+            } else {
+                iced::theme::Button::Secondary
+            };
+            let mut close_button = button(text("×")).style(iced::theme::Button::Text);
+            if self.tabs.len() > 1 {
+                close_button = close_button.on_press(ApplicationMessage::TabClosed(index));
+            }
+            tab_bar = tab_bar.push(
+                row![
+                    button(text(&tab.name))
+                        .style(style)
+                        .on_press(ApplicationMessage::TabSelected(index)),
+                    close_button,
+                ]
+                .align_items(iced::Alignment::Center),
+            );
+        }
+        tab_bar = tab_bar.push(button(text("+")).on_press(ApplicationMessage::NewTabPressed));
+
+        scrollable(column![
+            row![tab_bar].padding([10, 10, 0, 10]),
+            row![rendered_image].padding(10).spacing(10),
+            pixel_readout,
+            histogram_panel,
+            scopes_panel,
+            statistics_panel,
+            viewer_toolbar,
+            gallery_strip,
+            console_panel,
+        ])
+        .into()
+    }
+
+    // Banner with the outcome of the saves, which happen in the background, or of the copy
+    fn notification_banner(&self) -> Element<'_, ApplicationMessage> {
+        let notification = match (&self.save_result, &self.copy_result) {
+            (Some(Ok(path)), _) => Some(Ok(tr_args(Text::Saved, &[path]))),
+            (_, Some(Ok(()))) => Some(Ok(tr(Text::CopiedImage).to_string())),
+            (Some(Err(e)), _) | (_, Some(Err(e))) => Some(Err(e.clone())),
+            (None, None) => None,
+        };
+        match notification {
+            _ if self.saves_in_flight > 0 => {
+                row![text(tr_args(Text::Saving, &[&self.saves_in_flight]))]
+                    .padding(10)
+                    .into()
+            }
+            Some(result) => {
+                let message = match result {
+                    Ok(message) => text(message),
+                    Err(e) => text(e).style(iced::Color::from_rgb(0.8, 0.2, 0.2)),
+                };
+                row![
+                    message.width(Length::Fill),
+                    button(text(tr(Text::Dismiss)))
+                        .padding(5)
+                        .on_press(ApplicationMessage::SaveNotificationDismissed),
+                ]
+                .padding(10)
+                .spacing(10)
+                .align_items(iced::Alignment::Center)
+                .into()
+            }
+            None => row![].into(),
+        }
+    }
+
+    // What a look at the image needs, always at the bottom of the window
+    fn status_bar(&self) -> Element<'_, ApplicationMessage> {
+        let render_time = if self.render_job.is_some() {
+            tr_args(
+                Text::RenderingFor,
+                &[&format!(
+                    "{:.1}",
+                    self.render_started.elapsed().as_secs_f64()
+                )],
+            )
+        } else {
+            match self.render_history.iter().next() {
+                Some(stats) => tr_args(
+                    Text::RenderedIn,
+                    &[&format!("{:.2}", stats.render_time.as_secs_f64())],
+                ),
+                None => String::new(),
+            }
+        };
+        let zoom_percent = 100.0 * self.zoom.unwrap_or_else(|| self.fit_scale());
+        row![
+            text(format!(
+                "{}x{}",
+                self.render_settings.width, self.render_settings.height
+            )),
+            text(format!("{zoom_percent:.0}%")),
+            text(match self.cursor_pixel {
+                Some((x, y)) => format!("{x}, {y}"),
+                None => String::from("-"),
+            })
+            .width(100),
+            text(render_time).width(200),
+            text(if self.non_finite_pixels > 0 {
+                tr_args(Text::NonFinitePixels, &[&self.non_finite_pixels])
+            } else {
+                String::new()
+            })
+            .style(iced::Color::from_rgb(0.8, 0.2, 0.2)),
+            text(match &self.last_saved_path {
+                Some(path) => tr_args(Text::LastSaved, &[path]),
+                None => tr(Text::NotSavedYet).to_string(),
+            }),
+        ]
+        .padding([5, 10])
+        .spacing(20)
+        .into()
+    }
+
+    // The open dialog, centered in place of the rest of the UI
+    fn modal_view<'a>(&self, modal: &'a Modal) -> Element<'a, ApplicationMessage> {
+        let dialog = match modal {
+            Modal::ConfirmOverwrite { path } => {
+                let overwrite_button = button(text(tr(Text::Overwrite)))
+                    .on_press(ApplicationMessage::ConfirmOverwrite)
+                    .padding(10);
+                let version_button = button(text(tr(Text::SaveAsNewVersion)))
+                    .on_press(ApplicationMessage::SaveAsNewVersion)
+                    .padding(10);
+                let cancel_button = button(text(tr(Text::Cancel)))
+                    .on_press(ApplicationMessage::CancelSave)
+                    .padding(10);
+                column![
+                    text(tr_args(Text::OverwriteQuestion, &[path])),
+                    row![overwrite_button, version_button, cancel_button].spacing(10),
+                ]
+                .spacing(20)
+            }
+            Modal::Shortcuts => {
+                let mut shortcuts = column![].spacing(5);
+                for action in Action::ALL {
+                    shortcuts = shortcuts.push(row![
+                        text(tr(action.description())).width(400),
+                        text(self.keymap.keys(action).join(", ")),
+                    ]);
+                }
+                column![
+                    text(tr(Text::KeyboardShortcuts)).size(30),
+                    shortcuts,
+                    button(text(tr(Text::Close)))
+                        .on_press(ApplicationMessage::HelpToggled)
+                        .padding(10),
+                ]
+                .spacing(20)
+            }
+        };
+
+        container(
+            container(dialog)
+                .padding(20)
+                .style(iced::theme::Container::Box),
+        )
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .center_x()
+        .center_y()
+        .into()
+    }
+}
+
+// `content`, explaining what it does when hovered
+fn with_tooltip<'a>(
+    content: impl Into<Element<'a, ApplicationMessage>>,
+    tip: Text,
+) -> Element<'a, ApplicationMessage> {
+    tooltip(content, tr(tip), tooltip::Position::Bottom)
+        .gap(5)
+        .padding(10)
+        .style(iced::theme::Container::Box)
+        .into()
+}
+
+// Rows of the minimum, maximum, mean and standard deviation of each channel
+fn statistics_table<'a>(stats: &ChannelStats) -> Element<'a, ApplicationMessage> {
+    let cell = |content: String| text(content).width(100);
+    let stat_row = |name: Text, values: [f32; 4]| {
+        values
+            .iter()
+            .fold(row![cell(tr(name).to_owned())], |row, value| {
+                row.push(cell(format!("{value:.4}")))
+            })
+            .spacing(10)
+    };
+    let header = ["", "R", "G", "B", "A"]
+        .iter()
+        .fold(row![], |row, name| row.push(cell(name.to_string())))
+        .spacing(10);
+
+    column![
+        header,
+        stat_row(Text::Minimum, stats.min),
+        stat_row(Text::Maximum, stats.max),
+        stat_row(Text::Mean, stats.mean),
+        stat_row(Text::StandardDeviation, stats.std_dev),
+    ]
+    .spacing(5)
+    .into()
+}
@@ -0,0 +1,66 @@
+//! Undo and redo of the render and display settings, so that trying things out
+//! never loses a look that worked
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use crate::preset::Preset;
+
+/// How many changes can be undone
+const UNDO_LIMIT: usize = 100;
+/// Changes of the same setting closer together than this are undone at once,
+/// e.g. all the steps of dragging a slider
+pub const MERGE_INTERVAL: Duration = Duration::from_secs(1);
+
+pub struct UndoHistory {
+    /// The settings before each change, the most recent last
+    undo: VecDeque<Preset>,
+    redo: Vec<Preset>,
+}
+
+impl UndoHistory {
+    pub fn new() -> Self {
+        UndoHistory {
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+        }
+    }
+
+    /// Remember `before`, the settings a change started from, unless `merged`
+    /// with the previous change, which already remembers where it started from
+    pub fn record(&mut self, before: Preset, merged: bool) {
+        self.redo.clear();
+        if merged && !self.undo.is_empty() {
+            return;
+        }
+        if self.undo.len() == UNDO_LIMIT {
+            self.undo.pop_front();
+        }
+        self.undo.push_back(before);
+    }
+
+    /// The settings to go back to from `current`, which can then be redone
+    pub fn undo(&mut self, current: Preset) -> Option<Preset> {
+        let previous = self.undo.pop_back()?;
+        self.redo.push(current);
+        Some(previous)
+    }
+
+    pub fn redo(&mut self, current: Preset) -> Option<Preset> {
+        let next = self.redo.pop()?;
+        self.undo.push_back(current);
+        Some(next)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+}
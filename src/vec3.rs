@@ -0,0 +1,105 @@
+//! Just enough 3D vector math for the ray based scenes
+use std::ops::{Add, Mul, Sub};
+
+#[derive(Debug, Clone, Copy)]
+pub struct Vec3 {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+}
+
+pub const fn vec3(x: f32, y: f32, z: f32) -> Vec3 {
+    Vec3 { x, y, z }
+}
+
+impl Vec3 {
+    pub fn dot(self, other: Vec3) -> f32 {
+        self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    pub fn cross(self, other: Vec3) -> Vec3 {
+        vec3(
+            self.y * other.z - self.z * other.y,
+            self.z * other.x - self.x * other.z,
+            self.x * other.y - self.y * other.x,
+        )
+    }
+
+    pub fn length(self) -> f32 {
+        self.dot(self).sqrt()
+    }
+
+    pub fn normalized(self) -> Vec3 {
+        self * (1.0 / self.length())
+    }
+}
+
+impl Add for Vec3 {
+    type Output = Vec3;
+
+    fn add(self, other: Vec3) -> Vec3 {
+        vec3(self.x + other.x, self.y + other.y, self.z + other.z)
+    }
+}
+
+impl Sub for Vec3 {
+    type Output = Vec3;
+
+    fn sub(self, other: Vec3) -> Vec3 {
+        vec3(self.x - other.x, self.y - other.y, self.z - other.z)
+    }
+}
+
+impl Mul<f32> for Vec3 {
+    type Output = Vec3;
+
+    fn mul(self, scale: f32) -> Vec3 {
+        vec3(self.x * scale, self.y * scale, self.z * scale)
+    }
+}
+
+// Component-wise, for colors
+impl Mul for Vec3 {
+    type Output = Vec3;
+
+    fn mul(self, other: Vec3) -> Vec3 {
+        vec3(self.x * other.x, self.y * other.y, self.z * other.z)
+    }
+}
+
+/// Pinhole camera looking at a target, with Y up
+pub struct Camera {
+    eye: Vec3,
+    forward: Vec3,
+    right: Vec3,
+    up: Vec3,
+    /// Tangent of half the vertical field of view
+    half_height: f32,
+}
+
+impl Camera {
+    pub fn look_at(eye: Vec3, target: Vec3, vertical_fov_degrees: f32) -> Self {
+        let forward = (target - eye).normalized();
+        let right = forward.cross(vec3(0.0, 1.0, 0.0)).normalized();
+        let up = right.cross(forward);
+
+        Camera {
+            eye,
+            forward,
+            right,
+            up,
+            half_height: (vertical_fov_degrees.to_radians() / 2.0).tan(),
+        }
+    }
+
+    pub fn eye(&self) -> Vec3 {
+        self.eye
+    }
+
+    /// Normalized direction of the ray through the U,V coordinates, with V going up
+    pub fn ray_direction(&self, u: f32, v: f32, aspect_ratio: f32) -> Vec3 {
+        let x = (u * 2.0 - 1.0) * self.half_height * aspect_ratio;
+        let y = (v * 2.0 - 1.0) * self.half_height;
+        (self.forward + self.right * x + self.up * y).normalized()
+    }
+}
@@ -0,0 +1,474 @@
+//! The framebuffer viewer: shows the image at the zoom and position given by the
+//! application, pans it with the middle button, zooms it around the cursor with
+//! the wheel, and reports the other clicks, drags and hovers in image coordinates.
+//! The stock `image::Viewer` keeps its zoom to itself and can't do any of these.
+//! Zoomed in far enough, it outlines every pixel.
+use iced_native::event::{self, Event};
+use iced_native::image::{self, Handle};
+use iced_native::layout::{self, Layout};
+use iced_native::mouse;
+use iced_native::renderer;
+use iced_native::widget::{tree, Tree};
+use iced_native::{
+    Clipboard, Color, Element, Length, Point, Rectangle, Shell, Size, Vector, Widget,
+};
+
+/// How far the cursor needs to move for a press to become a drag, in logical pixels
+const DRAG_THRESHOLD: f32 = 4.0;
+/// Zoom factor of a line of scrolling
+const WHEEL_ZOOM: f32 = 1.25;
+/// Zoom from which the pixel grid is drawn, when enabled
+const PIXEL_GRID_ZOOM: f32 = 8.0;
+const PIXEL_GRID_COLOR: Color = Color::from_rgba(0.5, 0.5, 0.5, 0.6);
+
+/// All positions are normalized to the image,
+/// from (0, 0) at its top left to (1, 1) at its bottom right
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PointerEvent {
+    Clicked {
+        position: Point,
+        button: mouse::Button,
+    },
+    /// The left button is held down while moving, sent for every move
+    Dragged {
+        from: Point,
+        to: Point,
+    },
+    DragFinished {
+        from: Point,
+        to: Point,
+    },
+    /// `lines` is positive when scrolling up
+    Scrolled {
+        position: Point,
+        lines: f32,
+    },
+    /// The cursor moved over the image, without any button held down
+    Hovered {
+        position: Point,
+    },
+    /// The cursor moved out of the image, after hovering it
+    Left,
+}
+
+/// Zoom and pan of the view, as applied by the viewer
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct View {
+    /// Scale of the image, None to fit it in the viewer (never enlarging it)
+    pub zoom: Option<f32>,
+    /// Point of the image shown at the center of the viewer, in pixels.
+    /// None to center the image.
+    pub center: Option<Point>,
+}
+
+/// Where the image lands in the viewer, mapping between the two
+#[derive(Debug, Clone, Copy)]
+struct Projection {
+    /// Top left corner of the image, in the coordinates of the window
+    origin: Point,
+    scale: f32,
+    image_size: Size,
+}
+
+impl Projection {
+    /// The position in image pixels of a point of the window
+    fn to_pixels(self, point: Point) -> Point {
+        Point::new(
+            (point.x - self.origin.x) / self.scale,
+            (point.y - self.origin.y) / self.scale,
+        )
+    }
+
+    /// Like `to_pixels`, but normalized to the size of the image
+    fn to_normalized(self, point: Point) -> Point {
+        let pixels = self.to_pixels(point);
+        Point::new(
+            pixels.x / self.image_size.width,
+            pixels.y / self.image_size.height,
+        )
+    }
+
+    fn image_bounds(self) -> Rectangle {
+        let size = Size::new(
+            self.image_size.width * self.scale,
+            self.image_size.height * self.scale,
+        );
+        Rectangle::new(self.origin, size)
+    }
+}
+
+#[derive(Default)]
+struct State {
+    // Where the left button went down, in window coordinates
+    press_position: Option<Point>,
+    dragging: bool,
+    hovering: bool,
+    // Where the middle button went down, and the center of the view at that time
+    pan_origin: Option<(Point, Point)>,
+}
+
+pub struct Viewer<'a, Message> {
+    handle: Handle,
+    view: View,
+    width: Length,
+    height: Length,
+    min_zoom: f32,
+    max_zoom: f32,
+    on_view_change: Option<Box<dyn Fn(f32, Point) -> Message + 'a>>,
+    on_pointer: Option<Box<dyn Fn(PointerEvent) -> Message + 'a>>,
+    on_hover: Option<Box<dyn Fn(Option<Point>) -> Message + 'a>>,
+    forward_scroll: bool,
+    pixel_grid: bool,
+}
+
+impl<'a, Message> Viewer<'a, Message> {
+    pub fn new(handle: Handle, view: View) -> Self {
+        Viewer {
+            handle,
+            view,
+            width: Length::Fill,
+            height: Length::Fill,
+            min_zoom: 1.0 / 16.0,
+            max_zoom: 32.0,
+            on_view_change: None,
+            on_pointer: None,
+            on_hover: None,
+            forward_scroll: false,
+            pixel_grid: false,
+        }
+    }
+
+    pub fn width(mut self, width: impl Into<Length>) -> Self {
+        self.width = width.into();
+        self
+    }
+
+    pub fn height(mut self, height: impl Into<Length>) -> Self {
+        self.height = height.into();
+        self
+    }
+
+    pub fn zoom_range(mut self, min_zoom: f32, max_zoom: f32) -> Self {
+        self.min_zoom = min_zoom;
+        self.max_zoom = max_zoom;
+        self
+    }
+
+    /// Called with the new zoom and center when panning or zooming with the mouse
+    pub fn on_view_change(mut self, on_view_change: impl Fn(f32, Point) -> Message + 'a) -> Self {
+        self.on_view_change = Some(Box::new(on_view_change));
+        self
+    }
+
+    /// Called for the clicks, left button drags and hovers over the image
+    pub fn on_pointer(mut self, on_pointer: impl Fn(PointerEvent) -> Message + 'a) -> Self {
+        self.on_pointer = Some(Box::new(on_pointer));
+        self
+    }
+
+    /// Called with the normalized position of the cursor while it moves over the image,
+    /// and with None once it leaves, whether or not the clicks are captured
+    pub fn on_hover(mut self, on_hover: impl Fn(Option<Point>) -> Message + 'a) -> Self {
+        self.on_hover = Some(Box::new(on_hover));
+        self
+    }
+
+    /// Send the wheel to `on_pointer` instead of zooming the view
+    pub fn forward_scroll(mut self, forward_scroll: bool) -> Self {
+        self.forward_scroll = forward_scroll;
+        self
+    }
+
+    /// Draw the boundaries of the pixels once zoomed in past `PIXEL_GRID_ZOOM`
+    pub fn pixel_grid(mut self, pixel_grid: bool) -> Self {
+        self.pixel_grid = pixel_grid;
+        self
+    }
+
+    fn projection(&self, image_size: Size, bounds: Rectangle) -> Projection {
+        let fit_scale = (bounds.width / image_size.width)
+            .min(bounds.height / image_size.height)
+            .min(1.0);
+        let scale = self.view.zoom.unwrap_or(fit_scale);
+        let center = self
+            .view
+            .center
+            .unwrap_or(Point::new(image_size.width / 2.0, image_size.height / 2.0));
+
+        Projection {
+            origin: bounds.center() - Vector::new(center.x * scale, center.y * scale),
+            scale,
+            image_size,
+        }
+    }
+
+    /// The center of the view, kept over the image so that it can't get lost
+    fn clamp_center(center: Point, image_size: Size) -> Point {
+        Point::new(
+            center.x.clamp(0.0, image_size.width),
+            center.y.clamp(0.0, image_size.height),
+        )
+    }
+}
+
+impl<'a, Message, Renderer> Widget<Message, Renderer> for Viewer<'a, Message>
+where
+    Renderer: image::Renderer<Handle = Handle>,
+{
+    fn tag(&self) -> tree::Tag {
+        tree::Tag::of::<State>()
+    }
+
+    fn state(&self) -> tree::State {
+        tree::State::new(State::default())
+    }
+
+    fn width(&self) -> Length {
+        self.width
+    }
+
+    fn height(&self) -> Length {
+        self.height
+    }
+
+    fn layout(&self, _renderer: &Renderer, limits: &layout::Limits) -> layout::Node {
+        let limits = limits.width(self.width).height(self.height);
+        layout::Node::new(limits.resolve(Size::ZERO))
+    }
+
+    fn on_event(
+        &mut self,
+        tree: &mut Tree,
+        event: Event,
+        layout: Layout<'_>,
+        cursor_position: Point,
+        renderer: &Renderer,
+        _clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message>,
+    ) -> event::Status {
+        let bounds = layout.bounds();
+        let dimensions = renderer.dimensions(&self.handle);
+        let image_size = Size::new(dimensions.width as f32, dimensions.height as f32);
+        let projection = self.projection(image_size, bounds);
+        let state = tree.state.downcast_mut::<State>();
+        let is_over = bounds.contains(cursor_position);
+
+        let pointer_event = match event {
+            Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Middle)) if is_over => {
+                let center = projection.to_pixels(bounds.center());
+                state.pan_origin = Some((cursor_position, center));
+                return event::Status::Captured;
+            }
+            Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Middle))
+                if state.pan_origin.is_some() =>
+            {
+                state.pan_origin = None;
+                return event::Status::Captured;
+            }
+            Event::Mouse(mouse::Event::CursorMoved { position }) if state.pan_origin.is_some() => {
+                let Some((press_position, center)) = state.pan_origin else {
+                    return event::Status::Ignored;
+                };
+                if let Some(on_view_change) = &self.on_view_change {
+                    // The image moves along with the cursor
+                    let moved = position - press_position;
+                    let center = Point::new(
+                        center.x - moved.x / projection.scale,
+                        center.y - moved.y / projection.scale,
+                    );
+                    let center = Self::clamp_center(center, image_size);
+                    shell.publish(on_view_change(projection.scale, center));
+                }
+                return event::Status::Captured;
+            }
+            Event::Mouse(mouse::Event::WheelScrolled { delta }) if is_over => {
+                let lines = match delta {
+                    mouse::ScrollDelta::Lines { y, .. } => y,
+                    // Roughly what a line is worth on most platforms
+                    mouse::ScrollDelta::Pixels { y, .. } => y / 60.0,
+                };
+                if self.forward_scroll {
+                    PointerEvent::Scrolled {
+                        position: projection.to_normalized(cursor_position),
+                        lines,
+                    }
+                } else if let Some(on_view_change) = &self.on_view_change {
+                    // The pixel under the cursor stays there
+                    let zoom = (projection.scale * WHEEL_ZOOM.powf(lines))
+                        .clamp(self.min_zoom, self.max_zoom);
+                    let anchor = projection.to_pixels(cursor_position);
+                    let offset = cursor_position - bounds.center();
+                    let center = Point::new(anchor.x - offset.x / zoom, anchor.y - offset.y / zoom);
+                    let center = Self::clamp_center(center, image_size);
+                    shell.publish(on_view_change(zoom, center));
+                    return event::Status::Captured;
+                } else {
+                    return event::Status::Ignored;
+                }
+            }
+            Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left))
+                if is_over && self.on_pointer.is_some() =>
+            {
+                state.press_position = Some(cursor_position);
+                state.dragging = false;
+                return event::Status::Captured;
+            }
+            Event::Mouse(mouse::Event::ButtonPressed(button)) if is_over => PointerEvent::Clicked {
+                position: projection.to_normalized(cursor_position),
+                button,
+            },
+            Event::Mouse(mouse::Event::CursorMoved { position }) => {
+                let Some(press_position) = state.press_position else {
+                    let normalized = projection.to_normalized(position);
+                    let over_image = is_over
+                        && (0.0..1.0).contains(&normalized.x)
+                        && (0.0..1.0).contains(&normalized.y);
+                    // Other widgets might want to know about the cursor too
+                    if let Some(on_pointer) = &self.on_pointer {
+                        if over_image {
+                            shell.publish(on_pointer(PointerEvent::Hovered {
+                                position: normalized,
+                            }));
+                        } else if state.hovering {
+                            shell.publish(on_pointer(PointerEvent::Left));
+                        }
+                    }
+                    if let Some(on_hover) = &self.on_hover {
+                        if over_image {
+                            shell.publish(on_hover(Some(normalized)));
+                        } else if state.hovering {
+                            shell.publish(on_hover(None));
+                        }
+                    }
+                    state.hovering = over_image;
+                    return event::Status::Ignored;
+                };
+                if !state.dragging && press_position.distance(position) < DRAG_THRESHOLD {
+                    return event::Status::Ignored;
+                }
+                state.dragging = true;
+
+                PointerEvent::Dragged {
+                    from: projection.to_normalized(press_position),
+                    to: projection.to_normalized(position),
+                }
+            }
+            Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)) => {
+                let Some(press_position) = state.press_position.take() else {
+                    return event::Status::Ignored;
+                };
+
+                if state.dragging {
+                    state.dragging = false;
+                    PointerEvent::DragFinished {
+                        from: projection.to_normalized(press_position),
+                        to: projection.to_normalized(cursor_position),
+                    }
+                } else {
+                    PointerEvent::Clicked {
+                        position: projection.to_normalized(press_position),
+                        button: mouse::Button::Left,
+                    }
+                }
+            }
+            _ => return event::Status::Ignored,
+        };
+
+        match &self.on_pointer {
+            Some(on_pointer) => {
+                shell.publish(on_pointer(pointer_event));
+                event::Status::Captured
+            }
+            None => event::Status::Ignored,
+        }
+    }
+
+    fn draw(
+        &self,
+        _tree: &Tree,
+        renderer: &mut Renderer,
+        _theme: &Renderer::Theme,
+        _style: &renderer::Style,
+        layout: Layout<'_>,
+        _cursor_position: Point,
+        _viewport: &Rectangle,
+    ) {
+        let bounds = layout.bounds();
+        let dimensions = renderer.dimensions(&self.handle);
+        let image_size = Size::new(dimensions.width as f32, dimensions.height as f32);
+        let projection = self.projection(image_size, bounds);
+
+        // Zoomed in, the image goes past the viewer
+        renderer.with_layer(bounds, |renderer| {
+            image::Renderer::draw(renderer, self.handle.clone(), projection.image_bounds());
+        });
+
+        if !self.pixel_grid || projection.scale < PIXEL_GRID_ZOOM {
+            return;
+        }
+        // On a layer of its own, to go over the image. Only the visible lines are drawn.
+        let Some(visible) = projection.image_bounds().intersection(&bounds) else {
+            return;
+        };
+        let first = projection.to_pixels(visible.position());
+        let last = projection.to_pixels(Point::new(
+            visible.x + visible.width,
+            visible.y + visible.height,
+        ));
+        let line = |bounds: Rectangle| renderer::Quad {
+            bounds,
+            border_radius: 0.0.into(),
+            border_width: 0.0,
+            border_color: Color::TRANSPARENT,
+        };
+        renderer.with_layer(visible, |renderer| {
+            for x in first.x.ceil() as usize..=last.x.floor() as usize {
+                let screen_x = projection.origin.x + x as f32 * projection.scale;
+                let bounds = Rectangle::new(
+                    Point::new(screen_x.round(), visible.y),
+                    Size::new(1.0, visible.height),
+                );
+                renderer.fill_quad(line(bounds), PIXEL_GRID_COLOR);
+            }
+            for y in first.y.ceil() as usize..=last.y.floor() as usize {
+                let screen_y = projection.origin.y + y as f32 * projection.scale;
+                let bounds = Rectangle::new(
+                    Point::new(visible.x, screen_y.round()),
+                    Size::new(visible.width, 1.0),
+                );
+                renderer.fill_quad(line(bounds), PIXEL_GRID_COLOR);
+            }
+        });
+    }
+
+    fn mouse_interaction(
+        &self,
+        tree: &Tree,
+        layout: Layout<'_>,
+        cursor_position: Point,
+        _viewport: &Rectangle,
+        _renderer: &Renderer,
+    ) -> mouse::Interaction {
+        let state = tree.state.downcast_ref::<State>();
+
+        if state.pan_origin.is_some() || state.dragging {
+            mouse::Interaction::Grabbing
+        } else if !layout.bounds().contains(cursor_position) {
+            mouse::Interaction::Idle
+        } else if self.on_pointer.is_some() {
+            mouse::Interaction::Crosshair
+        } else {
+            mouse::Interaction::Grab
+        }
+    }
+}
+
+impl<'a, Message, Renderer> From<Viewer<'a, Message>> for Element<'a, Message, Renderer>
+where
+    Message: 'a,
+    Renderer: image::Renderer<Handle = Handle> + 'a,
+{
+    fn from(viewer: Viewer<'a, Message>) -> Self {
+        Element::new(viewer)
+    }
+}
@@ -0,0 +1,154 @@
+//! White balance of the scene linear image, with a Bradford chromatic adaptation
+//! See http://www.brucelindbloom.com/index.html?Eqn_ChromAdapt.html
+use colstodian::spaces::{AcesCg, CieXYZ};
+use colstodian::{Color, Scene};
+
+use std::ops::RangeInclusive;
+
+/// Temperature that leaves the image untouched, the one of D65
+pub const REFERENCE_TEMPERATURE: f32 = 6504.0;
+/// In Kelvin
+pub const TEMPERATURE_RANGE: RangeInclusive<f32> = 2000.0..=12000.0;
+/// From magenta to green
+pub const TINT_RANGE: RangeInclusive<f32> = -1.0..=1.0;
+
+pub type Matrix = [[f32; 3]; 3];
+
+/// From CIE XYZ to the cone response domain
+const BRADFORD: Matrix = [
+    [0.8951, 0.2664, -0.1614],
+    [-0.7502, 1.7135, 0.0367],
+    [0.0389, -0.0685, 1.0296],
+];
+const BRADFORD_INVERSE: Matrix = [
+    [0.986_993, -0.147_054, 0.159_963],
+    [0.432_305, 0.518_360, 0.049_291],
+    [-0.008_529, 0.040_043, 0.968_487],
+];
+
+/// Color of the light the scene was lit by, which gets adapted back to neutral
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WhiteBalance {
+    /// In Kelvin, lower temperatures are warmer lights, so the image gets cooler
+    pub temperature: f32,
+    /// Shift away from the blackbody locus, positive values are greener lights,
+    /// so the image gets more magenta
+    pub tint: f32,
+}
+
+impl Default for WhiteBalance {
+    fn default() -> Self {
+        WhiteBalance {
+            temperature: REFERENCE_TEMPERATURE,
+            tint: 0.0,
+        }
+    }
+}
+
+impl WhiteBalance {
+    pub fn is_neutral(&self) -> bool {
+        *self == WhiteBalance::default()
+    }
+
+    /// Matrix that adapts CIE XYZ colors lit by this white to the reference white
+    pub fn adaptation_matrix(&self) -> Matrix {
+        chromatic_adaptation(
+            white_xyz(self.temperature, self.tint),
+            white_xyz(REFERENCE_TEMPERATURE, 0.0),
+        )
+    }
+}
+
+/// Matrix that adapts CIE XYZ colors seen under the `source` white to the `destination` one
+pub fn chromatic_adaptation(source: [f32; 3], destination: [f32; 3]) -> Matrix {
+    let source_cone = multiply_vector(&BRADFORD, source);
+    let destination_cone = multiply_vector(&BRADFORD, destination);
+    let mut scale = [[0.0; 3]; 3];
+    for i in 0..3 {
+        scale[i][i] = destination_cone[i] / source_cone[i];
+    }
+
+    multiply(&BRADFORD_INVERSE, &multiply(&scale, &BRADFORD))
+}
+
+/// Apply an `adaptation_matrix` to a scene linear ACEScg color
+pub fn adapt(color: Color<AcesCg, Scene>, matrix: &Matrix) -> Color<AcesCg, Scene> {
+    let xyz = color.convert::<CieXYZ>();
+    let [x, y, z] = multiply_vector(matrix, [xyz.x, xyz.y, xyz.z]);
+    Color::<CieXYZ, Scene>::new(x, y, z).convert::<AcesCg>()
+}
+
+/// CIE XYZ of the white at `temperature` on the blackbody locus, with a luminance of 1,
+/// moved by `tint` perpendicular to it
+pub fn white_xyz(temperature: f32, tint: f32) -> [f32; 3] {
+    let (x, y) = planckian_xy(temperature);
+
+    // The tint is applied in CIE 1960 UCS, where the locus runs mostly along u
+    let denominator = -2.0 * x + 12.0 * y + 3.0;
+    let (u, v) = (4.0 * x / denominator, 6.0 * y / denominator + tint * 0.02);
+    let denominator = 2.0 * u - 8.0 * v + 4.0;
+    let (x, y) = (3.0 * u / denominator, 2.0 * v / denominator);
+
+    [x / y, 1.0, (1.0 - x - y) / y]
+}
+
+/// Chromaticity of a black body at `temperature`, with the approximation of Kim et al.
+/// See https://en.wikipedia.org/wiki/Planckian_locus#Approximation
+fn planckian_xy(temperature: f32) -> (f32, f32) {
+    let t = temperature.clamp(1667.0, 25000.0) as f64;
+    let (t2, t3) = (t * t, t * t * t);
+
+    let x = if t <= 4000.0 {
+        -0.266_123_9e9 / t3 - 0.234_358_9e6 / t2 + 0.877_695_6e3 / t + 0.179_910
+    } else {
+        -3.025_846_9e9 / t3 + 2.107_037_9e6 / t2 + 0.222_634_7e3 / t + 0.240_390
+    };
+    let (x2, x3) = (x * x, x * x * x);
+
+    let y = if t <= 2222.0 {
+        -1.106_381_4 * x3 - 1.348_110_20 * x2 + 2.185_558_32 * x - 0.202_196_83
+    } else if t <= 4000.0 {
+        -0.954_947_6 * x3 - 1.374_185_93 * x2 + 2.091_370_15 * x - 0.167_488_67
+    } else {
+        3.081_758_0 * x3 - 5.873_386_70 * x2 + 3.751_129_97 * x - 0.370_014_83
+    };
+
+    (x as f32, y as f32)
+}
+
+pub fn multiply(a: &Matrix, b: &Matrix) -> Matrix {
+    let mut product = [[0.0; 3]; 3];
+    for (row, product_row) in product.iter_mut().enumerate() {
+        for (column, value) in product_row.iter_mut().enumerate() {
+            *value = (0..3).map(|i| a[row][i] * b[i][column]).sum();
+        }
+    }
+    product
+}
+
+pub fn multiply_vector(matrix: &Matrix, vector: [f32; 3]) -> [f32; 3] {
+    matrix.map(|row| row[0] * vector[0] + row[1] * vector[1] + row[2] * vector[2])
+}
+
+pub fn invert(m: &Matrix) -> Option<Matrix> {
+    let cofactor = |row: usize, column: usize| {
+        let (r0, r1) = ((row + 1) % 3, (row + 2) % 3);
+        let (c0, c1) = ((column + 1) % 3, (column + 2) % 3);
+        m[r0][c0] * m[r1][c1] - m[r0][c1] * m[r1][c0]
+    };
+    let determinant: f32 = (0..3)
+        .map(|column| m[0][column] * cofactor(0, column))
+        .sum();
+    if determinant.abs() < 1e-9 {
+        return None;
+    }
+
+    // The inverse is the transposed matrix of cofactors, over the determinant
+    let mut inverse = [[0.0; 3]; 3];
+    for (row, inverse_row) in inverse.iter_mut().enumerate() {
+        for (column, value) in inverse_row.iter_mut().enumerate() {
+            *value = cofactor(column, row) / determinant;
+        }
+    }
+    Some(inverse)
+}